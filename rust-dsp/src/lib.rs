@@ -10,43 +10,184 @@
 //! - SIMD vectorization via rustfft
 //! - Loop unrolling and strength reduction
 //! - FFT-based autocorrelation (O(n log n) vs O(n²))
+//!
+//! Real-time safety:
+//! FFT plans are resolved once at construction, and the `_into` methods
+//! (`FftProcessor::power_spectrum_into`, `FftProcessor::magnitude_db_into`,
+//! `VoiceActivityDetector::detect_into`, `PitchDetector::detect_into`,
+//! `FormantAnalyzer::analyze_into`) write into caller-provided buffers, so
+//! their Rust-side computation does not allocate. From JavaScript the
+//! wasm-bindgen glue still mallocs and copies every `&[f32]`/`&mut [f32]`
+//! argument into WASM memory on each call; for a worklet, call them from Rust
+//! or on views into WASM memory. Methods returning `Vec` allocate their result.
 
 use wasm_bindgen::prelude::*;
-use rustfft::{FftPlanner, num_complex::Complex};
+use rustfft::{Fft, FftPlanner, num_complex::Complex};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::f32::consts::PI;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, OnceLock};
 
-// Console logging for WASM
+// Console logging for WASM (native builds, e.g. `cargo test`, skip the JS import)
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
 }
 
+#[cfg(target_arch = "wasm32")]
 macro_rules! console_log {
     ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+macro_rules! console_log {
+    ($($t:tt)*) => {{ let _ = format_args!($($t)*); }}
+}
+
+/// Shared FFT plan cache
+/// rustfft planners cache plans (and their twiddle tables) per size, so
+/// processors built from one handle with `with_planner` share that memory
+/// and skip re-planning. Single-threaded by design (`Rc`), matching WASM.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct SharedPlanner {
+    inner: Rc<RefCell<FftPlanner<f32>>>,
+}
+
+#[wasm_bindgen]
+impl SharedPlanner {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SharedPlanner {
+        SharedPlanner { inner: Rc::new(RefCell::new(FftPlanner::new())) }
+    }
+}
+
+impl Default for SharedPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedPlanner {
+    fn plan_forward(&self, size: usize) -> Arc<dyn Fft<f32>> {
+        self.inner.borrow_mut().plan_fft_forward(size)
+    }
+
+    fn plan_inverse(&self, size: usize) -> Arc<dyn Fft<f32>> {
+        self.inner.borrow_mut().plan_fft_inverse(size)
+    }
+}
+
+/// Analysis window shape (symmetric, `size - 1` denominator)
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WindowKind {
+    Hann,
+    Hamming,
+    Blackman,
+    Rectangular,
+}
+
+type WindowCache = Mutex<HashMap<(usize, WindowKind), Arc<[f32]>>>;
+
+/// Process-wide window cache: each (size, kind) is computed once and shared
+static WINDOW_CACHE: OnceLock<WindowCache> = OnceLock::new();
+
+/// Shared window of `size` samples, computed on first use
+fn cached_window(size: usize, kind: WindowKind) -> Arc<[f32]> {
+    let cache = WINDOW_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    cache.entry((size, kind))
+        .or_insert_with(|| {
+            #[cfg(test)]
+            tests::record_window_computation(size, kind);
+            compute_window(size, kind).into()
+        })
+        .clone()
+}
+
+fn compute_window(size: usize, kind: WindowKind) -> Vec<f32> {
+    let denom = size.saturating_sub(1).max(1) as f32;
+    (0..size)
+        .map(|i| {
+            let phase = 2.0 * PI * i as f32 / denom;
+            match kind {
+                WindowKind::Hann => 0.5 * (1.0 - phase.cos()),
+                WindowKind::Hamming => 0.54 - 0.46 * phase.cos(),
+                WindowKind::Blackman => 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos(),
+                WindowKind::Rectangular => 1.0,
+            }
+        })
+        .collect()
+}
+
+/// Frame feature behind `FftProcessor::beat_spectrum`'s self-similarity
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimilarityFeature {
+    /// Log power spectrum (dB per bin, DC excluded): timbre and rhythm
+    LogSpectrum,
+    /// 12 pitch-class energies (bins from 50 Hz, A = 440 Hz): harmony
+    Chroma,
+}
+
 /// High-performance FFT processor with pre-allocated buffers
 #[wasm_bindgen]
 pub struct FftProcessor {
     size: usize,
-    planner: FftPlanner<f32>,
-    window: Vec<f32>,
+    sample_rate: f32,
+    fft: Arc<dyn Fft<f32>>,
+    // Plan cache the processor was built from, for transforms of other sizes
+    planner: SharedPlanner,
+    window: Arc<[f32]>,
     buffer: Vec<Complex<f32>>,
     scratch: Vec<Complex<f32>>,
+    // Return complex_spectrum with the zero-frequency bin centered
+    shift_output: bool,
+    // Half-width in bins of the neighbourhood used by tonality_mask
+    tonality_neighborhood: usize,
+    // Height (dB) above the local noise floor that makes a tonal_noise_split peak
+    peak_prominence_db: f32,
+    // Peak-normalize frames before the FFT; the factor applied to the last frame
+    // and whether spectra are reported at that scale or mapped back to the input's
+    auto_scale: bool,
+    report_scaled: bool,
+    input_scale: f32,
+    // Spectrogram frames quieter than this (dB mean square) skip the FFT
+    skip_silence_db: f32,
+    // Frequency range (Hz) of the spectral_tilt line fit
+    tilt_min_hz: f32,
+    tilt_max_hz: f32,
+    // Total width (Hz) of the notch thd_plus_n removes around the fundamental
+    thd_notch_hz: f32,
+    // Log-spaced bands (50 Hz to Nyquist) reported by binaural_cues
+    cue_bands: usize,
+    // wow_flutter band split: wow below the split, flutter from the split to the maximum (Hz)
+    wow_split_hz: f32,
+    flutter_max_hz: f32,
+    // Frame feature compared by beat_spectrum
+    similarity_feature: SimilarityFeature,
+    // Log-spaced acoustic bands (50 Hz to Nyquist) of modulation_spectrum
+    modulation_bands: usize,
 }
 
 #[wasm_bindgen]
 impl FftProcessor {
     #[wasm_bindgen(constructor)]
     pub fn new(size: usize) -> FftProcessor {
-        // Pre-compute Hann window
-        let window: Vec<f32> = (0..size)
-            .map(|i| 0.5 * (1.0 - (2.0 * PI * i as f32 / (size - 1) as f32).cos()))
-            .collect();
+        FftProcessor::with_planner(size, &SharedPlanner::new())
+    }
+
+    /// Build a processor whose FFT plans come from a shared plan cache
+    #[wasm_bindgen]
+    pub fn with_planner(size: usize, planner: &SharedPlanner) -> FftProcessor {
+        // Pre-computed Hann window, shared with same-size processors
+        let window = cached_window(size, WindowKind::Hann);
         
-        let mut planner = FftPlanner::new();
-        let fft = planner.plan_fft_forward(size);
+        let fft = planner.plan_forward(size);
         let scratch = vec![Complex::new(0.0, 0.0); fft.get_inplace_scratch_len()];
         let buffer = vec![Complex::new(0.0, 0.0); size];
         
@@ -54,33 +195,60 @@ impl FftProcessor {
         
         FftProcessor {
             size,
-            planner,
+            sample_rate: 44100.0,
+            fft,
+            planner: planner.clone(),
             window,
             buffer,
             scratch,
+            shift_output: false,
+            tonality_neighborhood: 8,
+            peak_prominence_db: 10.0,
+            auto_scale: false,
+            report_scaled: false,
+            input_scale: 1.0,
+            skip_silence_db: f32::NEG_INFINITY,
+            tilt_min_hz: 100.0,
+            tilt_max_hz: f32::INFINITY,
+            thd_notch_hz: 50.0,
+            cue_bands: 8,
+            wow_split_hz: 4.0,
+            flutter_max_hz: 200.0,
+            similarity_feature: SimilarityFeature::LogSpectrum,
+            modulation_bands: 16,
         }
     }
 
-    /// Compute power spectrum - OPTIMIZED with pre-allocated buffers
-    #[wasm_bindgen]
-    pub fn power_spectrum(&mut self, samples: &[f32]) -> Vec<f32> {
-        let fft = self.planner.plan_fft_forward(self.size);
-        
+    /// Windowed in-place FFT of `samples` into the pre-allocated buffer
+    fn transform(&mut self, samples: &[f32]) {
         // Apply window directly into pre-allocated buffer
         let len = samples.len().min(self.size);
-        for i in 0..len {
-            self.buffer[i] = Complex::new(samples[i] * self.window[i], 0.0);
+        self.input_scale = if self.auto_scale {
+            let peak = samples[..len].iter().fold(0.0f32, |acc, &s| acc.max(s.abs()));
+            if peak > 0.0 { 1.0 / peak } else { 1.0 }
+        } else {
+            1.0
+        };
+        let scale = self.input_scale;
+        for ((b, &s), &w) in self.buffer.iter_mut().zip(&samples[..len]).zip(self.window.iter()) {
+            *b = Complex::new(s * scale * w, 0.0);
         }
         for i in len..self.size {
             self.buffer[i] = Complex::new(0.0, 0.0);
         }
         
         // In-place FFT
-        fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+        self.fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+    }
+
+    /// Compute power spectrum - OPTIMIZED with pre-allocated buffers
+    #[wasm_bindgen]
+    pub fn power_spectrum(&mut self, samples: &[f32]) -> Vec<f32> {
+        self.transform(samples);
         
         // Compute power spectrum
         let n_bins = self.size / 2 + 1;
-        let scale = 1.0 / self.size as f32;
+        let scale = self.output_power_gain() / self.size as f32;
         self.buffer.iter()
             .take(n_bins)
             .map(|c| (c.re * c.re + c.im * c.im) * scale)
@@ -94,431 +262,7912 @@ impl FftProcessor {
             .map(|&p| 10.0 * (p + 1e-10).log10())
             .collect()
     }
-}
 
-/// Voice Activity Detection - OPTIMIZED
-#[wasm_bindgen]
-pub struct VoiceActivityDetector {
-    frame_size: usize,
-    hop_size: usize,
-    energy_threshold: f32,
-    zcr_threshold: f32,
-    hangover_frames: usize,
-}
-
-#[wasm_bindgen]
-impl VoiceActivityDetector {
-    #[wasm_bindgen(constructor)]
-    pub fn new(frame_size: usize, hop_size: usize) -> VoiceActivityDetector {
-        console_log!("🦀 [Rust DSP] VAD initialized: frame={}, hop={}", frame_size, hop_size);
+    /// Alloc-free `power_spectrum`: writes up to `size / 2 + 1` bins into `out`
+    #[wasm_bindgen]
+    pub fn power_spectrum_into(&mut self, samples: &[f32], out: &mut [f32]) {
+        self.transform(samples);
         
-        VoiceActivityDetector {
-            frame_size,
-            hop_size,
-            energy_threshold: -40.0,
-            zcr_threshold: 0.1,
-            hangover_frames: 5,
+        let scale = self.output_power_gain() / self.size as f32;
+        for (o, c) in out.iter_mut().zip(self.buffer.iter().take(self.size / 2 + 1)) {
+            *o = (c.re * c.re + c.im * c.im) * scale;
         }
     }
 
+    /// Alloc-free `magnitude_db`: writes up to `size / 2 + 1` bins into `out`
     #[wasm_bindgen]
-    pub fn set_thresholds(&mut self, energy_db: f32, zcr: f32) {
-        self.energy_threshold = energy_db;
-        self.zcr_threshold = zcr;
+    pub fn magnitude_db_into(&mut self, samples: &[f32], out: &mut [f32]) {
+        self.power_spectrum_into(samples, out);
+        for o in out.iter_mut().take(self.size / 2 + 1) {
+            *o = 10.0 * (*o + 1e-10).log10();
+        }
     }
 
+    /// Power spectrogram, frame-major: `num_frames × (size / 2 + 1)` values.
+    /// `window` applies to this call only (the processor's own window is restored).
+    /// With `center`, the signal is zero-padded by `size / 2` on both sides so frame
+    /// t is centred on sample `t * hop_size`, giving `1 + len / hop_size` frames
+    /// (librosa's convention). Otherwise frame t starts at `t * hop_size`, giving
+    /// `1 + (len - size) / hop_size` frames.
     #[wasm_bindgen]
-    pub fn detect(&self, samples: &[f32]) -> Vec<u8> {
-        let num_frames = (samples.len().saturating_sub(self.frame_size)) / self.hop_size + 1;
-        let mut vad = Vec::with_capacity(num_frames);
-        let mut hangover_counter = 0;
+    pub fn spectrogram_ex(&mut self, samples: &[f32], hop_size: usize, window: WindowKind, center: bool) -> Vec<f32> {
+        let hop = hop_size.max(1);
+        let n_bins = self.size / 2 + 1;
         
-        for i in 0..num_frames {
-            let start = i * self.hop_size;
-            let end = (start + self.frame_size).min(samples.len());
-            let frame = &samples[start..end];
-            
-            // Vectorized energy computation
-            let energy: f32 = frame.iter().map(|&s| s * s).sum();
-            let energy_db = 10.0 * (energy / frame.len() as f32 + 1e-10).log10();
-            
-            // Vectorized ZCR
-            let zcr = frame.windows(2)
-                .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
-                .count() as f32 / frame.len() as f32;
-            
-            let is_speech = energy_db > self.energy_threshold && zcr < self.zcr_threshold;
-            
-            if is_speech {
-                hangover_counter = self.hangover_frames;
-            }
-            
-            if hangover_counter > 0 {
-                vad.push(1);
-                hangover_counter -= 1;
-            } else {
-                vad.push(0);
-            }
-        }
+        let num_frames = if center {
+            samples.len() / hop + 1
+        } else {
+            frame_count(samples.len(), self.size, hop)
+        };
         
-        vad
+        let mut output = Vec::with_capacity(num_frames * n_bins);
+        let _ = self.spectrogram_with(samples, hop_size, window, center, |_, row| {
+            output.extend_from_slice(row);
+            Ok::<(), ()>(())
+        });
+        output
     }
 
+    /// Streaming `spectrogram_ex`: calls `callback(frame_index, row)` per frame, where
+    /// `row` is a `Float32Array` view of the `size / 2 + 1` power bins into WASM memory,
+    /// reused between frames. The view is valid only during the callback, which must
+    /// copy it to keep it and must not call back into the module (that can grow memory
+    /// and detach the view). Stops at and rethrows the first callback exception.
     #[wasm_bindgen]
-    pub fn get_segments(&self, samples: &[f32]) -> Vec<u32> {
-        let vad = self.detect(samples);
-        let mut segments = Vec::new();
-        let mut in_segment = false;
-        let mut start = 0u32;
+    pub fn spectrogram_streaming(
+        &mut self,
+        samples: &[f32],
+        hop_size: usize,
+        window: WindowKind,
+        center: bool,
+        callback: &js_sys::Function,
+    ) -> Result<(), JsValue> {
+        self.spectrogram_with(samples, hop_size, window, center, |t, row| call_with_view(callback, t, row))
+    }
+
+    /// Per-bin tonal probability (0..1), `size / 2 + 1` bins.
+    /// For each bin k over the neighbourhood k ± `tonality_neighborhood`:
+    /// `(1 - flatness) * P[k] / max P`, where flatness is the geometric over the
+    /// arithmetic mean power. A spectral peak standing out of its surroundings
+    /// scores near 1; noise bins, and the skirts beside a peak, score low.
+    #[wasm_bindgen]
+    pub fn tonality_mask(&mut self, samples: &[f32]) -> Vec<f32> {
+        let power = self.power_spectrum(samples);
+        let n_bins = power.len();
+        let half = self.tonality_neighborhood;
         
-        for (i, &v) in vad.iter().enumerate() {
-            if v == 1 && !in_segment {
-                start = (i * self.hop_size) as u32;
-                in_segment = true;
-            } else if v == 0 && in_segment {
-                segments.push(start);
-                segments.push((i * self.hop_size) as u32);
-                in_segment = false;
-            }
+        (0..n_bins)
+            .map(|k| {
+                let local = &power[k.saturating_sub(half)..(k + half + 1).min(n_bins)];
+                let count = local.len() as f32;
+                let arith = local.iter().sum::<f32>() / count + 1e-20;
+                let geo = (local.iter().map(|&p| (p + 1e-20).ln()).sum::<f32>() / count).exp();
+                let peak = local.iter().fold(0.0f32, |acc, &p| acc.max(p)) + 1e-20;
+                ((1.0 - geo / arith) * (power[k] + 1e-20) / peak).clamp(0.0, 1.0)
+            })
+            .collect()
+    }
+
+    /// Half-width in bins of the `tonality_mask` neighbourhood (default 8)
+    #[wasm_bindgen]
+    pub fn set_tonality_neighborhood(&mut self, bins: usize) {
+        self.tonality_neighborhood = bins.max(1);
+    }
+
+    /// How far (dB) a local maximum must rise above the local noise floor to count
+    /// as a sinusoid in `tonal_noise_split` (default 10)
+    #[wasm_bindgen]
+    pub fn set_peak_prominence_db(&mut self, db: f32) {
+        self.peak_prominence_db = db.max(0.0);
+    }
+
+    /// Sines-plus-noise energy split `[tonal_ratio, noise_ratio]` of one frame,
+    /// summing to 1. The noise floor at each bin is the median power over
+    /// k ± `tonality_neighborhood`; local maxima at least the peak prominence above
+    /// it are modelled as sinusoids, each owning its Hann main lobe (±2 bins) minus
+    /// the floor there. The tonal energy is that modelled peak energy and the noise
+    /// energy the residual, the spectrum minus the modelled peaks. Silence gives
+    /// `[NaN, NaN]`.
+    #[wasm_bindgen]
+    pub fn tonal_noise_split(&mut self, samples: &[f32]) -> Vec<f32> {
+        let power = self.power_spectrum(samples);
+        let n_bins = power.len();
+        let total: f64 = power.iter().map(|&p| p as f64).sum();
+        if total <= 1e-20 {
+            return vec![f32::NAN, f32::NAN];
         }
         
-        if in_segment {
-            segments.push(start);
-            segments.push((vad.len() * self.hop_size) as u32);
-        }
+        // Step 1: running-median noise floor
+        let half = self.tonality_neighborhood;
+        let floor: Vec<f32> = (0..n_bins)
+            .map(|k| {
+                let mut local = power[k.saturating_sub(half)..(k + half + 1).min(n_bins)].to_vec();
+                let mid = local.len() / 2;
+                *local.select_nth_unstable_by(mid, |a, b| a.total_cmp(b)).1
+            })
+            .collect();
         
-        segments
+        // Step 2: prominent peaks claim their main lobes above the floor
+        let ratio = db_to_linear(self.peak_prominence_db).powi(2);
+        let mut claimed = vec![false; n_bins];
+        let mut tonal = 0.0f64;
+        for k in 1..n_bins.saturating_sub(1) {
+            if power[k] > power[k - 1] && power[k] >= power[k + 1] && power[k] >= ratio * floor[k] {
+                for j in k.saturating_sub(2)..(k + 3).min(n_bins) {
+                    if !claimed[j] {
+                        claimed[j] = true;
+                        tonal += (power[j] - floor[j]).max(0.0) as f64;
+                    }
+                }
+            }
+        }
+        let tonal_ratio = (tonal / total) as f32;
+        vec![tonal_ratio, 1.0 - tonal_ratio]
     }
-}
 
-/// ULTRA-OPTIMIZED YIN Pitch Detection Algorithm
-/// Uses FFT-based autocorrelation: O(n log n) instead of O(n²)
-#[wasm_bindgen]
-pub struct PitchDetector {
-    sample_rate: f32,
-    frame_size: usize,
-    threshold: f32,
-    // Pre-allocated buffers for FFT-based autocorrelation
-    fft_size: usize,
-    planner: FftPlanner<f32>,
-    buffer_a: Vec<Complex<f32>>,
-    buffer_b: Vec<Complex<f32>>,
-    scratch: Vec<Complex<f32>>,
-    diff: Vec<f32>,
-    cmnd: Vec<f32>,
-}
+    /// Peak-normalize each frame to ±1 before the FFT, so loud or long inputs keep
+    /// f32 headroom in squared-magnitude sums. The factor used is `input_scale()`.
+    #[wasm_bindgen]
+    pub fn set_auto_scale(&mut self, enabled: bool) {
+        self.auto_scale = enabled;
+    }
 
-#[wasm_bindgen]
-impl PitchDetector {
-    #[wasm_bindgen(constructor)]
-    pub fn new(sample_rate: f32, frame_size: usize) -> PitchDetector {
-        // FFT size must be power of 2 and at least 2x frame size for autocorrelation
-        let fft_size = (frame_size * 2).next_power_of_two();
-        
-        let mut planner = FftPlanner::new();
-        let fft = planner.plan_fft_forward(fft_size);
-        let scratch_len = fft.get_inplace_scratch_len();
-        
-        console_log!("🦀 [Rust DSP] YIN Pitch Detector: sr={}, frame={} (FFT-ACCELERATED)", sample_rate, frame_size);
-        
-        PitchDetector {
-            sample_rate,
-            frame_size,
-            threshold: 0.1,
-            fft_size,
-            planner,
-            buffer_a: vec![Complex::new(0.0, 0.0); fft_size],
-            buffer_b: vec![Complex::new(0.0, 0.0); fft_size],
-            scratch: vec![Complex::new(0.0, 0.0); scratch_len],
-            diff: vec![0.0; frame_size / 2],
-            cmnd: vec![0.0; frame_size / 2],
+    /// Spectrogram fast path: frames whose mean-square level is below `threshold_db`
+    /// skip the FFT and emit an all-zero power row. Louder frames are unchanged.
+    /// Pass `-Infinity` to disable (the default).
+    #[wasm_bindgen]
+    pub fn set_skip_silence(&mut self, threshold_db: f32) {
+        self.skip_silence_db = threshold_db;
+    }
+
+    /// Frequency range of the `spectral_tilt` fit (default 100 Hz up to Nyquist)
+    #[wasm_bindgen]
+    pub fn set_tilt_range(&mut self, min_hz: f32, max_hz: f32) {
+        self.tilt_min_hz = min_hz.max(0.0);
+        self.tilt_max_hz = max_hz;
+    }
+
+    /// Total width in Hz of the `thd_plus_n` fundamental notch (default 50)
+    #[wasm_bindgen]
+    pub fn set_thd_notch_width(&mut self, hz: f32) {
+        self.thd_notch_hz = hz.max(0.0);
+    }
+
+    /// Number of log-spaced bands between 50 Hz and Nyquist used by `binaural_cues` (default 8)
+    #[wasm_bindgen]
+    pub fn set_cue_bands(&mut self, num_bands: usize) {
+        self.cue_bands = num_bands.max(1);
+    }
+
+    /// Modulation bands of `wow_flutter`: wow below `split_hz` (default 4), flutter
+    /// from `split_hz` to `max_hz` (default 200)
+    #[wasm_bindgen]
+    pub fn set_wow_flutter_bands(&mut self, split_hz: f32, max_hz: f32) {
+        self.wow_split_hz = split_hz.max(0.01);
+        self.flutter_max_hz = max_hz.max(self.wow_split_hz * 1.01);
+    }
+
+    /// Frame feature used by `beat_spectrum` (default `LogSpectrum`)
+    #[wasm_bindgen]
+    pub fn set_similarity_feature(&mut self, feature: SimilarityFeature) {
+        self.similarity_feature = feature;
+    }
+
+    /// Number of log-spaced acoustic bands between 50 Hz and Nyquist used by
+    /// `modulation_spectrum` (default 16)
+    #[wasm_bindgen]
+    pub fn set_modulation_bands(&mut self, num_bands: usize) {
+        self.modulation_bands = num_bands.max(1);
+    }
+
+    /// With auto-scaling on, report spectra of the normalized frame (`true`) or
+    /// mapped back to the input's level (`false`, default)
+    #[wasm_bindgen]
+    pub fn set_report_scaled(&mut self, scaled: bool) {
+        self.report_scaled = scaled;
+    }
+
+    /// Amplitude factor applied to the last frame (1 when auto-scaling is off).
+    /// Scaled power divided by `input_scale()²` gives the input's power.
+    #[wasm_bindgen]
+    pub fn input_scale(&self) -> f32 {
+        self.input_scale
+    }
+
+    /// Power factor undoing the input scaling unless scaled output was requested
+    fn output_power_gain(&self) -> f32 {
+        if self.report_scaled {
+            1.0
+        } else {
+            1.0 / (self.input_scale * self.input_scale)
         }
     }
 
+    /// Analysis window applied before every transform (default Hann)
     #[wasm_bindgen]
-    pub fn set_threshold(&mut self, threshold: f32) {
-        self.threshold = threshold;
+    pub fn set_window(&mut self, kind: WindowKind) {
+        self.window = cached_window(self.size, kind);
     }
 
-    /// FFT-based autocorrelation - O(n log n) complexity
-    #[inline]
-    fn compute_autocorrelation(&mut self, samples: &[f32]) {
-        let n = samples.len().min(self.frame_size);
+    /// Center the zero-frequency bin in `complex_spectrum` output (fftshift)
+    #[wasm_bindgen]
+    pub fn set_fftshift(&mut self, enabled: bool) {
+        self.shift_output = enabled;
+    }
+
+    /// Full complex spectrum (all `size` bins) as interleaved `[re, im, re, im, ...]`
+    #[wasm_bindgen]
+    pub fn complex_spectrum(&mut self, samples: &[f32]) -> Vec<f32> {
+        self.transform(samples);
         
-        // Zero-pad and copy samples to buffer_a
-        for i in 0..n {
-            self.buffer_a[i] = Complex::new(samples[i], 0.0);
+        if self.shift_output {
+            self.buffer.rotate_right(self.size / 2);
         }
-        for i in n..self.fft_size {
-            self.buffer_a[i] = Complex::new(0.0, 0.0);
+        let gain = self.output_power_gain().sqrt();
+        self.buffer.iter().flat_map(|c| [c.re * gain, c.im * gain]).collect()
+    }
+
+    /// Sample rate used by the frequency-calibrated analyses (default 44100 Hz)
+    #[wasm_bindgen]
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Fingerprint of the configuration (size, rate, window and analysis options) for
+    /// keying result caches on `(config_hash, input_hash)`; per-frame state is excluded
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("FftProcessor")
+            .usize(self.size)
+            .f32(self.sample_rate)
+            .f32s(&self.window)
+            .bool(self.shift_output)
+            .usize(self.tonality_neighborhood)
+            .f32(self.peak_prominence_db)
+            .bool(self.auto_scale)
+            .bool(self.report_scaled)
+            .f32(self.skip_silence_db)
+            .f32(self.tilt_min_hz)
+            .f32(self.tilt_max_hz)
+            .f32(self.thd_notch_hz)
+            .usize(self.cue_bands)
+            .f32(self.wow_split_hz)
+            .f32(self.flutter_max_hz)
+            .usize(self.similarity_feature as usize)
+            .usize(self.modulation_bands)
+            .finish()
+    }
+
+    /// Amplitude envelope for AM analysis: the analytic-signal magnitude (FFT Hilbert
+    /// transform over the whole input), then a zero-phase Butterworth lowpass at
+    /// `smoothing_hz` (0 disables smoothing). One value per input sample.
+    #[wasm_bindgen]
+    pub fn am_envelope(&mut self, samples: &[f32], smoothing_hz: f32) -> Vec<f32> {
+        let n = samples.len();
+        if n == 0 {
+            return Vec::new();
         }
         
-        // Forward FFT
-        let fft_forward = self.planner.plan_fft_forward(self.fft_size);
-        fft_forward.process_with_scratch(&mut self.buffer_a, &mut self.scratch);
-        
-        // Compute power spectrum (autocorrelation in frequency domain)
-        for i in 0..self.fft_size {
-            let re = self.buffer_a[i].re;
-            let im = self.buffer_a[i].im;
-            self.buffer_b[i] = Complex::new(re * re + im * im, 0.0);
+        // Step 1: one-sided spectrum (positive bins doubled, negative bins zeroed)
+        let mut spectrum: Vec<Complex<f32>> = samples.iter().map(|&s| Complex::new(s, 0.0)).collect();
+        self.planner.plan_forward(n).process(&mut spectrum);
+        for (k, c) in spectrum.iter_mut().enumerate().skip(1) {
+            if 2 * k < n {
+                *c *= 2.0;
+            } else if 2 * k > n {
+                *c = Complex::new(0.0, 0.0);
+            }
         }
         
-        // Inverse FFT to get autocorrelation
-        let fft_inverse = self.planner.plan_fft_inverse(self.fft_size);
-        fft_inverse.process_with_scratch(&mut self.buffer_b, &mut self.scratch);
+        // Step 2: analytic-signal magnitude
+        self.planner.plan_inverse(n).process(&mut spectrum);
+        let envelope: Vec<f32> = spectrum.iter().map(|c| c.norm() / n as f32).collect();
         
-        // Normalize and compute difference function
-        let scale = 1.0 / self.fft_size as f32;
-        let r0 = self.buffer_b[0].re * scale; // Autocorrelation at lag 0
+        // Step 3: recover the modulation by lowpassing away the carrier residue
+        if smoothing_hz > 0.0 {
+            let cutoff = smoothing_hz.min(0.45 * self.sample_rate);
+            Biquad::lowpass(self.sample_rate, cutoff, std::f32::consts::FRAC_1_SQRT_2).filtfilt(&envelope)
+        } else {
+            envelope
+        }
+    }
+
+    /// Log-spectral distance in dB: per frame, the RMS over bins of the difference
+    /// between the two power spectra in dB (floored at -100 dB), averaged over frames.
+    /// Both signals use this processor's window with `size`-sample frames at a
+    /// `size / 2` hop over their common length.
+    #[wasm_bindgen]
+    pub fn log_spectral_distance(&mut self, a: &[f32], b: &[f32]) -> f32 {
+        let len = a.len().min(b.len());
+        if len == 0 {
+            return 0.0;
+        }
+        let n_bins = self.size / 2 + 1;
+        let hop = (self.size / 2).max(1);
+        let num_frames = frame_count(len, self.size, hop);
+        let mut spectrum_a = vec![0.0f32; n_bins];
+        let mut spectrum_b = vec![0.0f32; n_bins];
         
-        let tau_max = n / 2;
-        for tau in 0..tau_max {
-            let r_tau = self.buffer_b[tau].re * scale;
-            // d(tau) = r(0) + r(0) - 2*r(tau) = 2*(r(0) - r(tau))
-            self.diff[tau] = 2.0 * (r0 - r_tau);
+        let mut total = 0.0f32;
+        for i in 0..num_frames {
+            self.magnitude_db_into(frame_slice(&a[..len], i, self.size, hop), &mut spectrum_a);
+            self.magnitude_db_into(frame_slice(&b[..len], i, self.size, hop), &mut spectrum_b);
+            let mean_square = spectrum_a.iter()
+                .zip(&spectrum_b)
+                .map(|(&x, &y)| (x - y) * (x - y))
+                .sum::<f32>() / n_bins as f32;
+            total += mean_square.sqrt();
         }
+        total / num_frames as f32
     }
 
-    /// Detect pitch using FFT-accelerated YIN algorithm
+    /// Matching-EQ gains in dB for `num_bands` log-spaced bands, band i spanning
+    /// `20·(nyquist / 20)^(i / num_bands)` to `20·(nyquist / 20)^((i + 1) / num_bands)` Hz:
+    /// `10·log10(reference / source)` of each band's Welch power (50 % overlap).
+    /// Band powers come from the whole inputs, so the lengths may differ. Bands
+    /// without bins or with no power in either signal get 0 dB.
     #[wasm_bindgen]
-    pub fn detect(&mut self, samples: &[f32]) -> Vec<f32> {
-        let n = samples.len().min(self.frame_size);
-        let tau_max = n / 2;
+    pub fn match_eq(&mut self, source: &[f32], reference: &[f32], num_bands: usize) -> Vec<f32> {
+        let num_bands = num_bands.max(1);
+        let source_psd = self.welch_psd(source, self.size / 2);
+        let reference_psd = self.welch_psd(reference, self.size / 2);
+        let bin_hz = self.sample_rate / self.size as f32;
+        let ratio = (0.5 * self.sample_rate / 20.0).max(1.0);
+        let edge = |i: usize| 20.0 * ratio.powf(i as f32 / num_bands as f32);
         
-        // Step 1: FFT-based difference function (O(n log n))
-        self.compute_autocorrelation(samples);
+        (0..num_bands)
+            .map(|i| {
+                let first = ((edge(i) / bin_hz).ceil() as usize).max(1);
+                let last = ((edge(i + 1) / bin_hz).ceil() as usize).min(source_psd.len());
+                let band = |psd: &[f32]| psd[first.min(last)..last].iter().map(|&p| p as f64).sum::<f64>();
+                let (src, reference) = (band(&source_psd), band(&reference_psd));
+                if src > 0.0 && reference > 0.0 { (10.0 * (reference / src).log10()) as f32 } else { 0.0 }
+            })
+            .collect()
+    }
+
+    /// THD+N in percent: `100·sqrt(residual / total)` over a Blackman-windowed FFT
+    /// of the whole input at `sample_rate`. The fundamental is the strongest bin
+    /// within 5 % of `fundamental_hz`, refined by a parabola on its log power; the
+    /// notch (`set_thd_notch_width`, kept under half the fundamental and at least
+    /// the 3-bin main lobe either side) is centred on that estimate. Bins below
+    /// 10 Hz are left out of both sums so DC offset does not count as distortion.
+    #[wasm_bindgen]
+    pub fn thd_plus_n(&mut self, samples: &[f32], fundamental_hz: f32, sample_rate: f32) -> f32 {
+        let n = samples.len();
+        if n < 16 || fundamental_hz <= 0.0 || fundamental_hz >= 0.5 * sample_rate {
+            return f32::NAN;
+        }
+        let size = n.next_power_of_two();
+        let fft = self.planner.plan_forward(size);
+        let window = cached_window(n, WindowKind::Blackman);
+        let mut spectrum: Vec<Complex<f32>> = (0..size)
+            .map(|i| Complex::new(if i < n { samples[i] * window[i] } else { 0.0 }, 0.0))
+            .collect();
+        fft.process(&mut spectrum);
+        let power: Vec<f64> = spectrum[..size / 2 + 1].iter().map(|c| c.norm_sqr() as f64).collect();
+        let bin_hz = sample_rate / size as f32;
         
-        // Step 2: Cumulative mean normalized difference
-        self.cmnd[0] = 1.0;
-        let mut running_sum = 0.0f32;
+        // Step 1: interpolated fundamental near the nominal frequency
+        let reach = (0.05 * fundamental_hz / bin_hz).max(3.0);
+        let nominal = fundamental_hz / bin_hz;
+        let lo = ((nominal - reach).floor().max(1.0) as usize).min(power.len() - 2);
+        let hi = ((nominal + reach).ceil() as usize).clamp(lo + 1, power.len() - 2);
+        let peak = (lo..=hi).max_by(|&a, &b| power[a].total_cmp(&power[b])).unwrap_or(lo);
+        let [a, b, c] = [power[peak - 1], power[peak], power[peak + 1]].map(|p| (p + 1e-30).ln() as f32);
+        let center = peak as f32 + parabolic_peak(a, b, c).0;
         
-        for tau in 1..tau_max {
-            running_sum += self.diff[tau];
-            self.cmnd[tau] = if running_sum > 1e-10 {
-                self.diff[tau] * tau as f32 / running_sum
-            } else {
-                1.0
-            };
+        // Step 2: notch the fundamental and compare what is left to the total
+        let half_width = (0.5 * self.thd_notch_hz / bin_hz).min(0.5 * center).max(3.0);
+        let first = (10.0 / bin_hz).ceil() as usize;
+        let (mut total, mut residual) = (0.0f64, 0.0f64);
+        for (k, &p) in power.iter().enumerate().skip(first) {
+            total += p;
+            if (k as f32 - center).abs() > half_width {
+                residual += p;
+            }
+        }
+        if total <= 0.0 {
+            return f32::NAN;
+        }
+        (100.0 * (residual / total).sqrt()) as f32
+    }
+
+    /// Impulse response from an exponential-sweep measurement (Farina): `recorded`
+    /// is convolved by FFT with the inverse filter, the time-reversed sweep with a
+    /// `-6 dB/octave` envelope `e^(-k·τ)`. The sweep rate `k` (log-frequency per
+    /// sample) is a least-squares fit to log instantaneous frequency from the
+    /// sweep's interpolated zero crossings (periods under 8 samples are skipped).
+    /// The result is scaled so the sweep deconvolves to unit gain in its band, and
+    /// is the causal part only: `recorded.len()` samples starting at the linear
+    /// IR's lag 0. Harmonic distortion lands at negative lags and is dropped.
+    #[wasm_bindgen]
+    pub fn deconvolve_sweep(&mut self, recorded: &[f32], sweep: &[f32]) -> Vec<f32> {
+        let (r, s) = (recorded.len(), sweep.len());
+        if r == 0 || s < 2 {
+            return vec![0.0; r];
         }
         
-        // Step 3: Absolute threshold with parabolic interpolation
-        let min_period = (self.sample_rate / 500.0) as usize;
-        let max_period = (self.sample_rate / 50.0) as usize;
+        // Step 1: sweep rate from the instantaneous frequency at each half period
+        let crossings: Vec<f64> = sweep.windows(2)
+            .enumerate()
+            .filter(|(_, w)| (w[0] < 0.0) != (w[1] < 0.0) && w[0] != w[1])
+            .map(|(i, w)| i as f64 + (w[0] / (w[0] - w[1])) as f64)
+            .collect();
+        let points: Vec<(f64, f64)> = crossings.windows(2)
+            .filter(|c| c[1] - c[0] >= 4.0)
+            .map(|c| (0.5 * (c[0] + c[1]), (0.5 / (c[1] - c[0])).ln()))
+            .collect();
+        if points.len() < 2 {
+            return vec![0.0; r];
+        }
+        let (rate, log_start) = line_fit(&points);
         
-        for tau in min_period..max_period.min(tau_max) {
-            if self.cmnd[tau] < self.threshold {
-                // Parabolic interpolation for sub-sample accuracy
-                if tau > 0 && tau < tau_max - 1 {
-                    let s0 = self.cmnd[tau - 1];
-                    let s1 = self.cmnd[tau];
-                    let s2 = self.cmnd[tau + 1];
-                    
-                    let denom = s0 - 2.0 * s1 + s2;
-                    if denom.abs() > 1e-10 {
-                        let adjustment = (s0 - s2) / (2.0 * denom);
-                        let refined_tau = tau as f32 + adjustment.clamp(-0.5, 0.5);
-                        let frequency = self.sample_rate / refined_tau;
-                        let confidence = 1.0 - s1;
-                        return vec![frequency, confidence];
-                    }
-                }
-                
-                let frequency = self.sample_rate / tau as f32;
-                let confidence = 1.0 - self.cmnd[tau];
-                return vec![frequency, confidence];
-            }
+        // Step 2: inverse filter and FFT convolution
+        let size = (r + s - 1).next_power_of_two();
+        let forward = self.planner.plan_forward(size);
+        let to_spectrum = |signal: &mut dyn Iterator<Item = f32>| -> Vec<Complex<f32>> {
+            let mut spectrum: Vec<Complex<f32>> = signal.map(|x| Complex::new(x, 0.0)).collect();
+            spectrum.resize(size, Complex::new(0.0, 0.0));
+            forward.process(&mut spectrum);
+            spectrum
+        };
+        let inverse = to_spectrum(&mut (0..s).map(|tau| sweep[s - 1 - tau] * (-rate * tau as f64).exp() as f32));
+        let sweep_spectrum = to_spectrum(&mut sweep.iter().copied());
+        let mut output = to_spectrum(&mut recorded.iter().copied());
+        for (o, &h) in output.iter_mut().zip(&inverse) {
+            *o *= h;
         }
         
-        vec![0.0, 0.0]  // Unvoiced
+        // Step 3: unit gain over the middle of the swept band
+        let start_freq = log_start.exp();
+        let end_freq = (log_start + rate * (s as f64 - 1.0)).exp();
+        let (lo, hi) = (start_freq.min(end_freq), start_freq.max(end_freq).min(0.5));
+        let first = ((2.0 * lo * size as f64).ceil() as usize).max(1);
+        let last = ((0.5 * hi * size as f64).floor() as usize).max(first + 1).min(size / 2);
+        let gain = (first..last).map(|k| (sweep_spectrum[k] * inverse[k]).norm()).sum::<f32>() / (last - first) as f32;
+        
+        self.planner.plan_inverse(size).process(&mut output);
+        let scale = 1.0 / (size as f32 * gain.max(1e-20));
+        output[s - 1..s - 1 + r].iter().map(|c| c.re * scale).collect()
     }
 
-    /// Batch pitch detection - OPTIMIZED
+    /// Comb-filter notches as `[notch_spacing_hz, strength]`; a spacing `Δf` points
+    /// to a copy delayed by `sample_rate / Δf` samples. The Welch spectrum in dB
+    /// (floored 60 dB under its peak) has a `n_bins / 3` moving average removed,
+    /// and the strongest local maximum of its normalized autocorrelation over lags
+    /// 3..`n_bins / 6` bins (refined by a parabola) gives the spacing, with the
+    /// correlation as strength. Folding the spectrum at that period must show
+    /// notches deeper than the peaks are high, so harmonic spectra (evenly spaced
+    /// peaks) report strength 0. Silence gives `[0, 0]`.
     #[wasm_bindgen]
-    pub fn detect_batch(&mut self, samples: &[f32], hop_size: usize) -> Vec<f32> {
-        let num_frames = (samples.len().saturating_sub(self.frame_size)) / hop_size + 1;
-        let mut results = Vec::with_capacity(num_frames * 2);
+    pub fn detect_comb_filtering(&mut self, samples: &[f32]) -> Vec<f32> {
+        let psd = self.welch_psd(samples, self.size / 2);
+        let n_bins = psd.len();
+        let loudest = psd.iter().fold(0.0f32, |acc, &p| acc.max(p));
+        if loudest <= 0.0 || n_bins < 36 {
+            return vec![0.0, 0.0];
+        }
         
-        for i in 0..num_frames {
-            let start = i * hop_size;
-            let end = (start + self.frame_size).min(samples.len());
-            let result = self.detect(&samples[start..end]);
-            results.extend_from_slice(&result);
+        // Step 1: log spectrum with its slow trend removed
+        let floor = loudest * 1e-6;
+        let db: Vec<f32> = psd.iter().map(|&p| 10.0 * p.max(floor).log10()).collect();
+        let half = n_bins / 6;
+        let ripple: Vec<f32> = (0..n_bins)
+            .map(|k| {
+                let span = &db[k.saturating_sub(half)..(k + half + 1).min(n_bins)];
+                db[k] - span.iter().sum::<f32>() / span.len() as f32
+            })
+            .collect();
+        
+        // Step 2: autocorrelation peak over candidate notch spacings
+        let energy: f32 = ripple.iter().map(|r| r * r).sum();
+        if energy <= 1e-12 {
+            return vec![0.0, 0.0];
         }
+        let acf: Vec<f32> = (0..=half + 1)
+            .map(|lag| ripple.iter().zip(&ripple[lag..]).map(|(a, b)| a * b).sum::<f32>() / energy)
+            .collect();
+        let best = (3..=half)
+            .filter(|&lag| acf[lag] > acf[lag - 1] && acf[lag] >= acf[lag + 1])
+            .max_by(|&a, &b| acf[a].total_cmp(&acf[b]));
+        let Some(lag) = best else {
+            return vec![0.0, 0.0];
+        };
         
-        results
+        // Step 3: sub-bin period, then the notch check on the spectrum folded at it
+        let period = lag as f32 + parabolic_peak(acf[lag - 1], acf[lag], acf[lag + 1]).0;
+        let mut profile = vec![(0.0f32, 0usize); lag];
+        for (k, &r) in ripple.iter().enumerate() {
+            let phase = (((k as f32 / period).fract() * lag as f32) as usize).min(lag - 1);
+            profile[phase].0 += r;
+            profile[phase].1 += 1;
+        }
+        let folded: Vec<f32> = profile.iter().map(|&(sum, count)| sum / count.max(1) as f32).collect();
+        let mean = folded.iter().sum::<f32>() / lag as f32;
+        let deepest = folded.iter().fold(f32::INFINITY, |acc, &v| acc.min(v));
+        let highest = folded.iter().fold(f32::NEG_INFINITY, |acc, &v| acc.max(v));
+        let strength = if mean - deepest > highest - mean { acf[lag].max(0.0) } else { 0.0 };
+        vec![period * self.sample_rate / self.size as f32, strength]
     }
-}
-
-/// Formant Analyzer using LPC - OPTIMIZED
-#[wasm_bindgen]
-pub struct FormantAnalyzer {
-    sample_rate: f32,
-    lpc_order: usize,
-    // Pre-allocated buffers
-    autocorr: Vec<f32>,
-    lpc_coeffs: Vec<f32>,
-    response_re: Vec<f32>,
-    response_im: Vec<f32>,
-}
 
-#[wasm_bindgen]
-impl FormantAnalyzer {
-    #[wasm_bindgen(constructor)]
-    pub fn new(sample_rate: f32, lpc_order: usize) -> FormantAnalyzer {
-        console_log!("🦀 [Rust DSP] Formant Analyzer: sr={}, order={} (OPTIMIZED)", sample_rate, lpc_order);
+    /// Phase-vocoder frequency estimate (Hz) of the partial near `approx_hz`, from two
+    /// frames where `frame_b` starts `hop` samples after `frame_a`. The phase advance
+    /// at the bin nearest `approx_hz` minus that bin's expected advance `2π·k·hop/size`,
+    /// wrapped to ±π, gives the offset from the bin centre. Unambiguous while the
+    /// partial is within `sample_rate / (2·hop)` of the bin centre.
+    #[wasm_bindgen]
+    pub fn refine_pitch_phase(&mut self, frame_a: &[f32], frame_b: &[f32], approx_hz: f32, hop: usize) -> f32 {
+        let bin_hz = self.sample_rate / self.size as f32;
+        let k = ((approx_hz / bin_hz).round() as usize).min(self.size / 2);
+        let hop = hop.max(1) as f32;
         
-        FormantAnalyzer {
-            sample_rate,
-            lpc_order,
-            autocorr: vec![0.0; lpc_order + 1],
-            lpc_coeffs: vec![0.0; lpc_order],
-            response_re: vec![0.0; 512],
-            response_im: vec![0.0; 512],
+        // Step 1: phase of bin k in both frames
+        self.transform(frame_a);
+        let phase_a = self.buffer[k].arg();
+        self.transform(frame_b);
+        let phase_b = self.buffer[k].arg();
+        
+        // Step 2: deviation from the expected advance, wrapped to [-π, π)
+        let expected = 2.0 * PI * k as f32 * hop / self.size as f32;
+        let deviation = (phase_b - phase_a - expected + PI).rem_euclid(2.0 * PI) - PI;
+        
+        (k as f32 + deviation * self.size as f32 / (2.0 * PI * hop)) * bin_hz
+    }
+
+    /// Wow and flutter of a test-tone recording as `[wow_percent, flutter_percent]`:
+    /// the RMS frequency deviation in each modulation band relative to the mean
+    /// frequency. The tone's instantaneous frequency is tracked at 1 kHz by the
+    /// phase advance of the bin nearest `reference_hz` between frames 1 ms apart
+    /// (as in `refine_pitch_phase`), then split zero-phase into the wow and flutter
+    /// bands (crossover at the split, 6 dB); the first and last 250 ms are skipped
+    /// as filter settling. Frames average flutter over `size` samples, so pick a
+    /// short FFT (e.g. 5 ms) when measuring fast flutter. Inputs shorter than
+    /// one second, or a non-positive reference, give `[NaN, NaN]`.
+    #[wasm_bindgen]
+    pub fn wow_flutter(&mut self, samples: &[f32], reference_hz: f32, sample_rate: f32) -> Vec<f32> {
+        let hop = ((sample_rate / 1000.0) as usize).max(1);
+        let rate = sample_rate / hop as f32;
+        if reference_hz.is_nan() || reference_hz <= 0.0 || (samples.len() as f32) < sample_rate
+            || samples.len() < self.size + 2 * hop {
+            return vec![f32::NAN, f32::NAN];
         }
+        let bin_hz = sample_rate / self.size as f32;
+        let k = ((reference_hz / bin_hz).round() as usize).clamp(1, self.size / 2);
+        
+        // Step 1: phase of the reference bin in every frame
+        let phases: Vec<f32> = (0..frame_count(samples.len(), self.size, hop))
+            .map(|i| {
+                self.transform(frame_slice(samples, i, self.size, hop));
+                self.buffer[k].arg()
+            })
+            .collect();
+        
+        // Step 2: instantaneous frequency from the wrapped phase advance
+        let expected = 2.0 * PI * k as f32 * hop as f32 / self.size as f32;
+        let frequency: Vec<f32> = phases.windows(2)
+            .map(|w| {
+                let deviation = (w[1] - w[0] - expected + PI).rem_euclid(2.0 * PI) - PI;
+                (k as f32 + deviation * self.size as f32 / (2.0 * PI * hop as f32)) * bin_hz
+            })
+            .collect();
+        let mean = frequency.iter().sum::<f32>() / frequency.len() as f32;
+        let deviation: Vec<f32> = frequency.iter().map(|f| f - mean).collect();
+        
+        // Step 3: zero-phase band split and RMS away from the edges
+        let q = std::f32::consts::FRAC_1_SQRT_2;
+        let flutter_max = self.flutter_max_hz.min(0.45 * rate);
+        let wow = Biquad::lowpass(rate, self.wow_split_hz, q).filtfilt(&deviation);
+        let flutter = Biquad::lowpass(rate, flutter_max, q)
+            .filtfilt(&Biquad::highpass(rate, self.wow_split_hz, q).filtfilt(&deviation));
+        let edge = ((0.25 * rate) as usize).min(deviation.len() / 4);
+        let rms = |x: &[f32]| {
+            let x = &x[edge..x.len() - edge];
+            (x.iter().map(|v| v * v).sum::<f32>() / x.len() as f32).sqrt()
+        };
+        vec![100.0 * rms(&wow) / mean, 100.0 * rms(&flutter) / mean]
     }
 
-    /// Levinson-Durbin with pre-allocated buffers
-    fn compute_lpc(&mut self, samples: &[f32]) {
+    /// Super-resolution frequencies (Hz, ascending) of `num_tones` sinusoids in a
+    /// frame by MUSIC: the forward-backward averaged autocorrelation matrix of
+    /// order M = min(len / 2, 64) is eigendecomposed, and the pseudospectrum
+    /// `1 / Σ |V(ω)|²` over the M - 2·num_tones noise eigenvectors (each real tone
+    /// spans two dimensions) peaks at the tones. Peaks are picked on a fine FFT
+    /// grid and refined by golden-section search, so tones closer than one FFT
+    /// bin of the frame are resolved. Fewer peaks than requested return only
+    /// those found; empty when `2·num_tones >= M`.
+    #[wasm_bindgen]
+    pub fn estimate_frequencies(&mut self, samples: &[f32], num_tones: usize) -> Vec<f32> {
+        const GRID: usize = 16384;
         let n = samples.len();
-        let order = self.lpc_order;
+        let m = (n / 2).min(64);
+        let signal_dims = 2 * num_tones;
+        if num_tones == 0 || signal_dims >= m {
+            return Vec::new();
+        }
+        let mean = samples.iter().map(|&s| s as f64).sum::<f64>() / n as f64;
+        let x: Vec<f64> = samples.iter().map(|&s| s as f64 - mean).collect();
         
-        // Compute autocorrelation
-        for i in 0..=order {
-            self.autocorr[i] = 0.0;
-            for j in 0..(n - i) {
-                self.autocorr[i] += samples[j] * samples[j + i];
+        // Step 1: forward-backward covariance of M-sample snapshots
+        let snapshots = n - m + 1;
+        let mut covariance = vec![0.0f64; m * m];
+        for k in 0..snapshots {
+            let snapshot = &x[k..k + m];
+            for i in 0..m {
+                for j in i..m {
+                    covariance[i * m + j] += snapshot[i] * snapshot[j] + snapshot[m - 1 - i] * snapshot[m - 1 - j];
+                }
+            }
+        }
+        for i in 0..m {
+            for j in 0..i {
+                covariance[i * m + j] = covariance[j * m + i];
             }
         }
         
-        if self.autocorr[0].abs() < 1e-10 {
-            for c in self.lpc_coeffs.iter_mut() {
-                *c = 0.0;
+        // Step 2: noise subspace = eigenvectors of the M - 2p smallest eigenvalues
+        let (_, vectors) = symmetric_eigen(covariance, m);
+        let noise: Vec<&[f64]> = (0..m - signal_dims).map(|c| &vectors[c * m..(c + 1) * m]).collect();
+        let denominator = |omega: f64| -> f64 {
+            noise.iter()
+                .map(|v| {
+                    let (re, im) = v.iter().enumerate().fold((0.0f64, 0.0f64), |(re, im), (i, &value)| {
+                        (re + value * (omega * i as f64).cos(), im - value * (omega * i as f64).sin())
+                    });
+                    re * re + im * im
+                })
+                .sum()
+        };
+        
+        // Step 3: pseudospectrum denominator on a fine grid via zero-padded FFTs
+        let fft = self.planner.plan_forward(GRID);
+        let mut total = vec![0.0f64; GRID / 2 + 1];
+        let mut buffer = vec![Complex::new(0.0f32, 0.0); GRID];
+        for v in &noise {
+            buffer.iter_mut().for_each(|b| *b = Complex::new(0.0, 0.0));
+            for (b, &value) in buffer.iter_mut().zip(v.iter()) {
+                *b = Complex::new(value as f32, 0.0);
+            }
+            fft.process(&mut buffer);
+            for (t, b) in total.iter_mut().zip(&buffer) {
+                *t += b.norm_sqr() as f64;
             }
-            return;
         }
         
-        // Levinson-Durbin
-        let mut a_prev = vec![0.0f32; order];
-        let mut e = self.autocorr[0];
+        // Step 4: deepest minima, refined by golden-section search
+        let mut minima: Vec<usize> = (1..total.len() - 1)
+            .filter(|&k| total[k] < total[k - 1] && total[k] <= total[k + 1])
+            .collect();
+        minima.sort_by(|&a, &b| total[a].total_cmp(&total[b]));
+        let step = 2.0 * std::f64::consts::PI / GRID as f64;
+        let ratio = 0.5 * (5f64.sqrt() - 1.0);
+        let mut frequencies: Vec<f32> = minima.iter()
+            .take(num_tones)
+            .map(|&k| {
+                let (mut lo, mut hi) = ((k as f64 - 1.0) * step, (k as f64 + 1.0) * step);
+                for _ in 0..40 {
+                    let (a, b) = (hi - ratio * (hi - lo), lo + ratio * (hi - lo));
+                    if denominator(a) < denominator(b) { hi = b } else { lo = a }
+                }
+                (0.5 * (lo + hi) * self.sample_rate as f64 / (2.0 * std::f64::consts::PI)) as f32
+            })
+            .collect();
+        frequencies.sort_by(|a, b| a.total_cmp(b));
+        frequencies
+    }
+
+    /// The `n` strongest spectral peaks of a frame as `[freq_hz, amplitude, phase, ...]`,
+    /// strongest first. Frequency and amplitude come from a parabola through the peak
+    /// bin's log magnitude and its neighbours; amplitude is the sinusoid's peak
+    /// amplitude (window coherent gain removed); phase (radians) is read at the peak bin.
+    #[wasm_bindgen]
+    pub fn top_peaks(&mut self, samples: &[f32], n: usize) -> Vec<f32> {
+        self.transform(samples);
+        let n_bins = self.size / 2 + 1;
+        let magnitude: Vec<f32> = self.buffer[..n_bins].iter().map(|c| c.norm()).collect();
+        let coherent_gain = self.window.iter().sum::<f32>() * self.input_scale;
+        let bin_hz = self.sample_rate / self.size as f32;
         
-        for i in 0..order {
-            let mut lambda = self.autocorr[i + 1];
-            for j in 0..i {
-                lambda -= a_prev[j] * self.autocorr[i - j];
+        // Step 1: interpolated local maxima
+        let mut peaks: Vec<[f32; 3]> = (1..n_bins.saturating_sub(1))
+            .filter(|&k| magnitude[k] > magnitude[k - 1] && magnitude[k] >= magnitude[k + 1])
+            .map(|k| {
+                let [a, b, c] = [magnitude[k - 1], magnitude[k], magnitude[k + 1]].map(|m| (m + 1e-20).ln());
+                let (delta, log_peak) = parabolic_peak(a, b, c);
+                [(k as f32 + delta) * bin_hz, 2.0 * log_peak.exp() / coherent_gain, self.buffer[k].arg()]
+            })
+            .collect();
+        
+        // Step 2: strongest first
+        peaks.sort_by(|x, y| y[1].total_cmp(&x[1]));
+        peaks.into_iter().take(n).flatten().collect()
+    }
+
+    /// Clipping/distortion estimate, 0 (clean) to 1. Combines the fraction of samples
+    /// in flat-topped runs (3+ consecutive samples at the peak magnitude) with the
+    /// ratio of 3rd-and-higher harmonic power to the fundamental in the frame-averaged
+    /// spectrum; the two parts `c` and `s` combine as `1 - (1 - c)(1 - s)`.
+    #[wasm_bindgen]
+    pub fn distortion_score(&mut self, samples: &[f32]) -> f32 {
+        let peak = samples.iter().fold(0.0f32, |acc, &s| acc.max(s.abs()));
+        if peak <= 1e-6 {
+            return 0.0;
+        }
+        
+        // Step 1: sample-domain clipping ratio, saturating at 5 % clipped samples
+        let level = peak * (1.0 - 1e-5);
+        let mut clipped = 0usize;
+        let mut run = 0usize;
+        for &s in samples.iter().chain(std::iter::once(&0.0)) {
+            if s.abs() >= level {
+                run += 1;
+            } else {
+                if run >= 3 {
+                    clipped += run;
+                }
+                run = 0;
             }
-            lambda /= e;
-            
-            self.lpc_coeffs[i] = lambda;
-            for j in 0..i {
-                self.lpc_coeffs[j] = a_prev[j] - lambda * a_prev[i - 1 - j];
+        }
+        let clip_part = (20.0 * clipped as f32 / samples.len() as f32).min(1.0);
+        
+        // Step 2: frame-averaged power spectrum
+        let n_bins = self.size / 2 + 1;
+        let mut power = vec![0.0f32; n_bins];
+        let mut frame = vec![0.0f32; n_bins];
+        for chunk in samples.chunks(self.size) {
+            self.power_spectrum_into(chunk, &mut frame);
+            power.iter_mut().zip(&frame).for_each(|(p, &f)| *p += f);
+        }
+        
+        // Step 3: high-order harmonic power relative to the fundamental (±2 bins each)
+        let band = |center: usize| -> f32 {
+            power[center.saturating_sub(2)..(center + 3).min(n_bins)].iter().sum()
+        };
+        let fundamental = (1..n_bins).max_by(|&a, &b| power[a].total_cmp(&power[b])).unwrap_or(1);
+        let harmonics: f32 = (3..).map(|h| h * fundamental).take_while(|&k| k + 2 < n_bins).map(band).sum();
+        let ratio = harmonics / (band(fundamental) + 1e-12);
+        let spectral_part = ratio / (ratio + 0.01);
+        
+        1.0 - (1.0 - clip_part) * (1.0 - spectral_part)
+    }
+
+    /// Rolloff frequencies (Hz) of one frame: for each percentile p, the lowest bin
+    /// frequency below which a share p of the spectral power lies. Results follow the
+    /// order of `percentiles`; percentiles outside (0, 1] give NaN. The cumulative
+    /// power is built once and walked a single time in ascending percentile order.
+    #[wasm_bindgen]
+    pub fn spectral_rolloff_multi(&mut self, samples: &[f32], percentiles: &[f32]) -> Vec<f32> {
+        let power = self.power_spectrum(samples);
+        let bin_hz = self.sample_rate / self.size as f32;
+        let total: f32 = power.iter().sum();
+        let mut result = vec![f32::NAN; percentiles.len()];
+        
+        let mut order: Vec<usize> = (0..percentiles.len())
+            .filter(|&i| percentiles[i] > 0.0 && percentiles[i] <= 1.0)
+            .collect();
+        order.sort_by(|&a, &b| percentiles[a].total_cmp(&percentiles[b]));
+        
+        let mut cumulative = 0.0f32;
+        let mut bin = 0;
+        for i in order {
+            let target = percentiles[i] * total;
+            while bin < power.len() - 1 && cumulative + power[bin] < target {
+                cumulative += power[bin];
+                bin += 1;
             }
-            
-            e *= 1.0 - lambda * lambda;
-            a_prev.copy_from_slice(&self.lpc_coeffs);
+            result[i] = bin as f32 * bin_hz;
         }
+        result
     }
 
+    /// Zwicker sharpness in acum. The frame-averaged power spectrum is grouped into
+    /// 24 one-Bark bands (`z = 13·atan(0.00076 f) + 3.5·atan((f / 7500)²)`), each
+    /// band's specific loudness taken as `N'(z) = E^0.23`, and
+    /// `S = 0.11 · Σ N'(z)·g(z)·z / Σ N'(z)` with `g(z) = 1` up to 16 Bark and
+    /// `0.066·e^(0.171 z)` above. The 0.11 factor puts narrowband noise at 1 kHz
+    /// near 1 acum. Specific loudness here is uncalibrated (no level in phon), so
+    /// bands more than 60 dB below the loudest are treated as inaudible and the
+    /// value tracks spectral balance rather than absolute level.
     #[wasm_bindgen]
-    pub fn analyze(&mut self, samples: &[f32]) -> Vec<f32> {
-        self.compute_lpc(samples);
+    pub fn sharpness(&mut self, samples: &[f32]) -> f32 {
+        let n_bins = self.size / 2 + 1;
+        let bin_hz = self.sample_rate / self.size as f32;
         
-        let n_points = 512;
+        // Step 1: frame-averaged power grouped into Bark bands
+        let mut frame = vec![0.0f32; n_bins];
+        let mut bands = [0.0f32; 24];
+        for chunk in samples.chunks(self.size) {
+            self.power_spectrum_into(chunk, &mut frame);
+            for (k, &p) in frame.iter().enumerate().skip(1) {
+                let f = k as f32 * bin_hz;
+                let z = 13.0 * (0.00076 * f).atan() + 3.5 * (f / 7500.0).powi(2).atan();
+                bands[(z as usize).min(23)] += p;
+            }
+        }
         
-        // Compute frequency response
-        for i in 0..n_points {
-            let freq = i as f32 * self.sample_rate / 2.0 / n_points as f32;
-            let omega = 2.0 * PI * freq / self.sample_rate;
+        // Step 2: loudness-weighted centroid with the high-band emphasis g(z);
+        // bands 60 dB under the loudest stand in for the threshold of hearing
+        let floor = bands.iter().fold(0.0f32, |acc, &e| acc.max(e)) * 1e-6;
+        let (mut weighted, mut total) = (0.0f32, 0.0f32);
+        for (i, &energy) in bands.iter().enumerate() {
+            let z = i as f32 + 0.5;
+            let loudness = if energy > floor { energy.powf(0.23) } else { 0.0 };
+            let g = if z <= 16.0 { 1.0 } else { 0.066 * (0.171 * z).exp() };
+            weighted += loudness * g * z;
+            total += loudness;
+        }
+        if total <= 1e-12 { 0.0 } else { 0.11 * weighted / total }
+    }
+
+    /// Roughness in asper (uncalibrated level, like `sharpness`). Each critical band
+    /// (Zwicker's 24 band edges, 2nd-order high/low-pass pair) gets its Hilbert
+    /// envelope; the band's effective modulation depth is the RMS of the envelope's
+    /// modulation spectrum weighted by a roughness curve peaking at 70 Hz
+    /// (log-Gaussian, σ = 0.8 octave: about half weight at 35 and 140 Hz, almost none
+    /// at 4 Hz), over the mean envelope. The result is the mean depth weighted by
+    /// each band's specific loudness `E^0.23` (bands 60 dB below the loudest are
+    /// ignored), times 0.25, so 100 % AM at 70 Hz gives about 0.25 asper.
+    #[wasm_bindgen]
+    pub fn roughness(&mut self, samples: &[f32]) -> f32 {
+        const EDGES: [f32; 25] = [
+            20.0, 100.0, 200.0, 300.0, 400.0, 510.0, 630.0, 770.0, 920.0, 1080.0, 1270.0, 1480.0, 1720.0,
+            2000.0, 2320.0, 2700.0, 3150.0, 3700.0, 4400.0, 5300.0, 6400.0, 7700.0, 9500.0, 12000.0, 15500.0,
+        ];
+        let n = samples.len();
+        if n < 64 {
+            return 0.0;
+        }
+        let q = std::f32::consts::FRAC_1_SQRT_2;
+        let size = n.next_power_of_two();
+        let fft = self.planner.plan_forward(size);
+        let window = cached_window(n, WindowKind::Hann);
+        let window_sum: f32 = window.iter().sum();
+        let mod_hz = self.sample_rate / size as f32;
+        
+        // Step 1: per band energy and weighted modulation depth
+        let mut bands: Vec<(f32, f32)> = Vec::new();
+        let nyquist = 0.5 * self.sample_rate;
+        for pair in EDGES.windows(2).filter(|pair| pair[1] < nyquist) {
+            let low = Biquad::highpass(self.sample_rate, pair[0], q).process(samples);
+            let band = Biquad::lowpass(self.sample_rate, pair[1], q).process(&low);
+            let energy = frame_energy(&band, false);
+            let envelope = self.am_envelope(&band, 0.0);
+            let mean = envelope.iter().sum::<f32>() / n as f32;
+            if mean <= 1e-9 {
+                continue;
+            }
             
-            let mut real_sum = 1.0f32;
-            let mut imag_sum = 0.0f32;
+            let mut spectrum: Vec<Complex<f32>> = (0..size)
+                .map(|i| Complex::new(if i < n { (envelope[i] - mean) * window[i] } else { 0.0 }, 0.0))
+                .collect();
+            fft.process(&mut spectrum);
+            // Hann equivalent noise bandwidth is 1.5 bins: divide it out of the power sum
+            let weighted_power: f32 = spectrum[1..size / 2].iter()
+                .enumerate()
+                .map(|(k, c)| {
+                    let octaves = ((k + 1) as f32 * mod_hz / 70.0).log2();
+                    let weight = (-octaves * octaves / (2.0 * 0.64)).exp();
+                    weight * (2.0 * c.norm() / window_sum).powi(2)
+                })
+                .sum::<f32>() / 1.5;
+            bands.push((energy, weighted_power.sqrt() / mean));
+        }
+        
+        // Step 2: loudness-weighted mean depth
+        let loudest = bands.iter().fold(0.0f32, |acc, b| acc.max(b.0));
+        let (weighted, total) = bands.iter()
+            .filter(|b| b.0 > loudest * 1e-6)
+            .fold((0.0f32, 0.0f32), |(weighted, total), &(energy, depth)| {
+                let loudness = energy.powf(0.23);
+                (weighted + loudness * depth, total + loudness)
+            });
+        if total <= 0.0 { 0.0 } else { 0.25 * weighted / total }
+    }
+
+    /// Noise colour from the spectral slope, `[slope_db_per_octave, color_index]`.
+    /// The Welch PSD (50 % overlap) is averaged in third-octave bands between
+    /// max(100 Hz, 4 bins) and 80 % of Nyquist, away from DC and anti-alias roll-off,
+    /// and a line is fitted to band level (dB) against octaves. The index is the nearest
+    /// of white (0, 0 dB/oct), pink (1, -3 dB/oct) and brown (2, -6 dB/oct).
+    #[wasm_bindgen]
+    pub fn noise_color(&mut self, samples: &[f32]) -> Vec<f32> {
+        let psd = self.welch_psd(samples, (self.size / 2).max(1));
+        let bin_hz = self.sample_rate / self.size as f32;
+        let low = (4.0 * bin_hz).max(100.0);
+        let high = 0.4 * self.sample_rate;
+        
+        // Step 1: third-octave band levels
+        let mut points: Vec<(f32, f32)> = Vec::new();
+        let mut edge = low;
+        while edge * 2.0f32.powf(1.0 / 3.0) <= high {
+            let upper = edge * 2.0f32.powf(1.0 / 3.0);
+            let bins = (edge / bin_hz).ceil() as usize..((upper / bin_hz).ceil() as usize).min(psd.len());
+            if !bins.is_empty() {
+                let mean = psd[bins.clone()].iter().sum::<f32>() / bins.len() as f32;
+                points.push(((edge * upper).sqrt().log2(), 10.0 * (mean + 1e-20).log10()));
+            }
+            edge = upper;
+        }
+        if points.len() < 2 {
+            return vec![0.0, 0.0];
+        }
+        
+        // Step 2: least-squares slope in dB per octave
+        let slope = line_fit(&points).0 as f32;
+        vec![slope, (-slope / 3.0).round().clamp(0.0, 2.0)]
+    }
+
+    /// Welch-averaged one-sided power spectral density (units²/Hz), `size / 2 + 1` bins.
+    /// Segments of `size` samples spaced by `hop_size` are mean-removed, windowed
+    /// and averaged; scaling by fs·Σw² makes Σ PSD·Δf equal the signal variance.
+    #[wasm_bindgen]
+    pub fn welch_psd(&mut self, samples: &[f32], hop_size: usize) -> Vec<f32> {
+        let n_bins = self.size / 2 + 1;
+        let hop = hop_size.max(1);
+        let num_segments = frame_count(samples.len(), self.size, hop);
+        let mut psd = vec![0.0f32; n_bins];
+        
+        for seg in 0..num_segments {
+            let segment = frame_slice(samples, seg, self.size, hop);
+            let mean = segment.iter().sum::<f32>() / segment.len().max(1) as f32;
             
-            for (k, &coef) in self.lpc_coeffs.iter().enumerate() {
-                let angle = -((k + 1) as f32) * omega;
-                real_sum -= coef * angle.cos();
-                imag_sum -= coef * angle.sin();
+            for (i, b) in self.buffer.iter_mut().enumerate() {
+                let s = segment.get(i).map_or(0.0, |&s| s - mean);
+                *b = Complex::new(s * self.window[i], 0.0);
             }
+            self.fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
             
-            self.response_re[i] = 1.0 / (real_sum * real_sum + imag_sum * imag_sum + 1e-10).sqrt();
+            for (p, c) in psd.iter_mut().zip(&self.buffer) {
+                *p += c.re * c.re + c.im * c.im;
+            }
         }
         
-        // Find peaks (formants)
-        let mut formants = Vec::with_capacity(4);
-        for i in 1..(n_points - 1) {
-            if self.response_re[i] > self.response_re[i - 1] && 
-               self.response_re[i] > self.response_re[i + 1] {
-                let avg = (self.response_re[i - 1] + self.response_re[i + 1]) / 2.0;
-                if self.response_re[i] > avg * 1.5 {
-                    let freq = i as f32 * self.sample_rate / 2.0 / n_points as f32;
-                    formants.push(freq);
-                    if formants.len() >= 4 { break; }
+        // Window noise gain and one-sided folding (DC and Nyquist are not doubled)
+        let noise_gain: f32 = self.window.iter().map(|&w| w * w).sum();
+        let scale = 1.0 / (self.sample_rate * noise_gain * num_segments as f32);
+        for (k, p) in psd.iter_mut().enumerate() {
+            let fold = if k == 0 || (self.size.is_multiple_of(2) && k == n_bins - 1) { 1.0 } else { 2.0 };
+            *p *= fold * scale;
+        }
+        psd
+    }
+
+    /// Magnitude-squared coherence `|Sab|² / (Saa·Sbb)` per bin (`size / 2 + 1`
+    /// values in 0..1) from Welch averages over the common length of `a` and `b`,
+    /// segments as in `welch_psd`. A single segment gives 1 in every bin and K
+    /// independent segments bias unrelated signals to about 1/K, so fewer than 8
+    /// segments return an empty vector. Bins with no power in either signal are 0.
+    #[wasm_bindgen]
+    pub fn coherence(&mut self, a: &[f32], b: &[f32], hop_size: usize) -> Vec<f32> {
+        let n_bins = self.size / 2 + 1;
+        let hop = hop_size.max(1);
+        let len = a.len().min(b.len());
+        let num_segments = frame_count(len, self.size, hop);
+        if len < self.size || num_segments < 8 {
+            return Vec::new();
+        }
+        let mut spectrum_a = vec![Complex::new(0.0f32, 0.0); n_bins];
+        let mut cross = vec![Complex::new(0.0f32, 0.0); n_bins];
+        let mut power_a = vec![0.0f32; n_bins];
+        let mut power_b = vec![0.0f32; n_bins];
+        
+        for seg in 0..num_segments {
+            for (signal, is_a) in [(a, true), (b, false)] {
+                let segment = frame_slice(&signal[..len], seg, self.size, hop);
+                let mean = segment.iter().sum::<f32>() / self.size as f32;
+                for (i, buf) in self.buffer.iter_mut().enumerate() {
+                    *buf = Complex::new((segment[i] - mean) * self.window[i], 0.0);
+                }
+                self.fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+                if is_a {
+                    spectrum_a.copy_from_slice(&self.buffer[..n_bins]);
                 }
             }
+            
+            // Accumulate the cross- and auto-spectra
+            for k in 0..n_bins {
+                let (x, y) = (spectrum_a[k], self.buffer[k]);
+                cross[k] += x * y.conj();
+                power_a[k] += x.norm_sqr();
+                power_b[k] += y.norm_sqr();
+            }
         }
         
-        formants
+        (0..n_bins)
+            .map(|k| {
+                let denominator = power_a[k] * power_b[k];
+                if denominator > 1e-20 { (cross[k].norm_sqr() / denominator).min(1.0) } else { 0.0 }
+            })
+            .collect()
     }
-}
 
-/// ULTRA-OPTIMIZED Sinc Resampler
-/// Uses lookup table for sinc values and loop unrolling
-#[wasm_bindgen]
-pub fn resample(samples: &[f32], from_rate: f32, to_rate: f32) -> Vec<f32> {
-    let ratio = to_rate / from_rate;
-    let new_length = (samples.len() as f32 * ratio) as usize;
-    let mut output = Vec::with_capacity(new_length);
-    
-    // Smaller window for speed (quality tradeoff)
-    const WINDOW_SIZE: usize = 8;
-    const INV_WINDOW: f32 = 1.0 / WINDOW_SIZE as f32;
-    
-    for i in 0..new_length {
-        let src_pos = i as f32 / ratio;
-        let src_idx = src_pos as usize;
+    /// `[centroid_hz, spread_hz, skewness, kurtosis]` of one frame: the moments of
+    /// bin frequency weighted by magnitude (normalized to a distribution). Spread
+    /// is the standard deviation about the centroid; skewness and kurtosis are the
+    /// third and fourth central moments over spread³ and spread⁴ (kurtosis is not
+    /// excess: 3 for a Gaussian shape). Positive skewness means a tail toward high
+    /// frequencies. Silent frames give zeros, as do the normalized moments of a
+    /// single-bin spectrum.
+    #[wasm_bindgen]
+    pub fn spectral_moments(&mut self, samples: &[f32]) -> Vec<f32> {
+        let n_bins = self.size / 2 + 1;
+        let bin_hz = (self.sample_rate / self.size as f32) as f64;
+        let mut row = vec![0.0f32; n_bins];
+        self.power_spectrum_into(samples, &mut row);
+        let magnitude: Vec<f64> = row.iter().map(|&p| (p as f64).sqrt()).collect();
+        let total: f64 = magnitude.iter().sum();
+        if total <= 1e-10 {
+            return vec![0.0; 4];
+        }
         
-        let mut sample = 0.0f32;
-        let mut weight_sum = 0.0f32;
+        let moment = |center: f64, order: i32| -> f64 {
+            magnitude.iter().enumerate().map(|(k, &m)| m * (k as f64 * bin_hz - center).powi(order)).sum::<f64>() / total
+        };
+        let centroid = moment(0.0, 1);
+        let spread = moment(centroid, 2).sqrt();
+        if spread <= 1e-9 * bin_hz {
+            return vec![centroid as f32, 0.0, 0.0, 0.0];
+        }
+        let skewness = moment(centroid, 3) / spread.powi(3);
+        let kurtosis = moment(centroid, 4) / spread.powi(4);
+        vec![centroid as f32, spread as f32, skewness as f32, kurtosis as f32]
+    }
+
+    /// Inharmonicity coefficient B of the stiff-string model
+    /// `f_n = n·f0·sqrt(1 + B·n²)` for a tone near `fundamental_hz`. Partials are
+    /// tracked upward: each is searched within ±f0/4 of the partial position the
+    /// fit so far predicts, accepted if it is a local maximum no more than 60 dB
+    /// under the strongest bin, and its frequency refined by parabolic
+    /// interpolation of the dB spectrum. Since `(f_n / n)² = f0² + f0²·B·n²`, a
+    /// least-squares line through `(n², (f_n / n)²)` gives B (slope / intercept)
+    /// without trusting `fundamental_hz` exactly. Clamped at 0, so a harmonic
+    /// tone reads ~0; NaN for silence or when fewer than 3 partials are found.
+    #[wasm_bindgen]
+    pub fn inharmonicity(&mut self, samples: &[f32], fundamental_hz: f32) -> f32 {
+        let n_bins = self.size / 2 + 1;
+        let bin_hz = (self.sample_rate / self.size as f32) as f64;
+        let f0 = fundamental_hz as f64;
+        if fundamental_hz.is_nan() || f0 <= 2.0 * bin_hz {
+            return f32::NAN;
+        }
+        let mut row = vec![0.0f32; n_bins];
+        self.power_spectrum_into(samples, &mut row);
+        if row.iter().all(|&p| p <= 1e-20) {
+            return f32::NAN;
+        }
+        let db: Vec<f64> = row.iter().map(|&p| 10.0 * (p as f64 + 1e-20).log10()).collect();
+        let floor = db.iter().copied().fold(f64::NEG_INFINITY, f64::max) - 60.0;
         
-        let j_start = src_idx.saturating_sub(WINDOW_SIZE);
-        let j_end = (src_idx + WINDOW_SIZE).min(samples.len());
+        // Step 1: track partials, predicting each from the line y = a + b·x fitted
+        // so far through (n², (f_n / n)²)
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        let (mut a, mut b) = (f0 * f0, 0.0);
+        for n in 1..=64usize {
+            let nf = n as f64;
+            let predicted = nf * (a + b * nf * nf).max(0.0).sqrt();
+            if predicted + 0.25 * f0 >= 0.45 * self.sample_rate as f64 {
+                break;
+            }
+            let lo = (((predicted - 0.25 * f0) / bin_hz).ceil() as usize).max(1);
+            let hi = (((predicted + 0.25 * f0) / bin_hz).floor() as usize).min(n_bins - 2);
+            let Some(k) = (lo..=hi).max_by(|&i, &j| db[i].total_cmp(&db[j])) else { continue };
+            if db[k] < floor || db[k] < db[k - 1] || db[k] < db[k + 1] {
+                continue;
+            }
+            let offset = parabolic_peak(db[k - 1] as f32, db[k] as f32, db[k + 1] as f32).0;
+            let frequency = (k as f64 + offset as f64) * bin_hz;
+            points.push((nf * nf, (frequency / nf).powi(2)));
+            if points.len() >= 3 {
+                (b, a) = line_fit(&points);
+            }
+        }
         
-        // Unrolled inner loop with fused sinc-Lanczos
-        for j in j_start..j_end {
-            let x = (j as f32 - src_pos) * PI;
-            
-            // Fast sinc approximation for small x
-            let sinc = if x.abs() < 0.01 { 
-                1.0 - x * x / 6.0  // Taylor series
-            } else { 
-                x.sin() / x 
-            };
+        // Step 2: B = slope / intercept of the final fit
+        if points.len() < 3 || a <= 0.0 {
+            return f32::NAN;
+        }
+        (b / a).max(0.0) as f32
+    }
+
+    /// Level of each of the first `num_harmonics` harmonics of `fundamental_hz` in
+    /// dB relative to the fundamental (so the first entry is 0). Each harmonic is
+    /// the strongest local maximum within ±2 bins of `h·f0` (the Hann main lobe),
+    /// with its level refined by parabolic interpolation of the dB spectrum; a
+    /// harmonic with no maximum there reports the level of its nearest bin, so a
+    /// missing harmonic reads as its actual (low) level rather than being skipped.
+    /// Harmonics at or above Nyquist are NaN, and so is the whole profile for
+    /// silence or when the fundamental itself is no spectral peak within 60 dB of
+    /// the strongest bin.
+    #[wasm_bindgen]
+    pub fn harmonic_profile(&mut self, samples: &[f32], fundamental_hz: f32, num_harmonics: usize) -> Vec<f32> {
+        let n_bins = self.size / 2 + 1;
+        let bin_hz = self.sample_rate / self.size as f32;
+        let mut profile = vec![f32::NAN; num_harmonics];
+        if num_harmonics == 0 || fundamental_hz.is_nan() || fundamental_hz < 2.0 * bin_hz {
+            return profile;
+        }
+        let mut row = vec![0.0f32; n_bins];
+        self.power_spectrum_into(samples, &mut row);
+        let db: Vec<f32> = row.iter().map(|&p| 10.0 * (p + 1e-20).log10()).collect();
+        let floor = db.iter().copied().fold(f32::NEG_INFINITY, f32::max) - 60.0;
+        
+        // Peak level near `frequency`, and whether a local maximum was found
+        let level = |frequency: f32| -> (f32, bool) {
+            let center = (frequency / bin_hz).round() as usize;
+            let peak = (center.saturating_sub(2).max(1)..=(center + 2).min(n_bins - 2))
+                .filter(|&k| db[k] >= db[k - 1] && db[k] >= db[k + 1])
+                .max_by(|&i, &j| db[i].total_cmp(&db[j]));
+            match peak {
+                Some(k) => (parabolic_peak(db[k - 1], db[k], db[k + 1]).1, true),
+                None => (db[center.min(n_bins - 1)], false),
+            }
+        };
+        
+        // Step 1: voicing check on the fundamental
+        let (reference, is_peak) = level(fundamental_hz);
+        if !is_peak || reference < floor || row.iter().all(|&p| p <= 1e-20) {
+            return profile;
+        }
+        
+        // Step 2: every harmonic below Nyquist relative to it
+        for (h, value) in profile.iter_mut().enumerate() {
+            let frequency = (h + 1) as f32 * fundamental_hz;
+            if frequency < self.sample_rate / 2.0 {
+                *value = level(frequency).0 - reference;
+            }
+        }
+        profile
+    }
+
+    /// Spectral tilt per frame in dB/octave: the least-squares slope of the
+    /// log-magnitude spectrum against log2(frequency) over the tilt range. DC and
+    /// Nyquist never enter the fit; silent frames (or fewer than 2 bins) give NaN.
+    #[wasm_bindgen]
+    pub fn spectral_tilt(&mut self, samples: &[f32], hop_size: usize) -> Vec<f32> {
+        let n_bins = self.size / 2 + 1;
+        let hop = hop_size.max(1);
+        let bin_hz = self.sample_rate / self.size as f32;
+        let first = ((self.tilt_min_hz / bin_hz).ceil() as usize).max(1);
+        let last = ((self.tilt_max_hz / bin_hz).floor() as usize).min(n_bins - 2);
+        let octaves: Vec<f32> = (first..=last.max(first)).map(|k| (k as f32 * bin_hz).log2()).collect();
+        let num_frames = frame_count(samples.len(), self.size, hop);
+        
+        let mut row = vec![0.0f32; n_bins];
+        (0..num_frames)
+            .map(|t| {
+                self.power_spectrum_into(frame_slice(samples, t, self.size, hop), &mut row);
+                let bins = &row[first..=last.max(first)];
+                if last <= first || bins.iter().all(|&p| p <= 1e-20) {
+                    return f32::NAN;
+                }
+                
+                // Least-squares slope of 10·log10(power) over log2(f)
+                let points: Vec<(f32, f32)> = octaves.iter()
+                    .zip(bins)
+                    .map(|(&x, &p)| (x, 10.0 * (p + 1e-20).log10()))
+                    .collect();
+                line_fit(&points).0 as f32
+            })
+            .collect()
+    }
+
+    /// Per-frame timbre `[brightness, warmth, flux, ...]`, each in 0..1, over
+    /// frames `hop_size` apart. Brightness is the spectral centroid on a log scale
+    /// from 100 Hz (0) to 10 kHz (1, or Nyquist if lower); warmth the fraction of
+    /// the power below 500 Hz; flux the normalized spectral flux
+    /// `Σ| |X_t| - |X_t-1| | / Σ(|X_t| + |X_t-1|)` against the previous frame (0 for
+    /// the first), a cheap roughness proxy rather than the Sethares roughness of
+    /// `roughness`. DC is excluded throughout; silent frames give zeros.
+    #[wasm_bindgen]
+    pub fn timbre_descriptor(&mut self, samples: &[f32], hop_size: usize) -> Vec<f32> {
+        let n_bins = self.size / 2 + 1;
+        let hop = hop_size.max(1);
+        let bin_hz = self.sample_rate / self.size as f32;
+        let (low_hz, high_hz) = (100.0f32, 10000.0f32.min(self.sample_rate / 2.0));
+        let warm_end = ((500.0 / bin_hz).ceil() as usize).min(n_bins);
+        let num_frames = frame_count(samples.len(), self.size, hop);
+        
+        let mut row = vec![0.0f32; n_bins];
+        let mut previous = vec![0.0f32; n_bins];
+        let mut descriptors = Vec::with_capacity(3 * num_frames);
+        for t in 0..num_frames {
+            self.power_spectrum_into(frame_slice(samples, t, self.size, hop), &mut row);
+            let magnitude: Vec<f32> = row[1..].iter().map(|p| p.sqrt()).collect();
+            let total: f32 = row[1..].iter().sum();
+            if total <= 1e-20 {
+                descriptors.extend([0.0, 0.0, 0.0]);
+                previous.fill(0.0);
+                continue;
+            }
+            
+            // Step 1: brightness from the power-weighted centroid
+            let centroid = row[1..].iter().enumerate().map(|(k, &p)| (k + 1) as f32 * bin_hz * p).sum::<f32>() / total;
+            let brightness = ((centroid / low_hz).ln() / (high_hz / low_hz).ln()).clamp(0.0, 1.0);
             
-            // Lanczos window
-            let lx = x * INV_WINDOW;
-            let lanczos = if lx.abs() >= 1.0 {
+            // Step 2: warmth as the low-band power fraction
+            let warmth = row[1..warm_end.max(1)].iter().sum::<f32>() / total;
+            
+            // Step 3: normalized magnitude flux
+            let flux = if t == 0 {
                 0.0
-            } else if lx.abs() < 0.01 {
-                1.0 - lx * lx * PI * PI / 6.0
             } else {
-                let la = lx * PI;
-                la.sin() / la
+                let (change, sum) = magnitude.iter().zip(&previous[1..]).fold((0.0f32, 0.0f32), |(c, s), (&m, &p)| {
+                    (c + (m - p).abs(), s + m + p)
+                });
+                change / sum
             };
-            
-            let w = sinc * lanczos;
-            sample += samples[j] * w;
-            weight_sum += w;
+            descriptors.extend([brightness, warmth, flux]);
+            previous[1..].copy_from_slice(&magnitude);
         }
-        
-        output.push(sample / weight_sum.max(1e-10));
+        descriptors
     }
-    
-    console_log!("🦀 [Rust DSP] Resampled: {}Hz → {}Hz ({} → {} samples)", 
-                 from_rate, to_rate, samples.len(), new_length);
-    output
-}
 
-/// Initialize the WASM module
-#[wasm_bindgen(start)]
+    /// Beat spectrum (Foote): `B[l]` for lags of `l` frames (`l · hop_size`
+    /// samples), l = 0..num_frames/2, whose peaks mark repetition periods. Frames
+    /// are described by the similarity feature, mean-removed and unit-normalized,
+    /// so each self-similarity entry `S(i, j)` is a correlation in -1..1; `B[l]`
+    /// averages the l-th diagonal of S (computed diagonal by diagonal, never
+    /// stored), giving `B[0] = 1` for non-silent input. Silent frames compare as 0.
+    #[wasm_bindgen]
+    pub fn beat_spectrum(&mut self, samples: &[f32], hop_size: usize) -> Vec<f32> {
+        let n_bins = self.size / 2 + 1;
+        let hop = hop_size.max(1);
+        let bin_hz = self.sample_rate / self.size as f32;
+        let num_frames = frame_count(samples.len(), self.size, hop);
+        
+        // Step 1: normalized feature vector per frame
+        let mut row = vec![0.0f32; n_bins];
+        let features: Vec<Vec<f32>> = (0..num_frames)
+            .map(|t| {
+                self.power_spectrum_into(frame_slice(samples, t, self.size, hop), &mut row);
+                let mut feature: Vec<f32> = match self.similarity_feature {
+                    SimilarityFeature::LogSpectrum => row[1..].iter().map(|&p| 10.0 * (p + 1e-10).log10()).collect(),
+                    SimilarityFeature::Chroma => {
+                        let mut chroma = vec![0.0f32; 12];
+                        for (k, &p) in row.iter().enumerate().skip(((50.0 / bin_hz).ceil() as usize).max(1)) {
+                            let midi = 69.0 + 12.0 * (k as f32 * bin_hz / 440.0).log2();
+                            chroma[(midi.round() as i32).rem_euclid(12) as usize] += p;
+                        }
+                        chroma
+                    }
+                };
+                let mean = feature.iter().sum::<f32>() / feature.len().max(1) as f32;
+                feature.iter_mut().for_each(|v| *v -= mean);
+                let norm = feature.iter().map(|v| v * v).sum::<f32>().sqrt();
+                let silent = row.iter().all(|&p| p <= 1e-20);
+                let scale = if norm > 1e-12 && !silent { 1.0 / norm } else { 0.0 };
+                feature.iter_mut().for_each(|v| *v *= scale);
+                feature
+            })
+            .collect();
+        
+        // Step 2: mean of each self-similarity diagonal
+        (0..=num_frames / 2)
+            .map(|lag| {
+                let count = num_frames - lag;
+                (0..count)
+                    .map(|i| features[i].iter().zip(&features[i + lag]).map(|(a, b)| a * b).sum::<f32>())
+                    .sum::<f32>() / count as f32
+            })
+            .collect()
+    }
+
+    /// Modulation spectrum as `[num_bands, num_mod_bins, mod_bin_hz, map...]`: for
+    /// each log-spaced acoustic band the magnitude envelope is taken over frames
+    /// `hop_size` apart, mean-removed, Hann-windowed and zero-padded to a power
+    /// of two, and its FFT magnitude forms one band-major row. Modulation bin m
+    /// sits at `m · mod_bin_hz` Hz, where `mod_bin_hz` is the frame rate
+    /// `sample_rate / hop_size` divided by the padded length, so bins run from DC
+    /// up to half the frame rate. Bands holding no bin give zero rows.
+    #[wasm_bindgen]
+    pub fn modulation_spectrum(&mut self, samples: &[f32], hop_size: usize) -> Vec<f32> {
+        let n_bins = self.size / 2 + 1;
+        let hop = hop_size.max(1);
+        let bin_hz = self.sample_rate / self.size as f32;
+        let num_frames = frame_count(samples.len(), self.size, hop);
+        let num_bands = self.modulation_bands;
+        
+        // Step 1: band edges (bins), log-spaced from 50 Hz to Nyquist
+        let (low_hz, high_hz) = (50.0f32, self.sample_rate / 2.0);
+        let edge = |b: usize| low_hz * (high_hz / low_hz).powf(b as f32 / num_bands as f32);
+        let bands: Vec<(usize, usize)> = (0..num_bands)
+            .map(|b| {
+                let first = ((edge(b) / bin_hz).ceil() as usize).max(1);
+                let end = if b + 1 == num_bands { n_bins } else { ((edge(b + 1) / bin_hz).ceil() as usize).min(n_bins) };
+                (first, end.max(first))
+            })
+            .collect();
+        
+        // Step 2: per-band magnitude envelopes
+        let mut row = vec![0.0f32; n_bins];
+        let mut envelopes = vec![0.0f32; num_bands * num_frames];
+        for t in 0..num_frames {
+            self.power_spectrum_into(frame_slice(samples, t, self.size, hop), &mut row);
+            for (b, &(first, end)) in bands.iter().enumerate() {
+                envelopes[b * num_frames + t] = row[first..end].iter().sum::<f32>().sqrt();
+            }
+        }
+        
+        // Step 3: modulation FFT of each envelope
+        let length = num_frames.next_power_of_two();
+        let num_mod_bins = length / 2 + 1;
+        let fft = self.planner.plan_forward(length);
+        let window = cached_window(num_frames, WindowKind::Hann);
+        let mut buffer = vec![Complex::new(0.0f32, 0.0); length];
+        let mut output = Vec::with_capacity(3 + num_bands * num_mod_bins);
+        output.extend([num_bands as f32, num_mod_bins as f32, self.sample_rate / hop as f32 / length as f32]);
+        for envelope in envelopes.chunks(num_frames) {
+            let mean = envelope.iter().sum::<f32>() / num_frames as f32;
+            buffer.fill(Complex::new(0.0, 0.0));
+            for ((b, &e), &w) in buffer.iter_mut().zip(envelope).zip(window.iter()) {
+                *b = Complex::new((e - mean) * w, 0.0);
+            }
+            fft.process(&mut buffer);
+            output.extend(buffer[..num_mod_bins].iter().map(|c| c.norm()));
+        }
+        output
+    }
+
+    /// Spectral flatness per ISO octave band as `[nominal_center_hz, flatness, ...]`,
+    /// from 31.5 Hz up to the last band starting below Nyquist. Bands are the base-2
+    /// octaves of IEC 61260 (exact centres `1000·2^k`, edges at centre·2^±½); a bin
+    /// belongs to the band whose half-open range `[lower, upper)` holds its centre
+    /// frequency. Flatness is the geometric over the arithmetic mean of the Welch
+    /// PSD (50 % overlap) in the band: near 1 for noise, near 0 for tones. Bands
+    /// holding no bin, or silent ones, report NaN.
+    #[wasm_bindgen]
+    pub fn octave_flatness(&mut self, samples: &[f32]) -> Vec<f32> {
+        const NOMINAL: [f32; 10] = [31.5, 63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0];
+        let psd = self.welch_psd(samples, self.size / 2);
+        let bin_hz = self.sample_rate / self.size as f32;
+        let nyquist = self.sample_rate / 2.0;
+        
+        let mut bands = Vec::with_capacity(2 * NOMINAL.len());
+        for (k, &nominal) in (-5i32..).zip(NOMINAL.iter()) {
+            let center = 1000.0 * 2f32.powi(k);
+            let (lower, upper) = (center * std::f32::consts::FRAC_1_SQRT_2, center * std::f32::consts::SQRT_2);
+            if lower >= nyquist {
+                break;
+            }
+            let first = (lower / bin_hz).ceil() as usize;
+            let end = ((upper / bin_hz).ceil() as usize).min(psd.len());
+            let band = &psd[first.min(end)..end];
+            let mean = band.iter().map(|&p| p as f64).sum::<f64>() / band.len().max(1) as f64;
+            let flatness = if band.is_empty() || mean <= 0.0 {
+                f32::NAN
+            } else {
+                let log_mean = band.iter().map(|&p| (p as f64 + 1e-30).ln()).sum::<f64>() / band.len() as f64;
+                (log_mean.exp() / mean).min(1.0) as f32
+            };
+            bands.extend([nominal, flatness]);
+        }
+        bands
+    }
+
+    /// Occupied bandwidth `[low_hz, high_hz]`: the narrowest run of bins of the
+    /// Welch PSD (50 % overlap) holding `fraction` of the total power, e.g. 0.99.
+    /// Found with a two-pointer sweep, so the band can sit anywhere in the
+    /// spectrum. `fraction` outside (0, 1] or a silent input gives `[NaN, NaN]`.
+    #[wasm_bindgen]
+    pub fn occupied_bandwidth(&mut self, samples: &[f32], fraction: f32) -> Vec<f32> {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return vec![f32::NAN, f32::NAN];
+        }
+        let psd: Vec<f64> = self.welch_psd(samples, self.size / 2).iter().map(|&p| p as f64).collect();
+        let total: f64 = psd.iter().sum();
+        if total <= 0.0 {
+            return vec![f32::NAN, f32::NAN];
+        }
+        let target = fraction as f64 * total * (1.0 - 1e-9);
+        
+        // Shortest window [low, high] with at least `target` power
+        let mut best = (0, psd.len() - 1);
+        let mut low = 0;
+        let mut sum = 0.0f64;
+        for (high, &p) in psd.iter().enumerate() {
+            sum += p;
+            while sum - psd[low] >= target {
+                sum -= psd[low];
+                low += 1;
+            }
+            if sum >= target && high - low < best.1 - best.0 {
+                best = (low, high);
+            }
+        }
+        let bin_hz = self.sample_rate / self.size as f32;
+        vec![best.0 as f32 * bin_hz, best.1 as f32 * bin_hz]
+    }
+
+    /// Interaural cues per band as `[center_hz, itd_seconds, ild_db, ...]`, from the
+    /// cross- and auto-spectra averaged over frames `hop_size` apart. The ITD is
+    /// the group delay of the cross-spectrum phase: the phase is unwrapped across
+    /// the band's bins and its magnitude-weighted slope against ω taken, so it
+    /// is free of 2π ambiguity (single-bin bands fall back to phase / ω). Positive
+    /// ITDs mean the right channel lags; positive ILDs mean the left is louder.
+    /// Bands holding no bin, or silent in either channel, report NaN cues.
+    #[wasm_bindgen]
+    pub fn binaural_cues(&mut self, left: &[f32], right: &[f32], hop_size: usize) -> Vec<f32> {
+        let n_bins = self.size / 2 + 1;
+        let hop = hop_size.max(1);
+        let len = left.len().min(right.len());
+        let num_segments = frame_count(len, self.size, hop);
+        let mut spectrum_left = vec![Complex::new(0.0f32, 0.0); n_bins];
+        let mut cross = vec![Complex::new(0.0f32, 0.0); n_bins];
+        let mut power_left = vec![0.0f32; n_bins];
+        let mut power_right = vec![0.0f32; n_bins];
+        
+        // Step 1: averaged cross- and auto-spectra
+        for seg in 0..num_segments {
+            for (signal, is_left) in [(left, true), (right, false)] {
+                let segment = frame_slice(&signal[..len], seg, self.size, hop);
+                for (i, b) in self.buffer.iter_mut().enumerate() {
+                    *b = Complex::new(segment.get(i).map_or(0.0, |&s| s * self.window[i]), 0.0);
+                }
+                self.fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+                if is_left {
+                    spectrum_left.copy_from_slice(&self.buffer[..n_bins]);
+                }
+            }
+            for k in 0..n_bins {
+                let (l, r) = (spectrum_left[k], self.buffer[k]);
+                cross[k] += l * r.conj();
+                power_left[k] += l.norm_sqr();
+                power_right[k] += r.norm_sqr();
+            }
+        }
+        
+        // Step 2: ITD and ILD per log-spaced band
+        let bin_hz = self.sample_rate / self.size as f32;
+        let (low_hz, high_hz) = (50.0f32, self.sample_rate / 2.0);
+        let edge = |b: usize| low_hz * (high_hz / low_hz).powf(b as f32 / self.cue_bands as f32);
+        let mut cues = Vec::with_capacity(3 * self.cue_bands);
+        for b in 0..self.cue_bands {
+            let (lo, hi) = (edge(b), edge(b + 1));
+            let first = ((lo / bin_hz).ceil() as usize).max(1);
+            let end = if b + 1 == self.cue_bands { n_bins } else { ((hi / bin_hz).ceil() as usize).min(n_bins) };
+            cues.push((lo * hi).sqrt());
+            if first >= end {
+                cues.extend([f32::NAN, f32::NAN]);
+                continue;
+            }
+            let left_power: f32 = power_left[first..end].iter().sum();
+            let right_power: f32 = power_right[first..end].iter().sum();
+            if left_power <= 0.0 || right_power <= 0.0 {
+                cues.extend([f32::NAN, f32::NAN]);
+                continue;
+            }
+            let omega = |k: usize| 2.0 * PI * k as f32 * bin_hz;
+            let itd = if end - first == 1 {
+                cross[first].arg() / omega(first)
+            } else {
+                let mut phases = Vec::with_capacity(end - first);
+                let mut previous = cross[first].arg();
+                let mut offset = 0.0f32;
+                for (k, c) in (first..end).zip(&cross[first..end]) {
+                    let phase = c.arg();
+                    let step = phase - previous;
+                    offset -= 2.0 * PI * (step / (2.0 * PI)).round();
+                    previous = phase;
+                    phases.push((omega(k), phase + offset, c.norm()));
+                }
+                weighted_line_fit(phases.iter().map(|&(x, y, w)| (x as f64, y as f64, w as f64))).0 as f32
+            };
+            cues.extend([itd, 10.0 * (left_power / right_power).log10()]);
+        }
+        cues
+    }
+
+    /// Power of the high band over the low band in dB, from the Welch PSD (50 %
+    /// overlap). A band `[min_hz, max_hz]` sums the bins whose centre frequencies
+    /// lie within it (edges inclusive, clipped to Nyquist); a band containing no
+    /// bin centre gives NaN, and a silent low band +inf.
+    #[wasm_bindgen]
+    pub fn band_energy_ratio(
+        &mut self,
+        samples: &[f32],
+        low_min_hz: f32,
+        low_max_hz: f32,
+        high_min_hz: f32,
+        high_max_hz: f32,
+    ) -> f32 {
+        let bin_hz = self.sample_rate / self.size as f32;
+        let n_bins = self.size / 2 + 1;
+        let band = |min_hz: f32, max_hz: f32| {
+            let first = (min_hz.max(0.0) / bin_hz).ceil() as usize;
+            let last = ((max_hz / bin_hz).floor().max(-1.0) as isize).min(n_bins as isize - 1);
+            (first as isize <= last).then_some(first..last as usize + 1)
+        };
+        let (Some(low), Some(high)) = (band(low_min_hz, low_max_hz), band(high_min_hz, high_max_hz)) else {
+            return f32::NAN;
+        };
+        let psd = self.welch_psd(samples, self.size / 2);
+        let low_power: f32 = psd[low].iter().sum();
+        let high_power: f32 = psd[high].iter().sum();
+        10.0 * (high_power / low_power).log10()
+    }
+
+    /// Phase coherence per bin (`size / 2 + 1` values, 0..1) over frames spaced by
+    /// `hop_size`: the length of the mean unit phasor of `X_t·conj(X_{t-1})`, i.e. how
+    /// constant the bin's phase advance is. A steady partial scores near 1. Noise
+    /// scores low, though heavily overlapped frames share samples and lift it
+    /// (about 0.5 at 75 % overlap, under 0.2 at 50 % or less). Frame pairs where the
+    /// bin is more than 60 dB under the loudest bin of the input count as
+    /// incoherent, so a bin lit only by a brief transient scores about the
+    /// fraction of frames it is present in.
+    #[wasm_bindgen]
+    pub fn phase_coherence(&mut self, samples: &[f32], hop_size: usize) -> Vec<f32> {
+        let n_bins = self.size / 2 + 1;
+        let hop = hop_size.max(1);
+        if samples.len() < self.size {
+            return vec![0.0; n_bins];
+        }
+        let num_frames = frame_count(samples.len(), self.size, hop);
+        if num_frames < 2 {
+            return vec![0.0; n_bins];
+        }
+        
+        // Step 1: loudest bin anywhere, for the significance floor
+        let mut loudest = 0.0f32;
+        for t in 0..num_frames {
+            self.transform(frame_slice(samples, t, self.size, hop));
+            loudest = self.buffer[..n_bins].iter().fold(loudest, |acc, c| acc.max(c.norm_sqr()));
+        }
+        let floor = loudest * 1e-6;
+        
+        // Step 2: mean unit phasor of each bin's frame-to-frame phase advance
+        let mut previous = vec![Complex::new(0.0f32, 0.0); n_bins];
+        let mut sum = vec![Complex::new(0.0f32, 0.0); n_bins];
+        for t in 0..num_frames {
+            self.transform(frame_slice(samples, t, self.size, hop));
+            for ((s, p), &c) in sum.iter_mut().zip(previous.iter_mut()).zip(&self.buffer[..n_bins]) {
+                if t > 0 && c.norm_sqr() > floor && p.norm_sqr() > floor {
+                    let advance = c * p.conj();
+                    *s += advance / advance.norm();
+                }
+                *p = c;
+            }
+        }
+        sum.iter().map(|s| s.norm() / (num_frames - 1) as f32).collect()
+    }
+
+    /// Number of partials in one frame: bins that are the largest within ±4 bins
+    /// (the Hann main lobe and first sidelobes) and stand `min_prominence_db` above
+    /// the local floor, the median dB level over ±16 bins. DC is skipped. White
+    /// noise peaks reach 10-12 dB over that median, so prominences of 15 dB or more
+    /// read noise as 0 partials.
+    #[wasm_bindgen]
+    pub fn estimate_num_partials(&mut self, samples: &[f32], min_prominence_db: f32) -> usize {
+        const PEAK_REACH: usize = 4;
+        const FLOOR_REACH: usize = 16;
+        let db = self.magnitude_db(samples);
+        let n_bins = db.len();
+        let mut window = Vec::with_capacity(2 * FLOOR_REACH + 1);
+        
+        (1..n_bins)
+            .filter(|&k| {
+                let neighborhood = &db[k.saturating_sub(PEAK_REACH)..(k + PEAK_REACH + 1).min(n_bins)];
+                if neighborhood.iter().any(|&v| v > db[k]) {
+                    return false;
+                }
+                window.clear();
+                window.extend_from_slice(&db[k.saturating_sub(FLOOR_REACH).max(1)..(k + FLOOR_REACH + 1).min(n_bins)]);
+                window.sort_by(|a, b| a.total_cmp(b));
+                db[k] - window[window.len() / 2] >= min_prominence_db
+            })
+            .count()
+    }
+
+    /// Spectral kurtosis per bin (`size / 2 + 1` values) over frames spaced by
+    /// `hop_size`: `E[|X|⁴] / E[|X|²]² - 2`, which is 0 for stationary Gaussian
+    /// noise, -1 for a steady tone and large for bins with intermittent energy.
+    /// Needs at least 8 frames for stable moments; returns empty otherwise.
+    #[wasm_bindgen]
+    pub fn spectral_kurtosis(&mut self, samples: &[f32], hop_size: usize) -> Vec<f32> {
+        const MIN_FRAMES: usize = 8;
+        let n_bins = self.size / 2 + 1;
+        let hop = hop_size.max(1);
+        if samples.len() < self.size {
+            return Vec::new();
+        }
+        let num_frames = frame_count(samples.len(), self.size, hop);
+        if num_frames < MIN_FRAMES {
+            return Vec::new();
+        }
+        
+        // Step 1: first and second moments of each bin's power across frames
+        let mut row = vec![0.0f32; n_bins];
+        let mut sum = vec![0.0f64; n_bins];
+        let mut sum_sq = vec![0.0f64; n_bins];
+        for t in 0..num_frames {
+            self.power_spectrum_into(frame_slice(samples, t, self.size, hop), &mut row);
+            for ((s, q), &p) in sum.iter_mut().zip(sum_sq.iter_mut()).zip(&row) {
+                *s += p as f64;
+                *q += p as f64 * p as f64;
+            }
+        }
+        
+        // Step 2: normalized fourth moment, 0 where the bin is silent
+        let count = num_frames as f64;
+        sum.iter()
+            .zip(&sum_sq)
+            .map(|(&s, &q)| {
+                let mean = s / count;
+                if mean > 1e-20 { (q / count / (mean * mean) - 2.0) as f32 } else { 0.0 }
+            })
+            .collect()
+    }
+}
+
+impl FftProcessor {
+    /// `spectrogram_ex` driving a closure with each frame's power row instead of
+    /// collecting them; the row buffer is reused and the first `Err` stops the scan.
+    pub fn spectrogram_with<E>(
+        &mut self,
+        samples: &[f32],
+        hop_size: usize,
+        window: WindowKind,
+        center: bool,
+        mut f: impl FnMut(usize, &[f32]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let hop = hop_size.max(1);
+        
+        let padded: Vec<f32>;
+        let signal = if center {
+            let pad = vec![0.0f32; self.size / 2];
+            padded = [pad.as_slice(), samples, pad.as_slice()].concat();
+            padded.as_slice()
+        } else {
+            samples
+        };
+        let num_frames = frame_count(signal.len(), self.size, hop);
+        
+        let previous = std::mem::replace(&mut self.window, cached_window(self.size, window));
+        let mut row = vec![0.0f32; self.size / 2 + 1];
+        let mut status = Ok(());
+        for t in 0..num_frames {
+            let frame = frame_slice(signal, t, self.size, hop);
+            if !frame.is_empty() && frame_energy(frame, true) < self.skip_silence_db {
+                row.fill(0.0);
+            } else {
+                self.power_spectrum_into(frame, &mut row);
+            }
+            status = f(t, &row);
+            if status.is_err() {
+                break;
+            }
+        }
+        self.window = previous;
+        
+        status
+    }
+}
+
+/// Move the zero-frequency bin to the center of the spectrum.
+/// For odd lengths DC lands at index `n / 2`, matching the numpy convention.
+#[wasm_bindgen]
+pub fn fftshift(spectrum: &[f32]) -> Vec<f32> {
+    let mut shifted = spectrum.to_vec();
+    shifted.rotate_right(spectrum.len() / 2);
+    shifted
+}
+
+/// Inverse of `fftshift` (identical to it for even lengths)
+#[wasm_bindgen]
+pub fn ifftshift(spectrum: &[f32]) -> Vec<f32> {
+    let mut unshifted = spectrum.to_vec();
+    unshifted.rotate_left(spectrum.len() / 2);
+    unshifted
+}
+
+/// Sliding DFT: the unwindowed spectrum of the last `size` samples, updated in
+/// O(size) per pushed sample instead of a full FFT per hop.
+/// Each push applies X[k] = (X[k] - x_oldest + x_new)·e^(i2πk/N); every
+/// `refresh_interval` pushes the bins are recomputed exactly by FFT to stop
+/// f32 rounding drift from accumulating.
+#[wasm_bindgen]
+pub struct SlidingDft {
+    size: usize,
+    history: Vec<f32>,
+    pos: usize,
+    twiddles: Vec<Complex<f32>>,
+    bins: Vec<Complex<f32>>,
+    fft: Arc<dyn Fft<f32>>,
+    buffer: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    refresh_interval: usize,
+    since_refresh: usize,
+}
+
+#[wasm_bindgen]
+impl SlidingDft {
+    #[wasm_bindgen(constructor)]
+    pub fn new(size: usize) -> SlidingDft {
+        let n_bins = size / 2 + 1;
+        let twiddles = (0..n_bins)
+            .map(|k| {
+                let angle = 2.0 * std::f64::consts::PI * k as f64 / size as f64;
+                Complex::new(angle.cos() as f32, angle.sin() as f32)
+            })
+            .collect();
+        let fft = FftPlanner::new().plan_fft_forward(size);
+        let scratch = vec![Complex::new(0.0, 0.0); fft.get_inplace_scratch_len()];
+        
+        console_log!("🦀 [Rust DSP] Sliding DFT: {} samples", size);
+        
+        SlidingDft {
+            size,
+            history: vec![0.0; size],
+            pos: 0,
+            twiddles,
+            bins: vec![Complex::new(0.0, 0.0); n_bins],
+            fft,
+            buffer: vec![Complex::new(0.0, 0.0); size],
+            scratch,
+            refresh_interval: 8 * size,
+            since_refresh: 0,
+        }
+    }
+
+    /// Add one sample, dropping the oldest from the window
+    #[wasm_bindgen]
+    pub fn push(&mut self, sample: f32) {
+        let delta = sample - self.history[self.pos];
+        self.history[self.pos] = sample;
+        self.pos = (self.pos + 1) % self.size;
+        
+        for (bin, &twiddle) in self.bins.iter_mut().zip(&self.twiddles) {
+            *bin = (*bin + delta) * twiddle;
+        }
+        
+        self.since_refresh += 1;
+        if self.since_refresh >= self.refresh_interval {
+            self.refresh();
+        }
+    }
+
+    /// Current spectrum, `size / 2 + 1` bins interleaved as `[re, im, re, im, ...]`
+    #[wasm_bindgen]
+    pub fn spectrum(&self) -> Vec<f32> {
+        self.bins.iter().flat_map(|c| [c.re, c.im]).collect()
+    }
+
+    /// Pushes between exact FFT recomputations (default 8 × size)
+    #[wasm_bindgen]
+    pub fn set_refresh_interval(&mut self, samples: usize) {
+        self.refresh_interval = samples.max(1);
+    }
+
+    /// Fingerprint of the size and refresh interval (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("SlidingDft")
+            .usize(self.size)
+            .usize(self.refresh_interval)
+            .finish()
+    }
+
+    /// Samples to push before the spectrum covers real input only: one full window
+    #[wasm_bindgen]
+    pub fn warmup_samples(&self) -> usize {
+        self.size
+    }
+
+    /// Push warmup input (typically `warmup_samples` of pre-roll)
+    #[wasm_bindgen]
+    pub fn prime(&mut self, samples: &[f32]) {
+        for &s in samples {
+            self.push(s);
+        }
+    }
+
+    /// Clear the window and spectrum
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.bins.fill(Complex::new(0.0, 0.0));
+        self.pos = 0;
+        self.since_refresh = 0;
+    }
+}
+
+impl SlidingDft {
+    /// Recompute the bins exactly from the window, oldest sample first
+    fn refresh(&mut self) {
+        let (newer, older) = self.history.split_at(self.pos);
+        for (b, &s) in self.buffer.iter_mut().zip(older.iter().chain(newer)) {
+            *b = Complex::new(s, 0.0);
+        }
+        self.fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+        let n_bins = self.bins.len();
+        self.bins.copy_from_slice(&self.buffer[..n_bins]);
+        self.since_refresh = 0;
+    }
+}
+
+/// DC offset of a block: the sample mean (accumulated in f64)
+#[wasm_bindgen]
+pub fn dc_offset(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|&s| s as f64).sum::<f64>() / samples.len() as f64) as f32
+}
+
+/// Gain in dB available before the sample peak reaches 0 dBFS, `-20·log10(max |x|)`.
+/// Capped at 120 dB (returned for silence); negative only if samples exceed ±1.
+#[wasm_bindgen]
+pub fn headroom_db(samples: &[f32]) -> f32 {
+    let peak = samples.iter().fold(0.0f32, |acc, &s| acc.max(s.abs()));
+    if peak <= 1e-6 {
+        return 120.0;
+    }
+    (-20.0 * peak.log10()).min(120.0)
+}
+
+/// Per-frame crest factor, `20·log10(peak / RMS)` in dB, over frames of `frame_size`
+/// samples every `hop_size` (the last frame is truncated at the buffer end).
+/// About 3 dB for a sine, 0 dB for a square wave and far higher for clicks;
+/// silent frames (RMS below 1e-9) report 0.
+#[wasm_bindgen]
+pub fn crest_factor_contour(samples: &[f32], frame_size: usize, hop_size: usize) -> Vec<f32> {
+    let frame_size = frame_size.max(1);
+    let hop_size = hop_size.max(1);
+    (0..frame_count(samples.len(), frame_size, hop_size))
+        .map(|i| {
+            let frame = frame_slice(samples, i, frame_size, hop_size);
+            if frame.is_empty() {
+                return 0.0;
+            }
+            let rms = frame_energy(frame, false).sqrt();
+            let peak = frame.iter().fold(0.0f32, |acc, &s| acc.max(s.abs()));
+            if rms < 1e-9 { 0.0 } else { 20.0 * (peak / rms).log10() }
+        })
+        .collect()
+}
+
+/// Reverberation time in seconds of an impulse response; see `rt60_fit`
+#[wasm_bindgen]
+pub fn rt60(impulse: &[f32], sample_rate: f32) -> f32 {
+    rt60_fit(impulse, sample_rate)[0]
+}
+
+/// RT60 as `[seconds, fit_range_db]`, where the range is 30 (T30) or 20 (T20).
+/// From the IR's peak on, the noise floor is the mean power of the last 10 %;
+/// the IR is cut where a 10 ms moving power falls within 10 dB of that floor,
+/// the floor is subtracted, and the Schroeder backward integral gives the
+/// energy decay curve. A line fit from -5 dB down to -35 dB (T30) is
+/// extrapolated to -60 dB. T30 needs the first 10 ms to sit at least 45 dB above
+/// the floor; otherwise T20 (-5 to -25 dB, 35 dB needed) is used, and with less
+/// range than that the result is `[NaN, 0]`.
+#[wasm_bindgen]
+pub fn rt60_fit(impulse: &[f32], sample_rate: f32) -> Vec<f32> {
+    let peak = impulse.iter().enumerate().fold((0, 0.0f32), |best, (i, &x)| if x.abs() > best.1 { (i, x.abs()) } else { best }).0;
+    let energy: Vec<f64> = impulse[peak..].iter().map(|&x| x as f64 * x as f64).collect();
+    let n = energy.len();
+    if n < 16 {
+        return vec![f32::NAN, 0.0];
+    }
+    
+    // Step 1: noise floor and truncation point
+    let tail = &energy[n - (n / 10).max(1)..];
+    let noise = tail.iter().sum::<f64>() / tail.len() as f64;
+    let block = ((0.01 * sample_rate) as usize).clamp(1, n);
+    let mut end = n;
+    if noise > 0.0 {
+        let mut sum: f64 = energy[..block].iter().sum();
+        for i in block..n {
+            if sum / block as f64 <= 10.0 * noise {
+                end = i;
+                break;
+            }
+            sum += energy[i] - energy[i - block];
+        }
+    }
+    
+    // Step 2: Schroeder backward integration of the floor-compensated energy
+    let mut edc = vec![0.0f64; end];
+    let mut acc = 0.0f64;
+    for i in (0..end).rev() {
+        acc += (energy[i] - noise).max(0.0);
+        edc[i] = acc;
+    }
+    if acc <= 0.0 {
+        return vec![f32::NAN, 0.0];
+    }
+    let edc_db: Vec<f64> = edc.iter().map(|&e| 10.0 * (e / acc + 1e-300).log10()).collect();
+    
+    // Step 3: line fit over the widest range the decay reaches with 10 dB to spare
+    let onset = energy[..block].iter().sum::<f64>() / block as f64;
+    let dynamic_range = if noise > 0.0 { 10.0 * (onset / noise).log10() } else { f64::INFINITY };
+    let reaches = |db: f64| dynamic_range >= 10.0 - db && edc_db.iter().any(|&e| e <= db);
+    let range = if reaches(-35.0) { 30.0 } else if reaches(-25.0) { 20.0 } else { return vec![f32::NAN, 0.0] };
+    let points: Vec<(f64, f64)> = edc_db.iter()
+        .enumerate()
+        .filter(|(_, &e)| e <= -5.0 && e >= -5.0 - range)
+        .map(|(i, &e)| (i as f64 / sample_rate as f64, e))
+        .collect();
+    let (slope, _) = line_fit(&points);
+    if slope >= 0.0 {
+        return vec![f32::NAN, 0.0];
+    }
+    vec![(-60.0 / slope) as f32, range as f32]
+}
+
+/// Decay rate of a note tail in dB per second (positive while the note dies
+/// away). A 20 ms RMS envelope at 10 ms hops is taken in dB; the attack is
+/// skipped by starting at the first frame 3 dB under the envelope peak, and
+/// the tail ends where the level first comes within 10 dB of the noise floor
+/// (the mean power of the last 10 % of frames after the peak). A line fit of
+/// dB against time over that region gives the rate. NaN when the region
+/// holds fewer than 3 frames or the level does not fall.
+#[wasm_bindgen]
+pub fn decay_rate(samples: &[f32], sample_rate: f32) -> f32 {
+    let hop = ((0.01 * sample_rate) as usize).max(1);
+    let frame = 2 * hop;
+    if samples.len() < frame {
+        return f32::NAN;
+    }
+    
+    // Step 1: dB envelope and its peak
+    let env: Vec<f32> = (0..frame_count(samples.len(), frame, hop))
+        .map(|i| frame_energy(frame_slice(samples, i, frame, hop), true))
+        .collect();
+    let peak = env.iter().enumerate().fold(0, |best, (i, &e)| if e > env[best] { i } else { best });
+    
+    // Step 2: decay region between the attack and the noise floor
+    let after = &env[peak..];
+    let tail = &after[after.len() - (after.len() / 10).max(1)..];
+    let floor = 10.0 * (tail.iter().map(|&e| 10f32.powf(e / 10.0)).sum::<f32>() / tail.len() as f32).log10();
+    let Some(start) = after.iter().position(|&e| e <= env[peak] - 3.0) else { return f32::NAN };
+    let end = after[start..].iter().position(|&e| e <= floor + 10.0).map_or(after.len(), |i| start + i);
+    let region = &after[start..end];
+    if region.len() < 3 {
+        return f32::NAN;
+    }
+    
+    // Step 3: least-squares line of dB against seconds
+    let seconds = hop as f32 / sample_rate;
+    let points: Vec<(f32, f32)> = region.iter().enumerate().map(|(i, &e)| (i as f32 * seconds, e)).collect();
+    let (slope, _) = line_fit(&points);
+    if slope >= 0.0 {
+        return f32::NAN;
+    }
+    -slope as f32
+}
+
+/// Mono fold-down check as `[correlation, mono_energy_loss_db]`. Correlation is the
+/// zero-lag normalized cross-correlation (-1 anti-phase ... +1 in phase); the loss
+/// is the mean stereo channel energy over the energy of `(L + R) / 2`, in dB:
+/// 0 dB in phase, about 3 dB for uncorrelated channels, up to 120 dB (capped)
+/// when the channels cancel. Silence gives `[0, 0]`.
+#[wasm_bindgen]
+pub fn mono_compatibility(left: &[f32], right: &[f32]) -> Vec<f32> {
+    let (mut ll, mut rr, mut lr) = (0.0f64, 0.0f64, 0.0f64);
+    for (&l, &r) in left.iter().zip(right) {
+        ll += l as f64 * l as f64;
+        rr += r as f64 * r as f64;
+        lr += l as f64 * r as f64;
+    }
+    if ll + rr < 1e-20 {
+        return vec![0.0, 0.0];
+    }
+    let correlation = lr / (ll * rr).sqrt().max(1e-30);
+    let stereo = (ll + rr) / 2.0;
+    let mono = (ll + rr + 2.0 * lr) / 4.0;
+    let loss_db = (10.0 * (stereo / mono.max(1e-30)).log10()).min(120.0);
+    vec![correlation as f32, loss_db as f32]
+}
+
+/// Subtract the block's DC offset in place, leaving a zero-mean signal
+#[wasm_bindgen]
+pub fn remove_dc(samples: &mut [f32]) {
+    let offset = dc_offset(samples);
+    samples.iter_mut().for_each(|s| *s -= offset);
+}
+
+/// Largest power of two that fits in `signal_len`, but never below `min_size`
+/// (rounded up to a power of two), for sizing an `FftProcessor` without truncation
+#[wasm_bindgen]
+pub fn recommended_fft_size(signal_len: usize, min_size: usize) -> usize {
+    let floor = min_size.max(1).next_power_of_two();
+    if signal_len == 0 {
+        return floor;
+    }
+    let largest = 1usize << (usize::BITS - 1 - signal_len.leading_zeros());
+    largest.max(floor)
+}
+
+/// Smallest power-of-two FFT size whose bin spacing is at most `target_hz_per_bin`
+#[wasm_bindgen]
+pub fn fft_size_for_resolution(sample_rate: f32, target_hz_per_bin: f32) -> usize {
+    if target_hz_per_bin <= 0.0 {
+        return 1;
+    }
+    ((sample_rate / target_hz_per_bin).ceil().max(1.0) as usize).next_power_of_two()
+}
+
+/// Smallest power-of-two `PitchDetector` frame size that resolves `fmin_hz`: YIN
+/// searches lags up to half the frame, so the frame must hold two full periods
+/// plus the samples the dip search reads past the period. At this size enable
+/// `set_frame_normalization`: the plain difference function only dips below the
+/// threshold at lags under about `threshold * frame_size`.
+#[wasm_bindgen]
+pub fn pitch_frame_size_for_fmin(sample_rate: f32, fmin_hz: f32) -> usize {
+    if fmin_hz <= 0.0 {
+        return 1;
+    }
+    let period = (sample_rate / fmin_hz).ceil().max(1.0) as usize;
+    (2 * (period + 2)).next_power_of_two()
+}
+
+/// Bin spacing in Hz of an FFT of `fft_size` points
+#[wasm_bindgen]
+pub fn frequency_resolution(sample_rate: f32, fft_size: usize) -> f32 {
+    sample_rate / fft_size.max(1) as f32
+}
+
+/// Hop size (at least 1 sample) whose frame rate is closest to `target_fps`
+#[wasm_bindgen]
+pub fn hop_for_frame_rate(sample_rate: f32, target_fps: f32) -> usize {
+    if target_fps <= 0.0 {
+        return 1;
+    }
+    ((sample_rate / target_fps).round() as usize).max(1)
+}
+
+/// Analysis frames per second for a hop size
+#[wasm_bindgen]
+pub fn frame_rate(sample_rate: f32, hop_size: usize) -> f32 {
+    sample_rate / hop_size.max(1) as f32
+}
+
+/// Real-time framing `[frame_size, hop_size]` for a latency budget. Latency model:
+/// a block-based processor waits for a full frame, then emits a hop of output,
+/// so the delay is `(frame_size + hop_size) / sample_rate`. The frame is the
+/// smallest power of two holding two periods of `min_freq_hz` (clear of DC under
+/// a Hann main lobe); the hop is the largest power of two up to half the frame
+/// (50 % overlap, least CPU) that keeps the delay within `max_latency_ms`.
+/// Conflicting constraints (even a 1-sample hop would exceed the budget) or
+/// invalid arguments give an empty vector.
+#[wasm_bindgen]
+pub fn recommend_framing(sample_rate: f32, max_latency_ms: f32, min_freq_hz: f32) -> Vec<u32> {
+    if sample_rate.is_nan() || sample_rate <= 0.0 || min_freq_hz.is_nan() || min_freq_hz <= 0.0 {
+        return Vec::new();
+    }
+    let frame = ((2.0 * sample_rate / min_freq_hz).ceil().max(2.0) as usize).next_power_of_two();
+    let budget = (max_latency_ms * 0.001 * sample_rate).floor();
+    let mut hop = frame / 2;
+    while hop >= 1 {
+        if (frame + hop) as f32 <= budget {
+            return vec![frame as u32, hop as u32];
+        }
+        hop /= 2;
+    }
+    Vec::new()
+}
+
+/// Default mel band count for a sample rate: one band per 100 mel (HTK scale,
+/// `2595·log10(1 + f/700)`) from 0 Hz to Nyquist, so 16 kHz gives 28 bands and
+/// 48 kHz 40. Capped at `fft_size / 2`, below the `fft_size / 2 + 1` bins a
+/// filterbank can draw on; 0 for invalid arguments.
+#[wasm_bindgen]
+pub fn recommended_mel_bins(sample_rate: f32, fft_size: usize) -> usize {
+    if sample_rate.is_nan() || sample_rate <= 0.0 {
+        return 0;
+    }
+    let nyquist_mel = 2595.0 * (1.0 + sample_rate / 2.0 / 700.0).log10();
+    ((nyquist_mel / 100.0).round().max(1.0) as usize).min(fft_size / 2)
+}
+
+/// Analysis a `recommend_config` report is tuned for
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnalysisTask {
+    /// Speech/silence decisions with `VoiceActivityDetector`
+    SpeechVad,
+    /// Instrument and singing f0 with `PitchDetector`
+    MusicPitch,
+    /// LPC formant tracking with `FormantAnalyzer`
+    FormantAnalysis,
+    /// Magnitude or mel spectrogram display with `FftProcessor`
+    Spectrogram,
+}
+
+/// Suggested analysis parameters for `task` at `sample_rate`, as a JSON object.
+/// Every report has `task`, `sample_rate`, `frame_size`, `hop_size`, `fft_size`
+/// and `window` (a `WindowKind` variant name); frames are powers of two with
+/// `fft_size == frame_size` and `hop_size <= frame_size`. Task-specific keys
+/// name the constructor argument or setter they feed:
+/// - `SpeechVad`: frame nearest 25 ms, 10 ms hop, `energy_threshold_db` and
+///   `zcr_threshold` for `set_thresholds`
+/// - `MusicPitch`: frame from `pitch_frame_size_for_fmin` at 40 Hz,
+///   `min_frequency`/`max_frequency`, `yin_threshold`, `frame_normalization`
+/// - `FormantAnalysis`: frame nearest 25 ms, `analysis_sample_rate` (10 kHz,
+///   or the input rate below that) and `lpc_order` (2 + kHz),
+///   `min_prediction_gain_db`, Hamming window
+/// - `Spectrogram`: frame nearest 46 ms, 75 % overlap, `mel_bins`
+/// An invalid sample rate gives `{}`.
+#[wasm_bindgen]
+pub fn recommend_config(task: AnalysisTask, sample_rate: f32) -> String {
+    if sample_rate.is_nan() || sample_rate <= 0.0 {
+        return "{}".to_string();
+    }
+    let pow2_near_seconds = |seconds: f32| 1usize << ((seconds * sample_rate).max(2.0).log2().round() as u32);
+    
+    // Step 1: framing and window per task, plus the task's own parameters
+    let (frame, hop, window, extra) = match task {
+        AnalysisTask::SpeechVad => (
+            pow2_near_seconds(0.025),
+            hop_for_frame_rate(sample_rate, 100.0),
+            WindowKind::Hann,
+            "\"energy_threshold_db\":-40,\"zcr_threshold\":0.1".to_string(),
+        ),
+        AnalysisTask::MusicPitch => {
+            let (min_hz, max_hz) = (40.0f32, 2000.0f32.min(sample_rate / 4.0));
+            (
+                pitch_frame_size_for_fmin(sample_rate, min_hz),
+                hop_for_frame_rate(sample_rate, 100.0),
+                WindowKind::Rectangular,
+                format!(
+                    "\"min_frequency\":{},\"max_frequency\":{},\"yin_threshold\":0.1,\"frame_normalization\":true",
+                    min_hz, max_hz
+                ),
+            )
+        }
+        AnalysisTask::FormantAnalysis => {
+            let analysis_rate = sample_rate.min(10000.0);
+            (
+                pow2_near_seconds(0.025),
+                hop_for_frame_rate(sample_rate, 100.0),
+                WindowKind::Hamming,
+                format!(
+                    "\"analysis_sample_rate\":{},\"lpc_order\":{},\"min_prediction_gain_db\":6",
+                    analysis_rate,
+                    2 + (analysis_rate / 1000.0).round() as usize
+                ),
+            )
+        }
+        AnalysisTask::Spectrogram => {
+            let frame = pow2_near_seconds(0.046);
+            (frame, frame / 4, WindowKind::Hann, format!("\"mel_bins\":{}", recommended_mel_bins(sample_rate, frame)))
+        }
+    };
+    
+    // Step 2: serialize, keeping the hop within the frame
+    format!(
+        "{{\"task\":\"{:?}\",\"sample_rate\":{},\"frame_size\":{},\"hop_size\":{},\"fft_size\":{},\"window\":\"{:?}\",{}}}",
+        task,
+        sample_rate,
+        frame,
+        hop.clamp(1, frame),
+        frame,
+        window,
+        extra
+    )
+}
+
+/// Hop sizes (ascending divisors of `fft_size`) for which `window_kind` satisfies
+/// constant overlap-add. Each candidate's overlap sum `Σ_k w[n + k·hop]` is computed
+/// over one hop period and accepted when its peak-to-peak ripple is under 1 % of
+/// its mean. The windows are symmetric, so e.g. Hann passes at 1/2 overlap only
+/// from 256 points up, where the endpoint ripple falls below that.
+#[wasm_bindgen]
+pub fn cola_hops(window_kind: WindowKind, fft_size: usize) -> Vec<usize> {
+    let window = cached_window(fft_size, window_kind);
+    (1..=fft_size)
+        .filter(|&hop| fft_size.is_multiple_of(hop))
+        .filter(|&hop| {
+            let sums: Vec<f32> = (0..hop).map(|n| window[n..].iter().step_by(hop).sum()).collect();
+            let (min, max) = sums.iter().fold((f32::INFINITY, 0.0f32), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+            let mean = sums.iter().sum::<f32>() / hop as f32;
+            mean > 0.0 && max - min < 0.01 * mean
+        })
+        .collect()
+}
+
+/// Novelty function behind `VoiceActivityDetector::transient_density`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoveltyMode {
+    /// Rise of the 10 ms RMS envelope in dB (default)
+    EnergyRise,
+    /// Rise of the magnitude spectrum, summed over bins
+    SpectralFlux,
+    /// Deviation of each bin from its magnitude and phase predicted from the
+    /// previous two frames, which also catches soft note changes
+    ComplexDomain,
+}
+
+/// Which bin changes `NoveltyMode::SpectralFlux` sums
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FluxRectify {
+    /// Magnitude rises only, so note-offs do not register (default)
+    PositiveOnly,
+    /// Rises and falls alike
+    Full,
+    /// Euclidean norm of the rises, against the previous frame's Euclidean norm
+    L2,
+}
+
+/// Voice Activity Detection - OPTIMIZED
+#[wasm_bindgen]
+pub struct VoiceActivityDetector {
+    frame_size: usize,
+    hop_size: usize,
+    energy_threshold: f32,
+    zcr_threshold: f32,
+    hangover_frames: usize,
+    // Optional spectral gates: reject tonal (crest above) or noise-like (flatness above) frames
+    use_crest: bool,
+    use_flatness: bool,
+    crest_threshold_db: f32,
+    flatness_threshold: f32,
+    // Sliding Otsu calibration window in frames (0 = fixed threshold)
+    calibration_window: usize,
+    // Energy rise (dB) that marks an onset in transient_density
+    onset_rise_db: f32,
+    // Novelty function the onsets are picked from
+    novelty_mode: NoveltyMode,
+    flux_rectify: FluxRectify,
+    // Interior mutability keeps the detection API `&self`
+    spectrum: RefCell<(FftProcessor, Vec<f32>)>,
+}
+
+#[wasm_bindgen]
+impl VoiceActivityDetector {
+    #[wasm_bindgen(constructor)]
+    pub fn new(frame_size: usize, hop_size: usize) -> VoiceActivityDetector {
+        console_log!("🦀 [Rust DSP] VAD initialized: frame={}, hop={}", frame_size, hop_size);
+        
+        VoiceActivityDetector {
+            frame_size,
+            hop_size,
+            energy_threshold: -40.0,
+            zcr_threshold: 0.1,
+            hangover_frames: 5,
+            use_crest: false,
+            use_flatness: false,
+            crest_threshold_db: 35.0,
+            flatness_threshold: 0.5,
+            calibration_window: 0,
+            onset_rise_db: 9.0,
+            novelty_mode: NoveltyMode::EnergyRise,
+            flux_rectify: FluxRectify::PositiveOnly,
+            spectrum: RefCell::new((FftProcessor::new(frame_size), vec![0.0; frame_size / 2 + 1])),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn set_thresholds(&mut self, energy_db: f32, zcr: f32) {
+        self.energy_threshold = energy_db;
+        self.zcr_threshold = zcr;
+    }
+
+    /// Enable the spectral decision features (both off by default): frames whose
+    /// crest exceeds the crest threshold (sustained tones, music) or whose flatness
+    /// exceeds the flatness threshold (broadband noise) are not speech.
+    #[wasm_bindgen]
+    pub fn set_spectral_features(&mut self, use_crest: bool, use_flatness: bool) {
+        self.use_crest = use_crest;
+        self.use_flatness = use_flatness;
+    }
+
+    /// Re-estimate the energy threshold per frame by Otsu's method over the
+    /// `window_frames` frames around it, so the split follows slow gain changes.
+    /// The window is shifted inward at the buffer edges (no warmup); windows
+    /// spanning under 10 dB (all speech or all silence) keep the previous
+    /// calibrated threshold. 0 (default) keeps the fixed threshold.
+    #[wasm_bindgen]
+    pub fn set_sliding_calibration(&mut self, window_frames: usize) {
+        self.calibration_window = window_frames;
+    }
+
+    /// Energy rise in dB over 10 ms that `transient_density` counts as an onset
+    /// (default 9); lower is more sensitive
+    #[wasm_bindgen]
+    pub fn set_onset_sensitivity(&mut self, rise_db: f32) {
+        self.onset_rise_db = rise_db.max(0.0);
+    }
+
+    /// Novelty function `transient_density` picks onsets from (default
+    /// `EnergyRise`). The spectral modes read `frame_size` Hann frames ending
+    /// where each 10 ms energy frame ends, one per 5 ms hop, and express the
+    /// bin-summed change against the previous frame's summed magnitude as
+    /// `20·log10(1 + change / previous)`, so a broadband level jump scores its
+    /// dB rise in every mode and one onset sensitivity serves them all.
+    #[wasm_bindgen]
+    pub fn set_novelty_mode(&mut self, mode: NoveltyMode) {
+        self.novelty_mode = mode;
+    }
+
+    /// Rectification of the `SpectralFlux` novelty (default `PositiveOnly`)
+    #[wasm_bindgen]
+    pub fn set_flux_rectify(&mut self, mode: FluxRectify) {
+        self.flux_rectify = mode;
+    }
+
+    /// Spectral crest limit in dB (default 35) and flatness limit 0..1 (default 0.5)
+    #[wasm_bindgen]
+    pub fn set_spectral_thresholds(&mut self, crest_db: f32, flatness: f32) {
+        self.crest_threshold_db = crest_db;
+        self.flatness_threshold = flatness;
+    }
+
+    /// Fingerprint of the framing, thresholds and enabled features (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("VoiceActivityDetector")
+            .usize(self.frame_size)
+            .usize(self.hop_size)
+            .f32(self.energy_threshold)
+            .f32(self.zcr_threshold)
+            .usize(self.hangover_frames)
+            .bool(self.use_crest)
+            .bool(self.use_flatness)
+            .f32(self.crest_threshold_db)
+            .f32(self.flatness_threshold)
+            .usize(self.calibration_window)
+            .f32(self.onset_rise_db)
+            .usize(self.novelty_mode as usize)
+            .usize(self.flux_rectify as usize)
+            .finish()
+    }
+
+    /// Per-frame `[crest_db, flatness, ...]` with the VAD's framing: crest is the
+    /// magnitude-spectrum peak over its mean in dB, flatness the geometric over the
+    /// arithmetic mean of the power spectrum (DC excluded from both)
+    #[wasm_bindgen]
+    pub fn spectral_contour(&self, samples: &[f32]) -> Vec<f32> {
+        (0..self.num_frames(samples.len()))
+            .flat_map(|i| self.spectral_features(self.frame(samples, i)))
+            .collect()
+    }
+
+    /// `[crest_db, flatness]` of one frame
+    fn spectral_features(&self, frame: &[f32]) -> [f32; 2] {
+        let mut spectrum = self.spectrum.borrow_mut();
+        let (fft, power) = &mut *spectrum;
+        fft.power_spectrum_into(frame, power);
+        
+        // Step 1: crest of the magnitude spectrum
+        let bins = &power[1..];
+        let count = bins.len().max(1) as f32;
+        let mean_magnitude = bins.iter().map(|p| p.sqrt()).sum::<f32>() / count + 1e-12;
+        let peak = bins.iter().fold(0.0f32, |acc, &p| acc.max(p)).sqrt();
+        
+        // Step 2: flatness of the power spectrum
+        let mean = bins.iter().sum::<f32>() / count + 1e-12;
+        let log_mean = bins.iter().map(|&p| (p + 1e-12).ln()).sum::<f32>() / count;
+        [20.0 * (peak / mean_magnitude + 1e-12).log10(), (log_mean.exp() / mean).min(1.0)]
+    }
+
+    /// Number of analysis frames for a buffer of `len` samples
+    fn num_frames(&self, len: usize) -> usize {
+        frame_count(len, self.frame_size, self.hop_size)
+    }
+
+    /// Frame `i` of `samples` (truncated at the end of the buffer)
+    fn frame<'a>(&self, samples: &'a [f32], i: usize) -> &'a [f32] {
+        frame_slice(samples, i, self.frame_size, self.hop_size)
+    }
+
+    #[wasm_bindgen]
+    pub fn detect(&self, samples: &[f32]) -> Vec<u8> {
+        let num_frames = self.num_frames(samples.len());
+        let mut vad = vec![0u8; num_frames];
+        self.detect_into(samples, &mut vad);
+        vad
+    }
+
+    /// Alloc-free `detect`: writes one decision per frame into `out`, returns frames written
+    #[wasm_bindgen]
+    pub fn detect_into(&self, samples: &[f32], out: &mut [u8]) -> usize {
+        let num_frames = self.num_frames(samples.len()).min(out.len());
+        let mut hangover_counter = 0;
+        let thresholds = if self.calibration_window > 0 {
+            self.sliding_thresholds(samples)
+        } else {
+            Vec::new()
+        };
+        
+        for (i, decision) in out.iter_mut().take(num_frames).enumerate() {
+            let frame = self.frame(samples, i);
+            
+            // Vectorized energy computation
+            let energy_db = frame_energy(frame, true);
+            
+            // Vectorized ZCR
+            let zcr = zero_crossing_rate(frame);
+            
+            let threshold = thresholds.get(i).copied().unwrap_or(self.energy_threshold);
+            let mut is_speech = energy_db > threshold && zcr < self.zcr_threshold;
+            if is_speech && (self.use_crest || self.use_flatness) {
+                let [crest_db, flatness] = self.spectral_features(frame);
+                let tonal = self.use_crest && crest_db > self.crest_threshold_db;
+                let noisy = self.use_flatness && flatness > self.flatness_threshold;
+                is_speech = !tonal && !noisy;
+            }
+            
+            if is_speech {
+                hangover_counter = self.hangover_frames;
+            }
+            
+            if hangover_counter > 0 {
+                *decision = 1;
+                hangover_counter -= 1;
+            } else {
+                *decision = 0;
+            }
+        }
+        
+        num_frames
+    }
+
+    /// Per-sample 0/1 gate, `samples.len()` long. Frame i's decision covers its hop,
+    /// samples `[i * hop, (i + 1) * hop)`, the same mapping as `get_segments`;
+    /// samples after the last frame's hop are 0.
+    #[wasm_bindgen]
+    pub fn detect_sample_mask(&self, samples: &[f32]) -> Vec<u8> {
+        let mut mask = vec![0u8; samples.len()];
+        for (chunk, &v) in mask.chunks_mut(self.hop_size).zip(&self.detect(samples)) {
+            chunk.fill(v);
+        }
+        mask
+    }
+
+    /// Run-length encoded `detect` output: `[value, count, value, count, ...]`
+    #[wasm_bindgen]
+    pub fn detect_rle(&self, samples: &[f32]) -> Vec<u32> {
+        let vad = self.detect(samples);
+        let mut runs: Vec<u32> = Vec::new();
+        for &v in &vad {
+            let n = runs.len();
+            if n >= 2 && runs[n - 2] == v as u32 {
+                runs[n - 1] += 1;
+            } else {
+                runs.push(v as u32);
+                runs.push(1);
+            }
+        }
+        runs
+    }
+
+    /// Energy threshold (dB) separating silence from speech by Otsu's method on a
+    /// 64-bin histogram of the per-frame energies: the split maximizing the
+    /// between-class variance, centred when that maximum is flat across a gap
+    /// between the modes. When the frame energies span under 10 dB
+    /// (all speech or all silence, no second mode) the configured threshold is
+    /// returned unchanged. Pass the result to `set_thresholds`.
+    #[wasm_bindgen]
+    pub fn auto_threshold(&self, samples: &[f32]) -> f32 {
+        otsu_threshold(&self.energy_contour(samples, true)).unwrap_or(self.energy_threshold)
+    }
+
+    /// Two-component Gaussian mixture over the per-frame energies (dB), fitted
+    /// by EM, as `[noise_mean, noise_std, speech_mean, speech_std, threshold]`.
+    /// A soft-assignment generalization of `auto_threshold`: EM starts from a
+    /// split at the median and stops once the mean log-likelihood improves by
+    /// under 1e-6 (or after 200 iterations), with standard deviations floored at
+    /// 0.5 dB so a run of identical silent frames cannot collapse a component.
+    /// The threshold is where the two weighted densities cross between the
+    /// means (equal posterior); it is NaN when one component swallows the other.
+    /// Fewer than 4 frames, or energies spanning under 1 dB, give all NaN.
+    #[wasm_bindgen]
+    pub fn fit_energy_gmm(&self, samples: &[f32]) -> Vec<f32> {
+        const MAX_ITERATIONS: usize = 200;
+        const MIN_STD: f64 = 0.5;
+        let energies: Vec<f64> = self.energy_contour(samples, true).iter().map(|&e| e as f64).collect();
+        let n = energies.len();
+        let (lo, hi) = energies.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &e| (lo.min(e), hi.max(e)));
+        if n < 4 || hi - lo < 1.0 {
+            return vec![f32::NAN; 5];
+        }
+        
+        // Step 1: initialize from the halves either side of the median
+        let mut sorted = energies.clone();
+        sorted.sort_by(f64::total_cmp);
+        let moments = |half: &[f64]| {
+            let mean = half.iter().sum::<f64>() / half.len() as f64;
+            let var = half.iter().map(|&e| (e - mean) * (e - mean)).sum::<f64>() / half.len() as f64;
+            (mean, var.sqrt().max(MIN_STD))
+        };
+        let (lower, upper) = sorted.split_at(n / 2);
+        let (mut mean, mut std) = ([0.0f64; 2], [0.0f64; 2]);
+        (mean[0], std[0]) = moments(lower);
+        (mean[1], std[1]) = moments(upper);
+        let mut weight = [0.5f64; 2];
+        
+        // Step 2: EM until the log-likelihood stops improving
+        let log_density = |x: f64, w: f64, m: f64, s: f64| {
+            w.ln() - s.ln() - 0.5 * (2.0 * std::f64::consts::PI).ln() - 0.5 * ((x - m) / s).powi(2)
+        };
+        let mut responsibility = vec![0.0f64; n];
+        let mut previous = f64::NEG_INFINITY;
+        for _ in 0..MAX_ITERATIONS {
+            // E-step: posterior of the speech component, via log-sum-exp
+            let mut log_likelihood = 0.0;
+            for (r, &x) in responsibility.iter_mut().zip(&energies) {
+                let a = log_density(x, weight[0], mean[0], std[0]);
+                let b = log_density(x, weight[1], mean[1], std[1]);
+                let top = a.max(b);
+                let total = top + ((a - top).exp() + (b - top).exp()).ln();
+                *r = (b - total).exp();
+                log_likelihood += total;
+            }
+            log_likelihood /= n as f64;
+            
+            // M-step: re-estimate weights, means and floored deviations
+            let speech: f64 = responsibility.iter().sum();
+            let noise = n as f64 - speech;
+            if speech < 1e-9 || noise < 1e-9 {
+                break;
+            }
+            for (c, share) in [(0, noise), (1, speech)] {
+                let post = |r: f64| if c == 1 { r } else { 1.0 - r };
+                let m = responsibility.iter().zip(&energies).map(|(&r, &x)| post(r) * x).sum::<f64>() / share;
+                let var = responsibility.iter().zip(&energies)
+                    .map(|(&r, &x)| post(r) * (x - m) * (x - m))
+                    .sum::<f64>() / share;
+                mean[c] = m;
+                std[c] = var.sqrt().max(MIN_STD);
+                weight[c] = share / n as f64;
+            }
+            
+            if log_likelihood - previous < 1e-6 {
+                break;
+            }
+            previous = log_likelihood;
+        }
+        
+        // Step 3: keep the quieter component as noise
+        if mean[0] > mean[1] {
+            mean.swap(0, 1);
+            std.swap(0, 1);
+            weight.swap(0, 1);
+        }
+        
+        // Step 4: equal-posterior crossing between the means, by bisection
+        let margin = |x: f64| log_density(x, weight[0], mean[0], std[0]) - log_density(x, weight[1], mean[1], std[1]);
+        let threshold = if weight[0].min(weight[1]) > 1e-9 && margin(mean[0]) > 0.0 && margin(mean[1]) < 0.0 {
+            let (mut a, mut b) = (mean[0], mean[1]);
+            for _ in 0..60 {
+                let mid = 0.5 * (a + b);
+                if margin(mid) > 0.0 { a = mid } else { b = mid }
+            }
+            (0.5 * (a + b)) as f32
+        } else {
+            f32::NAN
+        };
+        vec![mean[0] as f32, std[0] as f32, mean[1] as f32, std[1] as f32, threshold]
+    }
+
+    /// Per-frame Otsu thresholds for the sliding calibration mode
+    fn sliding_thresholds(&self, samples: &[f32]) -> Vec<f32> {
+        let energies = self.energy_contour(samples, true);
+        let n = energies.len();
+        let window = self.calibration_window.min(n);
+        
+        // Step 1: Otsu split of the window centred on each frame
+        let local: Vec<Option<f32>> = (0..n)
+            .map(|i| {
+                let start = i.saturating_sub(window / 2).min(n - window);
+                otsu_threshold(&energies[start..start + window])
+            })
+            .collect();
+        
+        // Step 2: single-mode windows keep the previous calibrated threshold
+        let mut current = local.iter().flatten().next().copied().unwrap_or(self.energy_threshold);
+        local.iter()
+            .map(|t| {
+                if let Some(t) = t {
+                    current = *t;
+                }
+                current
+            })
+            .collect()
+    }
+
+    /// Per-frame energy using exactly the VAD's framing and energy definition,
+    /// so an overlay lines up with `detect` (dB when `db` is set, else mean square)
+    #[wasm_bindgen]
+    pub fn energy_contour(&self, samples: &[f32], db: bool) -> Vec<f32> {
+        (0..self.num_frames(samples.len()))
+            .map(|i| frame_energy(self.frame(samples, i), db))
+            .collect()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_segments(&self, samples: &[f32]) -> Vec<u32> {
+        let vad = self.detect(samples);
+        let mut segments = Vec::new();
+        let mut in_segment = false;
+        let mut start = 0u32;
+        
+        for (i, &v) in vad.iter().enumerate() {
+            if v == 1 && !in_segment {
+                start = (i * self.hop_size) as u32;
+                in_segment = true;
+            } else if v == 0 && in_segment {
+                segments.push(start);
+                segments.push((i * self.hop_size) as u32);
+                in_segment = false;
+            }
+        }
+        
+        if in_segment {
+            segments.push(start);
+            segments.push((vad.len() * self.hop_size) as u32);
+        }
+        
+        segments
+    }
+
+    /// Silence runs of at least `min_silence_ms` as `[start, end, ...]` sample pairs.
+    /// These are the gaps between `get_segments` speech segments, including the
+    /// leading and trailing silence at the buffer edges.
+    #[wasm_bindgen]
+    pub fn find_silences(&self, samples: &[f32], min_silence_ms: f32, sample_rate: f32) -> Vec<u32> {
+        let speech = self.get_segments(samples);
+        let min_len = (min_silence_ms * 0.001 * sample_rate) as u32;
+        let end = samples.len() as u32;
+        
+        // Gap boundaries: buffer start, each speech segment's (end, start), buffer end
+        let mut bounds = Vec::with_capacity(speech.len() + 2);
+        bounds.push(0);
+        bounds.extend_from_slice(&speech);
+        bounds.push(end);
+        
+        bounds.chunks_exact(2)
+            .map(|gap| [gap[0], gap[1].min(end)])
+            .filter(|&[start, stop]| stop > start && stop - start >= min_len)
+            .flatten()
+            .collect()
+    }
+
+    /// Split into at most `target_chunks` chunks at silences, as boundary offsets
+    /// `[0, split_1, ..., samples.len()]`. Each ideal uniform boundary `i * len / n`
+    /// moves to the middle of the nearest interior silence within half a chunk;
+    /// when no silence is that close the boundary is dropped rather than cutting
+    /// through speech, so fewer chunks come back.
+    #[wasm_bindgen]
+    pub fn segment_into(&self, samples: &[f32], target_chunks: usize, sample_rate: f32) -> Vec<u32> {
+        let len = samples.len() as f32;
+        let target_chunks = target_chunks.max(1);
+        let silences = self.find_silences(samples, 0.0, sample_rate);
+        let candidates: Vec<u32> = silences.chunks_exact(2)
+            .filter(|gap| gap[0] > 0 && gap[1] < samples.len() as u32)
+            .map(|gap| (gap[0] + gap[1]) / 2)
+            .collect();
+        let tolerance = 0.5 * len / target_chunks as f32;
+        
+        let mut boundaries = vec![0u32];
+        for i in 1..target_chunks {
+            let ideal = i as f32 * len / target_chunks as f32;
+            let previous = *boundaries.last().unwrap_or(&0);
+            let nearest = candidates.iter()
+                .copied()
+                .filter(|&c| c > previous)
+                .min_by(|&a, &b| (a as f32 - ideal).abs().total_cmp(&(b as f32 - ideal).abs()));
+            if let Some(split) = nearest.filter(|&c| (c as f32 - ideal).abs() <= tolerance) {
+                boundaries.push(split);
+            }
+        }
+        boundaries.push(samples.len() as u32);
+        boundaries
+    }
+
+    /// ADSR fit of the amplitude envelope as `[attack_ms, decay_ms, sustain_level,
+    /// release_ms]`. The envelope is a 5 ms RMS every 1 ms, smoothed over 10 ms.
+    /// Attack runs from the first frame above 10 % of the peak to the first above
+    /// 90 %. The sustain is the longest stretch of at least 50 ms after the peak
+    /// where the level moves less than 0.5 dB per 10 ms; `sustain_level` is its mean
+    /// relative to the peak, decay runs from the peak to it and release from it until
+    /// the level last drops below 10 % of the sustain. Without a clear sustain
+    /// (plucks, hits) decay is 0, `sustain_level` 1 and the release is the whole
+    /// fall from the peak to 10 % of it.
+    /// Silent input gives all zeros.
+    #[wasm_bindgen]
+    pub fn estimate_adsr(&self, samples: &[f32], sample_rate: f32) -> Vec<f32> {
+        let hop = ((0.001 * sample_rate) as usize).max(1);
+        let window = ((0.005 * sample_rate) as usize).max(1);
+        let ms_per_frame = 1000.0 * hop as f32 / sample_rate;
+        if samples.len() < window {
+            return vec![0.0; 4];
+        }
+        
+        // Step 1: smoothed RMS envelope
+        let rms: Vec<f32> = (0..frame_count(samples.len(), window, hop))
+            .map(|i| frame_energy(frame_slice(samples, i, window, hop), false).sqrt())
+            .collect();
+        let smooth = (10.0 / ms_per_frame).round().max(1.0) as usize;
+        let env: Vec<f32> = (0..rms.len())
+            .map(|i| {
+                let span = &rms[i.saturating_sub(smooth / 2)..(i + smooth - smooth / 2).min(rms.len())];
+                span.iter().sum::<f32>() / span.len() as f32
+            })
+            .collect();
+        let (peak_frame, peak) = env.iter().enumerate().fold((0, 0.0f32), |best, (i, &e)| if e > best.1 { (i, e) } else { best });
+        if peak <= 1e-9 {
+            return vec![0.0; 4];
+        }
+        
+        // Step 2: attack between the 10 % and 90 % crossings
+        let start = env.iter().position(|&e| e >= 0.1 * peak).unwrap_or(0);
+        let attack_end = env.iter().position(|&e| e >= 0.9 * peak).unwrap_or(peak_frame);
+        let end = env.iter().rposition(|&e| e >= 0.1 * peak).unwrap_or(peak_frame);
+        
+        // Step 3: longest flat stretch after the peak is the sustain
+        let lag = (10.0 / ms_per_frame).round().max(1.0) as usize;
+        let min_len = (50.0 / ms_per_frame).round() as usize;
+        let flat = |i: usize| {
+            i + lag <= end && (20.0 * ((env[i + lag] + 1e-12) / (env[i] + 1e-12)).log10()).abs() < 0.5
+        };
+        let (mut best, mut run_start) = ((0, 0), None);
+        for i in peak_frame..=end + 1 {
+            match (run_start, i <= end && flat(i)) {
+                (None, true) => run_start = Some(i),
+                (Some(s), false) => {
+                    if i - s > best.1 - best.0 {
+                        best = (s, i);
+                    }
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+        
+        let attack_ms = attack_end.saturating_sub(start) as f32 * ms_per_frame;
+        if best.1 - best.0 < min_len {
+            return vec![attack_ms, 0.0, 1.0, end.saturating_sub(peak_frame) as f32 * ms_per_frame];
+        }
+        let (sustain_start, sustain_end) = (best.0, best.1 + lag);
+        let sustain = env[sustain_start..sustain_end].iter().sum::<f32>() / (sustain_end - sustain_start) as f32;
+        let release_end = env.iter().rposition(|&e| e >= 0.1 * sustain).unwrap_or(end);
+        vec![
+            attack_ms,
+            (sustain_start - peak_frame) as f32 * ms_per_frame,
+            sustain / peak,
+            release_end.saturating_sub(sustain_end) as f32 * ms_per_frame,
+        ]
+    }
+
+    /// Signal-to-reverberation (direct-to-reverberant) ratio in dB, from the free
+    /// decays after offsets in a 10 ms / 5 ms energy envelope. An offset is the last
+    /// frame within 3 dB of the preceding 100 ms plateau, followed by a drop of at
+    /// least 20 dB within 150 ms. The late tail
+    /// (15 ms after the offset, down to 5 dB above the decay's floor) is fit with a
+    /// line and extrapolated back to the offset to give the reverberant level there;
+    /// the rest of the peak energy is direct. Decays with no measurable tail count
+    /// as 60 dB (dry). The result is the mean over decays, clamped to -30..60 dB,
+    /// or NaN when the signal never stops (continuous speech, sustained music).
+    #[wasm_bindgen]
+    pub fn reverberation_ratio(&self, samples: &[f32], sample_rate: f32) -> f32 {
+        let frame = ((0.01 * sample_rate) as usize).max(1);
+        let hop = ((0.005 * sample_rate) as usize).max(1);
+        if samples.len() < frame {
+            return f32::NAN;
+        }
+        
+        // Step 1: short-time energy envelope
+        let env: Vec<f32> = (0..frame_count(samples.len(), frame, hop))
+            .map(|i| frame_energy(frame_slice(samples, i, frame, hop), true))
+            .collect();
+        let loudest = env.iter().fold(f32::NEG_INFINITY, |acc, &e| acc.max(e));
+        let drop_window = 30; // 150 ms
+        let max_decay = 80; // 400 ms
+        
+        // Step 2: walk the offsets and measure each free decay
+        let mut ratios = Vec::new();
+        let mut p = 0;
+        while p + drop_window < env.len() {
+            // Last frame of a plateau, after which the level stays below it
+            let peak = env[p];
+            let plateau = env[p.saturating_sub(20)..=p].iter().fold(f32::NEG_INFINITY, |acc, &e| acc.max(e));
+            let is_peak = peak > loudest - 40.0
+                && peak >= plateau - 3.0
+                && env[p + 1..p + 4].iter().all(|&e| e < plateau - 3.0)
+                && env[p + 1..=p + drop_window].iter().any(|&e| e <= peak - 20.0);
+            if !is_peak {
+                p += 1;
+                continue;
+            }
+            
+            // Decay lasts until the envelope rises 3 dB above its running minimum
+            let mut floor = peak;
+            let mut end = p + 1;
+            while end < env.len().min(p + max_decay) && env[end] <= floor + 3.0 {
+                floor = floor.min(env[end]);
+                end += 1;
+            }
+            
+            // Late tail: least-squares line through the frames above the floor
+            let tail: Vec<(f32, f32)> = (p + 3..end)
+                .filter(|&k| env[k] > floor + 5.0)
+                .map(|k| ((k - p) as f32, env[k]))
+                .collect();
+            let ratio = if tail.len() < 4 {
+                60.0
+            } else {
+                let (slope, intercept) = line_fit(&tail);
+                let (slope, intercept) = (slope as f32, intercept as f32);
+                // Level just before the offset (the offset frame itself straddles it)
+                let before = &env[p.saturating_sub(3)..p.max(1)];
+                let level = before.iter().map(|&e| 10f32.powf(e / 10.0)).sum::<f32>() / before.len() as f32;
+                let reverberant = 10f32.powf(intercept / 10.0).min(level);
+                let direct = level - reverberant;
+                if slope < 0.0 { 10.0 * (direct.max(1e-12) / reverberant).log10() } else { 60.0 }
+            };
+            ratios.push(ratio.clamp(-30.0, 60.0));
+            p = end;
+        }
+        
+        if ratios.is_empty() {
+            return f32::NAN;
+        }
+        ratios.iter().sum::<f32>() / ratios.len() as f32
+    }
+
+    /// Speech rate in syllables per second from syllable nuclei: a 20 ms RMS
+    /// envelope at 100 frames/s is band-passed zero-phase around the syllabic
+    /// rate (2-8 Hz) and its peaks counted. A nucleus needs a prominence of at
+    /// least 10 % of the largest one and of the mean envelope (so a steady sound's
+    /// ripple never counts), envelope energy above the energy threshold, and
+    /// 120 ms to the next taller nucleus. Inputs shorter than one frame give 0.
+    #[wasm_bindgen]
+    pub fn syllable_rate(&self, samples: &[f32], sample_rate: f32) -> f32 {
+        const ENVELOPE_RATE: f32 = 100.0;
+        const MIN_SPACING: usize = 12;
+        let hop = ((sample_rate / ENVELOPE_RATE) as usize).max(1);
+        let frame = 2 * hop;
+        if samples.len() < frame {
+            return 0.0;
+        }
+        
+        // Step 1: RMS envelope band-passed around the syllable rate
+        let envelope: Vec<f32> = (0..frame_count(samples.len(), frame, hop))
+            .map(|i| frame_energy(frame_slice(samples, i, frame, hop), false).sqrt())
+            .collect();
+        let filtered = Biquad::bandpass(ENVELOPE_RATE, 4.0, 4.0 / 6.0).filtfilt(&envelope);
+        
+        // Step 2: prominent, loud local maxima
+        let gate = db_to_linear(self.energy_threshold);
+        let prominences: Vec<(usize, f32)> = (1..filtered.len().saturating_sub(1))
+            .filter(|&i| filtered[i] > filtered[i - 1] && filtered[i] >= filtered[i + 1] && envelope[i] > gate)
+            .map(|i| (i, peak_prominence(&filtered, i)))
+            .collect();
+        let largest = prominences.iter().fold(0.0f32, |acc, p| acc.max(p.1));
+        let mean_level = envelope.iter().sum::<f32>() / envelope.len() as f32;
+        let min_prominence = 0.1 * largest.max(mean_level);
+        let mut candidates: Vec<usize> = prominences.iter()
+            .filter(|p| p.1 >= min_prominence && p.1 > 0.0)
+            .map(|p| p.0)
+            .collect();
+        
+        // Step 3: tallest first, dropping peaks too close to an accepted one
+        candidates.sort_by(|&a, &b| filtered[b].total_cmp(&filtered[a]));
+        let mut nuclei: Vec<usize> = Vec::new();
+        for c in candidates {
+            if nuclei.iter().all(|&n| n.abs_diff(c) >= MIN_SPACING) {
+                nuclei.push(c);
+            }
+        }
+        nuclei.len() as f32 * sample_rate / samples.len() as f32
+    }
+
+    /// Onsets per second, for telling smooth textures from busy percussive ones.
+    /// A 10 ms RMS envelope at 5 ms hops gives the energy gate; an onset is a
+    /// local maximum of the novelty (`onset_novelty`) reaching the onset
+    /// sensitivity, on a frame above the energy threshold, at least 20 ms after
+    /// the previous onset. Buffers under 0.5 s give NaN, too short for a
+    /// meaningful rate.
+    #[wasm_bindgen]
+    pub fn transient_density(&self, samples: &[f32], sample_rate: f32) -> f32 {
+        const MIN_SPACING: usize = 4;
+        if sample_rate.is_nan() || (samples.len() as f32) < 0.5 * sample_rate {
+            return f32::NAN;
+        }
+        let Some((envelope, rise)) = self.onset_curves(samples, sample_rate) else {
+            return f32::NAN;
+        };
+        
+        // Loud local maxima of the novelty, spaced apart
+        let mut onsets = 0usize;
+        let mut last: Option<usize> = None;
+        for i in 2..rise.len() {
+            let next = rise.get(i + 1).copied().unwrap_or(f32::NEG_INFINITY);
+            let is_peak = rise[i] >= self.onset_rise_db && rise[i] > rise[i - 1] && rise[i] >= next;
+            if is_peak && envelope[i] > self.energy_threshold && last.is_none_or(|l| i - l >= MIN_SPACING) {
+                onsets += 1;
+                last = Some(i);
+            }
+        }
+        onsets as f32 * sample_rate / samples.len() as f32
+    }
+
+    /// Onset novelty in dB per 5 ms hop under the novelty mode, the curve
+    /// `transient_density` picks onsets from. `EnergyRise` compares each 10 ms
+    /// RMS frame with the one 10 ms earlier (its last non-overlapping
+    /// predecessor). The first two hops read 0, as do spectral-mode hops until a
+    /// whole `frame_size` frame and one earlier frame (two for `ComplexDomain`)
+    /// are available. Empty when the buffer is shorter than one 10 ms frame.
+    #[wasm_bindgen]
+    pub fn onset_novelty(&self, samples: &[f32], sample_rate: f32) -> Vec<f32> {
+        self.onset_curves(samples, sample_rate).map_or_else(Vec::new, |(_, novelty)| novelty)
+    }
+
+    /// `(energy_db, novelty)` per 5 ms hop of 10 ms frames, or `None` when no
+    /// frame fits
+    fn onset_curves(&self, samples: &[f32], sample_rate: f32) -> Option<(Vec<f32>, Vec<f32>)> {
+        let hop = ((0.005 * sample_rate) as usize).max(1);
+        let frame = 2 * hop;
+        if sample_rate.is_nan() || samples.len() < frame {
+            return None;
+        }
+        
+        // Step 1: dB envelope
+        let envelope: Vec<f32> = (0..frame_count(samples.len(), frame, hop))
+            .map(|i| frame_energy(frame_slice(samples, i, frame, hop), true))
+            .collect();
+        
+        // Step 2: its rise over one frame length, or the spectral change
+        let novelty = match self.novelty_mode {
+            NoveltyMode::EnergyRise => (0..envelope.len())
+                .map(|i| if i < 2 { 0.0 } else { envelope[i] - envelope[i - 2] })
+                .collect(),
+            mode => {
+                let mut spectrum = self.spectrum.borrow_mut();
+                let fft = &mut spectrum.0;
+                let n_bins = self.frame_size / 2 + 1;
+                let mut history = [vec![Complex::new(0.0f32, 0.0); n_bins], vec![Complex::new(0.0f32, 0.0); n_bins]];
+                let mut frames_seen = 0usize;
+                (0..envelope.len())
+                    .map(|i| {
+                        let Some(start) = (i * hop + frame).checked_sub(self.frame_size) else {
+                            return 0.0;
+                        };
+                        fft.transform(&samples[start..start + self.frame_size]);
+                        let current = &fft.buffer[..n_bins];
+                        let [previous, before] = &history;
+                        let deltas = || current.iter().zip(previous).map(|(c, p)| c.norm() - p.norm());
+                        let l1: f32 = previous.iter().map(|c| c.norm()).sum();
+                        let (change, reference) = match (mode, self.flux_rectify) {
+                            (NoveltyMode::ComplexDomain, _) => {
+                                let deviation = current.iter()
+                                    .zip(previous.iter().zip(before))
+                                    .map(|(&c, (&p, &b))| {
+                                        // Previous magnitude, phase advanced by the last frame-to-frame advance
+                                        let advance = p * b.conj();
+                                        let predicted = if advance.norm() > 0.0 { p * advance / advance.norm() } else { p };
+                                        (c - predicted).norm()
+                                    })
+                                    .sum();
+                                (deviation, l1)
+                            }
+                            (_, FluxRectify::PositiveOnly) => (deltas().map(|d| d.max(0.0)).sum(), l1),
+                            (_, FluxRectify::Full) => (deltas().map(f32::abs).sum(), l1),
+                            (_, FluxRectify::L2) => (
+                                deltas().map(|d| d.max(0.0).powi(2)).sum::<f32>().sqrt(),
+                                previous.iter().map(|c| c.norm_sqr()).sum::<f32>().sqrt(),
+                            ),
+                        };
+                        let needed = if mode == NoveltyMode::ComplexDomain { 2 } else { 1 };
+                        history.rotate_right(1);
+                        history[0].copy_from_slice(current);
+                        frames_seen += 1;
+                        if frames_seen <= needed {
+                            return 0.0;
+                        }
+                        20.0 * (1.0 + change / (reference + 1e-9)).log10()
+                    })
+                    .collect()
+            }
+        };
+        Some((envelope, novelty))
+    }
+
+    /// Energy entropy per frame in bits: each frame is split into `num_subframes`
+    /// near-equal sub-blocks and the Shannon entropy of their energy fractions
+    /// taken. A steady frame approaches the maximum `log2(num_subframes)`; an
+    /// abrupt onset concentrates energy in a few sub-blocks and drops it. Silent
+    /// frames count as uniform (the maximum), so the output never holds NaN.
+    #[wasm_bindgen]
+    pub fn energy_entropy(&self, samples: &[f32], num_subframes: usize) -> Vec<f32> {
+        let k = num_subframes.max(1);
+        (0..self.num_frames(samples.len()))
+            .map(|i| {
+                let frame = self.frame(samples, i);
+                let energies: Vec<f32> = (0..k)
+                    .map(|j| frame[j * frame.len() / k..(j + 1) * frame.len() / k].iter().map(|&s| s * s).sum())
+                    .collect();
+                let total: f32 = energies.iter().sum();
+                if total <= 1e-20 {
+                    return (k as f32).log2();
+                }
+                -energies.iter()
+                    .map(|&e| e / total)
+                    .filter(|&p| p > 0.0)
+                    .map(|p| p * p.log2())
+                    .sum::<f32>()
+            })
+            .collect()
+    }
+}
+
+/// Mean-square frame energy shared by every energy-based feature.
+/// In dB mode a 1e-10 floor keeps silence at -100 dB instead of -inf.
+#[inline]
+fn frame_energy(frame: &[f32], db: bool) -> f32 {
+    let energy: f32 = frame.iter().map(|&s| s * s).sum();
+    let mean_square = energy / frame.len() as f32;
+    if db {
+        10.0 * (mean_square + 1e-10).log10()
+    } else {
+        mean_square
+    }
+}
+
+/// Number of `frame_size` frames taken every `hop_size` samples from a buffer
+/// of `len` samples; a buffer shorter than one frame still gives one frame
+#[inline]
+fn frame_count(len: usize, frame_size: usize, hop_size: usize) -> usize {
+    len.saturating_sub(frame_size) / hop_size + 1
+}
+
+/// Frame `i` of that framing (truncated at the end of the buffer)
+#[inline]
+fn frame_slice(samples: &[f32], i: usize, frame_size: usize, hop_size: usize) -> &[f32] {
+    let start = (i * hop_size).min(samples.len());
+    let end = (start + frame_size).min(samples.len());
+    &samples[start..end]
+}
+
+/// Weighted least-squares line through `(x, y, weight)` points as
+/// `(slope, intercept)`, accumulated in f64 (West's running update). Points
+/// without spread in x give slope 0 through the weighted mean of y.
+fn weighted_line_fit(points: impl IntoIterator<Item = (f64, f64, f64)>) -> (f64, f64) {
+    let (mut total, mut mean_x, mut mean_y, mut sxx, mut sxy) = (0.0f64, 0.0f64, 0.0f64, 0.0f64, 0.0f64);
+    for (x, y, w) in points.into_iter().filter(|p| p.2 > 0.0) {
+        total += w;
+        let dx = x - mean_x;
+        mean_x += dx * w / total;
+        mean_y += (y - mean_y) * w / total;
+        sxx += w * dx * (x - mean_x);
+        sxy += w * dx * (y - mean_y);
+    }
+    let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+    (slope, mean_y - slope * mean_x)
+}
+
+/// Least-squares line through `(x, y)` points as `(slope, intercept)`
+fn line_fit<T: Into<f64> + Copy>(points: &[(T, T)]) -> (f64, f64) {
+    weighted_line_fit(points.iter().map(|&(x, y)| (x.into(), y.into(), 1.0)))
+}
+
+/// Vertex of the parabola through `(-1, a)`, `(0, b)` and `(1, c)` as
+/// `(offset, height)`, the offset clamped to ±0.5. Without a maximum there
+/// (flat or curving upward) the middle point `(0, b)` is returned.
+fn parabolic_peak(a: f32, b: f32, c: f32) -> (f32, f32) {
+    let curvature = a - 2.0 * b + c;
+    if curvature >= 0.0 {
+        return (0.0, b);
+    }
+    let offset = (0.5 * (a - c) / curvature).clamp(-0.5, 0.5);
+    (offset, b - 0.25 * (a - c) * offset)
+}
+
+/// Otsu split (dB) of frame energies on a 64-bin histogram: the split maximizing
+/// the between-class variance, centred when that maximum is flat across a gap
+/// between the modes. None when the energies span under 10 dB (one mode only).
+fn otsu_threshold(energies: &[f32]) -> Option<f32> {
+    const BINS: usize = 64;
+    let lo = energies.iter().fold(f32::INFINITY, |acc, &e| acc.min(e));
+    let hi = energies.iter().fold(f32::NEG_INFINITY, |acc, &e| acc.max(e));
+    if energies.is_empty() || hi - lo < 10.0 {
+        return None;
+    }
+    
+    // Step 1: energy histogram
+    let width = (hi - lo) / BINS as f32;
+    let mut histogram = [0.0f32; BINS];
+    for &e in energies {
+        histogram[(((e - lo) / width) as usize).min(BINS - 1)] += 1.0;
+    }
+    let center = |i: usize| lo + (i as f32 + 0.5) * width;
+    
+    // Step 2: between-class variance of every split
+    let total = energies.len() as f32;
+    let total_sum: f32 = histogram.iter().enumerate().map(|(i, &h)| h * center(i)).sum();
+    let (mut weight_low, mut sum_low) = (0.0f32, 0.0f32);
+    let between: Vec<f32> = histogram[..BINS - 1].iter()
+        .enumerate()
+        .map(|(i, &h)| {
+            weight_low += h;
+            sum_low += h * center(i);
+            let weight_high = total - weight_low;
+            if weight_low == 0.0 || weight_high == 0.0 {
+                return 0.0;
+            }
+            let mean_low = sum_low / weight_low;
+            let mean_high = (total_sum - sum_low) / weight_high;
+            weight_low * weight_high * (mean_low - mean_high) * (mean_low - mean_high)
+        })
+        .collect();
+    
+    // Step 3: middle of the (often flat) maximum, i.e. midway across the empty gap
+    let peak = between.iter().fold(0.0f32, |acc, &v| acc.max(v));
+    let first = between.iter().position(|&v| v >= peak * 0.999).unwrap_or(0);
+    let last = between.iter().rposition(|&v| v >= peak * 0.999).unwrap_or(first);
+    Some(lo + ((first + last) as f32 / 2.0 + 1.0) * width)
+}
+
+/// Fraction of adjacent sample pairs whose sign differs
+#[inline]
+fn zero_crossing_rate(frame: &[f32]) -> f32 {
+    frame.windows(2)
+        .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+        .count() as f32 / frame.len() as f32
+}
+
+/// ULTRA-OPTIMIZED YIN Pitch Detection Algorithm
+/// Uses FFT-based autocorrelation: O(n log n) instead of O(n²)
+#[wasm_bindgen]
+pub struct PitchDetector {
+    sample_rate: f32,
+    frame_size: usize,
+    threshold: f32,
+    // Normalize d(tau) by its overlap length so thresholds hold across frame sizes
+    frame_normalization: bool,
+    // CMND points in the sub-sample lag fit
+    lag_refinement: usize,
+    // detect_batch reports frames below this confidence as unvoiced
+    min_confidence: f32,
+    // detect_batch frames quieter than this (dB mean square) skip YIN
+    skip_silence_db: f32,
+    // Unit of the reported pitch, and the 0-cent reference for `PitchUnits::Cents`
+    units: PitchUnits,
+    cents_reference: f32,
+    // Searched pitch range in Hz
+    min_frequency: f32,
+    max_frequency: f32,
+    // Subharmonic summation: candidate grid step in cents and per-harmonic weight decay
+    shs_resolution_cents: f32,
+    shs_decay: f32,
+    // Equal-loudness weighting of the SHS magnitude spectrum
+    loudness_weighting: bool,
+    // Glide detection: shortest reported glide and slowest slope (semitones/s)
+    glide_min_duration_ms: f32,
+    glide_min_rate: f32,
+    // MPM: key maxima above this fraction of the highest are pitch candidates
+    mpm_threshold: f32,
+    // Pre-allocated buffers for FFT-based autocorrelation
+    fft_size: usize,
+    fft_forward: Arc<dyn Fft<f32>>,
+    fft_inverse: Arc<dyn Fft<f32>>,
+    buffer_a: Vec<Complex<f32>>,
+    buffer_b: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    diff: Vec<f32>,
+    cmnd: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl PitchDetector {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32, frame_size: usize) -> PitchDetector {
+        PitchDetector::with_planner(sample_rate, frame_size, &SharedPlanner::new())
+    }
+
+    /// Build a detector whose FFT plans come from a shared plan cache
+    #[wasm_bindgen]
+    pub fn with_planner(sample_rate: f32, frame_size: usize, planner: &SharedPlanner) -> PitchDetector {
+        // FFT size must be power of 2 and at least 2x frame size for autocorrelation
+        let fft_size = (frame_size * 2).next_power_of_two();
+        
+        let fft_forward = planner.plan_forward(fft_size);
+        let fft_inverse = planner.plan_inverse(fft_size);
+        let scratch_len = fft_forward.get_inplace_scratch_len()
+            .max(fft_inverse.get_inplace_scratch_len());
+        
+        console_log!("🦀 [Rust DSP] YIN Pitch Detector: sr={}, frame={} (FFT-ACCELERATED)", sample_rate, frame_size);
+        
+        PitchDetector {
+            sample_rate,
+            frame_size,
+            threshold: 0.1,
+            frame_normalization: false,
+            lag_refinement: 3,
+            min_confidence: 0.0,
+            skip_silence_db: f32::NEG_INFINITY,
+            units: PitchUnits::Hz,
+            cents_reference: 440.0,
+            min_frequency: 50.0,
+            max_frequency: 500.0,
+            shs_resolution_cents: 10.0,
+            shs_decay: 0.84,
+            loudness_weighting: false,
+            glide_min_duration_ms: 150.0,
+            glide_min_rate: 2.0,
+            mpm_threshold: 0.93,
+            fft_size,
+            fft_forward,
+            fft_inverse,
+            buffer_a: vec![Complex::new(0.0, 0.0); fft_size],
+            buffer_b: vec![Complex::new(0.0, 0.0); fft_size],
+            scratch: vec![Complex::new(0.0, 0.0); scratch_len],
+            diff: vec![0.0; frame_size / 2],
+            cmnd: vec![0.0; frame_size / 2],
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold;
+    }
+
+    /// Fraction of the highest NSDF key maximum the `detect_mpm` pick must reach (default 0.93)
+    #[wasm_bindgen]
+    pub fn set_mpm_threshold(&mut self, fraction: f32) {
+        self.mpm_threshold = fraction.clamp(0.0, 1.0);
+    }
+
+    /// Use the exact overlap-normalized difference function.
+    /// The default shortcut d(tau) = 2(r(0) - r(tau)) counts the full-frame energy at
+    /// every lag, which biases d upward by roughly tau/n and shifts the effective
+    /// threshold when `frame_size` changes. When enabled, d(tau) is computed over the
+    /// n - tau overlapping samples and divided by that count, so a given `threshold`
+    /// yields the same voicing decisions at any frame size.
+    #[wasm_bindgen]
+    pub fn set_frame_normalization(&mut self, enabled: bool) {
+        self.frame_normalization = enabled;
+    }
+
+    /// FFT-based autocorrelation - O(n log n) complexity
+    #[inline]
+    fn compute_autocorrelation(&mut self, samples: &[f32]) {
+        let n = samples.len().min(self.frame_size);
+        
+        // Zero-pad and copy samples to buffer_a
+        for (b, &s) in self.buffer_a.iter_mut().zip(&samples[..n]) {
+            *b = Complex::new(s, 0.0);
+        }
+        for i in n..self.fft_size {
+            self.buffer_a[i] = Complex::new(0.0, 0.0);
+        }
+        
+        // Forward FFT
+        self.fft_forward.process_with_scratch(&mut self.buffer_a, &mut self.scratch);
+        
+        // Compute power spectrum (autocorrelation in frequency domain)
+        for i in 0..self.fft_size {
+            let re = self.buffer_a[i].re;
+            let im = self.buffer_a[i].im;
+            self.buffer_b[i] = Complex::new(re * re + im * im, 0.0);
+        }
+        
+        // Inverse FFT to get autocorrelation
+        self.fft_inverse.process_with_scratch(&mut self.buffer_b, &mut self.scratch);
+        
+        // Normalize and compute difference function
+        let scale = 1.0 / self.fft_size as f32;
+        let r0 = self.buffer_b[0].re * scale; // Autocorrelation at lag 0
+        
+        let tau_max = n / 2;
+        if self.frame_normalization {
+            // d(tau) = sum_{j<n-tau} x_j² + sum_{j>=tau} x_j² - 2 r(tau), per overlapping sample
+            let mut head_energy = r0;
+            let mut tail_energy = r0;
+            for tau in 0..tau_max {
+                if tau > 0 {
+                    head_energy -= samples[n - tau] * samples[n - tau];
+                    tail_energy -= samples[tau - 1] * samples[tau - 1];
+                }
+                let r_tau = self.buffer_b[tau].re * scale;
+                self.diff[tau] = (head_energy + tail_energy - 2.0 * r_tau).max(0.0) / (n - tau) as f32;
+            }
+            return;
+        }
+        
+        for tau in 0..tau_max {
+            let r_tau = self.buffer_b[tau].re * scale;
+            // d(tau) = r(0) + r(0) - 2*r(tau) = 2*(r(0) - r(tau))
+            self.diff[tau] = 2.0 * (r0 - r_tau);
+        }
+    }
+
+    /// Periodicity strength against candidate period: the NSDF of `detect_mpm`
+    /// (1 for a perfect repeat, -1 for a perfect inversion) over the valid lag
+    /// range, entry i being lag `floor(sample_rate / max_frequency) + i` samples up
+    /// to `floor(sample_rate / min_frequency)` (exclusive) or half the frame,
+    /// whichever is shorter. A periodic frame peaks near its period's lag.
+    #[wasm_bindgen]
+    pub fn periodicity_curve(&mut self, samples: &[f32]) -> Vec<f32> {
+        let nsdf = self.nsdf(samples);
+        let (min_period, search_end) = self.lag_range(self.min_frequency, self.max_frequency, nsdf.len());
+        nsdf[min_period.min(search_end)..search_end].to_vec()
+    }
+
+    /// McLeod Pitch Method: the normalized square difference function
+    /// n(τ) = 2r(τ) / Σ(x_j² + x_{j+τ}²) over the overlap (in -1..1, from the FFT
+    /// autocorrelation), its key maxima (the highest point between each positive-
+    /// and negative-going zero crossing), and the first key maximum within the
+    /// pitch range reaching the MPM threshold times the highest one, refined by a
+    /// parabola. Returns `[frequency, clarity]` in the configured units, clarity
+    /// being the interpolated NSDF peak; `[0, 0]` when no key maximum exists.
+    #[wasm_bindgen]
+    pub fn detect_mpm(&mut self, samples: &[f32]) -> Vec<f32> {
+        // Step 1: NSDF from the autocorrelation and the overlap energies
+        let nsdf = self.nsdf(samples);
+        let tau_max = nsdf.len();
+        
+        // Step 2: key maxima after the first negative-going zero crossing
+        let (min_period, search_end) = self.lag_range(self.min_frequency, self.max_frequency, tau_max);
+        let mut key_maxima: Vec<usize> = Vec::new();
+        let mut tau = nsdf.iter().position(|&v| v < 0.0).unwrap_or(tau_max);
+        while tau < tau_max {
+            let Some(rise) = nsdf[tau..].iter().position(|&v| v > 0.0) else { break };
+            let start = tau + rise;
+            let end = nsdf[start..].iter().position(|&v| v <= 0.0).map_or(tau_max, |fall| start + fall);
+            let best = (start..end).fold(start, |best, t| if nsdf[t] > nsdf[best] { t } else { best });
+            if (min_period..search_end).contains(&best) {
+                key_maxima.push(best);
+            }
+            tau = end;
+        }
+        let highest = key_maxima.iter().fold(0.0f32, |acc, &t| acc.max(nsdf[t]));
+        let Some(&best) = key_maxima.iter().find(|&&t| nsdf[t] >= self.mpm_threshold * highest) else {
+            return vec![0.0, 0.0];
+        };
+        
+        // Step 3: parabolic peak interpolation
+        let (lag, clarity) = if best > 0 && best + 1 < tau_max {
+            let (offset, height) = parabolic_peak(nsdf[best - 1], nsdf[best], nsdf[best + 1]);
+            (best as f32 + offset, height)
+        } else {
+            (best as f32, nsdf[best])
+        };
+        self.to_units([self.sample_rate / lag, clarity.min(1.0)]).to_vec()
+    }
+
+    /// Detect pitch using FFT-accelerated YIN algorithm
+    #[wasm_bindgen]
+    pub fn detect(&mut self, samples: &[f32]) -> Vec<f32> {
+        let result = self.detect_frame(samples);
+        self.to_units(result).to_vec()
+    }
+
+    /// Alloc-free `detect`: writes up to two values, `[frequency, confidence]`,
+    /// into `out` and returns how many were written
+    #[wasm_bindgen]
+    pub fn detect_into(&mut self, samples: &[f32], out: &mut [f32]) -> usize {
+        let result = self.detect_frame(samples);
+        let result = self.to_units(result);
+        let written = out.len().min(2);
+        out[..written].copy_from_slice(&result[..written]);
+        written
+    }
+
+    /// YIN at three resolutions: the full frame over the configured pitch range,
+    /// then the centre half and quarter up to `sample_rate / 8` (each from 4 periods
+    /// per frame). The most confident voiced estimate wins, except that an
+    /// octave-related higher estimate within 0.1 confidence which the winning
+    /// resolution could not reach replaces it, so a subharmonic lock at the long
+    /// frame never beats the true pitch from a short one. Returns `[pitch, confidence]`.
+    #[wasm_bindgen]
+    pub fn detect_multiresolution(&mut self, samples: &[f32]) -> Vec<f32> {
+        let n = samples.len().min(self.frame_size);
+        
+        // Step 1: [frequency, confidence, range_min, range_max] per resolution
+        let mut estimates: Vec<[f32; 4]> = Vec::with_capacity(3);
+        for level in 0..3 {
+            let len = n >> level;
+            if len < 64 {
+                break;
+            }
+            let (low, high) = if level == 0 {
+                (self.min_frequency, self.max_frequency)
+            } else {
+                (self.min_frequency.max(4.0 * self.sample_rate / len as f32), self.sample_rate / 8.0)
+            };
+            if low >= high {
+                continue;
+            }
+            let start = (n - len) / 2;
+            let [tau, confidence] = self.detect_lag_in(&samples[start..start + len], low, high);
+            if tau > 0.0 {
+                estimates.push([self.sample_rate / tau, confidence, low, high]);
+            }
+        }
+        
+        // Step 2: most confident estimate, then octave-consistency check
+        let mut best = match estimates.iter().copied().reduce(|a, b| if b[1] > a[1] { b } else { a }) {
+            Some(e) => e,
+            None => return self.to_units([0.0, 0.0]).to_vec(),
+        };
+        for e in &estimates {
+            let octaves = (e[0] / best[0]).log2();
+            let related = octaves > 0.5 && (octaves - octaves.round()).abs() < 0.05;
+            if related && e[1] >= best[1] - 0.1 && e[0] > best[3] {
+                best = *e;
+            }
+        }
+        
+        self.to_units([best[0], best[1]]).to_vec()
+    }
+
+    /// Unit of every detected pitch a method reports: `detect`, `detect_into`,
+    /// `detect_batch` and each other detector returning a frequency, with the
+    /// unit's unvoiced sentinel. Confidences, lags, and methods taking a Hz pitch
+    /// track as input are unaffected.
+    #[wasm_bindgen]
+    pub fn set_pitch_units(&mut self, units: PitchUnits) {
+        self.units = units;
+    }
+
+    /// Frequency that reads as 0 cents in `PitchUnits::Cents` (default 440 Hz)
+    #[wasm_bindgen]
+    pub fn set_cents_reference(&mut self, hz: f32) {
+        self.cents_reference = hz;
+    }
+
+    /// Nearest note in scientific pitch notation ("A4", "C#3"), with A4 at the cents
+    /// reference (default 440 Hz) so C4 is middle C. Unvoiced (≤ 0 Hz) gives "--".
+    #[wasm_bindgen]
+    pub fn note_name(&self, freq: f32) -> String {
+        const NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+        if !(freq > 0.0 && freq.is_finite()) {
+            return "--".to_string();
+        }
+        let midi = (69.0 + 12.0 * (freq / self.cents_reference).log2()).round() as i32;
+        format!("{}{}", NAMES[midi.rem_euclid(12) as usize], midi.div_euclid(12) - 1)
+    }
+
+    /// `note_name` for every value of a pitch contour in Hz
+    #[wasm_bindgen]
+    pub fn note_name_batch(&self, freqs: &[f32]) -> Vec<String> {
+        freqs.iter().map(|&f| self.note_name(f)).collect()
+    }
+
+    /// Prosody summary of a Hz pitch track with frames `hop_size` apart:
+    /// `[mean_hz, median_hz, min_hz, max_hz, range_semitones, slope_st_per_sec,
+    /// contour]` over voiced frames (positive pitch, confidence at least the
+    /// minimum confidence). The slope is the least-squares line of semitones
+    /// against time over those frames; its change across the voiced span and
+    /// the residual RMS give the contour code: 0 flat (under 1 semitone),
+    /// 1 rising, 2 falling, 3 complex (residual above 1 semitone and half the
+    /// change). All NaN when no frame is voiced.
+    #[wasm_bindgen]
+    pub fn pitch_statistics(&self, pitch_track: &[f32], confidences: &[f32], hop_size: usize) -> Vec<f32> {
+        let frame_seconds = hop_size.max(1) as f32 / self.sample_rate;
+        let voiced: Vec<(f32, f32)> = pitch_track.iter()
+            .zip(confidences)
+            .enumerate()
+            .filter(|(_, (&f, &c))| f > 0.0 && f.is_finite() && c >= self.min_confidence)
+            .map(|(i, (&f, _))| (i as f32 * frame_seconds, f))
+            .collect();
+        if voiced.is_empty() {
+            return vec![f32::NAN; 7];
+        }
+        
+        // Step 1: level statistics in Hz
+        let count = voiced.len() as f32;
+        let mut sorted: Vec<f32> = voiced.iter().map(|v| v.1).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let mid = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) { 0.5 * (sorted[mid - 1] + sorted[mid]) } else { sorted[mid] };
+        let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+        let mean = sorted.iter().sum::<f32>() / count;
+        
+        // Step 2: regression of semitones on time
+        let points: Vec<(f32, f32)> = voiced.iter().map(|&(t, f)| (t, 12.0 * (f / min).log2())).collect();
+        let (slope, intercept) = line_fit(&points);
+        let (slope, intercept) = (slope as f32, intercept as f32);
+        
+        // Step 3: contour type from the fitted change and the residual
+        let change = slope * (points[points.len() - 1].0 - points[0].0);
+        let residual = (points.iter()
+            .map(|&(x, y)| (y - intercept - slope * x).powi(2))
+            .sum::<f32>() / count)
+            .sqrt();
+        let contour = if residual > 1.0f32.max(0.5 * change.abs()) {
+            3.0
+        } else if change.abs() < 1.0 {
+            0.0
+        } else if change > 0.0 {
+            1.0
+        } else {
+            2.0
+        };
+        vec![mean, median, min, max, 12.0 * (max / min).log2(), slope, contour]
+    }
+
+    /// Convert a `[frequency, confidence]` result from Hz to the configured units
+    fn to_units(&self, result: [f32; 2]) -> [f32; 2] {
+        let [frequency, confidence] = result;
+        let value = if frequency <= 0.0 {
+            match self.units {
+                PitchUnits::Hz | PitchUnits::Log2Hz => 0.0,
+                PitchUnits::Midi => -1.0,
+                PitchUnits::Cents => f32::NAN,
+            }
+        } else {
+            match self.units {
+                PitchUnits::Hz => frequency,
+                PitchUnits::Midi => 69.0 + 12.0 * (frequency / 440.0).log2(),
+                PitchUnits::Cents => 1200.0 * (frequency / self.cents_reference).log2(),
+                PitchUnits::Log2Hz => frequency.log2(),
+            }
+        };
+        [value, confidence]
+    }
+
+    /// Fill `cmnd` for a frame (YIN steps 1-2); returns the number of valid lags
+    fn compute_cmnd(&mut self, samples: &[f32]) -> usize {
+        let n = samples.len().min(self.frame_size);
+        let tau_max = n / 2;
+        
+        // Step 1: FFT-based difference function (O(n log n))
+        self.compute_autocorrelation(samples);
+        
+        // Step 2: Cumulative mean normalized difference
+        self.cmnd[0] = 1.0;
+        let mut running_sum = 0.0f32;
+        
+        for tau in 1..tau_max {
+            running_sum += self.diff[tau];
+            self.cmnd[tau] = if running_sum > 1e-10 {
+                self.diff[tau] * tau as f32 / running_sum
+            } else {
+                1.0
+            };
+        }
+        
+        tau_max
+    }
+
+    /// YIN aperiodicity of a frame: the CMND minimum over the pitch range
+    /// (0 = perfectly periodic, around 1 for noise or silence)
+    fn aperiodicity(&mut self, samples: &[f32]) -> f32 {
+        let tau_max = self.compute_cmnd(samples);
+        let (min_period, search_end) = self.lag_range(self.min_frequency, self.max_frequency, tau_max);
+        self.cmnd[min_period.min(search_end)..search_end]
+            .iter()
+            .fold(1.0f32, |acc, &d| acc.min(d))
+    }
+
+    /// YIN period of a frame as `[period_samples, confidence]`, where the period is
+    /// the refined sub-sample lag (`sample_rate / period` is exactly the frequency
+    /// `detect` reports). Unvoiced frames give `[0, 0]`. Not affected by `PitchUnits`.
+    #[wasm_bindgen]
+    pub fn detect_period(&mut self, samples: &[f32]) -> Vec<f32> {
+        self.detect_lag(samples).to_vec()
+    }
+
+    /// Periodicity strength of a frame, `1 - aperiodicity` clamped to 0..1: near 1
+    /// for a clean tone, near 0 for noise or silence. Independent of `threshold`.
+    #[wasm_bindgen]
+    pub fn periodicity(&mut self, samples: &[f32]) -> f32 {
+        (1.0 - self.aperiodicity(samples)).clamp(0.0, 1.0)
+    }
+
+    /// Pitch near a known reference as `[pitch, confidence]` in the configured
+    /// units: the CMND minimum over lags within ±`search_cents` of `reference_hz`,
+    /// refined by parabolic interpolation, with confidence `1 - cmnd`. No threshold
+    /// is applied. When the minimum sits on the edge of the window with lower
+    /// values just outside it, the true period is out of range and the unvoiced
+    /// result is returned.
+    #[wasm_bindgen]
+    pub fn track_reference_pitch(&mut self, samples: &[f32], reference_hz: f32, search_cents: f32) -> Vec<f32> {
+        if reference_hz <= 0.0 {
+            return self.to_units([0.0, 0.0]).to_vec();
+        }
+        let tau_max = self.compute_cmnd(samples);
+        let span = 2f32.powf(search_cents.abs() / 1200.0);
+        let min_lag = ((self.sample_rate / (reference_hz * span)).floor() as usize).max(2);
+        let max_lag = ((self.sample_rate * span / reference_hz).ceil() as usize).min(tau_max.saturating_sub(2));
+        if min_lag > max_lag {
+            return self.to_units([0.0, 0.0]).to_vec();
+        }
+        
+        let best = (min_lag..=max_lag)
+            .min_by(|&a, &b| self.cmnd[a].total_cmp(&self.cmnd[b]))
+            .unwrap_or(min_lag);
+        let outside = (best == min_lag && self.cmnd[best - 1] < self.cmnd[best])
+            || (best == max_lag && self.cmnd[best + 1] < self.cmnd[best]);
+        if outside {
+            return self.to_units([0.0, 0.0]).to_vec();
+        }
+        let refined_tau = self.refine_lag(best, tau_max);
+        self.to_units([self.sample_rate / refined_tau, (1.0 - self.cmnd[best]).clamp(0.0, 1.0)]).to_vec()
+    }
+
+    /// YIN estimate for a single frame as `[frequency, confidence]`
+    fn detect_frame(&mut self, samples: &[f32]) -> [f32; 2] {
+        match self.detect_lag(samples) {
+            [tau, confidence] if tau > 0.0 => [self.sample_rate / tau, confidence],
+            _ => [0.0, 0.0],
+        }
+    }
+
+    /// Lag search window `[min_period, search_end)` for a frequency range
+    /// NSDF `n(τ)` of the frame for τ = 0..frame/2, clamped to -1..1 (0 where the
+    /// overlap is silent)
+    fn nsdf(&mut self, samples: &[f32]) -> Vec<f32> {
+        let n = samples.len().min(self.frame_size);
+        self.compute_autocorrelation(samples);
+        let scale = 1.0 / self.fft_size as f32;
+        let r0 = self.buffer_b[0].re * scale;
+        let (mut head_energy, mut tail_energy) = (r0, r0);
+        let mut nsdf = vec![0.0f32; n / 2];
+        for (tau, value) in nsdf.iter_mut().enumerate() {
+            if tau > 0 {
+                head_energy -= samples[n - tau] * samples[n - tau];
+                tail_energy -= samples[tau - 1] * samples[tau - 1];
+            }
+            let energy = head_energy + tail_energy;
+            *value = if energy > 1e-10 { (2.0 * self.buffer_b[tau].re * scale / energy).clamp(-1.0, 1.0) } else { 0.0 };
+        }
+        nsdf
+    }
+
+    fn lag_range(&self, min_frequency: f32, max_frequency: f32, tau_max: usize) -> (usize, usize) {
+        let min_period = (self.sample_rate / max_frequency) as usize;
+        let max_period = (self.sample_rate / min_frequency) as usize;
+        (min_period, max_period.min(tau_max))
+    }
+
+    /// YIN estimate for a single frame as `[refined_lag, confidence]`
+    fn detect_lag(&mut self, samples: &[f32]) -> [f32; 2] {
+        self.detect_lag_in(samples, self.min_frequency, self.max_frequency)
+    }
+
+    /// `detect_lag` searching only `min_frequency..max_frequency`
+    fn detect_lag_in(&mut self, samples: &[f32], min_frequency: f32, max_frequency: f32) -> [f32; 2] {
+        let tau_max = self.compute_cmnd(samples);
+        
+        // Step 3: Absolute threshold, then parabolic interpolation of the dip
+        let (min_period, search_end) = self.lag_range(min_frequency, max_frequency, tau_max);
+        for tau in min_period..search_end {
+            if self.cmnd[tau] < self.threshold {
+                // Step 4: follow the dip down to its local minimum
+                let mut best = tau;
+                while best + 1 < search_end && self.cmnd[best + 1] < self.cmnd[best] {
+                    best += 1;
+                }
+                
+                let refined_tau = self.refine_lag(best, tau_max);
+                let confidence = 1.0 - self.cmnd[best];
+                return [refined_tau, confidence];
+            }
+        }
+        
+        [0.0, 0.0]  // Unvoiced
+    }
+
+    /// Probabilistic YIN (pYIN) candidates as `[pitch, probability, ...]`, most
+    /// probable first, pitches in the configured units. Instead of one threshold,
+    /// 100 thresholds 0.01..1.00 weighted by a Beta(2, 18) prior (mean 0.1) each
+    /// vote for the first CMND dip below them; thresholds with no dip give 1% of
+    /// their weight to the global minimum.
+    /// Probabilities sum to the voiced probability (≤ 1), the rest being unvoiced,
+    /// which makes the output a per-frame observation for an HMM tracker.
+    #[wasm_bindgen]
+    pub fn detect_pyin(&mut self, samples: &[f32]) -> Vec<f32> {
+        const NUM_THRESHOLDS: usize = 100;
+        const NO_DIP_WEIGHT: f32 = 0.01;
+        let tau_max = self.compute_cmnd(samples);
+        let (min_period, search_end) = self.lag_range(self.min_frequency, self.max_frequency, tau_max);
+        if min_period >= search_end {
+            return Vec::new();
+        }
+        
+        // Step 1: Beta(2, 18) prior over the thresholds
+        let thresholds: Vec<f32> = (1..=NUM_THRESHOLDS).map(|i| i as f32 / NUM_THRESHOLDS as f32).collect();
+        let prior: Vec<f32> = thresholds.iter().map(|&s| s * (1.0 - s).powi(17)).collect();
+        let prior_sum: f32 = prior.iter().sum();
+        
+        // Step 2: each threshold votes for its dip
+        let global_min = (min_period..search_end)
+            .min_by(|&a, &b| self.cmnd[a].total_cmp(&self.cmnd[b]))
+            .unwrap_or(min_period);
+        let mut votes: Vec<(usize, f32)> = Vec::new();
+        for (&threshold, &weight) in thresholds.iter().zip(&prior) {
+            let weight = weight / prior_sum;
+            let (tau, weight) = match (min_period..search_end).find(|&tau| self.cmnd[tau] < threshold) {
+                Some(mut tau) => {
+                    while tau + 1 < search_end && self.cmnd[tau + 1] < self.cmnd[tau] {
+                        tau += 1;
+                    }
+                    (tau, weight)
+                }
+                None => (global_min, weight * NO_DIP_WEIGHT),
+            };
+            match votes.iter_mut().find(|(t, _)| *t == tau) {
+                Some((_, p)) => *p += weight,
+                None => votes.push((tau, weight)),
+            }
+        }
+        
+        // Step 3: most probable candidate first
+        votes.sort_by(|a, b| b.1.total_cmp(&a.1));
+        votes.iter()
+            .flat_map(|&(tau, probability)| self.to_units([self.sample_rate / self.refine_lag(tau, tau_max), probability]))
+            .collect()
+    }
+
+    /// Subharmonic-summation pitch as `[pitch, confidence]` in the configured units.
+    /// For each candidate f on a log grid over the pitch range (default 50-500 Hz),
+    /// the Hann-windowed magnitude spectrum is summed at f, 2f, ..., `num_harmonics`·f
+    /// with weight `decay^(n-1)`; the best-scoring candidate wins. Confidence is that
+    /// score over the score of a spectrum at its maximum under every harmonic. Finds
+    /// a weak or missing fundamental.
+    #[wasm_bindgen]
+    pub fn detect_shs(&mut self, samples: &[f32], num_harmonics: usize) -> Vec<f32> {
+        let (candidates, salience) = self.shs_salience(samples, num_harmonics);
+        let result = match salience.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)) {
+            Some((best, &score)) if score > 0.0 => [candidates[best], score],
+            _ => [0.0, 0.0],
+        };
+        self.to_units(result).to_vec()
+    }
+
+    /// Perceived (virtual) pitch as `[pitch, strength]` in the configured units,
+    /// after Hermes' perceptually weighted subharmonic summation. The magnitude
+    /// spectrum is reduced to its peaks (±2 bins around local maxima within 60 dB
+    /// of the strongest), compressed to a 0..1 level above that 60 dB floor (loudness
+    /// grows roughly with dB, not amplitude), and weighted by the auditory
+    /// sensitivity curve `0.5 + atan(3·log2(f / 65.5)) / π`, which discounts
+    /// low partials. Summing it over 15 harmonics as in `detect_shs` gives the
+    /// residue pitch of a tone missing its fundamental, not its lowest partial.
+    /// Strength is the winner's contrast over the average candidate,
+    /// `1 - mean / best` in 0..1: near 0.9 for clear complex tones, near 0.3
+    /// for noise, whose dense peaks score every candidate alike. Silence reads
+    /// as unvoiced with strength 0.
+    #[wasm_bindgen]
+    pub fn virtual_pitch(&mut self, samples: &[f32]) -> Vec<f32> {
+        const NUM_HARMONICS: usize = 15;
+        let magnitude = self.shs_magnitude(samples);
+        let n_bins = magnitude.len();
+        let loudest = magnitude.iter().fold(0.0f32, |acc, &m| acc.max(m));
+        if loudest <= 0.0 {
+            return self.to_units([0.0, 0.0]).to_vec();
+        }
+        
+        // Step 1: peak-enhanced, compressed, sensitivity-weighted spectrum
+        let bin_hz = self.sample_rate / self.fft_size as f32;
+        let level = |m: f32| (1.0 + 20.0 * (m / loudest + 1e-12).log10() / 60.0).max(0.0);
+        let mut perceptual = vec![0.0f32; n_bins];
+        for k in 1..n_bins.saturating_sub(1) {
+            if magnitude[k] > magnitude[k - 1] && magnitude[k] >= magnitude[k + 1] && level(magnitude[k]) > 0.0 {
+                for j in k.saturating_sub(2)..(k + 3).min(n_bins) {
+                    let sensitivity = 0.5 + (3.0 * (j.max(1) as f32 * bin_hz / 65.5).log2()).atan() / PI;
+                    perceptual[j] = level(magnitude[j]) * sensitivity;
+                }
+            }
+        }
+        
+        // Step 2: subharmonic summation over the weighted spectrum
+        let peak = perceptual.iter().fold(0.0f32, |acc, &p| acc.max(p));
+        let (candidates, salience) = self.shs_scores(&perceptual, peak, NUM_HARMONICS);
+        let mean = salience.iter().sum::<f32>() / salience.len().max(1) as f32;
+        let result = match salience.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)) {
+            Some((best, &score)) if score > 0.0 => [candidates[best], 1.0 - mean / score],
+            _ => [0.0, 0.0],
+        };
+        self.to_units(result).to_vec()
+    }
+
+    /// Cepstral peak prominence in dB, a voice-quality (dysphonia) measure: the
+    /// power cepstrum (dB) of the Hann-windowed frame's log power spectrum, a
+    /// least-squares line over quefrencies from 1 ms to the longest period, and the
+    /// height of the cepstral peak within the pitch range's periods above that
+    /// line. A clean periodic voice scores around 45-50 dB and breathy voice or
+    /// noise much less (white noise's random cepstral maxima sit near 15 dB);
+    /// a silent frame gives NaN.
+    #[wasm_bindgen]
+    pub fn cpp(&mut self, samples: &[f32]) -> f32 {
+        let n = samples.len().min(self.frame_size);
+        if frame_energy(&samples[..n], false) <= 0.0 {
+            return f32::NAN;
+        }
+        
+        // Step 1: log power spectrum (dB), then its inverse FFT
+        let window = cached_window(n, WindowKind::Hann);
+        for (i, b) in self.buffer_a.iter_mut().enumerate() {
+            *b = Complex::new(if i < n { samples[i] * window[i] } else { 0.0 }, 0.0);
+        }
+        self.fft_forward.process_with_scratch(&mut self.buffer_a, &mut self.scratch);
+        for (b, a) in self.buffer_b.iter_mut().zip(&self.buffer_a) {
+            *b = Complex::new(10.0 * (a.norm_sqr() + 1e-12).log10(), 0.0);
+        }
+        self.fft_inverse.process_with_scratch(&mut self.buffer_b, &mut self.scratch);
+        let scale = 1.0 / self.fft_size as f32;
+        let cepstrum_db = |c: Complex<f32>| 10.0 * ((c.re * scale) * (c.re * scale) + 1e-12).log10();
+        
+        // Step 2: regression line from 1 ms to the longest searched period
+        let (min_period, search_end) = self.lag_range(self.min_frequency, self.max_frequency, self.fft_size / 2);
+        let fit_start = ((0.001 * self.sample_rate) as usize).clamp(1, min_period.max(1));
+        if min_period >= search_end || search_end - fit_start < 2 {
+            return f32::NAN;
+        }
+        let points: Vec<(f32, f32)> = (fit_start..search_end)
+            .map(|q| (q as f32, cepstrum_db(self.buffer_b[q])))
+            .collect();
+        let (slope, intercept) = line_fit(&points);
+        
+        // Step 3: highest peak within the pitch periods, above the line
+        let (peak_q, peak_db) = points.iter()
+            .filter(|p| p.0 >= min_period as f32)
+            .fold((0.0f32, f32::NEG_INFINITY), |best, &(x, y)| if y > best.1 { (x, y) } else { best });
+        peak_db - (intercept + slope * peak_q as f64) as f32
+    }
+
+    /// Two dominant pitches as `[pitch1, conf1, pitch2, conf2, interval_semitones]`,
+    /// strongest first and in the configured units, from 8-harmonic SHS salience. After the first pitch is
+    /// picked its harmonics are cancelled from the spectrum (±2 bins each) and the
+    /// salience recomputed, so a second voice must be explained by partials of
+    /// its own. Candidates within 3 % of an integer ratio to the first (its
+    /// harmonics and subharmonics, octaves included) are skipped. A second pitch
+    /// scoring under 40 % of the first is not clearly present and reads as
+    /// unvoiced with a NaN interval; silence gives both unvoiced.
+    #[wasm_bindgen]
+    pub fn detect_duet(&mut self, samples: &[f32]) -> Vec<f32> {
+        let magnitude = self.shs_magnitude(samples);
+        let [f1, conf1, f2, conf2] = self.duet_candidates(magnitude);
+        let (second, interval) = if conf1 > 0.0 && conf2 >= 0.4 * conf1 {
+            ([f2, conf2], (12.0 * (f2 / f1).log2()).abs())
+        } else {
+            ([0.0, 0.0], f32::NAN)
+        };
+        let [p1, c1] = self.to_units([f1, conf1]);
+        let [p2, c2] = self.to_units(second);
+        vec![p1, c1, p2, c2, interval]
+    }
+
+    /// Per-frame double-talk probability 0..1 over frames `hop_size` apart, for
+    /// flagging overlapping speakers. Each frame's `detect_duet` candidates give
+    /// the salience of the second, harmonically unrelated pitch relative to the
+    /// first; a logistic around the 40 % presence limit maps that ratio to a
+    /// probability, which is then scaled by the spectral tonality (1 - flatness
+    /// against white noise's ~0.56) so noise, whose SHS picks are arbitrary,
+    /// stays low. Silent frames give 0.
+    #[wasm_bindgen]
+    pub fn detect_overlap(&mut self, samples: &[f32], hop_size: usize) -> Vec<f32> {
+        let hop_size = hop_size.max(1);
+        (0..frame_count(samples.len(), self.frame_size, hop_size))
+            .map(|i| {
+                let magnitude = self.shs_magnitude(frame_slice(samples, i, self.frame_size, hop_size));
+                
+                // Step 1: tonality from the flatness of the power spectrum
+                let bins = &magnitude[1..];
+                let count = bins.len().max(1) as f32;
+                let mean = bins.iter().map(|m| m * m).sum::<f32>() / count + 1e-12;
+                let log_mean = bins.iter().map(|m| (m * m + 1e-12).ln()).sum::<f32>() / count;
+                let tonality = (1.0 - (log_mean.exp() / mean) / 0.56).clamp(0.0, 1.0);
+                
+                // Step 2: strength of an unrelated second pitch
+                let [_, conf1, _, conf2] = self.duet_candidates(magnitude);
+                if conf1 <= 0.0 {
+                    return 0.0;
+                }
+                let presence = 1.0 / (1.0 + (-(conf2 / conf1 - 0.4) / 0.08).exp());
+                presence * tonality
+            })
+            .collect()
+    }
+
+    /// Step of the SHS candidate grid in cents (default 10)
+    #[wasm_bindgen]
+    pub fn set_shs_resolution(&mut self, cents: f32) {
+        self.shs_resolution_cents = cents.clamp(1.0, 100.0);
+    }
+
+    /// SHS weight ratio between successive harmonics (default 0.84, after Hermes)
+    #[wasm_bindgen]
+    pub fn set_shs_decay(&mut self, decay: f32) {
+        self.shs_decay = decay.clamp(0.0, 1.0);
+    }
+
+    /// Weight the magnitude spectrum behind the SHS methods (`detect_shs`,
+    /// `detect_duet`, `detect_overlap`, `virtual_pitch`) by an equal-loudness
+    /// curve before the harmonic sums (default off). The curve is A-weighting
+    /// (IEC 61672), the usual closed form of the inverted 40-phon ISO 226
+    /// contour: 0 dB at 1 kHz, about -19 dB at 100 Hz and -30 dB at 50 Hz, so
+    /// a loud hum or rumble below the voice no longer outscores its harmonics.
+    #[wasm_bindgen]
+    pub fn set_loudness_weighting(&mut self, enabled: bool) {
+        self.loudness_weighting = enabled;
+    }
+
+    /// Number of CMND points used for the sub-sample parabola fit (odd, default 3).
+    /// Wider fits average out the coarse lag grid at low pitches (long periods).
+    #[wasm_bindgen]
+    pub fn set_lag_refinement(&mut self, points: usize) {
+        self.lag_refinement = (points.clamp(3, 15) / 2) * 2 + 1;
+    }
+
+    /// Least-squares parabola through the CMND around `tau`; returns the vertex
+    /// lag. Falls back to the integer lag if the fit is not a convex minimum.
+    fn refine_lag(&self, tau: usize, tau_max: usize) -> f32 {
+        let half = (self.lag_refinement / 2).min(tau).min(tau_max.saturating_sub(tau + 1));
+        if half == 0 {
+            return tau as f32;
+        }
+        
+        // Symmetric abscissae: Σx = Σx³ = 0, so the normal equations decouple.
+        // Values are taken relative to the dip bottom to avoid f32 cancellation.
+        let (mut n, mut sx2, mut sx4) = (0.0f32, 0.0f32, 0.0f32);
+        let (mut sy, mut sxy, mut sx2y) = (0.0f32, 0.0f32, 0.0f32);
+        for k in 0..=2 * half {
+            let x = k as f32 - half as f32;
+            let y = self.cmnd[tau + k - half] - self.cmnd[tau];
+            n += 1.0;
+            sx2 += x * x;
+            sx4 += x * x * x * x;
+            sy += y;
+            sxy += x * y;
+            sx2y += x * x * y;
+        }
+        
+        let curvature = (n * sx2y - sx2 * sy) / (n * sx4 - sx2 * sx2);
+        if curvature <= 1e-10 {
+            return tau as f32;
+        }
+        let slope = sxy / sx2;
+        let adjustment = -slope / (2.0 * curvature);
+        tau as f32 + adjustment.clamp(-0.5, 0.5)
+    }
+
+    /// Confidence gate for `detect_batch` (default 0 = off). Frames that pass the
+    /// YIN threshold but have `confidence < conf` are emitted as `[0, confidence]`.
+    /// Single-frame `detect` is not affected.
+    #[wasm_bindgen]
+    pub fn set_min_confidence(&mut self, conf: f32) {
+        self.min_confidence = conf;
+    }
+
+    /// Frequency range searched by YIN, pYIN and SHS (default 50-500 Hz)
+    #[wasm_bindgen]
+    pub fn set_frequency_range(&mut self, min_hz: f32, max_hz: f32) {
+        self.min_frequency = min_hz.max(1.0);
+        self.max_frequency = max_hz.max(self.min_frequency * 1.01);
+    }
+
+    /// Histogram of detected frequencies over frames `hop_size` apart: `num_bins`
+    /// log-spaced bins spanning the frequency range, normalized to sum to 1.
+    /// Unvoiced frames (including those below `set_min_confidence`) are excluded;
+    /// all zeros when no frame is voiced.
+    #[wasm_bindgen]
+    pub fn period_histogram(&mut self, samples: &[f32], hop_size: usize, num_bins: usize) -> Vec<f32> {
+        let num_bins = num_bins.max(1);
+        let mut histogram = vec![0.0f32; num_bins];
+        let span = (self.max_frequency / self.min_frequency).ln();
+        let hop_size = hop_size.max(1);
+        let num_frames = frame_count(samples.len(), self.frame_size, hop_size);
+        let mut voiced = 0usize;
+        
+        for i in 0..num_frames {
+            let [frequency, confidence] = self.detect_frame(frame_slice(samples, i, self.frame_size, hop_size));
+            if frequency > 0.0 && confidence >= self.min_confidence {
+                let bin = ((frequency / self.min_frequency).ln() / span * num_bins as f32).max(0.0) as usize;
+                histogram[bin.min(num_bins - 1)] += 1.0;
+                voiced += 1;
+            }
+        }
+        
+        if voiced > 0 {
+            histogram.iter_mut().for_each(|h| *h /= voiced as f32);
+        }
+        histogram
+    }
+
+    /// Glissandi in a Hz pitch track (one value per `hop_size` samples, unvoiced <= 0)
+    /// as `[start_frame, end_frame, semitones_per_second, ...]`, end exclusive.
+    /// The track is smoothed over one vibrato period (200 ms) within each voiced run,
+    /// so vibrato averages out; glides are the remaining monotonic stretches whose
+    /// slope stays above the minimum rate for at least the minimum duration.
+    /// The rate is the least-squares slope of the unsmoothed track over the glide.
+    #[wasm_bindgen]
+    pub fn detect_glides(&self, pitch_track: &[f32], hop_size: usize, sample_rate: f32) -> Vec<f32> {
+        let frame_seconds = hop_size.max(1) as f32 / sample_rate;
+        let half_window = (0.1 / frame_seconds).round() as usize;
+        let min_frames = ((self.glide_min_duration_ms / 1000.0 / frame_seconds).ceil() as usize).max(2);
+        let semitones: Vec<f32> = pitch_track.iter()
+            .map(|&f| if f > 0.0 { 12.0 * (f / 440.0).log2() } else { f32::NAN })
+            .collect();
+        let mut glides = Vec::new();
+        
+        let mut start = 0;
+        while start < semitones.len() {
+            if semitones[start].is_nan() {
+                start += 1;
+                continue;
+            }
+            let end = semitones[start..].iter().position(|s| s.is_nan()).map_or(semitones.len(), |p| start + p);
+            let run = &semitones[start..end];
+            
+            // Step 1: centred moving average, window shrunk symmetrically at the run edges
+            let smoothed: Vec<f32> = (0..run.len())
+                .map(|i| {
+                    let h = half_window.min(i).min(run.len() - 1 - i);
+                    run[i - h..=i + h].iter().sum::<f32>() / (2 * h + 1) as f32
+                })
+                .collect();
+            
+            // Step 2: stretches where the smoothed slope keeps one sign above the minimum rate
+            let min_step = self.glide_min_rate * frame_seconds;
+            let direction = |i: usize| -> i32 {
+                let step = smoothed[i + 1] - smoothed[i];
+                if step > min_step { 1 } else if step < -min_step { -1 } else { 0 }
+            };
+            let mut i = 0;
+            while i + 1 < run.len() {
+                let dir = direction(i);
+                let mut j = i + 1;
+                while j + 1 < run.len() && direction(j) == dir {
+                    j += 1;
+                }
+                if dir != 0 && j + 1 - i >= min_frames {
+                    // Step 3: least-squares slope of the raw track over frames i..=j
+                    let points: Vec<(f32, f32)> = (i..=j).map(|t| (t as f32, run[t])).collect();
+                    let (slope, _) = line_fit(&points);
+                    glides.extend_from_slice(&[(start + i) as f32, (start + j + 1) as f32, slope as f32 / frame_seconds]);
+                }
+                i = j;
+            }
+            start = end;
+        }
+        
+        glides
+    }
+
+    /// Shortest glide `detect_glides` reports (default 150 ms) and its minimum
+    /// slope in semitones per second (default 2)
+    #[wasm_bindgen]
+    pub fn set_glide_params(&mut self, min_duration_ms: f32, min_rate: f32) {
+        self.glide_min_duration_ms = min_duration_ms.max(0.0);
+        self.glide_min_rate = min_rate.max(0.0);
+    }
+
+    /// `detect_batch` fast path: frames whose mean-square level is below
+    /// `threshold_db` skip YIN and are emitted as unvoiced `[0, 0]`. Louder frames
+    /// are unchanged. Pass `-Infinity` to disable (the default).
+    #[wasm_bindgen]
+    pub fn set_skip_silence(&mut self, threshold_db: f32) {
+        self.skip_silence_db = threshold_db;
+    }
+
+    /// Fingerprint of every detection parameter (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("PitchDetector")
+            .f32(self.sample_rate)
+            .usize(self.frame_size)
+            .f32(self.threshold)
+            .bool(self.frame_normalization)
+            .usize(self.lag_refinement)
+            .f32(self.min_confidence)
+            .f32(self.skip_silence_db)
+            .usize(self.units as usize)
+            .f32(self.cents_reference)
+            .f32(self.min_frequency)
+            .f32(self.max_frequency)
+            .f32(self.shs_resolution_cents)
+            .f32(self.shs_decay)
+            .bool(self.loudness_weighting)
+            .f32(self.glide_min_duration_ms)
+            .f32(self.glide_min_rate)
+            .f32(self.mpm_threshold)
+            .finish()
+    }
+
+    /// Batch pitch detection - OPTIMIZED
+    #[wasm_bindgen]
+    pub fn detect_batch(&mut self, samples: &[f32], hop_size: usize) -> Vec<f32> {
+        let num_frames = (samples.len().saturating_sub(self.frame_size)) / hop_size + 1;
+        let mut results = Vec::with_capacity(num_frames * 2);
+        let _ = self.detect_batch_with(samples, hop_size, |_, result| {
+            results.extend_from_slice(result);
+            Ok::<(), ()>(())
+        });
+        results
+    }
+
+    /// Confidence-weighted smoothed pitch, one value per `detect_batch` frame in
+    /// the configured units. An exponential smoother on log2(frequency) with a
+    /// time constant of `smoothing` frames (0 = none) moves by `coeff · confidence`
+    /// of each voiced frame's deviation, so low-confidence frames barely move it.
+    /// It starts at the first voiced frame and holds its value through unvoiced
+    /// frames instead of resetting; frames before any voicing read as unvoiced.
+    #[wasm_bindgen]
+    pub fn smoothed_pitch(&mut self, samples: &[f32], hop_size: usize, smoothing: f32) -> Vec<f32> {
+        let hop = hop_size.max(1);
+        let num_frames = frame_count(samples.len(), self.frame_size, hop);
+        let coeff = if smoothing > 0.0 { 1.0 - (-1.0 / smoothing).exp() } else { 1.0 };
+        let mut state: Option<f32> = None;
+        
+        let mut contour = Vec::with_capacity(num_frames);
+        for i in 0..num_frames {
+            let [frequency, confidence] = self.detect_frame_gated(frame_slice(samples, i, self.frame_size, hop));
+            if frequency > 0.0 {
+                let target = frequency.log2();
+                let weight = coeff * confidence.clamp(0.0, 1.0);
+                state = Some(state.map_or(target, |s| s + weight * (target - s)));
+            }
+            let result = state.map_or([0.0, 0.0], |s| [s.exp2(), 1.0]);
+            contour.push(self.to_units(result)[0]);
+        }
+        contour
+    }
+
+    /// Streaming `detect_batch`: calls `callback(frame_index, result)` per frame, where
+    /// `result` is a `Float32Array` view of `[pitch, confidence]` into WASM memory
+    /// (no per-frame allocation). The view is valid only during the callback, which
+    /// must copy it to keep it and must not call back into the module (that can grow
+    /// memory and detach the view). Stops at and rethrows the first callback exception.
+    #[wasm_bindgen]
+    pub fn detect_batch_streaming(&mut self, samples: &[f32], hop_size: usize, callback: &js_sys::Function) -> Result<(), JsValue> {
+        self.detect_batch_with(samples, hop_size, |i, result| call_with_view(callback, i, result))
+    }
+}
+
+impl PitchDetector {
+    /// SHS candidate frequencies and their normalized (0..1) harmonic sums
+    fn shs_salience(&mut self, samples: &[f32], num_harmonics: usize) -> (Vec<f32>, Vec<f32>) {
+        let magnitude = self.shs_magnitude(samples);
+        let peak = magnitude.iter().fold(0.0f32, |acc, &m| acc.max(m));
+        self.shs_scores(&magnitude, peak, num_harmonics)
+    }
+
+    /// Hann-windowed, zero-padded magnitude spectrum (`fft_size / 2 + 1` bins),
+    /// equal-loudness weighted when that is enabled
+    fn shs_magnitude(&mut self, samples: &[f32]) -> Vec<f32> {
+        let n = samples.len().min(self.frame_size);
+        let window = cached_window(n, WindowKind::Hann);
+        for (i, b) in self.buffer_a.iter_mut().enumerate() {
+            *b = Complex::new(if i < n { samples[i] * window[i] } else { 0.0 }, 0.0);
+        }
+        self.fft_forward.process_with_scratch(&mut self.buffer_a, &mut self.scratch);
+        let bin_hz = self.sample_rate / self.fft_size as f32;
+        self.buffer_a[..self.fft_size / 2 + 1].iter()
+            .enumerate()
+            .map(|(k, c)| if self.loudness_weighting { c.norm() * equal_loudness_gain(k as f32 * bin_hz) } else { c.norm() })
+            .collect()
+    }
+
+    /// `[freq1, conf1, freq2, conf2]` behind `detect_duet`, before the 40 % test:
+    /// the second entry is the strongest unrelated pitch left after cancelling the
+    /// first one's harmonics, however weak (all zeros for silence)
+    fn duet_candidates(&self, mut magnitude: Vec<f32>) -> [f32; 4] {
+        const NUM_HARMONICS: usize = 8;
+        let peak = magnitude.iter().fold(0.0f32, |acc, &m| acc.max(m));
+        let strongest = |candidates: &[f32], salience: &[f32], allowed: &dyn Fn(f32) -> bool| {
+            candidates.iter()
+                .zip(salience)
+                .filter(|(&f, _)| allowed(f))
+                .fold((0.0f32, 0.0f32), |best, (&f, &score)| if score > best.1 { (f, score) } else { best })
+        };
+        
+        // Step 1: the strongest pitch
+        let (candidates, salience) = self.shs_scores(&magnitude, peak, NUM_HARMONICS);
+        let (f1, conf1) = strongest(&candidates, &salience, &|_| true);
+        if conf1 <= 0.0 {
+            return [0.0; 4];
+        }
+        
+        // Step 2: cancel its harmonics and look for an unrelated second pitch
+        let bin_hz = self.sample_rate / self.fft_size as f32;
+        let mut h = 1.0f32;
+        while h * f1 / bin_hz < magnitude.len() as f32 {
+            let center = (h * f1 / bin_hz).round() as usize;
+            let bins = center.saturating_sub(2)..(center + 3).min(magnitude.len());
+            magnitude[bins].fill(0.0);
+            h += 1.0;
+        }
+        let (candidates, salience) = self.shs_scores(&magnitude, peak, NUM_HARMONICS);
+        let unrelated = |f: f32| {
+            let ratio = f.max(f1) / f.min(f1);
+            (ratio - ratio.round()).abs() > 0.03 * ratio
+        };
+        let (f2, conf2) = strongest(&candidates, &salience, &unrelated);
+        [f1, conf1, f2, conf2]
+    }
+
+    /// SHS candidates and their harmonic sums over `magnitude`, normalized by a
+    /// spectrum at `peak` under every harmonic
+    fn shs_scores(&self, magnitude: &[f32], peak: f32, num_harmonics: usize) -> (Vec<f32>, Vec<f32>) {
+        let num_harmonics = num_harmonics.max(1);
+        let n_bins = magnitude.len();
+        
+        // Weighted sums at the harmonics of each log-spaced candidate
+        let bin_hz = self.sample_rate / self.fft_size as f32;
+        let step = 2.0f32.powf(self.shs_resolution_cents / 1200.0);
+        let num_candidates = ((self.max_frequency / self.min_frequency).ln() / step.ln()) as usize + 1;
+        let weight_total: f32 = (0..num_harmonics).map(|h| self.shs_decay.powi(h as i32)).sum();
+        let candidates: Vec<f32> = (0..num_candidates).map(|i| self.min_frequency * step.powi(i as i32)).collect();
+        let salience = candidates.iter()
+            .map(|&f0| {
+                let score: f32 = (1..=num_harmonics)
+                    .map(|h| {
+                        let pos = h as f32 * f0 / bin_hz;
+                        let bin = pos as usize;
+                        if bin + 1 >= n_bins {
+                            return 0.0;
+                        }
+                        let frac = pos - bin as f32;
+                        let value = magnitude[bin] * (1.0 - frac) + magnitude[bin + 1] * frac;
+                        self.shs_decay.powi(h as i32 - 1) * value
+                    })
+                    .sum();
+                if peak > 0.0 { score / (weight_total * peak) } else { 0.0 }
+            })
+            .collect();
+        
+        (candidates, salience)
+    }
+
+    /// `detect_batch` driving a closure with each frame's `[pitch, confidence]`
+    /// instead of collecting them; the first `Err` from `f` stops the scan.
+    pub fn detect_batch_with<E>(
+        &mut self,
+        samples: &[f32],
+        hop_size: usize,
+        mut f: impl FnMut(usize, &[f32]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let num_frames = frame_count(samples.len(), self.frame_size, hop_size);
+        
+        for i in 0..num_frames {
+            let result = self.detect_frame_gated(frame_slice(samples, i, self.frame_size, hop_size));
+            f(i, &self.to_units(result))?;
+        }
+        
+        Ok(())
+    }
+
+    /// `[pitch_hz, confidence]` of one batch frame: silent frames (below the
+    /// skip-silence level) are not analysed, and pitches under the minimum
+    /// confidence read as unvoiced
+    fn detect_frame_gated(&mut self, frame: &[f32]) -> [f32; 2] {
+        let mut result = if !frame.is_empty() && frame_energy(frame, true) < self.skip_silence_db {
+            [0.0, 0.0]
+        } else {
+            self.detect_frame(frame)
+        };
+        if result[1] < self.min_confidence {
+            result[0] = 0.0;
+        }
+        result
+    }
+}
+
+/// A-weighting amplitude gain at `freq` Hz, 1 at 1 kHz (IEC 61672)
+fn equal_loudness_gain(freq: f32) -> f32 {
+    let response = |f: f32| {
+        let f2 = f * f;
+        12194.0f32.powi(2) * f2 * f2
+            / ((f2 + 20.6f32.powi(2))
+                * ((f2 + 107.7f32.powi(2)) * (f2 + 737.9f32.powi(2))).sqrt()
+                * (f2 + 12194.0f32.powi(2)))
+    };
+    response(freq) / response(1000.0)
+}
+
+/// Invoke a JS frame callback with `(index, Float32Array view of values)`
+fn call_with_view(callback: &js_sys::Function, index: usize, values: &[f32]) -> Result<(), JsValue> {
+    // SAFETY: the view aliases `values` in linear memory, so it is sound only
+    // under the callers' documented contract: the callback copies the view if
+    // it keeps it and does not re-enter the module (a re-entrant call could
+    // grow memory and detach the view, or observe `values` being reused).
+    let view = unsafe { js_sys::Float32Array::view(values) };
+    callback.call2(&JsValue::NULL, &JsValue::from(index as u32), &view).map(|_| ())
+}
+
+/// Unit of the pitch reported by `PitchDetector`, with its unvoiced sentinel
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PitchUnits {
+    /// Frequency in Hz; unvoiced = 0
+    Hz,
+    /// MIDI note number (A4 = 440 Hz = 69); unvoiced = -1
+    Midi,
+    /// Cents relative to the detector's cents reference; unvoiced = NaN
+    Cents,
+    /// log2 of the frequency in Hz; unvoiced = 0
+    Log2Hz,
+}
+
+/// Pitch-shift ratio `target_hz / detected_hz` moving a detected pitch onto MIDI
+/// note `target_midi` (A4 = 69 = 440 Hz, equal temperament). Unvoiced input
+/// (0, negative or NaN `detected_hz`) gives 1, no shift.
+#[wasm_bindgen]
+pub fn shift_ratio_to_note(detected_hz: f32, target_midi: u32) -> f32 {
+    if detected_hz.is_nan() || detected_hz <= 0.0 {
+        return 1.0;
+    }
+    let target_hz = 440.0 * 2f64.powf((target_midi as f64 - 69.0) / 12.0);
+    (target_hz / detected_hz as f64) as f32
+}
+
+/// `shift_ratio_to_note` in semitones (`12·log2(ratio)`); 0 for unvoiced input
+#[wasm_bindgen]
+pub fn shift_semitones_to_note(detected_hz: f32, target_midi: u32) -> f32 {
+    if detected_hz.is_nan() || detected_hz <= 0.0 {
+        return 0.0;
+    }
+    (target_midi as f64 - 69.0 - 12.0 * (detected_hz as f64 / 440.0).log2()) as f32
+}
+
+/// Strongest 0.5-20 Hz modulation of an envelope (amplitude or pitch contour sampled
+/// at `sample_rate_of_envelope`) as `[mod_freq_hz, mod_depth, regularity]`.
+/// The mean-removed, Hann-windowed envelope is zero-padded (at least 4×) and the
+/// peak refined by parabolic interpolation. Depth is the modulation's peak amplitude
+/// in the envelope's own units; regularity is the share of 0.5-20 Hz power within
+/// the peak's Hann main lobe (±2 unpadded bins), near 1 for a steady
+/// tremolo or vibrato and low for random fluctuation. `[0, 0, 0]` without a peak.
+#[wasm_bindgen]
+pub fn dominant_modulation(envelope: &[f32], sample_rate_of_envelope: f32) -> Vec<f32> {
+    let n = envelope.len();
+    if n < 4 {
+        return vec![0.0, 0.0, 0.0];
+    }
+    
+    // Step 1: windowed, zero-padded spectrum of the zero-mean envelope
+    let size = (4 * n).next_power_of_two();
+    let mean = envelope.iter().sum::<f32>() / n as f32;
+    let window = cached_window(n, WindowKind::Hann);
+    let mut spectrum: Vec<Complex<f32>> = (0..size)
+        .map(|i| Complex::new(if i < n { (envelope[i] - mean) * window[i] } else { 0.0 }, 0.0))
+        .collect();
+    FftPlanner::new().plan_fft_forward(size).process(&mut spectrum);
+    let magnitude: Vec<f32> = spectrum[..size / 2 + 1].iter().map(|c| c.norm()).collect();
+    
+    // Step 2: peak in the modulation band
+    let bin_hz = sample_rate_of_envelope / size as f32;
+    let lo = ((0.5 / bin_hz).ceil() as usize).max(1);
+    let hi = ((20.0 / bin_hz).floor() as usize).min(size / 2 - 1);
+    if lo > hi {
+        return vec![0.0, 0.0, 0.0];
+    }
+    let k = (lo..=hi).max_by(|&a, &b| magnitude[a].total_cmp(&magnitude[b])).unwrap_or(lo);
+    if magnitude[k] <= 1e-12 {
+        return vec![0.0, 0.0, 0.0];
+    }
+    let (delta, peak) = parabolic_peak(magnitude[k - 1], magnitude[k], magnitude[k + 1]);
+    
+    // Step 3: depth from the window's coherent gain, regularity from the main lobe share
+    let depth = 2.0 * peak / window.iter().sum::<f32>();
+    let lobe = 2 * size / n;
+    let band_power: f32 = magnitude[lo..=hi].iter().map(|m| m * m).sum();
+    let lobe_power: f32 = magnitude[k.saturating_sub(lobe).max(lo)..=(k + lobe).min(hi)].iter().map(|m| m * m).sum();
+    vec![(k as f32 + delta) * bin_hz, depth, lobe_power / band_power]
+}
+
+/// Closest target note to a sung frequency as `[reference_hz, cents_error]`, where the
+/// error is `1200·log2(freq / reference)` (positive = sharp) and closeness is
+/// measured in cents. Unvoiced input (≤ 0 or NaN) or no usable reference gives `[0, NaN]`.
+#[wasm_bindgen]
+pub fn nearest_reference_pitch(freq: f32, reference_hz: &[f32]) -> Vec<f32> {
+    if freq.is_nan() || freq <= 0.0 {
+        return vec![0.0, f32::NAN];
+    }
+    reference_hz.iter()
+        .filter(|&&r| r > 0.0)
+        .map(|&r| (r, 1200.0 * (freq / r).log2()))
+        .min_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+        .map_or(vec![0.0, f32::NAN], |(r, cents)| vec![r, cents])
+}
+
+/// Pull a pitch track (Hz) toward the nearest allowed note of a scale.
+/// `scale_mask[pc] != 0` allows pitch class `pc` (0 = C ... 11 = B), with A4 tuned
+/// to `reference_hz`. Each pitch moves `strength` (0 = none, 1 = hard snap) of the
+/// way to its target in the cents domain. Unvoiced (≤ 0 or NaN) frames pass
+/// through, as does everything when no pitch class is allowed.
+#[wasm_bindgen]
+pub fn snap_to_scale(pitches: &[f32], scale_mask: &[u8], reference_hz: f32, strength: f32) -> Vec<f32> {
+    let allowed: Vec<bool> = (0..12).map(|pc| scale_mask.get(pc).is_some_and(|&m| m != 0)).collect();
+    let strength = strength.clamp(0.0, 1.0);
+    
+    pitches.iter()
+        .map(|&hz| {
+            if !allowed.contains(&true) || hz.is_nan() || hz <= 0.0 {
+                return hz;
+            }
+            // Nearest allowed semitone, searching outward from the rounded note
+            let note = 69.0 + 12.0 * (hz / reference_hz).log2();
+            let target = (0..=6)
+                .flat_map(|d| [note.round() - d as f32, note.round() + d as f32])
+                .filter(|&m| allowed[(m as i32).rem_euclid(12) as usize])
+                .min_by(|a, b| (a - note).abs().total_cmp(&(b - note).abs()))
+                .unwrap_or(note);
+            let snapped = note + strength * (target - note);
+            reference_hz * 2.0f32.powf((snapped - 69.0) / 12.0)
+        })
+        .collect()
+}
+
+/// Per-frame voicing probability from energy, periodicity, ZCR and spectral tilt.
+///
+/// Each feature is mapped to roughly [-1, 1] (positive = voice-like):
+/// - energy: `(dB + 40) / 20`, so -60 dB -> -1 and -20 dB -> +1
+/// - periodicity: `1 - 2 * aperiodicity`, from the YIN CMND minimum
+/// - ZCR: `1 - zcr / 0.15`, so 0.15 crossings per sample is neutral
+/// - tilt: lag-1 normalized autocorrelation, near +1 for low-pass (voiced)
+///   spectra and negative for high-frequency fricative energy
+///
+/// and the probability is `1 / (1 + exp(-(bias + Σ wᵢ·fᵢ)))`.
+/// Defaults (energy 3, periodicity 3, ZCR 1, tilt 1, bias 0) put clean
+/// vowels near 1, unvoiced fricatives around 0.1-0.3 and silence near 0.
+#[wasm_bindgen]
+pub struct VoicingEstimator {
+    frame_size: usize,
+    hop_size: usize,
+    energy_weight: f32,
+    periodicity_weight: f32,
+    zcr_weight: f32,
+    tilt_weight: f32,
+    bias: f32,
+    // classify_frames: frames quieter than this are silence
+    silence_threshold_db: f32,
+    pitch: PitchDetector,
+}
+
+#[wasm_bindgen]
+impl VoicingEstimator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32, frame_size: usize, hop_size: usize) -> VoicingEstimator {
+        console_log!("🦀 [Rust DSP] Voicing Estimator: sr={}, frame={}, hop={}", sample_rate, frame_size, hop_size);
+        
+        VoicingEstimator {
+            frame_size,
+            hop_size,
+            energy_weight: 3.0,
+            periodicity_weight: 3.0,
+            zcr_weight: 1.0,
+            tilt_weight: 1.0,
+            bias: 0.0,
+            silence_threshold_db: -50.0,
+            pitch: PitchDetector::new(sample_rate, frame_size),
+        }
+    }
+
+    /// Logistic weights applied to the normalized features
+    #[wasm_bindgen]
+    pub fn set_weights(&mut self, energy: f32, periodicity: f32, zcr: f32, tilt: f32) {
+        self.energy_weight = energy;
+        self.periodicity_weight = periodicity;
+        self.zcr_weight = zcr;
+        self.tilt_weight = tilt;
+    }
+
+    /// Constant added to the weighted sum before the logistic
+    #[wasm_bindgen]
+    pub fn set_bias(&mut self, bias: f32) {
+        self.bias = bias;
+    }
+
+    /// Voicing probability (0..1) of a single frame
+    #[wasm_bindgen]
+    pub fn probability(&mut self, frame: &[f32]) -> f32 {
+        if frame.is_empty() {
+            return 0.0;
+        }
+        
+        // Step 1: normalized features
+        let energy = ((frame_energy(frame, true) + 40.0) / 20.0).clamp(-1.0, 1.0);
+        let periodicity = 1.0 - 2.0 * self.pitch.aperiodicity(frame).clamp(0.0, 1.0);
+        let zcr = (1.0 - zero_crossing_rate(frame) / 0.15).clamp(-1.0, 1.0);
+        let r0: f32 = frame.iter().map(|&s| s * s).sum();
+        let r1: f32 = frame.windows(2).map(|w| w[0] * w[1]).sum();
+        let tilt = if r0 > 1e-10 { r1 / r0 } else { 0.0 };
+        
+        // Step 2: weighted logistic
+        let z = self.bias
+            + self.energy_weight * energy
+            + self.periodicity_weight * periodicity
+            + self.zcr_weight * zcr
+            + self.tilt_weight * tilt;
+        1.0 / (1.0 + (-z).exp())
+    }
+
+    /// Voicing probability for each `frame_size` frame taken every `hop_size` samples
+    #[wasm_bindgen]
+    pub fn probabilities(&mut self, samples: &[f32]) -> Vec<f32> {
+        (0..frame_count(samples.len(), self.frame_size, self.hop_size))
+            .map(|i| {
+                let frame = frame_slice(samples, i, self.frame_size, self.hop_size);
+                self.probability(frame)
+            })
+            .collect()
+    }
+
+    /// Energy below which `classify_frames` reports silence (default -50 dB)
+    #[wasm_bindgen]
+    pub fn set_silence_threshold(&mut self, energy_db: f32) {
+        self.silence_threshold_db = energy_db;
+    }
+
+    /// Fingerprint of the framing, weights and inner pitch detector (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("VoicingEstimator")
+            .usize(self.frame_size)
+            .usize(self.hop_size)
+            .f32(self.energy_weight)
+            .f32(self.periodicity_weight)
+            .f32(self.zcr_weight)
+            .f32(self.tilt_weight)
+            .f32(self.bias)
+            .f32(self.silence_threshold_db)
+            .u64(self.pitch.config_hash())
+            .finish()
+    }
+
+    /// Three-way frame labels: 0 = silence, 1 = unvoiced, 2 = voiced
+    /// (voicing probability above 0.5)
+    #[wasm_bindgen]
+    pub fn classify_frames(&mut self, samples: &[f32]) -> Vec<u8> {
+        (0..frame_count(samples.len(), self.frame_size, self.hop_size))
+            .map(|i| {
+                let frame = frame_slice(samples, i, self.frame_size, self.hop_size);
+                if frame.is_empty() || frame_energy(frame, true) < self.silence_threshold_db {
+                    0
+                } else if self.probability(frame) > 0.5 {
+                    2
+                } else {
+                    1
+                }
+            })
+            .collect()
+    }
+
+    /// Sample offset where voicing begins: the centre of the first frame in a run of
+    /// 3 voiced frames, so a single stray voiced frame in noise is ignored.
+    /// -1 if never voiced.
+    #[wasm_bindgen]
+    pub fn voicing_onset(&mut self, samples: &[f32]) -> i32 {
+        const MIN_VOICED_RUN: usize = 3;
+        let labels = self.classify_frames(samples);
+        labels.windows(MIN_VOICED_RUN.min(labels.len()).max(1))
+            .position(|run| run.iter().all(|&label| label == 2))
+            .map_or(-1, |i| (i * self.hop_size + self.frame_size / 2).min(samples.len()) as i32)
+    }
+}
+
+/// Speech vs music discrimination over decision windows (default 1 s) of 20 ms
+/// frames with a 10 ms hop. Three window features, each mapped through a logistic
+/// and averaged:
+/// - 4 Hz modulation: variance (dB²) of the frame log-energy contour band-passed to
+///   2-8 Hz, the syllabic rhythm speech has and sustained music lacks
+/// - ZCR: fraction of frames whose ZCR exceeds 1.5× the window mean (voiced and
+///   unvoiced speech alternate; music stays steady)
+/// - spectral flux regularity: coefficient of variation of the frame-to-frame flux
+///   of the normalized magnitude spectrum (bursty for speech)
+#[wasm_bindgen]
+pub struct SpeechMusicClassifier {
+    sample_rate: f32,
+    frame_size: usize,
+    hop_size: usize,
+    window_ms: f32,
+    fft: FftProcessor,
+}
+
+#[wasm_bindgen]
+impl SpeechMusicClassifier {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32) -> SpeechMusicClassifier {
+        console_log!("🦀 [Rust DSP] Speech/Music Classifier: sr={}", sample_rate);
+        
+        let frame_size = ((0.02 * sample_rate).round() as usize).max(16);
+        let mut fft = FftProcessor::new(frame_size);
+        fft.set_sample_rate(sample_rate);
+        SpeechMusicClassifier {
+            sample_rate,
+            frame_size,
+            hop_size: frame_size / 2,
+            window_ms: 1000.0,
+            fft,
+        }
+    }
+
+    /// Decision window length in milliseconds (default 1000, at least 250)
+    #[wasm_bindgen]
+    pub fn set_window_ms(&mut self, window_ms: f32) {
+        self.window_ms = window_ms.max(250.0);
+    }
+
+    /// Fingerprint of the framing and analysis window (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("SpeechMusicClassifier")
+            .f32(self.sample_rate)
+            .usize(self.frame_size)
+            .usize(self.hop_size)
+            .f32(self.window_ms)
+            .u64(self.fft.config_hash())
+            .finish()
+    }
+
+    /// 0 (music) to 1 (speech), averaged over the decision windows; 0.5 when
+    /// the input is shorter than one frame
+    #[wasm_bindgen]
+    pub fn speech_music_score(&mut self, samples: &[f32]) -> f32 {
+        if samples.len() < self.frame_size {
+            return 0.5;
+        }
+        let num_frames = frame_count(samples.len(), self.frame_size, self.hop_size);
+        let n_bins = self.frame_size / 2 + 1;
+        
+        // Step 1: per-frame log energy, ZCR and spectral flux
+        let mut energy_db = Vec::with_capacity(num_frames);
+        let mut zcr = Vec::with_capacity(num_frames);
+        let mut flux = Vec::with_capacity(num_frames);
+        let mut power = vec![0.0f32; n_bins];
+        let mut previous = vec![0.0f32; n_bins];
+        for i in 0..num_frames {
+            let frame = frame_slice(samples, i, self.frame_size, self.hop_size);
+            energy_db.push(10.0 * (frame_energy(frame, false) + 1e-8).log10());
+            zcr.push(zero_crossing_rate(frame));
+            
+            self.fft.power_spectrum_into(frame, &mut power);
+            let norm = power.iter().sum::<f32>().sqrt() + 1e-12;
+            let mut frame_flux = 0.0f32;
+            for (p, prev) in power.iter().zip(previous.iter_mut()) {
+                let magnitude = p.sqrt() / norm;
+                frame_flux += (magnitude - *prev) * (magnitude - *prev);
+                *prev = magnitude;
+            }
+            if i > 0 {
+                flux.push(frame_flux);
+            }
+        }
+        
+        // Step 2: 2-8 Hz band of the energy contour (frame rate = sample_rate / hop)
+        let frame_rate = self.sample_rate / self.hop_size as f32;
+        let q = std::f32::consts::FRAC_1_SQRT_2;
+        let modulation = Biquad::lowpass(frame_rate, 8.0, q)
+            .filtfilt(&Biquad::highpass(frame_rate, 2.0, q).filtfilt(&energy_db));
+        
+        // Step 3: score each decision window
+        let window = ((self.window_ms / 1000.0 * frame_rate) as usize).clamp(2, num_frames);
+        let logistic = |x: f32, center: f32, width: f32| 1.0 / (1.0 + (-(x - center) / width).exp());
+        let windows = num_frames / window;
+        let total: f32 = (0..windows)
+            .map(|w| {
+                let range = w * window..(w + 1) * window;
+                let band = &modulation[range.clone()];
+                let band_mean = band.iter().sum::<f32>() / window as f32;
+                let modulation_var = band.iter().map(|&m| (m - band_mean) * (m - band_mean)).sum::<f32>() / window as f32;
+                
+                let rates = &zcr[range.clone()];
+                let zcr_mean = rates.iter().sum::<f32>() / window as f32;
+                let hzcrr = rates.iter().filter(|&&z| z > 1.5 * zcr_mean).count() as f32 / window as f32;
+                
+                let fluxes = &flux[range.start.min(flux.len())..range.end.min(flux.len())];
+                let count = fluxes.len().max(1) as f32;
+                let flux_mean = fluxes.iter().sum::<f32>() / count;
+                let flux_std = (fluxes.iter().map(|&f| (f - flux_mean) * (f - flux_mean)).sum::<f32>() / count).sqrt();
+                let flux_cv = flux_std / (flux_mean + 1e-12);
+                
+                (logistic(modulation_var, 20.0, 8.0) + logistic(hzcrr, 0.1, 0.04) + logistic(flux_cv, 1.0, 0.3)) / 3.0
+            })
+            .sum();
+        total / windows as f32
+    }
+}
+
+/// Formant Analyzer using LPC - OPTIMIZED
+#[wasm_bindgen]
+pub struct FormantAnalyzer {
+    sample_rate: f32,
+    lpc_order: usize,
+    // Pre-allocated buffers
+    autocorr: Vec<f32>,
+    lpc_coeffs: Vec<f32>,
+    lpc_prev: Vec<f32>,
+    response_re: Vec<f32>,
+    // Moving-average width (bins, odd) applied to the response before peak picking
+    response_smoothing: usize,
+    response_smoothed: Vec<f32>,
+    // Peak prominence threshold as a fraction of the response maximum (0 = legacy test)
+    formant_prominence: f32,
+    // Final Levinson-Durbin prediction error of the last fit
+    prediction_error: f32,
+    // analyze_batch voicing gate and sentinel policy
+    min_prediction_gain_db: f32,
+    unvoiced_mode: UnvoicedMode,
+    // Optional decimation stage: target rate (0 = analyze at sample_rate),
+    // anti-aliasing FIR taps and the reusable decimated frame
+    analysis_rate: f32,
+    decimation_taps: Vec<f32>,
+    decimated: Vec<f32>,
+    // Largest LPC pole radius after fitting (0 = leave poles untouched)
+    max_pole_radius: f32,
+}
+
+#[wasm_bindgen]
+impl FormantAnalyzer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32, lpc_order: usize) -> FormantAnalyzer {
+        console_log!("🦀 [Rust DSP] Formant Analyzer: sr={}, order={} (OPTIMIZED)", sample_rate, lpc_order);
+        
+        FormantAnalyzer {
+            sample_rate,
+            lpc_order,
+            autocorr: vec![0.0; lpc_order + 1],
+            lpc_coeffs: vec![0.0; lpc_order],
+            lpc_prev: vec![0.0; lpc_order],
+            response_re: vec![0.0; 512],
+            response_smoothing: 1,
+            response_smoothed: vec![0.0; 512],
+            formant_prominence: 0.0,
+            prediction_error: 0.0,
+            min_prediction_gain_db: 6.0,
+            unvoiced_mode: UnvoicedMode::Zero,
+            analysis_rate: 0.0,
+            decimation_taps: Vec::new(),
+            decimated: Vec::new(),
+            max_pole_radius: 0.0,
+        }
+    }
+
+    /// Lowpass and decimate frames to `hz` before LPC (e.g. 10000 for speech formants).
+    /// Focuses the LPC order on the formant band and speeds up analysis; reported
+    /// formants stay in Hz. `hz <= 0` or `hz >= sample_rate` disables decimation.
+    #[wasm_bindgen]
+    pub fn set_analysis_sample_rate(&mut self, hz: f32) {
+        if hz <= 0.0 || hz >= self.sample_rate {
+            self.analysis_rate = 0.0;
+            self.decimation_taps.clear();
+            return;
+        }
+        
+        // Blackman-windowed sinc: passband to 0.4·hz, stopband from the new Nyquist
+        let cutoff = 0.45 * hz / self.sample_rate;
+        let transition = 0.1 * hz / self.sample_rate;
+        let num_taps = ((5.5 / transition).ceil() as usize) | 1;
+        let center = (num_taps / 2) as f32;
+        self.decimation_taps = (0..num_taps)
+            .map(|i| {
+                let x = i as f32 - center;
+                let sinc = if x == 0.0 { 2.0 * cutoff } else { (2.0 * PI * cutoff * x).sin() / (PI * x) };
+                let phase = 2.0 * PI * i as f32 / (num_taps - 1) as f32;
+                sinc * (0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos())
+            })
+            .collect();
+        let gain: f32 = self.decimation_taps.iter().sum();
+        self.decimation_taps.iter_mut().for_each(|t| *t /= gain);
+        self.analysis_rate = hz;
+    }
+
+    /// Rate the LPC model runs at (the decimated rate when decimation is enabled)
+    fn lpc_rate(&self) -> f32 {
+        if self.analysis_rate > 0.0 { self.analysis_rate } else { self.sample_rate }
+    }
+
+    /// Anti-alias filter `samples` and resample them to `analysis_rate` into `out`
+    fn decimate_into(&self, samples: &[f32], out: &mut Vec<f32>) {
+        let taps = &self.decimation_taps;
+        let half = (taps.len() / 2) as isize;
+        let filtered_at = |idx: isize| -> f32 {
+            taps.iter().enumerate()
+                .map(|(k, &t)| {
+                    let j = idx + half - k as isize;
+                    if j >= 0 && (j as usize) < samples.len() { t * samples[j as usize] } else { 0.0 }
+                })
+                .sum()
+        };
+        
+        let step = self.sample_rate / self.analysis_rate;
+        let out_len = (samples.len() as f32 / step) as usize;
+        out.clear();
+        for m in 0..out_len {
+            // Linear interpolation between filtered samples (signal is oversampled ≥2x here)
+            let pos = m as f32 * step;
+            let idx = pos as isize;
+            let frac = pos - idx as f32;
+            out.push(filtered_at(idx) * (1.0 - frac) + filtered_at(idx + 1) * frac);
+        }
+    }
+
+    /// Fit the LPC model to a frame, decimating first when enabled
+    fn fit_lpc(&mut self, samples: &[f32]) {
+        if self.analysis_rate > 0.0 {
+            let mut frame = std::mem::take(&mut self.decimated);
+            self.decimate_into(samples, &mut frame);
+            self.compute_lpc(&frame);
+            self.decimated = frame;
+        } else {
+            self.compute_lpc(samples);
+        }
+        if self.max_pole_radius > 0.0 {
+            clamp_pole_radius(&mut self.lpc_coeffs, self.max_pole_radius);
+        }
+    }
+
+    /// Keep every LPC pole within `max_radius` (e.g. 0.98) for stable synthesis.
+    /// After Levinson-Durbin the predictor polynomial is factored, poles outside the
+    /// unit circle are reflected inside, and any still beyond `max_radius` are pulled
+    /// in radially. Pole angles (formant frequencies) are kept; only bandwidths widen.
+    /// 0 disables (default).
+    #[wasm_bindgen]
+    pub fn stabilize(&mut self, max_radius: f32) {
+        self.max_pole_radius = max_radius.clamp(0.0, 1.0);
+    }
+
+    /// Accept formant peaks whose prominence is at least `frac` of the response maximum.
+    /// A value of 0 restores the local `1.5x neighbour average` heuristic.
+    #[wasm_bindgen]
+    pub fn set_formant_prominence(&mut self, frac: f32) {
+        self.formant_prominence = frac.clamp(0.0, 1.0);
+    }
+
+    /// F2 transition of one token as `[onset_f2, target_f2, slope]`, slope in Hz per
+    /// frame. Only the voiced run starting at frame `voicing_onset` is used (it ends
+    /// at the first frame ≤ 0 or NaN). The target is where F2 settles: the first
+    /// frame after which it changes by under 0.5 % per frame for 3 frames, else the
+    /// run's midpoint (the usual locus-equation vowel target). `[0, 0, 0]` if the
+    /// onset frame is unvoiced. Locus equations regress onset on target across tokens.
+    #[wasm_bindgen]
+    pub fn formant_transition(&self, f2_track: &[f32], voicing_onset: usize) -> Vec<f32> {
+        let voiced = |f: f32| f > 0.0 && !f.is_nan();
+        let run = match f2_track.get(voicing_onset..) {
+            Some(rest) if rest.first().is_some_and(|&f| voiced(f)) => {
+                let len = rest.iter().position(|&f| !voiced(f)).unwrap_or(rest.len());
+                &rest[..len]
+            }
+            _ => return vec![0.0, 0.0, 0.0],
+        };
+        
+        let settled = |i: usize| {
+            i + 3 < run.len() && (i..i + 3).all(|j| (run[j + 1] - run[j]).abs() < 0.005 * run[j])
+        };
+        let target = (1..run.len()).find(|&i| settled(i)).unwrap_or(run.len() / 2);
+        let slope = if target > 0 { (run[target] - run[0]) / target as f32 } else { 0.0 };
+        vec![run[0], run[target], slope]
+    }
+
+    /// LPC order in `1..=max_order` minimizing an information criterion over the
+    /// frame (decimated first when an analysis rate is set, as in `analyze`).
+    /// A single Levinson-Durbin run to `max_order` supplies the prediction error
+    /// `E_p` of every intermediate order p, scored as `N·ln(E_p / N) + 2p` (AIC)
+    /// or `+ p·ln N` (BIC). Does not change the analyzer's own order.
+    #[wasm_bindgen]
+    pub fn select_order(&mut self, samples: &[f32], max_order: usize, criterion: OrderCriterion) -> usize {
+        let mut frame = std::mem::take(&mut self.decimated);
+        let signal: &[f32] = if self.analysis_rate > 0.0 {
+            self.decimate_into(samples, &mut frame);
+            &frame
+        } else {
+            samples
+        };
+        let n = signal.len();
+        let max_order = max_order.min(n.saturating_sub(1)).max(1);
+        
+        // Step 1: autocorrelation to max_order (f64, the sweep can go deep)
+        let r: Vec<f64> = (0..=max_order)
+            .map(|lag| signal.iter().zip(&signal[lag.min(n)..]).map(|(&x, &y)| x as f64 * y as f64).sum())
+            .collect();
+        self.decimated = frame;
+        if r[0] < 1e-12 {
+            return 1;
+        }
+        
+        // Step 2: Levinson-Durbin, scoring the error after each order
+        let penalty = match criterion {
+            OrderCriterion::Aic => 2.0,
+            OrderCriterion::Bic => (n as f64).ln(),
+        };
+        let mut a = vec![0.0f64; max_order];
+        let mut previous = vec![0.0f64; max_order];
+        let mut error = r[0];
+        let mut best = (f64::INFINITY, 1);
+        for i in 0..max_order {
+            let mut lambda = r[i + 1];
+            for j in 0..i {
+                lambda -= previous[j] * r[i - j];
+            }
+            lambda /= error;
+            a[i] = lambda;
+            for j in 0..i {
+                a[j] = previous[j] - lambda * previous[i - 1 - j];
+            }
+            error = (error * (1.0 - lambda * lambda)).max(1e-30);
+            previous.copy_from_slice(&a);
+            
+            let order = i + 1;
+            let score = n as f64 * (error / n as f64).ln() + penalty * order as f64;
+            if score < best.0 {
+                best = (score, order);
+            }
+        }
+        best.1
+    }
+
+    /// Levinson-Durbin with pre-allocated buffers
+    fn compute_lpc(&mut self, samples: &[f32]) {
+        let n = samples.len();
+        let order = self.lpc_order;
+        
+        // Frames no longer than the order fit nothing: zero predictor, zero gain
+        if n <= order {
+            self.autocorr.fill(0.0);
+            self.lpc_coeffs.fill(0.0);
+            self.prediction_error = 0.0;
+            return;
+        }
+        
+        // Compute autocorrelation
+        for i in 0..=order {
+            self.autocorr[i] = 0.0;
+            for j in 0..(n - i) {
+                self.autocorr[i] += samples[j] * samples[j + i];
+            }
+        }
+        
+        if self.autocorr[0].abs() < 1e-10 {
+            for c in self.lpc_coeffs.iter_mut() {
+                *c = 0.0;
+            }
+            self.prediction_error = self.autocorr[0];
+            return;
+        }
+        
+        // Levinson-Durbin
+        let a_prev = &mut self.lpc_prev;
+        a_prev.fill(0.0);
+        let mut e = self.autocorr[0];
+        
+        for i in 0..order {
+            let mut lambda = self.autocorr[i + 1];
+            for (j, &a) in a_prev.iter().enumerate().take(i) {
+                lambda -= a * self.autocorr[i - j];
+            }
+            lambda /= e;
+            
+            self.lpc_coeffs[i] = lambda;
+            for j in 0..i {
+                self.lpc_coeffs[j] = a_prev[j] - lambda * a_prev[i - 1 - j];
+            }
+            
+            e *= 1.0 - lambda * lambda;
+            a_prev.copy_from_slice(&self.lpc_coeffs);
+        }
+        self.prediction_error = e;
+    }
+
+    /// LPC prediction gain of the last fitted frame in dB (energy / residual energy).
+    /// Voiced speech is typically well above 10 dB; noise and silence sit near 0 dB.
+    fn prediction_gain_db(&self) -> f32 {
+        if self.autocorr[0] < 1e-10 {
+            return 0.0;
+        }
+        10.0 * (self.autocorr[0] / self.prediction_error.max(1e-20)).log10()
+    }
+
+    /// How `analyze_batch` fills formant slots with no formant (default `Zero`)
+    #[wasm_bindgen]
+    pub fn set_unvoiced_mode(&mut self, mode: UnvoicedMode) {
+        self.unvoiced_mode = mode;
+    }
+
+    /// Minimum LPC prediction gain for `analyze_batch` to report formants (default 6 dB)
+    #[wasm_bindgen]
+    pub fn set_min_prediction_gain(&mut self, gain_db: f32) {
+        self.min_prediction_gain_db = gain_db;
+    }
+
+    /// Batch formant tracking: 4 slots per frame `[F1, F2, F3, F4, ...]`.
+    /// Frames below the prediction-gain gate (silence, fricatives) report no formants;
+    /// empty slots are filled according to the `UnvoicedMode`.
+    #[wasm_bindgen]
+    pub fn analyze_batch(&mut self, samples: &[f32], frame_size: usize, hop_size: usize) -> Vec<f32> {
+        let hop = hop_size.max(1);
+        let num_frames = frame_count(samples.len(), frame_size, hop);
+        let mut results = Vec::with_capacity(num_frames * 4);
+        let mut last = [0.0f32; 4];
+        
+        for i in 0..num_frames {
+            let mut formants = [0.0f32; 4];
+            let mut count = self.analyze_into(frame_slice(samples, i, frame_size, hop), &mut formants);
+            if self.prediction_gain_db() < self.min_prediction_gain_db {
+                count = 0;
+            }
+            
+            for (slot, formant) in formants.iter_mut().enumerate() {
+                if slot < count {
+                    last[slot] = *formant;
+                } else {
+                    *formant = match self.unvoiced_mode {
+                        UnvoicedMode::Zero => 0.0,
+                        UnvoicedMode::Nan => f32::NAN,
+                        UnvoicedMode::HoldLast => last[slot],
+                    };
+                }
+            }
+            results.extend_from_slice(&formants);
+        }
+        
+        results
+    }
+
+    #[wasm_bindgen]
+    pub fn analyze(&mut self, samples: &[f32]) -> Vec<f32> {
+        let mut formants = [0.0f32; 4];
+        let count = self.analyze_into(samples, &mut formants);
+        formants[..count].to_vec()
+    }
+
+    /// Alloc-free `analyze`: writes up to 4 formants into `out`, returns the count found
+    #[wasm_bindgen]
+    pub fn analyze_into(&mut self, samples: &[f32], out: &mut [f32]) -> usize {
+        self.fit_lpc(samples);
+        
+        let n_points = 512;
+        
+        // Compute frequency response
+        for i in 0..n_points {
+            let freq = i as f32 * self.sample_rate / 2.0 / n_points as f32;
+            let omega = 2.0 * PI * freq / self.sample_rate;
+            
+            let mut real_sum = 1.0f32;
+            let mut imag_sum = 0.0f32;
+            
+            for (k, &coef) in self.lpc_coeffs.iter().enumerate() {
+                let angle = -((k + 1) as f32) * omega;
+                real_sum -= coef * angle.cos();
+                imag_sum -= coef * angle.sin();
+            }
+            
+            self.response_re[i] = 1.0 / (real_sum * real_sum + imag_sum * imag_sum + 1e-10).sqrt();
+        }
+        
+        // Optional centred moving average (symmetric, so peaks stay in place)
+        let response = if self.response_smoothing > 1 {
+            let half = self.response_smoothing / 2;
+            for (i, smoothed) in self.response_smoothed.iter_mut().take(n_points).enumerate() {
+                let window = &self.response_re[i.saturating_sub(half)..(i + half + 1).min(n_points)];
+                *smoothed = window.iter().sum::<f32>() / window.len() as f32;
+            }
+            &self.response_smoothed[..n_points]
+        } else {
+            &self.response_re[..n_points]
+        };
+        
+        // Find peaks (formants)
+        let bin_hz = self.lpc_rate() / 2.0 / n_points as f32;
+        pick_formant_peaks(response, bin_hz, self.formant_prominence, out)
+    }
+
+    /// Moving-average width in response bins applied before peak picking
+    /// (rounded up to odd; 1 = off, the default). Merges ripple-split peaks of one formant.
+    #[wasm_bindgen]
+    pub fn set_response_smoothing(&mut self, bins: usize) {
+        self.response_smoothing = bins.max(1) | 1;
+    }
+
+    /// Fingerprint of the LPC and peak-picking parameters (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("FormantAnalyzer")
+            .f32(self.sample_rate)
+            .usize(self.lpc_order)
+            .usize(self.response_smoothing)
+            .f32(self.formant_prominence)
+            .f32(self.min_prediction_gain_db)
+            .usize(self.unvoiced_mode as usize)
+            .f32(self.analysis_rate)
+            .f32(self.max_pole_radius)
+            .finish()
+    }
+
+    /// Glottal closure instants (sample indices) from the LPC residual. Each 10 ms
+    /// hop is inverse-filtered with the LPC of the 30 ms frame around it (at the
+    /// full sample rate, no decimation). The hop's period T0 is the shortest
+    /// 2.5-20 ms autocorrelation lag of the residual magnitude scoring within 10 %
+    /// of the strongest. A GCI is a residual peak that is the largest within ±T0/2
+    /// and at least 30 % of the frame's largest, giving about one instant per
+    /// pitch period in voiced speech.
+    #[wasm_bindgen]
+    pub fn detect_gci(&mut self, samples: &[f32]) -> Vec<u32> {
+        let frame = (0.03 * self.sample_rate) as usize;
+        let hop = ((0.01 * self.sample_rate) as usize).max(1);
+        let order = self.lpc_order;
+        let len = samples.len();
+        if len < frame.max(order + 1) {
+            return Vec::new();
+        }
+        
+        // Step 1: LPC residual magnitude, hop by hop
+        let mut residual = vec![0.0f32; len];
+        for start in (0..len).step_by(hop) {
+            let frame_start = (start + hop / 2).saturating_sub(frame / 2).min(len - frame);
+            self.compute_lpc(&samples[frame_start..frame_start + frame]);
+            for n in start.max(order)..(start + hop).min(len) {
+                let predicted: f32 = self.lpc_coeffs.iter().enumerate().map(|(k, &a)| a * samples[n - 1 - k]).sum();
+                residual[n] = (samples[n] - predicted).abs();
+            }
+        }
+        
+        // Step 2: per-hop period and peak level from the surrounding frame
+        let min_lag = (0.0025 * self.sample_rate) as usize;
+        let max_lag = (0.02 * self.sample_rate) as usize;
+        let hops: Vec<(usize, f32)> = (0..len.div_ceil(hop))
+            .map(|h| {
+                let frame_start = (h * hop + hop / 2).saturating_sub(frame / 2).min(len - frame);
+                let window = &residual[frame_start..frame_start + frame];
+                // Shortest lag within 10 % of the best, so period multiples never win
+                let scores: Vec<f32> = (min_lag..max_lag.min(frame - 1))
+                    .map(|lag| window.iter().zip(&window[lag..]).map(|(x, y)| x * y).sum::<f32>() / (frame - lag) as f32)
+                    .collect();
+                let best = scores.iter().fold(0.0f32, |acc, &v| acc.max(v));
+                let period = min_lag + scores.iter().position(|&v| v >= 0.9 * best).unwrap_or(0);
+                (period, window.iter().fold(0.0f32, |acc, &e| acc.max(e)))
+            })
+            .collect();
+        
+        // Step 3: dominant residual peaks, one per local period
+        (0..len)
+            .filter(|&n| {
+                let (period, level) = hops[n / hop];
+                let half = (period / 2).max(1);
+                let e = residual[n];
+                e > 0.0 && e >= 0.3 * level
+                    && residual[n.saturating_sub(half)..n].iter().all(|&x| x < e)
+                    && residual[n + 1..(n + half + 1).min(len)].iter().all(|&x| x <= e)
+            })
+            .map(|n| n as u32)
+            .collect()
+    }
+
+    /// LPC predictor coefficients `a[1..=order]` (x[n] ≈ Σ a[k]·x[n-k]) for a frame
+    #[wasm_bindgen]
+    pub fn lpc_coefficients(&mut self, samples: &[f32]) -> Vec<f32> {
+        self.fit_lpc(samples);
+        self.lpc_coeffs.clone()
+    }
+}
+
+/// Clamp the poles of 1 / (1 - Σ a[k]·z^-k) to `max_radius`, keeping their angles.
+/// Poles outside the unit circle are first reflected to 1 / conj(z).
+fn clamp_pole_radius(coeffs: &mut [f32], max_radius: f32) {
+    let order = coeffs.len();
+    if order == 0 {
+        return;
+    }
+    
+    // Step 1: roots of z^p - a1·z^(p-1) - ... - ap (Durand-Kerner, in f64)
+    let poly: Vec<f64> = std::iter::once(1.0).chain(coeffs.iter().map(|&a| -a as f64)).collect();
+    let eval = |z: Complex<f64>| poly.iter().fold(Complex::new(0.0, 0.0), |acc, &c| acc * z + c);
+    let mut roots: Vec<Complex<f64>> = (0..order).map(|k| Complex::new(0.4, 0.9).powu(k as u32)).collect();
+    for _ in 0..500 {
+        let mut largest_step = 0.0f64;
+        for i in 0..order {
+            let denom = roots.iter().enumerate()
+                .filter(|&(j, _)| j != i)
+                .fold(Complex::new(1.0, 0.0), |acc, (_, &r)| acc * (roots[i] - r));
+            let step = eval(roots[i]) / denom;
+            roots[i] -= step;
+            largest_step = largest_step.max(step.norm());
+        }
+        if largest_step < 1e-12 {
+            break;
+        }
+    }
+    
+    // Step 2: reflect, then clamp the radius
+    let max_radius = max_radius as f64;
+    let mut changed = false;
+    for r in roots.iter_mut() {
+        if r.norm() > 1.0 {
+            *r = 1.0 / r.conj();
+            changed = true;
+        }
+        let radius = r.norm();
+        if radius > max_radius {
+            *r *= max_radius / radius;
+            changed = true;
+        }
+    }
+    if !changed {
+        return;
+    }
+    
+    // Step 3: expand Π (z - r) back into predictor coefficients
+    let mut expanded = vec![Complex::new(1.0f64, 0.0); 1];
+    for &r in &roots {
+        expanded.push(Complex::new(0.0, 0.0));
+        for k in (1..expanded.len()).rev() {
+            let prev = expanded[k - 1];
+            expanded[k] -= r * prev;
+        }
+    }
+    for (a, c) in coeffs.iter_mut().zip(&expanded[1..]) {
+        *a = -c.re as f32;
+    }
+}
+
+/// Sentinel for formant slots with no formant in `FormantAnalyzer::analyze_batch`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnvoicedMode {
+    /// Report 0 Hz (legacy padding)
+    Zero,
+    /// Report NaN, which plotting libraries skip
+    Nan,
+    /// Repeat the slot's last reported formant
+    HoldLast,
+}
+
+/// Information criterion for `FormantAnalyzer::select_order`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderCriterion {
+    /// Akaike: penalty 2 per coefficient
+    Aic,
+    /// Bayesian (Schwarz): penalty ln N per coefficient, favouring lower orders
+    Bic,
+}
+
+/// Pick up to `out.len().min(4)` formant peaks from an LPC magnitude response.
+/// `prominence > 0` selects the prominence test, otherwise the 1.5x neighbour-average test.
+fn pick_formant_peaks(response: &[f32], bin_hz: f32, prominence: f32, out: &mut [f32]) -> usize {
+    let n_points = response.len();
+    let min_prominence = prominence * response.iter().fold(0.0f32, |m, &r| m.max(r));
+    let max_formants = out.len().min(4);
+    let mut count = 0;
+    for i in 1..(n_points - 1) {
+        if count >= max_formants { break; }
+        if response[i] > response[i - 1] && response[i] > response[i + 1] {
+            let is_formant = if prominence > 0.0 {
+                peak_prominence(response, i) >= min_prominence
+            } else {
+                let avg = (response[i - 1] + response[i + 1]) / 2.0;
+                response[i] > avg * 1.5
+            };
+            if is_formant {
+                out[count] = i as f32 * bin_hz;
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// Topographic prominence of the peak at `peak`: its height above the higher of
+/// the two lowest points reached before climbing to a taller value (or the edge).
+fn peak_prominence(values: &[f32], peak: usize) -> f32 {
+    let height = values[peak];
+    let mut left_min = height;
+    for &v in values[..peak].iter().rev() {
+        if v > height { break; }
+        left_min = left_min.min(v);
+    }
+    let mut right_min = height;
+    for &v in &values[peak + 1..] {
+        if v > height { break; }
+        right_min = right_min.min(v);
+    }
+    height - left_min.max(right_min)
+}
+
+/// Double-precision Formant Analyzer for offline/scientific use
+/// Same LPC pipeline as `FormantAnalyzer`, but autocorrelation and
+/// Levinson-Durbin accumulate in f64 to avoid f32 round-off on long frames
+#[wasm_bindgen]
+pub struct FormantAnalyzerF64 {
+    sample_rate: f64,
+    lpc_order: usize,
+    // Pre-allocated buffers
+    autocorr: Vec<f64>,
+    lpc_coeffs: Vec<f64>,
+    lpc_prev: Vec<f64>,
+    response: Vec<f32>,
+    formant_prominence: f32,
+}
+
+#[wasm_bindgen]
+impl FormantAnalyzerF64 {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f64, lpc_order: usize) -> FormantAnalyzerF64 {
+        console_log!("🦀 [Rust DSP] Formant Analyzer (f64): sr={}, order={}", sample_rate, lpc_order);
+        
+        FormantAnalyzerF64 {
+            sample_rate,
+            lpc_order,
+            autocorr: vec![0.0; lpc_order + 1],
+            lpc_coeffs: vec![0.0; lpc_order],
+            lpc_prev: vec![0.0; lpc_order],
+            response: vec![0.0; 512],
+            formant_prominence: 0.0,
+        }
+    }
+
+    /// See `FormantAnalyzer::set_formant_prominence`
+    #[wasm_bindgen]
+    pub fn set_formant_prominence(&mut self, frac: f32) {
+        self.formant_prominence = frac.clamp(0.0, 1.0);
+    }
+
+    /// Fingerprint of the LPC parameters (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("FormantAnalyzerF64")
+            .f64(self.sample_rate)
+            .usize(self.lpc_order)
+            .f32(self.formant_prominence)
+            .finish()
+    }
+
+    /// Levinson-Durbin in double precision
+    fn compute_lpc(&mut self, samples: &[f64]) {
+        let n = samples.len();
+        let order = self.lpc_order;
+        
+        // Frames no longer than the order have no lag-`order` product: no fit
+        self.lpc_coeffs.fill(0.0);
+        if n <= order {
+            self.autocorr.fill(0.0);
+            return;
+        }
+        for i in 0..=order {
+            self.autocorr[i] = samples[..n - i].iter()
+                .zip(&samples[i..])
+                .map(|(&a, &b)| a * b)
+                .sum();
+        }
+        
+        if self.autocorr[0].abs() < 1e-300 {
+            return;
+        }
+        
+        let a_prev = &mut self.lpc_prev;
+        a_prev.fill(0.0);
+        let mut e = self.autocorr[0];
+        
+        for i in 0..order {
+            let mut lambda = self.autocorr[i + 1];
+            for (j, &a) in a_prev.iter().enumerate().take(i) {
+                lambda -= a * self.autocorr[i - j];
+            }
+            lambda /= e;
+            
+            self.lpc_coeffs[i] = lambda;
+            for j in 0..i {
+                self.lpc_coeffs[j] = a_prev[j] - lambda * a_prev[i - 1 - j];
+            }
+            
+            e *= 1.0 - lambda * lambda;
+            a_prev.copy_from_slice(&self.lpc_coeffs);
+        }
+    }
+
+    /// LPC predictor coefficients `a[1..=order]` computed in f64
+    #[wasm_bindgen]
+    pub fn lpc_coefficients(&mut self, samples: &[f64]) -> Vec<f64> {
+        self.compute_lpc(samples);
+        self.lpc_coeffs.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn analyze(&mut self, samples: &[f64]) -> Vec<f64> {
+        self.compute_lpc(samples);
+        
+        let n_points = self.response.len();
+        for i in 0..n_points {
+            let omega = std::f64::consts::PI * i as f64 / n_points as f64;
+            
+            let mut real_sum = 1.0f64;
+            let mut imag_sum = 0.0f64;
+            for (k, &coef) in self.lpc_coeffs.iter().enumerate() {
+                let angle = -((k + 1) as f64) * omega;
+                real_sum -= coef * angle.cos();
+                imag_sum -= coef * angle.sin();
+            }
+            
+            self.response[i] = (1.0 / (real_sum * real_sum + imag_sum * imag_sum + 1e-20).sqrt()) as f32;
+        }
+        
+        let mut formants = [0.0f32; 4];
+        let bin_hz = (self.sample_rate / 2.0 / n_points as f64) as f32;
+        let count = pick_formant_peaks(&self.response, bin_hz, self.formant_prominence, &mut formants);
+        formants[..count].iter().map(|&f| f as f64).collect()
+    }
+}
+
+/// Largest integer decimation factor whose new Nyquist (`sample_rate / 2M`) is still
+/// strictly above `max_useful_hz`, so decimating by it cannot alias that band.
+/// Returns 1 when no decimation is possible (or `max_useful_hz <= 0`).
+#[wasm_bindgen]
+pub fn max_decimation_factor(sample_rate: f32, max_useful_hz: f32) -> usize {
+    if !(max_useful_hz > 0.0 && sample_rate > 0.0) {
+        return 1;
+    }
+    let limit = sample_rate / (2.0 * max_useful_hz);
+    ((limit.ceil() as usize).saturating_sub(1)).max(1)
+}
+
+/// Lanczos kernel width used by `resample` and a default `Resampler`
+const DEFAULT_RESAMPLER_TAPS: usize = 8;
+
+/// Kernel width (`Resampler::set_taps`) whose aliasing is at least `attenuation_db`
+/// down for input above 1.5× the cutoff. Interpolated in log-width from measured
+/// attenuation of the Lanczos kernel (8 taps ≈ 36 dB, 32 ≈ 60 dB, 128 ≈ 83 dB) and
+/// extrapolated past the table at ~13 dB per doubling.
+#[wasm_bindgen]
+pub fn resampler_taps_for_attenuation(attenuation_db: f32) -> usize {
+    const MEASURED: [(f32, f32); 13] = [
+        (2.0, 4.7), (3.0, 11.8), (4.0, 16.0), (6.0, 24.4), (8.0, 36.0), (12.0, 42.2), (16.0, 47.7),
+        (24.0, 54.3), (32.0, 60.6), (48.0, 65.8), (64.0, 70.2), (96.0, 76.9), (128.0, 83.6),
+    ];
+    if attenuation_db.is_nan() || attenuation_db <= MEASURED[0].1 {
+        return 1;
+    }
+    
+    // Segment bracketing the request; the last one is extended past the table
+    let k = MEASURED.iter().position(|&(_, db)| db >= attenuation_db).unwrap_or(MEASURED.len() - 1).max(1);
+    let ((t0, a0), (t1, a1)) = (MEASURED[k - 1], MEASURED[k]);
+    let log_taps = t0.log2() + (attenuation_db - a0) / (a1 - a0) * (t1 / t0).log2();
+    (log_taps.exp2().ceil() as usize).min(4096)
+}
+
+/// ULTRA-OPTIMIZED Sinc Resampler
+/// Uses lookup table for sinc values and loop unrolling
+#[wasm_bindgen]
+pub fn resample(samples: &[f32], from_rate: f32, to_rate: f32) -> Vec<f32> {
+    let ratio = to_rate / from_rate;
+    let new_length = (samples.len() as f32 * ratio) as usize;
+    let output = resample_by_ratio(samples, ratio, new_length, 1.0, DEFAULT_RESAMPLER_TAPS);
+    
+    console_log!("🦀 [Rust DSP] Resampled: {}Hz → {}Hz ({} → {} samples)", 
+                 from_rate, to_rate, samples.len(), new_length);
+    output
+}
+
+/// `resample`, returning the samples together with their sample rate (`to_rate`).
+/// Like `resample` it keeps the full-band kernel when downsampling, so content
+/// above the new Nyquist aliases; `Resampler` lowers the cutoff instead.
+#[wasm_bindgen]
+pub fn resample_with_rate(samples: &[f32], from_rate: f32, to_rate: f32) -> ResampledAudio {
+    ResampledAudio {
+        samples: resample(samples, from_rate, to_rate),
+        sample_rate: to_rate,
+    }
+}
+
+/// Resample to exactly `length` samples. The reported rate is recomputed from the
+/// actual conversion ratio: `from_rate * length / samples.len()`. The kernel is
+/// full-band as in `resample`; shortening a buffer does not filter out aliasing.
+#[wasm_bindgen]
+pub fn resample_to_length(samples: &[f32], from_rate: f32, length: usize) -> ResampledAudio {
+    let ratio = length as f32 / samples.len().max(1) as f32;
+    ResampledAudio {
+        samples: resample_by_ratio(samples, ratio, length, 1.0, DEFAULT_RESAMPLER_TAPS),
+        sample_rate: from_rate * ratio,
+    }
+}
+
+/// Lanczos-windowed sinc interpolation producing `new_length` samples at `ratio` (out/in).
+/// `cutoff` is the kernel cutoff as a fraction of the input Nyquist; the kernel
+/// widens by `1 / cutoff` so the window keeps the same number of sinc lobes.
+/// `taps` is the Lanczos half-width in sinc zero crossings.
+fn resample_by_ratio(samples: &[f32], ratio: f32, new_length: usize, cutoff: f32, taps: usize) -> Vec<f32> {
+    let mut output = Vec::with_capacity(new_length);
+    
+    let taps = taps.max(1);
+    let inv_window = 1.0 / taps as f32;
+    let half_span = (taps as f32 / cutoff).ceil() as usize;
+    
+    for i in 0..new_length {
+        let src_pos = i as f32 / ratio;
+        let src_idx = src_pos as usize;
+        
+        let mut sample = 0.0f32;
+        let mut weight_sum = 0.0f32;
+        
+        let j_start = src_idx.saturating_sub(half_span);
+        let j_end = (src_idx + half_span).min(samples.len());
+        
+        // Unrolled inner loop with fused sinc-Lanczos
+        for (j, &s) in samples.iter().enumerate().take(j_end).skip(j_start) {
+            let w = lanczos_sinc((j as f32 - src_pos) * PI * cutoff, inv_window);
+            sample += s * w;
+            weight_sum += w;
+        }
+        
+        output.push(sample / weight_sum.max(1e-10));
+    }
+    
+    output
+}
+
+/// Lanczos-windowed sinc at `x` (radians of the sinc argument); `inv_window` is
+/// `1 / taps`, so the window vanishes beyond `taps` zero crossings
+#[inline]
+fn lanczos_sinc(x: f32, inv_window: f32) -> f32 {
+    // Fast sinc approximation for small x
+    let sinc = if x.abs() < 0.01 { 
+        1.0 - x * x / 6.0  // Taylor series
+    } else { 
+        x.sin() / x 
+    };
+    
+    // Lanczos window
+    let lx = x * inv_window;
+    let lanczos = if lx.abs() >= 1.0 {
+        0.0
+    } else if lx.abs() < 0.01 {
+        1.0 - lx * lx * PI * PI / 6.0
+    } else {
+        let la = lx * PI;
+        la.sin() / la
+    };
+    
+    sinc * lanczos
+}
+
+/// Resampled samples tagged with the sample rate they are at
+#[wasm_bindgen]
+pub struct ResampledAudio {
+    samples: Vec<f32>,
+    sample_rate: f32,
+}
+
+#[wasm_bindgen]
+impl ResampledAudio {
+    #[wasm_bindgen(getter)]
+    pub fn samples(&self) -> Vec<f32> {
+        self.samples.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+}
+
+/// Fixed-rate sinc resampler that remembers its input and output rates
+#[wasm_bindgen]
+pub struct Resampler {
+    from_rate: f32,
+    to_rate: f32,
+    // Kernel cutoff relative to the lower of the two Nyquist frequencies
+    cutoff_scale: f32,
+    taps: usize,
+}
+
+#[wasm_bindgen]
+impl Resampler {
+    #[wasm_bindgen(constructor)]
+    pub fn new(from_rate: f32, to_rate: f32) -> Resampler {
+        console_log!("🦀 [Rust DSP] Resampler: {}Hz → {}Hz", from_rate, to_rate);
+        
+        Resampler { from_rate, to_rate, cutoff_scale: 1.0, taps: DEFAULT_RESAMPLER_TAPS }
+    }
+
+    /// Scale the anti-aliasing cutoff (default 1.0 = the output Nyquist when
+    /// downsampling, the input Nyquist when upsampling). Below 1 trades top-end
+    /// brightness for less aliasing; above 1 lets some aliasing through.
+    /// Clamped to 0.5..1.2, and never beyond the input Nyquist.
+    #[wasm_bindgen]
+    pub fn set_cutoff_scale(&mut self, scale: f32) {
+        self.cutoff_scale = scale.clamp(0.5, 1.2);
+    }
+
+    /// Lanczos kernel width (default 8). Wider kernels attenuate aliasing more at
+    /// a proportional cost; see `resampler_taps_for_attenuation`.
+    #[wasm_bindgen]
+    pub fn set_taps(&mut self, taps: usize) {
+        self.taps = taps.max(1);
+    }
+
+    /// Fingerprint of the rates and kernel (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("Resampler")
+            .f32(self.from_rate)
+            .f32(self.to_rate)
+            .f32(self.cutoff_scale)
+            .usize(self.taps)
+            .finish()
+    }
+
+    #[wasm_bindgen]
+    pub fn process(&self, samples: &[f32]) -> Vec<f32> {
+        let ratio = self.to_rate / self.from_rate;
+        let new_length = (samples.len() as f32 * ratio) as usize;
+        let cutoff = (self.cutoff_scale * ratio.min(1.0)).min(1.0);
+        resample_by_ratio(samples, ratio, new_length, cutoff, self.taps)
+    }
+
+    /// Sample rate of the buffers returned by `process`
+    #[wasm_bindgen]
+    pub fn output_sample_rate(&self) -> f32 {
+        self.to_rate
+    }
+}
+
+/// Kernel width preset for `make_resampler` and `PolyphaseResampler`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResampleQuality {
+    /// 8 Lanczos taps, aliasing ~36 dB down
+    Fast,
+    /// 32 taps, ~60 dB
+    Balanced,
+    /// 128 taps, ~83 dB
+    High,
+}
+
+impl ResampleQuality {
+    fn taps(self) -> usize {
+        match self {
+            ResampleQuality::Fast => DEFAULT_RESAMPLER_TAPS,
+            ResampleQuality::Balanced => 32,
+            ResampleQuality::High => 128,
+        }
+    }
+}
+
+/// Common interface of the resamplers `make_resampler` chooses between
+pub trait Resample {
+    /// Resample a whole buffer
+    fn process(&self, samples: &[f32]) -> Vec<f32>;
+    /// Sample rate of the buffers returned by `process`
+    fn output_sample_rate(&self) -> f32;
+    /// `(up, down)` when the conversion runs on an exact rational ratio
+    fn rational_ratio(&self) -> Option<(u32, u32)>;
+}
+
+impl Resample for Resampler {
+    fn process(&self, samples: &[f32]) -> Vec<f32> {
+        Resampler::process(self, samples)
+    }
+
+    fn output_sample_rate(&self) -> f32 {
+        Resampler::output_sample_rate(self)
+    }
+
+    fn rational_ratio(&self) -> Option<(u32, u32)> {
+        None
+    }
+}
+
+/// Exact rational resampler: output sample m sits at input position
+/// `m · down / up`, whose integer part and phase `(m · down) mod up` are exact,
+/// so nothing drifts over long buffers. Each of the `up` phases has its
+/// Lanczos kernel (the same one `Resampler` evaluates per sample) precomputed
+/// once; kernels are renormalized where they run off the buffer edges.
+#[wasm_bindgen]
+pub struct PolyphaseResampler {
+    from_rate: f32,
+    up: u32,
+    down: u32,
+    taps: usize,
+    // Kernel offsets -half_span..=half_span relative to the integer position
+    half_span: usize,
+    // up × (2·half_span + 1) weights, phase-major
+    bank: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl PolyphaseResampler {
+    /// Convert `from_rate` by exactly `up / down` (both at least 1)
+    #[wasm_bindgen(constructor)]
+    pub fn new(from_rate: f32, up: u32, down: u32, quality: ResampleQuality) -> PolyphaseResampler {
+        let (up, down) = (up.max(1), down.max(1));
+        console_log!("🦀 [Rust DSP] Polyphase Resampler: {}Hz × {}/{}", from_rate, up, down);
+        
+        let taps = quality.taps();
+        let cutoff = (up as f32 / down as f32).min(1.0);
+        let half_span = (taps as f32 / cutoff).ceil() as usize;
+        let width = 2 * half_span + 1;
+        let inv_window = 1.0 / taps as f32;
+        let mut bank = Vec::with_capacity(up as usize * width);
+        for phase in 0..up {
+            let frac = phase as f32 / up as f32;
+            bank.extend((0..width).map(|k| lanczos_sinc((k as f32 - half_span as f32 - frac) * PI * cutoff, inv_window)));
+        }
+        
+        PolyphaseResampler { from_rate, up, down, taps, half_span, bank }
+    }
+
+    /// Fingerprint of the rate, ratio and kernel (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("PolyphaseResampler")
+            .f32(self.from_rate)
+            .u64(self.up as u64)
+            .u64(self.down as u64)
+            .usize(self.taps)
+            .finish()
+    }
+
+    /// `floor(len · up / down)` output samples
+    #[wasm_bindgen]
+    pub fn process(&self, samples: &[f32]) -> Vec<f32> {
+        let (up, down) = (self.up as u64, self.down as u64);
+        let new_length = (samples.len() as u64 * up / down) as usize;
+        let width = 2 * self.half_span + 1;
+        
+        (0..new_length as u64)
+            .map(|m| {
+                let position = m * down;
+                let (index, phase) = ((position / up) as usize, (position % up) as usize);
+                let kernel = &self.bank[phase * width..(phase + 1) * width];
+                let first = index.saturating_sub(self.half_span);
+                let end = (index + self.half_span + 1).min(samples.len());
+                let (mut sample, mut weight_sum) = (0.0f32, 0.0f32);
+                for (j, &s) in (first..end).zip(&samples[first..end]) {
+                    let w = kernel[j + self.half_span - index];
+                    sample += s * w;
+                    weight_sum += w;
+                }
+                sample / weight_sum.max(1e-10)
+            })
+            .collect()
+    }
+
+    /// Sample rate of the buffers returned by `process`
+    #[wasm_bindgen]
+    pub fn output_sample_rate(&self) -> f32 {
+        (self.from_rate as f64 * self.up as f64 / self.down as f64) as f32
+    }
+}
+
+impl Resample for PolyphaseResampler {
+    fn process(&self, samples: &[f32]) -> Vec<f32> {
+        PolyphaseResampler::process(self, samples)
+    }
+
+    fn output_sample_rate(&self) -> f32 {
+        PolyphaseResampler::output_sample_rate(self)
+    }
+
+    fn rational_ratio(&self) -> Option<(u32, u32)> {
+        Some((self.up, self.down))
+    }
+}
+
+/// Resampler for `from_rate → to_rate`: the continued-fraction expansion of the
+/// ratio gives its best rational approximations, and the first with `up <= 1024`
+/// and `down <= 4096` within 1e-9 (relative) of the ratio selects the exact
+/// `PolyphaseResampler` (44.1 → 48 kHz is 160/147). Otherwise, as for
+/// irrational or drifting ratios, the sinc `Resampler` interpolates directly.
+pub fn make_resampler(from_rate: f32, to_rate: f32, quality: ResampleQuality) -> Box<dyn Resample> {
+    match rational_approximation(to_rate as f64 / from_rate as f64, 1024, 4096) {
+        Some((up, down)) => Box::new(PolyphaseResampler::new(from_rate, up, down, quality)),
+        None => {
+            let mut resampler = Resampler::new(from_rate, to_rate);
+            resampler.set_taps(quality.taps());
+            Box::new(resampler)
+        }
+    }
+}
+
+/// `make_resampler` for JavaScript, which cannot hold a `Box<dyn Resample>`:
+/// the same rational-or-sinc choice behind one exported type
+#[wasm_bindgen]
+pub struct AutoResampler {
+    inner: Box<dyn Resample>,
+}
+
+#[wasm_bindgen]
+impl AutoResampler {
+    /// Pick the resampler for `from_rate → to_rate` as `make_resampler` does
+    #[wasm_bindgen(constructor)]
+    pub fn new(from_rate: f32, to_rate: f32, quality: ResampleQuality) -> AutoResampler {
+        AutoResampler { inner: make_resampler(from_rate, to_rate, quality) }
+    }
+
+    /// Resample a whole buffer
+    #[wasm_bindgen]
+    pub fn process(&self, samples: &[f32]) -> Vec<f32> {
+        self.inner.process(samples)
+    }
+
+    /// Sample rate of the buffers returned by `process`
+    #[wasm_bindgen]
+    pub fn output_sample_rate(&self) -> f32 {
+        self.inner.output_sample_rate()
+    }
+
+    /// Whether the exact rational `PolyphaseResampler` was chosen
+    #[wasm_bindgen]
+    pub fn is_polyphase(&self) -> bool {
+        self.inner.rational_ratio().is_some()
+    }
+}
+
+/// First continued-fraction convergent `p / q` of `ratio` within 1e-9 relative
+/// error, if one exists with `p <= max_up` and `q <= max_down`
+fn rational_approximation(ratio: f64, max_up: u64, max_down: u64) -> Option<(u32, u32)> {
+    if !ratio.is_finite() || ratio <= 0.0 {
+        return None;
+    }
+    let (mut p0, mut q0, mut p1, mut q1) = (0u64, 1u64, 1u64, 0u64);
+    let mut x = ratio;
+    for _ in 0..64 {
+        let a = x.floor();
+        if a > max_up.max(max_down) as f64 {
+            return None;
+        }
+        let a = a as u64;
+        (p0, q0, p1, q1) = (p1, q1, a * p1 + p0, a * q1 + q0);
+        if p1 > max_up || q1 > max_down {
+            return None;
+        }
+        if (p1 as f64 / q1 as f64 - ratio).abs() <= 1e-9 * ratio {
+            return Some((p1 as u32, q1 as u32));
+        }
+        let rest = x - a as f64;
+        if rest <= 0.0 {
+            return None;
+        }
+        x = 1.0 / rest;
+    }
+    None
+}
+
+/// Local frame distance used by `dtw_alignment`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DtwDistance {
+    /// Euclidean distance between the feature vectors
+    Euclidean,
+    /// 1 - cosine similarity (0 for identical directions; a zero vector is 1 away
+    /// from anything but another zero vector)
+    Cosine,
+}
+
+/// DTW warping path `[i0, j0, i1, j1, ...]` from `(0, 0)` to the last frames of
+/// both sequences, with its total cost
+#[wasm_bindgen]
+pub struct DtwAlignment {
+    path: Vec<u32>,
+    cost: f32,
+}
+
+#[wasm_bindgen]
+impl DtwAlignment {
+    #[wasm_bindgen(getter)]
+    pub fn path(&self) -> Vec<u32> {
+        self.path.clone()
+    }
+
+    /// Sum of the local distances along the path (NaN for an empty sequence)
+    #[wasm_bindgen(getter)]
+    pub fn cost(&self) -> f32 {
+        self.cost
+    }
+}
+
+/// Optimal dynamic-time-warping path between two feature sequences stored row by
+/// row (`num_features` values per frame), as `[i0, j0, i1, j1, ...]`. Euclidean
+/// distances, no band; see `dtw_alignment` for the options and the path cost.
+#[wasm_bindgen]
+pub fn dtw(a: &[f32], b: &[f32], num_features: usize) -> Vec<u32> {
+    dtw_alignment(a, b, num_features, DtwDistance::Euclidean, 0).path
+}
+
+/// Dynamic time warping with horizontal, vertical and diagonal unit steps. A
+/// non-zero `band` is a Sakoe-Chiba constraint: row i only visits columns within
+/// `band` of the straight line between the sequence ends (widened so consecutive
+/// rows stay connected), so time and memory are O(N·band) instead of O(N·M).
+/// Only two cost rows are kept; back-pointers take one byte per visited cell.
+#[wasm_bindgen]
+pub fn dtw_alignment(a: &[f32], b: &[f32], num_features: usize, distance: DtwDistance, band: usize) -> DtwAlignment {
+    let d = num_features.max(1);
+    let (n, m) = (a.len() / d, b.len() / d);
+    if n == 0 || m == 0 {
+        return DtwAlignment { path: Vec::new(), cost: f32::NAN };
+    }
+    let local = |i: usize, j: usize| {
+        let (x, y) = (&a[i * d..(i + 1) * d], &b[j * d..(j + 1) * d]);
+        match distance {
+            DtwDistance::Euclidean => x.iter().zip(y).map(|(p, q)| (p - q) * (p - q)).sum::<f32>().sqrt(),
+            DtwDistance::Cosine => {
+                let dot: f32 = x.iter().zip(y).map(|(p, q)| p * q).sum();
+                let norms = x.iter().map(|p| p * p).sum::<f32>() * y.iter().map(|q| q * q).sum::<f32>();
+                if norms > 0.0 {
+                    1.0 - dot / norms.sqrt()
+                } else if x == y {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    };
+    let slope = (m - 1) as f32 / (n - 1).max(1) as f32;
+    let columns = |i: usize| {
+        if band == 0 {
+            return (0, m - 1);
+        }
+        let low = ((i as f32 * slope) as usize).saturating_sub(band);
+        let high = (((i + 1) as f32 * slope) as usize + band).min(m - 1);
+        (low, high)
+    };
+    
+    // Step 1: accumulated cost, row by row; steps are 0 diagonal, 1 vertical, 2 horizontal
+    let mut steps: Vec<u8> = Vec::new();
+    let mut row_starts: Vec<(usize, usize)> = Vec::with_capacity(n);
+    let (mut previous, mut previous_low) = (Vec::<f32>::new(), 0usize);
+    for i in 0..n {
+        let (low, high) = columns(i);
+        let above = |j: usize| {
+            if i > 0 && j >= previous_low && j < previous_low + previous.len() {
+                previous[j - previous_low]
+            } else {
+                f32::INFINITY
+            }
+        };
+        let mut row = vec![f32::INFINITY; high - low + 1];
+        row_starts.push((steps.len(), low));
+        for j in low..=high {
+            let (best, step) = if i == 0 && j == 0 {
+                (0.0, 0)
+            } else {
+                let diagonal = if j > 0 { above(j - 1) } else { f32::INFINITY };
+                let left = if j > low { row[j - 1 - low] } else { f32::INFINITY };
+                [(diagonal, 0u8), (above(j), 1), (left, 2)].into_iter()
+                    .fold((f32::INFINITY, 0), |best, c| if c.0 < best.0 { c } else { best })
+            };
+            row[j - low] = best + local(i, j);
+            steps.push(step);
+        }
+        previous = row;
+        previous_low = low;
+    }
+    let cost = previous[m - 1 - previous_low];
+    
+    // Step 2: backtrack from the end
+    let (mut i, mut j) = (n - 1, m - 1);
+    let mut path = Vec::with_capacity(2 * (n + m));
+    loop {
+        path.push([i as u32, j as u32]);
+        if i == 0 && j == 0 {
+            break;
+        }
+        let (start, low) = row_starts[i];
+        match steps[start + j - low] {
+            0 => {
+                i -= 1;
+                j -= 1;
+            }
+            1 => i -= 1,
+            _ => j -= 1,
+        }
+    }
+    DtwAlignment { path: path.into_iter().rev().flatten().collect(), cost }
+}
+
+/// Which zero crossings `snap_to_zero_crossing` may land on
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrossingSlope {
+    /// Either direction
+    Any,
+    /// Negative to non-negative (the crossings `find_loop_points` uses)
+    Rising,
+    /// Non-negative to negative
+    Falling,
+}
+
+/// Nearest zero crossing to `index` within `search_radius` samples, for click-free
+/// slice boundaries. A crossing at `i` means `samples[i - 1]` and `samples[i]`
+/// straddle zero, so cutting before `i` starts the slice on the new half-cycle;
+/// ties prefer the earlier crossing. Returns `index` unchanged when no crossing of
+/// the requested slope lies in the radius.
+#[wasm_bindgen]
+pub fn snap_to_zero_crossing(
+    samples: &[f32],
+    index: usize,
+    search_radius: usize,
+    slope: CrossingSlope,
+) -> usize {
+    let is_crossing = |i: usize| {
+        if i == 0 || i >= samples.len() {
+            return false;
+        }
+        let (before, after) = (samples[i - 1], samples[i]);
+        let rising = before < 0.0 && after >= 0.0;
+        let falling = before >= 0.0 && after < 0.0;
+        match slope {
+            CrossingSlope::Any => rising || falling,
+            CrossingSlope::Rising => rising,
+            CrossingSlope::Falling => falling,
+        }
+    };
+    
+    (0..=search_radius)
+        .flat_map(|d| [index.checked_sub(d), index.checked_add(d)])
+        .flatten()
+        .find(|&i| is_crossing(i))
+        .unwrap_or(index)
+}
+
+/// Best seamless loop `[start, end]` (end exclusive: playback jumps from `end` back
+/// to `start`) with `end - start >= min_loop_len`, or empty if none exists.
+/// Candidates are upward zero crossings; each pair is scored by the squared
+/// mismatch of the waveform in a ±2 ms window around the two boundaries plus the
+/// mismatch of their slopes, relative to the window energy. O(crossings²).
+#[wasm_bindgen]
+pub fn find_loop_points(samples: &[f32], min_loop_len: usize, sample_rate: f32) -> Vec<u32> {
+    let n = samples.len();
+    let half = ((0.002 * sample_rate) as usize).max(1);
+    
+    // Step 1: upward zero crossings with room for the comparison window
+    let crossings: Vec<usize> = (half.max(1)..n.saturating_sub(half + 1))
+        .filter(|&i| samples[i - 1] < 0.0 && samples[i] >= 0.0)
+        .collect();
+    
+    // Step 2: boundary cost for every admissible pair
+    let mut best: Option<(f32, usize, usize)> = None;
+    for (a, &start) in crossings.iter().enumerate() {
+        for &end in crossings[a + 1..].iter().filter(|&&e| e - start >= min_loop_len) {
+            let (mut mismatch, mut energy) = (0.0f32, 1e-10f32);
+            for k in 0..2 * half {
+                let (s, e) = (samples[start + k - half], samples[end + k - half]);
+                mismatch += (s - e) * (s - e);
+                energy += s * s + e * e;
+            }
+            let slope_start = samples[start + 1] - samples[start];
+            let slope_end = samples[end + 1] - samples[end];
+            let cost = mismatch / energy + (slope_start - slope_end).powi(2) / (slope_start.powi(2) + slope_end.powi(2) + 1e-10);
+            if best.is_none_or(|(c, _, _)| cost < c) {
+                best = Some((cost, start, end));
+            }
+        }
+    }
+    
+    best.map_or(Vec::new(), |(_, start, end)| vec![start as u32, end as u32])
+}
+
+/// Gaps between successive onsets (sample positions) in seconds; empty for
+/// fewer than two onsets
+#[wasm_bindgen]
+pub fn inter_onset_intervals(onsets: &[u32], sample_rate: f32) -> Vec<f32> {
+    onsets.windows(2)
+        .map(|w| (w[1] as f32 - w[0] as f32) / sample_rate)
+        .collect()
+}
+
+/// Snap IOIs (seconds) to a note grid at `tempo_bpm`, returning durations in beats.
+/// `subdivisions` grid steps per beat (4 = sixteenth notes in 4/4); every interval
+/// rounds to at least one step.
+#[wasm_bindgen]
+pub fn quantize_iois(iois: &[f32], tempo_bpm: f32, subdivisions: u32) -> Vec<f32> {
+    let step = 1.0 / subdivisions.max(1) as f32;
+    let beats_per_second = tempo_bpm / 60.0;
+    iois.iter()
+        .map(|&ioi| ((ioi * beats_per_second / step).round().max(1.0)) * step)
+        .collect()
+}
+
+/// Beat phase (0..1 of a beat) aligning an onset-strength envelope (one value per
+/// `hop_size` samples) with `bpm`: beats then fall at `(phase + k)·60 / bpm`
+/// seconds from the envelope's first frame. Each of 200 candidate phases is
+/// scored by the mean envelope value (linearly interpolated) under a pulse train
+/// at the beat period, a cross-correlation with the onset envelope.
+#[wasm_bindgen]
+pub fn beat_phase(onset_env: &[f32], bpm: f32, hop_size: usize, sample_rate: f32) -> f32 {
+    const STEPS: usize = 200;
+    let period = 60.0 * sample_rate / (bpm.max(1.0) * hop_size.max(1) as f32);
+    if onset_env.len() < 2 || period <= 0.0 {
+        return 0.0;
+    }
+    let at = |t: f32| -> f32 {
+        let i = t as usize;
+        let frac = t - i as f32;
+        onset_env[i] * (1.0 - frac) + onset_env.get(i + 1).map_or(0.0, |&v| v * frac)
+    };
+    let last = (onset_env.len() - 1) as f32;
+    
+    (0..STEPS)
+        .map(|step| {
+            let phase = step as f32 / STEPS as f32;
+            let pulses: Vec<f32> = (0..)
+                .map(|k| (phase + k as f32) * period)
+                .take_while(|&t| t <= last)
+                .collect();
+            let score = pulses.iter().map(|&t| at(t)).sum::<f32>() / pulses.len().max(1) as f32;
+            (phase, score)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0.0, |(phase, _)| phase)
+}
+
+/// Sample-timestamp tracking for streaming processors' `process_at`
+#[derive(Clone, Copy, Debug)]
+struct StreamClock {
+    // Timestamp the next contiguous block should start at
+    expected: Option<f64>,
+    discontinuity: bool,
+    reset_on_gap: bool,
+}
+
+impl Default for StreamClock {
+    fn default() -> Self {
+        StreamClock { expected: None, discontinuity: false, reset_on_gap: true }
+    }
+}
+
+impl StreamClock {
+    /// Record a block of `len` samples starting at `timestamp`; true when the
+    /// caller should reset its state (gap or overlap with reset enabled)
+    fn advance(&mut self, timestamp: f64, len: usize) -> bool {
+        self.discontinuity = self.expected.is_some_and(|expected| timestamp != expected);
+        self.expected = Some(timestamp + len as f64);
+        self.discontinuity && self.reset_on_gap
+    }
+}
+
+/// Click and dropout repair by AR prediction.
+/// Each 2048-sample block gets an order-16 AR model. A sample is a click when both
+/// its forward and its backward prediction errors exceed `threshold` times the
+/// block's robust residual scale (1.4826·median |error|); requiring both keeps
+/// the flag on the corrupted samples themselves rather than their neighbours.
+/// A run then extends over adjacent samples that fail the prediction made from
+/// the clean side, so multi-sample clicks are covered. Flagged runs up to
+/// `max_click_ms` long are rebuilt by crossfading a forward and a backward AR
+/// extrapolation; every other sample passes through untouched.
+#[wasm_bindgen]
+pub struct Declicker {
+    sample_rate: f32,
+    threshold: f32,
+    max_click_ms: f32,
+}
+
+#[wasm_bindgen]
+impl Declicker {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32) -> Declicker {
+        console_log!("🦀 [Rust DSP] Declicker: sr={}", sample_rate);
+        
+        Declicker {
+            sample_rate,
+            threshold: 20.0,
+            max_click_ms: 2.0,
+        }
+    }
+
+    /// Detection threshold in robust residual deviations (default 20)
+    #[wasm_bindgen]
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold.max(1.0);
+    }
+
+    /// Longest run treated as a click (default 2 ms); longer runs are left alone
+    #[wasm_bindgen]
+    pub fn set_max_click_ms(&mut self, max_click_ms: f32) {
+        self.max_click_ms = max_click_ms.max(0.0);
+    }
+
+    /// Fingerprint of the detection parameters (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("Declicker")
+            .f32(self.sample_rate)
+            .f32(self.threshold)
+            .f32(self.max_click_ms)
+            .finish()
+    }
+
+    #[wasm_bindgen]
+    pub fn declick(&mut self, samples: &[f32]) -> Vec<f32> {
+        const ORDER: usize = 16;
+        const BLOCK: usize = 2048;
+        let mut output = samples.to_vec();
+        if samples.len() < 4 * ORDER {
+            return output;
+        }
+        let max_len = ((self.max_click_ms * 0.001 * self.sample_rate) as usize).max(1);
+        
+        for start in (0..samples.len()).step_by(BLOCK) {
+            let end = (start + BLOCK).min(samples.len());
+            let coeffs = ar_coefficients(&samples[start..end], ORDER);
+            let predict = |history: &[f32]| -> f32 {
+                coeffs.iter().zip(history.iter().rev()).map(|(&a, &x)| a * x).sum()
+            };
+            
+            // Step 1: forward and backward prediction errors where both contexts exist
+            let lo = start.max(ORDER);
+            let hi = end.min(samples.len() - ORDER);
+            if lo >= hi {
+                continue;
+            }
+            let (forward, backward): (Vec<f32>, Vec<f32>) = (lo..hi)
+                .map(|n| {
+                    let after: Vec<f32> = samples[n + 1..=n + ORDER].iter().rev().copied().collect();
+                    ((samples[n] - predict(&samples[n - ORDER..n])).abs(), (samples[n] - predict(&after)).abs())
+                })
+                .unzip();
+            let mut sorted = forward.clone();
+            sorted.sort_by(|a, b| a.total_cmp(b));
+            let rms = frame_energy(&samples[start..end], false).sqrt();
+            let limit = self.threshold * (1.4826 * sorted[sorted.len() / 2]).max(1e-4 * rms).max(1e-9);
+            
+            // Step 2: click cores fail both predictions; a run then grows left while the
+            // next sample fails its forward prediction (from clean history) and right
+            // while it fails its backward one, covering multi-sample clicks exactly
+            let mut n = 0;
+            while n < forward.len() {
+                if forward[n].min(backward[n]) <= limit {
+                    n += 1;
+                    continue;
+                }
+                let mut first = n;
+                while first > 0 && forward[first - 1] > limit && n - first < max_len {
+                    first -= 1;
+                }
+                let mut last = n + 1;
+                while last < forward.len() && backward[last] > limit && last - first <= max_len {
+                    last += 1;
+                }
+                n = last;
+                let (a, b) = (lo + first, lo + last);
+                if b - a > max_len {
+                    continue;
+                }
+                
+                // Step 3: crossfade forward and backward AR extrapolations over [a, b)
+                let len = b - a;
+                let mut ahead = output[a - ORDER..a].to_vec();
+                let mut behind: Vec<f32> = samples[b..b + ORDER].iter().rev().copied().collect();
+                for _ in 0..len {
+                    let value = predict(&ahead[ahead.len() - ORDER..]);
+                    ahead.push(value);
+                    let value = predict(&behind[behind.len() - ORDER..]);
+                    behind.push(value);
+                }
+                for i in 0..len {
+                    let w = (i + 1) as f32 / (len + 1) as f32;
+                    output[a + i] = (1.0 - w) * ahead[ORDER + i] + w * behind[ORDER + len - 1 - i];
+                }
+            }
+        }
+        
+        output
+    }
+}
+
+/// Autocorrelation-method AR coefficients `a` of `order`, predicting
+/// `x[n] ≈ Σ a[k]·x[n-1-k]` (Levinson-Durbin in f64)
+fn ar_coefficients(samples: &[f32], order: usize) -> Vec<f32> {
+    let r: Vec<f64> = (0..=order)
+        .map(|lag| {
+            samples.iter().zip(samples.iter().skip(lag)).map(|(&x, &y)| x as f64 * y as f64).sum()
+        })
+        .collect();
+    let mut a = vec![0.0f64; order];
+    if r[0] < 1e-12 {
+        return vec![0.0; order];
+    }
+    let mut error = r[0] * (1.0 + 1e-9);
+    for i in 0..order {
+        let mut lambda = r[i + 1];
+        for j in 0..i {
+            lambda -= a[j] * r[i - j];
+        }
+        lambda /= error;
+        let previous = a.clone();
+        a[i] = lambda;
+        for j in 0..i {
+            a[j] = previous[j] - lambda * previous[i - 1 - j];
+        }
+        error *= 1.0 - lambda * lambda;
+    }
+    a.into_iter().map(|c| c as f32).collect()
+}
+
+/// Eigenvalues (ascending) and matching unit eigenvectors (stored one after
+/// another, `m` values each) of a symmetric `m × m` row-major matrix, by cyclic
+/// Jacobi rotations
+fn symmetric_eigen(mut a: Vec<f64>, m: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut v = vec![0.0f64; m * m];
+    for i in 0..m {
+        v[i * m + i] = 1.0;
+    }
+    for _sweep in 0..100 {
+        let off: f64 = (0..m).flat_map(|i| (0..m).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i * m + j] * a[i * m + j])
+            .sum();
+        let scale: f64 = (0..m).map(|i| a[i * m + i] * a[i * m + i]).sum();
+        if off <= 1e-22 * scale.max(1e-300) {
+            break;
+        }
+        for p in 0..m {
+            for q in p + 1..m {
+                let apq = a[p * m + q];
+                if apq.abs() < 1e-300 {
+                    continue;
+                }
+                // Rotation zeroing a[p][q]
+                let theta = (a[q * m + q] - a[p * m + p]) / (2.0 * apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..m {
+                    let (akp, akq) = (a[k * m + p], a[k * m + q]);
+                    a[k * m + p] = c * akp - s * akq;
+                    a[k * m + q] = s * akp + c * akq;
+                }
+                for k in 0..m {
+                    let (apk, aqk) = (a[p * m + k], a[q * m + k]);
+                    a[p * m + k] = c * apk - s * aqk;
+                    a[q * m + k] = s * apk + c * aqk;
+                }
+                for k in 0..m {
+                    let (vkp, vkq) = (v[k * m + p], v[k * m + q]);
+                    v[k * m + p] = c * vkp - s * vkq;
+                    v[k * m + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    
+    // Sort by eigenvalue; column c of v is the eigenvector of a[c][c]
+    let mut order: Vec<usize> = (0..m).collect();
+    order.sort_by(|&i, &j| a[i * m + i].total_cmp(&a[j * m + j]));
+    let values = order.iter().map(|&c| a[c * m + c]).collect();
+    let vectors = order.iter().flat_map(|&c| (0..m).map(move |k| (k, c))).map(|(k, c)| v[k * m + c]).collect();
+    (values, vectors)
+}
+
+/// Automatic Gain Control for levelling input before analysis
+/// Tracks a 50 ms RMS estimate and steers the gain toward `target_rms_db`
+/// with separate attack (gain reduction) and release (gain increase) times.
+/// The gain is held while a 5 ms level sits below the gate or more than 10 dB
+/// under the 50 ms level, so pauses in speech are never boosted toward the
+/// target, not even while the slow estimate decays into them (no pumping).
+#[wasm_bindgen]
+pub struct Agc {
+    sample_rate: f32,
+    target_rms: f32,
+    max_gain: f32,
+    gate_level: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    detector_coeff: f32,
+    // Fast (5 ms) level that detects pauses before the 50 ms one has decayed
+    fast_coeff: f32,
+    // Streaming state
+    power: f32,
+    fast_power: f32,
+    gain: f32,
+    clock: StreamClock,
+}
+
+#[wasm_bindgen]
+impl Agc {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32, target_rms_db: f32) -> Agc {
+        console_log!("🦀 [Rust DSP] AGC initialized: sr={}, target={} dBFS", sample_rate, target_rms_db);
+        
+        let mut agc = Agc {
+            sample_rate,
+            target_rms: db_to_linear(target_rms_db),
+            max_gain: db_to_linear(20.0),
+            gate_level: db_to_linear(-60.0),
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            detector_coeff: time_constant_coeff(50.0, sample_rate),
+            fast_coeff: time_constant_coeff(5.0, sample_rate),
+            power: 0.0,
+            fast_power: 0.0,
+            gain: 1.0,
+            clock: StreamClock::default(),
+        };
+        agc.set_attack_release(20.0, 1000.0);
+        agc
+    }
+
+    /// Gain smoothing times: attack when reducing gain, release (slow) when raising it
+    #[wasm_bindgen]
+    pub fn set_attack_release(&mut self, attack_ms: f32, release_ms: f32) {
+        self.attack_coeff = time_constant_coeff(attack_ms, self.sample_rate);
+        self.release_coeff = time_constant_coeff(release_ms, self.sample_rate);
+    }
+
+    /// Upper bound on the applied gain (default 20 dB)
+    #[wasm_bindgen]
+    pub fn set_max_gain_db(&mut self, max_gain_db: f32) {
+        self.max_gain = db_to_linear(max_gain_db.max(0.0));
+    }
+
+    /// RMS level below which the gain is frozen (default -60 dBFS)
+    #[wasm_bindgen]
+    pub fn set_gate_db(&mut self, gate_db: f32) {
+        self.gate_level = db_to_linear(gate_db);
+    }
+
+    /// Currently applied gain in dB
+    #[wasm_bindgen]
+    pub fn gain_db(&self) -> f32 {
+        20.0 * self.gain.max(1e-10).log10()
+    }
+
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.power = 0.0;
+        self.fast_power = 0.0;
+        self.gain = 1.0;
+    }
+
+    /// Samples to feed before trusting the output: the level detector and then the
+    /// slower gain time constant settling from a reset, each to within 60 dB
+    #[wasm_bindgen]
+    pub fn warmup_samples(&self) -> usize {
+        let gain_coeff = self.attack_coeff.min(self.release_coeff);
+        settle_samples(1.0 - self.detector_coeff).saturating_add(settle_samples(1.0 - gain_coeff))
+    }
+
+    /// Run warmup input through the detector and gain without producing output
+    #[wasm_bindgen]
+    pub fn prime(&mut self, samples: &[f32]) {
+        let _ = self.process(samples);
+    }
+
+    #[wasm_bindgen]
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        
+        for &s in samples {
+            self.power += self.detector_coeff * (s * s - self.power);
+            self.fast_power += self.fast_coeff * (s * s - self.fast_power);
+            let rms = self.power.sqrt();
+            
+            let gated = self.fast_power.sqrt() <= self.gate_level || self.fast_power < 0.1 * self.power;
+            if !gated {
+                let desired = (self.target_rms / rms).min(self.max_gain);
+                let coeff = if desired < self.gain { self.attack_coeff } else { self.release_coeff };
+                self.gain += coeff * (desired - self.gain);
+            }
+            
+            output.push(s * self.gain);
+        }
+        
+        output
+    }
+
+    /// `process` for a block starting at sample `timestamp`. A block that does not
+    /// start where the previous one ended (dropped or repeated buffer) flags a
+    /// discontinuity and, unless disabled, resets the gain before processing.
+    #[wasm_bindgen]
+    pub fn process_at(&mut self, samples: &[f32], timestamp: f64) -> Vec<f32> {
+        if self.clock.advance(timestamp, samples.len()) {
+            self.reset();
+        }
+        self.process(samples)
+    }
+
+    /// Whether the last `process_at` block was non-contiguous with the one before
+    #[wasm_bindgen]
+    pub fn had_discontinuity(&self) -> bool {
+        self.clock.discontinuity
+    }
+
+    /// Reset state when `process_at` detects a gap (default true)
+    #[wasm_bindgen]
+    pub fn set_reset_on_discontinuity(&mut self, enabled: bool) {
+        self.clock.reset_on_gap = enabled;
+    }
+
+    /// Fingerprint of the target, limits and time constants, not the running gain
+    /// (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("Agc")
+            .f32(self.sample_rate)
+            .f32(self.target_rms)
+            .f32(self.max_gain)
+            .f32(self.gate_level)
+            .f32(self.attack_coeff)
+            .f32(self.release_coeff)
+            .f32(self.detector_coeff)
+            .f32(self.fast_coeff)
+            .finish()
+    }
+}
+
+/// Second-order IIR section (transposed direct form II), normalized so a0 = 1
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+#[wasm_bindgen]
+impl Biquad {
+    #[wasm_bindgen(constructor)]
+    pub fn new(b0: f32, b1: f32, b2: f32, a1: f32, a2: f32) -> Biquad {
+        Biquad { b0, b1, b2, a1, a2, z1: 0.0, z2: 0.0 }
+    }
+
+    /// Fingerprint of the coefficients, not the filter state (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("Biquad")
+            .f32(self.b0)
+            .f32(self.b1)
+            .f32(self.b2)
+            .f32(self.a1)
+            .f32(self.a2)
+            .finish()
+    }
+
+    /// RBJ cookbook low-pass (Q = 0.7071 for Butterworth)
+    #[wasm_bindgen]
+    pub fn lowpass(sample_rate: f32, freq: f32, q: f32) -> Biquad {
+        let (cos, alpha) = rbj_params(sample_rate, freq, q);
+        Biquad::normalized([(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// RBJ cookbook high-pass (Q = 0.7071 for Butterworth)
+    #[wasm_bindgen]
+    pub fn highpass(sample_rate: f32, freq: f32, q: f32) -> Biquad {
+        let (cos, alpha) = rbj_params(sample_rate, freq, q);
+        Biquad::normalized([(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// RBJ cookbook band-pass with 0 dB peak gain at `freq` (bandwidth `freq / q`)
+    #[wasm_bindgen]
+    pub fn bandpass(sample_rate: f32, freq: f32, q: f32) -> Biquad {
+        let (cos, alpha) = rbj_params(sample_rate, freq, q);
+        Biquad::normalized([alpha, 0.0, -alpha], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// RBJ cookbook notch: a zero at `freq`, unity gain away from it (-3 dB width `freq / q`)
+    #[wasm_bindgen]
+    pub fn notch(sample_rate: f32, freq: f32, q: f32) -> Biquad {
+        let (cos, alpha) = rbj_params(sample_rate, freq, q);
+        Biquad::normalized([1.0, -2.0 * cos, 1.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// RBJ cookbook all-pass: unity magnitude, phase turning through -180° at `freq`
+    #[wasm_bindgen]
+    pub fn allpass(sample_rate: f32, freq: f32, q: f32) -> Biquad {
+        let (cos, alpha) = rbj_params(sample_rate, freq, q);
+        Biquad::normalized([1.0 - alpha, -2.0 * cos, 1.0 + alpha], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    #[wasm_bindgen]
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        samples.iter().map(|&s| self.process_sample(s)).collect()
+    }
+
+    /// Clear the filter state
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+
+    /// Samples to feed before trusting the output: the time for the slowest pole's
+    /// transient to decay 60 dB (`usize::MAX` if a pole is on or outside the unit circle)
+    #[wasm_bindgen]
+    pub fn warmup_samples(&self) -> usize {
+        let (a1, a2) = (self.a1 as f64, self.a2 as f64);
+        let discriminant = a1 * a1 - 4.0 * a2;
+        let radius = if discriminant < 0.0 {
+            a2.sqrt()
+        } else {
+            let root = discriminant.sqrt();
+            ((-a1 + root) / 2.0).abs().max(((-a1 - root) / 2.0).abs())
+        };
+        settle_samples(radius as f32)
+    }
+
+    /// Run warmup input through the filter without producing output
+    #[wasm_bindgen]
+    pub fn prime(&mut self, samples: &[f32]) {
+        for &s in samples {
+            self.process_sample(s);
+        }
+    }
+
+    /// Zero-phase filtering (offline): forward then backward through the filter,
+    /// squaring the magnitude response and cancelling the phase. Edges are
+    /// odd-reflection padded and each pass starts from the steady state for its
+    /// first sample, which keeps start-up transients out of the output.
+    /// Uses a copy of the coefficients; this filter's own state is untouched.
+    #[wasm_bindgen]
+    pub fn filtfilt(&self, samples: &[f32]) -> Vec<f32> {
+        let n = samples.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let pad = 9.min(n - 1);
+        
+        // Step 1: odd reflection about the end samples
+        let mut extended = Vec::with_capacity(n + 2 * pad);
+        extended.extend((1..=pad).rev().map(|i| 2.0 * samples[0] - samples[i]));
+        extended.extend_from_slice(samples);
+        extended.extend((1..=pad).map(|i| 2.0 * samples[n - 1] - samples[n - 1 - i]));
+        
+        // Step 2: forward pass, then the same filter over the reversed result
+        let mut filter = self.clone();
+        for _ in 0..2 {
+            filter.settle(extended[0]);
+            for s in extended.iter_mut() {
+                *s = filter.process_sample(*s);
+            }
+            extended.reverse();
+        }
+        
+        extended[pad..pad + n].to_vec()
+    }
+}
+
+impl Biquad {
+    /// Set the state to the steady state reached by a constant input `x`
+    fn settle(&mut self, x: f32) {
+        let den = 1.0 + self.a1 + self.a2;
+        if den.abs() < 1e-12 {
+            self.reset();
+            return;
+        }
+        let dc_gain = (self.b0 + self.b1 + self.b2) / den;
+        self.z1 = (dc_gain - self.b0) * x;
+        self.z2 = (self.b2 - self.a2 * dc_gain) * x;
+    }
+
+    fn normalized(b: [f32; 3], a: [f32; 3]) -> Biquad {
+        Biquad::new(b[0] / a[0], b[1] / a[0], b[2] / a[0], a[1] / a[0], a[2] / a[0])
+    }
+
+    #[inline]
+    fn process_sample(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// `(cos w0, alpha)` for the RBJ cookbook biquads
+#[inline]
+fn rbj_params(sample_rate: f32, freq: f32, q: f32) -> (f32, f32) {
+    let w0 = 2.0 * PI * freq / sample_rate;
+    (w0.cos(), w0.sin() / (2.0 * q))
+}
+
+/// Feed-forward peak compressor with a hard knee.
+/// With look-ahead the signal is delayed while the detector runs on the
+/// undelayed input, so the gain is already down when a transient arrives.
+#[wasm_bindgen]
+pub struct Compressor {
+    sample_rate: f32,
+    threshold_db: f32,
+    ratio: f32,
+    makeup: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    // Look-ahead delay line (empty when disabled), carried across blocks
+    delay: Vec<f32>,
+    delay_pos: usize,
+    envelope: f32,
+    clock: StreamClock,
+}
+
+#[wasm_bindgen]
+impl Compressor {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32) -> Compressor {
+        console_log!("🦀 [Rust DSP] Compressor: sr={}", sample_rate);
+        
+        Compressor {
+            sample_rate,
+            threshold_db: -20.0,
+            ratio: 4.0,
+            makeup: 1.0,
+            attack_coeff: time_constant_coeff(10.0, sample_rate),
+            release_coeff: time_constant_coeff(100.0, sample_rate),
+            delay: Vec::new(),
+            delay_pos: 0,
+            envelope: 0.0,
+            clock: StreamClock::default(),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn set_threshold_db(&mut self, threshold_db: f32) {
+        self.threshold_db = threshold_db;
+    }
+
+    /// Compression ratio (1 = no compression)
+    #[wasm_bindgen]
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = ratio.max(1.0);
+    }
+
+    /// Envelope attack and release times in milliseconds (defaults 10 / 100)
+    #[wasm_bindgen]
+    pub fn set_attack_release(&mut self, attack_ms: f32, release_ms: f32) {
+        self.attack_coeff = time_constant_coeff(attack_ms, self.sample_rate);
+        self.release_coeff = time_constant_coeff(release_ms, self.sample_rate);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_makeup_db(&mut self, makeup_db: f32) {
+        self.makeup = db_to_linear(makeup_db);
+    }
+
+    /// Delay the signal by `lookahead_ms` (default 0) so gain reduction starts
+    /// before a transient reaches the output. Use an attack no longer than the
+    /// look-ahead to catch the peak fully. Clears the delay line.
+    #[wasm_bindgen]
+    pub fn set_lookahead_ms(&mut self, lookahead_ms: f32) {
+        let length = (lookahead_ms.max(0.0) * 0.001 * self.sample_rate).round() as usize;
+        self.delay = vec![0.0; length];
+        self.delay_pos = 0;
+    }
+
+    /// Latency added by the look-ahead, in samples
+    #[wasm_bindgen]
+    pub fn latency_samples(&self) -> usize {
+        self.delay.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        samples.iter().map(|&s| self.process_sample(s)).collect()
+    }
+
+    /// Clear the envelope follower and the look-ahead delay line
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+        self.delay.fill(0.0);
+        self.delay_pos = 0;
+    }
+
+    /// Samples to feed before trusting the output: the slower envelope time
+    /// constant settling to within 60 dB, plus the look-ahead delay
+    #[wasm_bindgen]
+    pub fn warmup_samples(&self) -> usize {
+        settle_samples(1.0 - self.attack_coeff.min(self.release_coeff)).saturating_add(self.delay.len())
+    }
+
+    /// Run warmup input through the envelope follower and the look-ahead delay
+    /// without producing output
+    #[wasm_bindgen]
+    pub fn prime(&mut self, samples: &[f32]) {
+        for &s in samples {
+            self.process_sample(s);
+        }
+    }
+
+    /// `process` for a block starting at sample `timestamp`; a gap since the
+    /// previous block flags a discontinuity and (by default) resets the envelope
+    #[wasm_bindgen]
+    pub fn process_at(&mut self, samples: &[f32], timestamp: f64) -> Vec<f32> {
+        if self.clock.advance(timestamp, samples.len()) {
+            self.reset();
+        }
+        self.process(samples)
+    }
+
+    /// Whether the last `process_at` block was non-contiguous with the one before
+    #[wasm_bindgen]
+    pub fn had_discontinuity(&self) -> bool {
+        self.clock.discontinuity
+    }
+
+    /// Reset state when `process_at` detects a gap (default true)
+    #[wasm_bindgen]
+    pub fn set_reset_on_discontinuity(&mut self, enabled: bool) {
+        self.clock.reset_on_gap = enabled;
+    }
+
+    /// Fingerprint of the curve and time constants, not the envelope (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("Compressor")
+            .f32(self.sample_rate)
+            .f32(self.threshold_db)
+            .f32(self.ratio)
+            .f32(self.makeup)
+            .f32(self.attack_coeff)
+            .f32(self.release_coeff)
+            .usize(self.delay.len())
+            .finish()
+    }
+}
+
+impl Compressor {
+    #[inline]
+    fn process_sample(&mut self, x: f32) -> f32 {
+        let level = x.abs();
+        let coeff = if level > self.envelope { self.attack_coeff } else { self.release_coeff };
+        self.envelope += coeff * (level - self.envelope);
+        
+        let over_db = 20.0 * (self.envelope + 1e-10).log10() - self.threshold_db;
+        let gain = if over_db > 0.0 {
+            db_to_linear(-over_db * (1.0 - 1.0 / self.ratio))
+        } else {
+            1.0
+        };
+        
+        // The gain from the undelayed detector applies to the delayed sample
+        let delayed = match self.delay.get_mut(self.delay_pos) {
+            Some(slot) => std::mem::replace(slot, x),
+            None => return x * gain * self.makeup,
+        };
+        self.delay_pos = (self.delay_pos + 1) % self.delay.len();
+        delayed * gain * self.makeup
+    }
+}
+
+/// `num_bands - 1` crossover frequencies spaced geometrically between `fmin` and
+/// `fmax` (the band edges), e.g. for `MultibandCompressor::new`. Empty when
+/// `num_bands < 2` or the range is not `0 < fmin < fmax`.
+#[wasm_bindgen]
+pub fn log_crossovers(num_bands: usize, fmin: f32, fmax: f32) -> Vec<f32> {
+    if num_bands < 2 || !(fmin > 0.0 && fmax > fmin) {
+        return Vec::new();
+    }
+    let ratio = fmax / fmin;
+    (1..num_bands)
+        .map(|i| fmin * ratio.powf(i as f32 / num_bands as f32))
+        .collect()
+}
+
+/// Multi-band compressor on a Linkwitz-Riley (LR4) crossover tree.
+/// Crossover i splits the remaining signal into an LR4 low band and an LR4 high
+/// remainder. Since LR4 low + high equals a 2nd-order all-pass, each band is also
+/// passed through the all-passes of the crossovers above it, so at unity ratio
+/// the bands sum to an all-pass of the input (flat magnitude response).
+#[wasm_bindgen]
+pub struct MultibandCompressor {
+    lowpass: Vec<[Biquad; 2]>,
+    highpass: Vec<[Biquad; 2]>,
+    // Phase compensation per band: all-passes of every higher crossover
+    allpass: Vec<Vec<Biquad>>,
+    compressors: Vec<Compressor>,
+    clock: StreamClock,
+}
+
+#[wasm_bindgen]
+impl MultibandCompressor {
+    /// `crossovers` in Hz (sorted internally); yields `crossovers.len() + 1` bands
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32, crossovers: &[f32]) -> MultibandCompressor {
+        let mut freqs = crossovers.to_vec();
+        freqs.sort_by(|a, b| a.total_cmp(b));
+        let q = std::f32::consts::FRAC_1_SQRT_2;
+        let num_bands = freqs.len() + 1;
+        
+        console_log!("🦀 [Rust DSP] Multiband Compressor: sr={}, bands={}", sample_rate, num_bands);
+        
+        let butterworth = |filter: fn(f32, f32, f32) -> Biquad, f: f32| [filter(sample_rate, f, q), filter(sample_rate, f, q)];
+        MultibandCompressor {
+            lowpass: freqs.iter().map(|&f| butterworth(Biquad::lowpass, f)).collect(),
+            highpass: freqs.iter().map(|&f| butterworth(Biquad::highpass, f)).collect(),
+            allpass: (0..num_bands)
+                .map(|band| freqs.iter().skip(band + 1).map(|&f| Biquad::allpass(sample_rate, f, q)).collect())
+                .collect(),
+            compressors: (0..num_bands).map(|_| Compressor::new(sample_rate)).collect(),
+            clock: StreamClock::default(),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn num_bands(&self) -> usize {
+        self.compressors.len()
+    }
+
+    /// Compressor settings for one band (band 0 is the lowest); out-of-range bands are ignored
+    #[wasm_bindgen]
+    pub fn set_band(&mut self, band: usize, threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32, makeup_db: f32) {
+        if let Some(compressor) = self.compressors.get_mut(band) {
+            compressor.set_threshold_db(threshold_db);
+            compressor.set_ratio(ratio);
+            compressor.set_attack_release(attack_ms, release_ms);
+            compressor.set_makeup_db(makeup_db);
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        let last = self.compressors.len() - 1;
+        samples.iter()
+            .map(|&s| {
+                let mut rest = s;
+                let mut output = 0.0f32;
+                for band in 0..=last {
+                    // Step 1: split off this band
+                    let mut x = if band < last {
+                        let [lp1, lp2] = &mut self.lowpass[band];
+                        let [hp1, hp2] = &mut self.highpass[band];
+                        let low = lp2.process_sample(lp1.process_sample(rest));
+                        rest = hp2.process_sample(hp1.process_sample(rest));
+                        low
+                    } else {
+                        rest
+                    };
+                    
+                    // Step 2: align phase with the higher bands, then compress
+                    for ap in self.allpass[band].iter_mut() {
+                        x = ap.process_sample(x);
+                    }
+                    output += self.compressors[band].process_sample(x);
+                }
+                output
+            })
+            .collect()
+    }
+
+    /// Samples to feed before trusting the output: over the bands, the longest
+    /// chain of crossover and all-pass warmups plus that band's compressor warmup
+    #[wasm_bindgen]
+    pub fn warmup_samples(&self) -> usize {
+        let last = self.compressors.len() - 1;
+        (0..=last)
+            .map(|band| {
+                let low = if band < last { &self.lowpass[band][..] } else { &[] };
+                self.highpass[..band].iter()
+                    .flatten()
+                    .chain(low)
+                    .chain(&self.allpass[band])
+                    .map(Biquad::warmup_samples)
+                    .fold(self.compressors[band].warmup_samples(), usize::saturating_add)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Run warmup input through the crossover and compressors without producing output
+    #[wasm_bindgen]
+    pub fn prime(&mut self, samples: &[f32]) {
+        let _ = self.process(samples);
+    }
+
+    /// Clear every filter and envelope state
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        for filter in self.lowpass.iter_mut().chain(self.highpass.iter_mut()).flatten() {
+            filter.reset();
+        }
+        for filter in self.allpass.iter_mut().flatten() {
+            filter.reset();
+        }
+        for compressor in self.compressors.iter_mut() {
+            compressor.reset();
+        }
+    }
+
+    /// `process` for a block starting at sample `timestamp`; a gap since the
+    /// previous block flags a discontinuity and (by default) clears every
+    /// filter and envelope, so stale crossover state is never mixed into new audio
+    #[wasm_bindgen]
+    pub fn process_at(&mut self, samples: &[f32], timestamp: f64) -> Vec<f32> {
+        if self.clock.advance(timestamp, samples.len()) {
+            self.reset();
+        }
+        self.process(samples)
+    }
+
+    /// Whether the last `process_at` block was non-contiguous with the one before
+    #[wasm_bindgen]
+    pub fn had_discontinuity(&self) -> bool {
+        self.clock.discontinuity
+    }
+
+    /// Reset state when `process_at` detects a gap (default true)
+    #[wasm_bindgen]
+    pub fn set_reset_on_discontinuity(&mut self, enabled: bool) {
+        self.clock.reset_on_gap = enabled;
+    }
+
+    /// Fingerprint of the crossover filters and every band's compressor
+    /// (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        let filters = self.lowpass.iter()
+            .chain(&self.highpass)
+            .flatten()
+            .chain(self.allpass.iter().flatten());
+        let hash = filters.fold(Fnv1a::new("MultibandCompressor"), |h, f| h.u64(f.config_hash()));
+        self.compressors.iter().fold(hash, |h, c| h.u64(c.config_hash())).finish()
+    }
+}
+
+/// Split-band de-esser: a dynamic cut on the sibilant band only.
+/// The band (default 5-10 kHz) is isolated with a band-pass centred on the band's
+/// geometric mean and its peak envelope drives a hard-knee gain computer. The
+/// output is `x - (1 - gain)·band`, a peaking cut of depth `gain` that never boosts
+/// any frequency; while the band stays under the threshold the input passes
+/// through bit-for-bit.
+#[wasm_bindgen]
+pub struct DeEsser {
+    sample_rate: f32,
+    bandpass: Biquad,
+    threshold_db: f32,
+    ratio: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: f32,
+}
+
+#[wasm_bindgen]
+impl DeEsser {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32) -> DeEsser {
+        console_log!("🦀 [Rust DSP] De-esser: sr={}", sample_rate);
+        
+        let mut de_esser = DeEsser {
+            sample_rate,
+            bandpass: Biquad::new(0.0, 0.0, 0.0, 0.0, 0.0),
+            threshold_db: -30.0,
+            ratio: 4.0,
+            attack_coeff: time_constant_coeff(1.0, sample_rate),
+            release_coeff: time_constant_coeff(60.0, sample_rate),
+            envelope: 0.0,
+        };
+        de_esser.set_band(5000.0, 10000.0);
+        de_esser
+    }
+
+    /// Sibilant band edges in Hz (the upper edge is kept below 0.45·sample_rate)
+    #[wasm_bindgen]
+    pub fn set_band(&mut self, low_hz: f32, high_hz: f32) {
+        let high = high_hz.min(0.45 * self.sample_rate);
+        let low = low_hz.clamp(1.0, 0.99 * high);
+        let center = (low * high).sqrt();
+        self.bandpass = Biquad::bandpass(self.sample_rate, center, center / (high - low));
+    }
+
+    /// Band peak level in dBFS above which the band is attenuated (default -30)
+    #[wasm_bindgen]
+    pub fn set_threshold_db(&mut self, threshold_db: f32) {
+        self.threshold_db = threshold_db;
+    }
+
+    /// Compression ratio applied to the band above the threshold (default 4)
+    #[wasm_bindgen]
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = ratio.max(1.0);
+    }
+
+    /// Envelope attack and release times in milliseconds (defaults 1 / 60)
+    #[wasm_bindgen]
+    pub fn set_attack_release(&mut self, attack_ms: f32, release_ms: f32) {
+        self.attack_coeff = time_constant_coeff(attack_ms, self.sample_rate);
+        self.release_coeff = time_constant_coeff(release_ms, self.sample_rate);
+    }
+
+    /// Fingerprint of the band and gain curve, not the envelope (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("DeEsser")
+            .f32(self.sample_rate)
+            .u64(self.bandpass.config_hash())
+            .f32(self.threshold_db)
+            .f32(self.ratio)
+            .f32(self.attack_coeff)
+            .f32(self.release_coeff)
+            .finish()
+    }
+
+    #[wasm_bindgen]
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        samples.iter()
+            .map(|&x| {
+                // Step 1: isolate the band and follow its peak level
+                let band = self.bandpass.process_sample(x);
+                let level = band.abs();
+                let coeff = if level > self.envelope { self.attack_coeff } else { self.release_coeff };
+                self.envelope += coeff * (level - self.envelope);
+                
+                // Step 2: remove the band's share of the gain reduction
+                let over_db = 20.0 * (self.envelope + 1e-10).log10() - self.threshold_db;
+                if over_db > 0.0 {
+                    x - (1.0 - db_to_linear(-over_db * (1.0 - 1.0 / self.ratio))) * band
+                } else {
+                    x
+                }
+            })
+            .collect()
+    }
+
+    /// Samples to feed before trusting the output: the band filter's warmup plus
+    /// the slower envelope time constant settling to within 60 dB
+    #[wasm_bindgen]
+    pub fn warmup_samples(&self) -> usize {
+        let envelope = settle_samples(1.0 - self.attack_coeff.min(self.release_coeff));
+        self.bandpass.warmup_samples().saturating_add(envelope)
+    }
+
+    /// Run warmup input through the band filter and envelope without producing output
+    #[wasm_bindgen]
+    pub fn prime(&mut self, samples: &[f32]) {
+        let _ = self.process(samples);
+    }
+
+    /// Clear the band filter and the envelope follower
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.bandpass.reset();
+        self.envelope = 0.0;
+    }
+}
+
+/// Mains hum remover: a cascade of narrow notches at the mains frequency and its
+/// harmonics (those below 0.45·sample_rate). The default Q of 30 gives notches
+/// 2 Hz wide at 60 Hz, scaling with each harmonic so all stay equally narrow in
+/// musical terms. A `mains_hz` of 0 auto-detects 50 vs 60 Hz from the first
+/// processed block.
+#[wasm_bindgen]
+pub struct HumRemover {
+    sample_rate: f32,
+    mains_hz: f32,
+    num_harmonics: usize,
+    q: f32,
+    notches: Vec<Biquad>,
+}
+
+#[wasm_bindgen]
+impl HumRemover {
+    #[wasm_bindgen(constructor)]
+    pub fn new(mains_hz: f32, sample_rate: f32, num_harmonics: usize) -> HumRemover {
+        console_log!("🦀 [Rust DSP] Hum Remover: mains={} Hz, harmonics={}, sr={}", mains_hz, num_harmonics, sample_rate);
+        
+        let mut remover = HumRemover {
+            sample_rate,
+            mains_hz: 0.0,
+            num_harmonics: num_harmonics.max(1),
+            q: 30.0,
+            notches: Vec::new(),
+        };
+        if mains_hz > 0.0 {
+            remover.set_mains_hz(mains_hz);
+        }
+        remover
+    }
+
+    /// Mains fundamental in Hz (0 until auto-detection has run)
+    #[wasm_bindgen]
+    pub fn mains_hz(&self) -> f32 {
+        self.mains_hz
+    }
+
+    /// Retune the notches to `mains_hz`, clearing their state
+    #[wasm_bindgen]
+    pub fn set_mains_hz(&mut self, mains_hz: f32) {
+        self.mains_hz = mains_hz;
+        self.notches = (1..=self.num_harmonics)
+            .map(|h| h as f32 * mains_hz)
+            .take_while(|&f| f < 0.45 * self.sample_rate)
+            .map(|f| Biquad::notch(self.sample_rate, f, self.q))
+            .collect();
+    }
+
+    /// Notch quality factor (default 30); higher is narrower
+    #[wasm_bindgen]
+    pub fn set_q(&mut self, q: f32) {
+        self.q = q.max(0.5);
+        if self.mains_hz > 0.0 {
+            self.set_mains_hz(self.mains_hz);
+        }
+    }
+
+    /// Pick 50 or 60 Hz mains from `samples` by comparing the Goertzel power at
+    /// each candidate's first `num_harmonics` (at least 3) harmonics, retune to
+    /// the winner and return it. Needs about half a second of audio, since the
+    /// candidates' harmonics must be resolved from each other.
+    #[wasm_bindgen]
+    pub fn detect_mains(&mut self, samples: &[f32]) -> f32 {
+        let harmonics = self.num_harmonics.max(3);
+        let nyquist = 0.45 * self.sample_rate;
+        let power = |base: f32| -> f64 {
+            (1..=harmonics)
+                .map(|h| h as f32 * base)
+                .filter(|&f| f < nyquist)
+                .map(|f| goertzel_power(samples, f, self.sample_rate))
+                .sum()
+        };
+        let mains = if power(60.0) > power(50.0) { 60.0 } else { 50.0 };
+        self.set_mains_hz(mains);
+        mains
+    }
+
+    /// Fingerprint of the notch bank, not the filter state (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        let hash = Fnv1a::new("HumRemover")
+            .f32(self.sample_rate)
+            .f32(self.mains_hz)
+            .usize(self.num_harmonics)
+            .f32(self.q);
+        self.notches.iter().fold(hash, |h, n| h.u64(n.config_hash())).finish()
+    }
+
+    #[wasm_bindgen]
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        if self.mains_hz <= 0.0 {
+            self.detect_mains(samples);
+        }
+        let mut output = samples.to_vec();
+        for notch in self.notches.iter_mut() {
+            for s in output.iter_mut() {
+                *s = notch.process_sample(*s);
+            }
+        }
+        output
+    }
+
+    /// Samples to feed before trusting the output: the notches run in cascade, so
+    /// the sum of their warmups (0 before auto-detection has tuned them)
+    #[wasm_bindgen]
+    pub fn warmup_samples(&self) -> usize {
+        self.notches.iter().fold(0usize, |acc, n| acc.saturating_add(n.warmup_samples()))
+    }
+
+    /// Run warmup input through the notches without producing output (auto-detects
+    /// the mains frequency from it first when unset)
+    #[wasm_bindgen]
+    pub fn prime(&mut self, samples: &[f32]) {
+        let _ = self.process(samples);
+    }
+
+    /// Clear the notch filter states
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.notches.iter_mut().for_each(Biquad::reset);
+    }
+}
+
+/// Power of `samples` at `freq` by the Goertzel recurrence (f64 accumulation)
+fn goertzel_power(samples: &[f32], freq: f32, sample_rate: f32) -> f64 {
+    let coeff = 2.0 * (2.0 * std::f64::consts::PI * freq as f64 / sample_rate as f64).cos();
+    let (mut s1, mut s2) = (0.0f64, 0.0f64);
+    for &x in samples {
+        let s0 = x as f64 + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    s1 * s1 + s2 * s2 - coeff * s1 * s2
+}
+
+/// Harmonic-percussive source separation by median filtering (Fitzgerald):
+/// sustained partials are smooth along time, transients along frequency.
+/// The STFT magnitude is median-filtered across frames (harmonic) and across
+/// bins (percussive), soft masks `H^p / (H^p + P^p)` split each bin, and both
+/// parts are resynthesized with the original phase by weighted overlap-add.
+#[wasm_bindgen]
+pub struct Hpss {
+    fft_size: usize,
+    hop_size: usize,
+    // Median lengths: frames for the harmonic filter, bins for the percussive one
+    harmonic_frames: usize,
+    percussive_bins: usize,
+    mask_power: f32,
+    // Percussive-to-harmonic ratio above which `transient_steady_split` calls a bin transient
+    transient_threshold: f32,
+    fft_forward: Arc<dyn Fft<f32>>,
+    fft_inverse: Arc<dyn Fft<f32>>,
+    window: Arc<[f32]>,
+    scratch: Vec<Complex<f32>>,
+}
+
+#[wasm_bindgen]
+impl Hpss {
+    #[wasm_bindgen(constructor)]
+    pub fn new(fft_size: usize, hop_size: usize) -> Hpss {
+        console_log!("🦀 [Rust DSP] HPSS initialized: fft={}, hop={}", fft_size, hop_size);
+        
+        let planner = SharedPlanner::new();
+        let fft_forward = planner.plan_forward(fft_size);
+        let fft_inverse = planner.plan_inverse(fft_size);
+        let scratch_len = fft_forward.get_inplace_scratch_len().max(fft_inverse.get_inplace_scratch_len());
+        Hpss {
+            fft_size,
+            hop_size: hop_size.max(1),
+            harmonic_frames: 17,
+            percussive_bins: 17,
+            mask_power: 2.0,
+            transient_threshold: 2.0,
+            fft_forward,
+            fft_inverse,
+            window: cached_window(fft_size, WindowKind::Hann),
+            scratch: vec![Complex::new(0.0, 0.0); scratch_len],
+        }
+    }
+
+    /// Median lengths (made odd): frames for the harmonic filter and bins for the
+    /// percussive filter (both default 17). Longer filters separate more strongly.
+    #[wasm_bindgen]
+    pub fn set_filter_sizes(&mut self, harmonic_frames: usize, percussive_bins: usize) {
+        self.harmonic_frames = harmonic_frames.max(1) | 1;
+        self.percussive_bins = percussive_bins.max(1) | 1;
+    }
+
+    /// Soft-mask exponent (default 2, Wiener-like); large values approach binary masks
+    #[wasm_bindgen]
+    pub fn set_mask_power(&mut self, power: f32) {
+        self.mask_power = power.max(0.1);
+    }
+
+    /// Percussive-to-harmonic magnitude ratio above which `transient_steady_split`
+    /// assigns a bin to the transient part (default 2, Driedger's separation
+    /// factor); 1 splits at equal dominance, larger values keep more in the steady part
+    #[wasm_bindgen]
+    pub fn set_transient_threshold(&mut self, ratio: f32) {
+        self.transient_threshold = if ratio.is_nan() { 2.0 } else { ratio.max(1e-3) };
+    }
+
+    /// Fingerprint of the framing, filter sizes, mask power and transient threshold (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("Hpss")
+            .usize(self.fft_size)
+            .usize(self.hop_size)
+            .usize(self.harmonic_frames)
+            .usize(self.percussive_bins)
+            .f32(self.mask_power)
+            .f32(self.transient_threshold)
+            .finish()
+    }
+
+    /// Separate `samples` into `[harmonic..., percussive...]`, each part
+    /// `samples.len()` long, so `out[..n]` is harmonic and `out[n..]` percussive.
+    /// The masks sum to one, so the two parts add back to the input.
+    #[wasm_bindgen]
+    pub fn separate(&mut self, samples: &[f32]) -> Vec<f32> {
+        let power = self.mask_power;
+        self.split_with(samples, |h, p| {
+            let (h, p) = (h.powf(power), p.powf(power));
+            if h + p > 0.0 { h / (h + p) } else { 0.5 }
+        })
+    }
+
+    /// Split `samples` into a steady (tonal, ambience) and a transient part as
+    /// `[num_samples, steady..., transient...]`. Each STFT bin goes wholly to
+    /// the transient part when its frequency-median magnitude exceeds
+    /// `set_transient_threshold` times its time-median magnitude, otherwise to
+    /// the steady part; the binary masks are complementary, so the two parts
+    /// sum back to the input up to overlap-add rounding.
+    #[wasm_bindgen]
+    pub fn transient_steady_split(&mut self, samples: &[f32]) -> Vec<f32> {
+        let threshold = self.transient_threshold;
+        let parts = self.split_with(samples, |h, p| if p > threshold * h { 0.0 } else { 1.0 });
+        let mut output = Vec::with_capacity(parts.len() + 1);
+        output.push(samples.len() as f32);
+        output.extend(parts);
+        output
+    }
+
+    /// Median-filter separation shared by `separate` and `transient_steady_split`:
+    /// `mask(harmonic, percussive)` gives each bin's share of the first part,
+    /// the rest goes to the second; returns `[first..., second...]`
+    fn split_with(&mut self, samples: &[f32], mask: impl Fn(f32, f32) -> f32) -> Vec<f32> {
+        let (size, hop) = (self.fft_size, self.hop_size);
+        let n_bins = size / 2 + 1;
+        let len = samples.len();
+        if len == 0 || size < 2 {
+            return vec![0.0; 2 * len];
+        }
+        
+        // Step 1: STFT with frames centred on multiples of the hop
+        let half = size / 2;
+        let num_frames = (len + half) / hop + 1;
+        let mut spectra: Vec<Vec<Complex<f32>>> = Vec::with_capacity(num_frames);
+        let mut buffer = vec![Complex::new(0.0f32, 0.0); size];
+        for t in 0..num_frames {
+            for (i, b) in buffer.iter_mut().enumerate() {
+                let index = (t * hop + i).wrapping_sub(half);
+                *b = Complex::new(samples.get(index).map_or(0.0, |&s| s * self.window[i]), 0.0);
+            }
+            self.fft_forward.process_with_scratch(&mut buffer, &mut self.scratch);
+            spectra.push(buffer[..n_bins].to_vec());
+        }
+        let magnitude: Vec<Vec<f32>> = spectra.iter().map(|s| s.iter().map(|c| c.norm()).collect()).collect();
+        
+        // Step 2: median filters along time (harmonic) and frequency (percussive)
+        let median = |values: &mut Vec<f32>| {
+            let mid = values.len() / 2;
+            *values.select_nth_unstable_by(mid, f32::total_cmp).1
+        };
+        let (h_half, p_half) = (self.harmonic_frames / 2, self.percussive_bins / 2);
+        let mut window = Vec::with_capacity(self.harmonic_frames.max(self.percussive_bins));
+        let mut harmonic = vec![vec![0.0f32; n_bins]; num_frames];
+        let mut percussive = vec![vec![0.0f32; n_bins]; num_frames];
+        for t in 0..num_frames {
+            for k in 0..n_bins {
+                window.clear();
+                window.extend((t.saturating_sub(h_half)..(t + h_half + 1).min(num_frames)).map(|u| magnitude[u][k]));
+                harmonic[t][k] = median(&mut window);
+                window.clear();
+                window.extend_from_slice(&magnitude[t][k.saturating_sub(p_half)..(k + p_half + 1).min(n_bins)]);
+                percussive[t][k] = median(&mut window);
+            }
+        }
+        
+        // Step 3: masks, inverse FFT and weighted overlap-add of both parts
+        let mut output = vec![0.0f32; 2 * len];
+        let mut norm = vec![0.0f32; len];
+        for (t, spectrum) in spectra.iter().enumerate() {
+            let masks: Vec<f32> = (0..n_bins).map(|k| mask(harmonic[t][k], percussive[t][k])).collect();
+            for (part, offset) in [(0usize, 0usize), (1, len)] {
+                for (k, b) in buffer.iter_mut().enumerate() {
+                    let bin = if k < n_bins { k } else { size - k };
+                    let gain = if part == 0 { masks[bin] } else { 1.0 - masks[bin] };
+                    let value = if k < n_bins { spectrum[k] } else { spectrum[bin].conj() };
+                    *b = value * gain;
+                }
+                self.fft_inverse.process_with_scratch(&mut buffer, &mut self.scratch);
+                for (i, b) in buffer.iter().enumerate() {
+                    let index = (t * hop + i).wrapping_sub(half);
+                    if index < len {
+                        output[offset + index] += b.re * self.window[i] / size as f32;
+                    }
+                }
+            }
+            for (i, &w) in self.window.iter().enumerate() {
+                let index = (t * hop + i).wrapping_sub(half);
+                if index < len {
+                    norm[index] += w * w;
+                }
+            }
+        }
+        for (i, &w) in norm.iter().enumerate() {
+            let gain = if w > 1e-6 { 1.0 / w } else { 0.0 };
+            output[i] *= gain;
+            output[len + i] *= gain;
+        }
+        output
+    }
+}
+
+/// ITU-R BS.1770 loudness meter (mono)
+/// K-weighting (high shelf + RLB high-pass) is derived for the given sample rate,
+/// then 400 ms blocks with 75% overlap are gated at -70 LUFS (absolute) and
+/// 10 LU below the ungated mean (relative). True peak uses 4x oversampling.
+#[wasm_bindgen]
+pub struct LoudnessMeter {
+    sample_rate: f32,
+    shelf: Biquad,
+    highpass: Biquad,
+}
+
+#[wasm_bindgen]
+impl LoudnessMeter {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32) -> LoudnessMeter {
+        console_log!("🦀 [Rust DSP] Loudness Meter (BS.1770): sr={}", sample_rate);
+        
+        // Stage 1: +4 dB high shelf around 1.68 kHz (head diffraction)
+        let k = (PI * 1681.9745 / sample_rate).tan();
+        let q = 0.70717524;
+        let vh = 10.0f32.powf(3.9998439 / 20.0);
+        let vb = vh.powf(0.49966677);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+            2.0 * (k * k - 1.0) / a0,
+            (1.0 - k / q + k * k) / a0,
+        );
+        
+        // Stage 2: RLB high-pass around 38 Hz
+        let k = (PI * 38.13547 / sample_rate).tan();
+        let q = 0.500327;
+        let a0 = 1.0 + k / q + k * k;
+        let highpass = Biquad::new(
+            1.0,
+            -2.0,
+            1.0,
+            2.0 * (k * k - 1.0) / a0,
+            (1.0 - k / q + k * k) / a0,
+        );
+        
+        LoudnessMeter { sample_rate, shelf, highpass }
+    }
+
+    /// Fingerprint of the K-weighting filters (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("LoudnessMeter")
+            .f32(self.sample_rate)
+            .u64(self.shelf.config_hash())
+            .u64(self.highpass.config_hash())
+            .finish()
+    }
+
+    /// K-weighted mean square of each `seconds`-long block (400 ms gating blocks
+    /// or 3 s short-term windows), 100 ms hop
+    fn block_powers(&self, samples: &[f32], seconds: f32) -> Vec<f32> {
+        let (mut shelf, mut highpass) = (self.shelf.clone(), self.highpass.clone());
+        let weighted: Vec<f32> = samples.iter()
+            .map(|&s| highpass.process_sample(shelf.process_sample(s)))
+            .collect();
+        
+        let block = (seconds * self.sample_rate) as usize;
+        let hop = ((0.1 * self.sample_rate) as usize).max(1);
+        if block == 0 || weighted.len() < block {
+            return Vec::new();
+        }
+        (0..frame_count(weighted.len(), block, hop))
+            .map(|i| frame_energy(frame_slice(&weighted, i, block, hop), false))
+            .collect()
+    }
+
+    /// Gated integrated loudness in LUFS (-inf when every block is below -70 LUFS
+    /// or the input is shorter than one 400 ms block)
+    #[wasm_bindgen]
+    pub fn integrated_loudness(&self, samples: &[f32]) -> f32 {
+        let powers = self.block_powers(samples, 0.4);
+        let loudness = |p: f32| -0.691 + 10.0 * p.log10();
+        let gated_mean = |threshold: f32| {
+            let kept: Vec<f32> = powers.iter().copied().filter(|&p| loudness(p) > threshold).collect();
+            if kept.is_empty() {
+                None
+            } else {
+                Some(kept.iter().sum::<f32>() / kept.len() as f32)
+            }
+        };
+        
+        // Step 1: absolute gate, Step 2: relative gate 10 LU below the result,
+        // over the blocks that passed the absolute gate only
+        match gated_mean(-70.0) {
+            Some(p) => gated_mean((loudness(p) - 10.0).max(-70.0)).map_or(f32::NEG_INFINITY, loudness),
+            None => f32::NEG_INFINITY,
+        }
+    }
+
+    /// Loudness range (LRA, EBU Tech 3342) in LU: the spread between the 10th and
+    /// 95th percentiles of the 3 s short-term loudness, after the same -70 LUFS
+    /// absolute gate as `integrated_loudness` and a relative gate 20 LU below the
+    /// absolutely gated mean. NaN when no short-term window survives gating.
+    #[wasm_bindgen]
+    pub fn loudness_range(&self, samples: &[f32]) -> f32 {
+        let loudness = |p: f32| -0.691 + 10.0 * p.log10();
+        let powers: Vec<f32> = self.block_powers(samples, 3.0)
+            .into_iter()
+            .filter(|&p| loudness(p) > -70.0)
+            .collect();
+        if powers.is_empty() {
+            return f32::NAN;
+        }
+        
+        // Step 1: relative gate 20 LU below the absolutely gated mean
+        let threshold = loudness(powers.iter().sum::<f32>() / powers.len() as f32) - 20.0;
+        let mut levels: Vec<f32> = powers.iter().map(|&p| loudness(p)).filter(|&l| l > threshold).collect();
+        levels.sort_by(|a, b| a.total_cmp(b));
+        
+        // Step 2: nearest-rank percentiles
+        let percentile = |q: f32| levels[((q * (levels.len() - 1) as f32).round() as usize).min(levels.len() - 1)];
+        percentile(0.95) - percentile(0.10)
+    }
+
+    /// True peak in dBTP (4x oversampled)
+    #[wasm_bindgen]
+    pub fn true_peak_db(&self, samples: &[f32]) -> f32 {
+        let peak = oversampled_peaks(samples).iter().fold(0.0f32, |acc, &p| acc.max(p));
+        20.0 * (peak + 1e-10).log10()
+    }
+}
+
+/// Normalize to `target_lufs` integrated loudness with a true-peak ceiling.
+/// The full gain is applied, then a look-ahead limiter (1.5 ms, min-filtered and
+/// box-smoothed gain) pulls any inter-sample peak down to `true_peak_ceiling_db`.
+/// Input with no gated loudness (silence) is returned unchanged.
+#[wasm_bindgen]
+pub fn normalize_lufs(samples: &[f32], sample_rate: f32, target_lufs: f32, true_peak_ceiling_db: f32) -> Vec<f32> {
+    let meter = LoudnessMeter::new(sample_rate);
+    let loudness = meter.integrated_loudness(samples);
+    if !loudness.is_finite() {
+        return samples.to_vec();
+    }
+    
+    // Step 1: loudness gain
+    let gain = db_to_linear(target_lufs - loudness);
+    let mut output: Vec<f32> = samples.iter().map(|&s| s * gain).collect();
+    
+    // Step 2: gain each sample needs to keep its inter-sample peak at the ceiling
+    let ceiling = db_to_linear(true_peak_ceiling_db);
+    let needed: Vec<f32> = oversampled_peaks(&output)
+        .iter()
+        .map(|&p| if p > ceiling { ceiling / p } else { 1.0 })
+        .collect();
+    if needed.iter().all(|&g| g >= 1.0) {
+        return output;
+    }
+    
+    // Step 3: look-ahead limiter. A ±2L minimum followed by a ±L average never
+    // exceeds the needed gain at any sample, while ramping smoothly around peaks.
+    let half = ((0.0015 * sample_rate) as usize).max(1);
+    let n = needed.len();
+    let min_gain: Vec<f32> = (0..n)
+        .map(|i| needed[i.saturating_sub(2 * half)..(i + 2 * half + 1).min(n)]
+            .iter()
+            .fold(1.0f32, |acc, &g| acc.min(g)))
+        .collect();
+    for (i, s) in output.iter_mut().enumerate() {
+        let window = &min_gain[i.saturating_sub(half)..(i + half + 1).min(n)];
+        *s *= window.iter().sum::<f32>() / window.len() as f32;
+    }
+    
+    // Step 4: interpolation can still overshoot by a hair; trim it
+    let peak = oversampled_peaks(&output).iter().fold(0.0f32, |acc, &p| acc.max(p));
+    if peak > ceiling {
+        let trim = ceiling / peak;
+        output.iter_mut().for_each(|s| *s *= trim);
+    }
+    
+    output
+}
+
+/// Shared normalization for a batch of files. `observe` each file in turn; only
+/// the running true peak (4x oversampled), sum of squares and sample count are
+/// kept, plus the last few samples as interpolation context, never the audio.
+/// Observed blocks are one continuous stream for the true peak, so splitting a
+/// file into blocks reads the same peak. `finalize` recommends one gain for the
+/// whole batch: the gain taking the batch RMS to the target, reduced if needed
+/// so the loudest peak in any file stays under the peak ceiling.
+#[wasm_bindgen]
+pub struct BatchNormalizer {
+    target_rms_db: f32,
+    peak_ceiling_db: f32,
+    // Streaming state
+    peak: f32,
+    sum_squares: f64,
+    count: u64,
+    // Last samples seen; the final `pending` still await right-hand context
+    context: Vec<f32>,
+    pending: usize,
+}
+
+#[wasm_bindgen]
+impl BatchNormalizer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(target_rms_db: f32) -> BatchNormalizer {
+        console_log!("🦀 [Rust DSP] Batch Normalizer: target={} dBFS", target_rms_db);
+        
+        BatchNormalizer {
+            target_rms_db,
+            peak_ceiling_db: -1.0,
+            peak: 0.0,
+            sum_squares: 0.0,
+            count: 0,
+            context: Vec::with_capacity(2 * TRUE_PEAK_TAPS),
+            pending: 0,
+        }
+    }
+
+    /// True-peak limit in dBFS the recommended gain respects (default -1)
+    #[wasm_bindgen]
+    pub fn set_peak_ceiling_db(&mut self, ceiling_db: f32) {
+        self.peak_ceiling_db = ceiling_db;
+    }
+
+    /// Accumulate one file (or a block of one)
+    #[wasm_bindgen]
+    pub fn observe(&mut self, samples: &[f32]) {
+        // Peaks of samples whose interpolation kernel is complete; keep the rest pending
+        let start = self.context.len() - self.pending;
+        self.context.extend_from_slice(samples);
+        let settled = self.context.len().saturating_sub(TRUE_PEAK_TAPS).max(start);
+        self.peak = (start..settled).map(|n| oversampled_peak(&self.context, n)).fold(self.peak, f32::max);
+        self.pending = self.context.len() - settled;
+        let drop = self.context.len().saturating_sub(2 * TRUE_PEAK_TAPS);
+        self.context.drain(..drop);
+        self.sum_squares += samples.iter().map(|&s| s as f64 * s as f64).sum::<f64>();
+        self.count += samples.len() as u64;
+    }
+
+    /// `[peak_dbfs, rms_dbfs, gain_db]` over everything observed. The gain is
+    /// `target - rms`, capped at `ceiling - peak`. All NaN before any samples;
+    /// a silent batch reports -inf levels and a NaN gain.
+    #[wasm_bindgen]
+    pub fn finalize(&self) -> Vec<f32> {
+        if self.count == 0 {
+            return vec![f32::NAN; 3];
+        }
+        let start = self.context.len() - self.pending;
+        let peak = (start..self.context.len()).map(|n| oversampled_peak(&self.context, n)).fold(self.peak, f32::max);
+        let peak_db = 20.0 * peak.log10();
+        let rms_db = (10.0 * (self.sum_squares / self.count as f64).log10()) as f32;
+        if peak <= 0.0 {
+            return vec![peak_db, rms_db, f32::NAN];
+        }
+        let gain_db = (self.target_rms_db - rms_db).min(self.peak_ceiling_db - peak_db);
+        vec![peak_db, rms_db, gain_db]
+    }
+
+    /// Forget every observed file
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.peak = 0.0;
+        self.sum_squares = 0.0;
+        self.count = 0;
+        self.context.clear();
+        self.pending = 0;
+    }
+
+    /// Fingerprint of the target and ceiling, not the accumulated statistics
+    /// (see `FftProcessor::config_hash`)
+    #[wasm_bindgen]
+    pub fn config_hash(&self) -> u64 {
+        Fnv1a::new("BatchNormalizer")
+            .f32(self.target_rms_db)
+            .f32(self.peak_ceiling_db)
+            .finish()
+    }
+}
+
+/// Half-length of the true-peak interpolation kernel: the points after x[n]
+/// draw on x[n - 7] through x[n + 8]
+const TRUE_PEAK_TAPS: usize = 8;
+
+/// Per-sample peak magnitude over 4x oversampling: |x[n]| and the three
+/// windowed-sinc interpolated points between x[n] and x[n + 1]
+fn oversampled_peaks(samples: &[f32]) -> Vec<f32> {
+    (0..samples.len()).map(|n| oversampled_peak(samples, n)).collect()
+}
+
+/// `oversampled_peaks` at one index, treating samples outside the slice as zero
+fn oversampled_peak(samples: &[f32], n: usize) -> f32 {
+    const TAPS: isize = TRUE_PEAK_TAPS as isize;
+    const PHASES: usize = 4;
+    let (n, len) = (n as isize, samples.len() as isize);
+    
+    let mut peak = samples[n as usize].abs();
+    for phase in 1..PHASES {
+        let frac = phase as f32 / PHASES as f32;
+        let mut value = 0.0f32;
+        for j in (1 - TAPS)..=TAPS {
+            let idx = n + j;
+            if idx < 0 || idx >= len {
+                continue;
+            }
+            let x = j as f32 - frac;
+            let sinc = (PI * x).sin() / (PI * x);
+            let window = 0.5 * (1.0 + (PI * x / TAPS as f32).cos());
+            value += samples[idx as usize] * sinc * window;
+        }
+        peak = peak.max(value.abs());
+    }
+    peak
+}
+
+/// Convert decibels to a linear amplitude factor
+#[inline]
+fn db_to_linear(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+/// Samples for a decaying mode `radius^n` to fall 60 dB, the settling criterion
+/// of every `warmup_samples`; `usize::MAX` for modes that never decay
+fn settle_samples(radius: f32) -> usize {
+    let radius = radius.abs() as f64;
+    if radius >= 1.0 || radius.is_nan() {
+        return usize::MAX;
+    }
+    if radius <= 1e-3 {
+        return 1;
+    }
+    (1e-3f64.ln() / radius.ln()).ceil() as usize
+}
+
+/// One-pole smoothing coefficient reaching ~63% of a step after `time_ms`
+#[inline]
+fn time_constant_coeff(time_ms: f32, sample_rate: f32) -> f32 {
+    let samples = (time_ms * 0.001 * sample_rate).max(1.0);
+    1.0 - (-1.0 / samples).exp()
+}
+
+/// Order-sensitive 64-bit FNV-1a fingerprint of a signal's sample bits, for keying
+/// result caches together with a processor's `config_hash`
+#[wasm_bindgen]
+pub fn input_hash(samples: &[f32]) -> u64 {
+    Fnv1a::new("input").usize(samples.len()).f32s(samples).finish()
+}
+
+/// 64-bit FNV-1a over little-endian parameter bytes, seeded with a type tag so
+/// processors with coinciding parameters still hash apart
+#[derive(Clone, Copy)]
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new(tag: &str) -> Fnv1a {
+        Fnv1a(0xcbf2_9ce4_8422_2325).bytes(tag.as_bytes())
+    }
+
+    fn bytes(mut self, bytes: &[u8]) -> Fnv1a {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+        self
+    }
+
+    fn u64(self, value: u64) -> Fnv1a {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn usize(self, value: usize) -> Fnv1a {
+        self.u64(value as u64)
+    }
+
+    fn bool(self, value: bool) -> Fnv1a {
+        self.bytes(&[value as u8])
+    }
+
+    fn f32(self, value: f32) -> Fnv1a {
+        self.bytes(&value.to_bits().to_le_bytes())
+    }
+
+    fn f64(self, value: f64) -> Fnv1a {
+        self.u64(value.to_bits())
+    }
+
+    fn f32s(self, values: &[f32]) -> Fnv1a {
+        values.iter().fold(self, |h, &v| h.f32(v))
+    }
+
+    fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+/// Initialize the WASM module
+#[wasm_bindgen(start)]
 pub fn init() {
     console_log!("🦀 [Rust DSP] ULTRA-OPTIMIZED Sanctuary DSP module loaded");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    /// Every (size, kind) the window cache has computed, in order
+    static WINDOW_COMPUTATIONS: Mutex<Vec<(usize, WindowKind)>> = Mutex::new(Vec::new());
+
+    pub(super) fn record_window_computation(size: usize, kind: WindowKind) {
+        WINDOW_COMPUTATIONS.lock().unwrap_or_else(|e| e.into_inner()).push((size, kind));
+    }
+
+    fn computations_of(size: usize, kind: WindowKind) -> usize {
+        let log = WINDOW_COMPUTATIONS.lock().unwrap_or_else(|e| e.into_inner());
+        log.iter().filter(|&&key| key == (size, kind)).count()
+    }
+
+    #[test]
+    fn same_size_processors_share_one_window_computation() {
+        // Sizes unused elsewhere, so the counts start at zero
+        let processors: Vec<FftProcessor> = (0..32).map(|_| FftProcessor::new(1000)).collect();
+        assert_eq!(computations_of(1000, WindowKind::Hann), 1);
+        assert!(processors.iter().all(|p| Arc::ptr_eq(&p.window, &processors[0].window)));
+        
+        // Other sizes and kinds are separate entries with their own values
+        let mut hamming = FftProcessor::new(1000);
+        hamming.set_window(WindowKind::Hamming);
+        let other_size = FftProcessor::new(1001);
+        assert_eq!(computations_of(1000, WindowKind::Hamming), 1);
+        assert_eq!(computations_of(1001, WindowKind::Hann), 1);
+        assert_eq!(computations_of(1000, WindowKind::Hann), 1);
+        assert_eq!(&hamming.window[..], &compute_window(1000, WindowKind::Hamming)[..]);
+        assert_eq!(&other_size.window[..], &compute_window(1001, WindowKind::Hann)[..]);
+    }
+
+    #[test]
+    fn window_cache_is_shared_across_threads() {
+        let windows: Vec<Arc<[f32]>> = (0..8)
+            .map(|_| thread::spawn(|| cached_window(1003, WindowKind::Blackman)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        assert_eq!(computations_of(1003, WindowKind::Blackman), 1);
+        assert!(windows.iter().all(|w| Arc::ptr_eq(w, &windows[0])));
+    }
+}
@@ -0,0 +1,68 @@
+//! Steady-state `_into` calls must not touch the heap. A counting global
+//! allocator needs its own test binary, so this file holds only that check.
+
+use sanctuary_dsp::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local! {
+    // Per thread, so tests running in parallel don't count each other's allocations
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+    
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn into_methods_do_not_allocate_in_steady_state() {
+    let x: Vec<f32> = (0..2048).map(|i| (i as f32 * 0.07).sin()).collect();
+    let mut fft = FftProcessor::new(1024);
+    let mut spectrum = vec![0.0f32; 513];
+    let vad = VoiceActivityDetector::new(512, 256);
+    let mut decisions = vec![0u8; 16];
+    let mut pitch = PitchDetector::new(16000.0, 1024);
+    let mut estimate = [0.0f32; 2];
+    let mut formants = FormantAnalyzer::new(16000.0, 12);
+    let mut found = [0.0f32; 4];
+    
+    // Warm-up call per processor, then a counted steady-state pass
+    fft.power_spectrum_into(&x, &mut spectrum);
+    pitch.detect_into(&x, &mut estimate);
+    formants.analyze_into(&x[..512], &mut found);
+    
+    let before = allocations();
+    fft.power_spectrum_into(&x, &mut spectrum);
+    fft.magnitude_db_into(&x, &mut spectrum);
+    vad.detect_into(&x, &mut decisions);
+    let written = pitch.detect_into(&x, &mut estimate);
+    formants.analyze_into(&x[..512], &mut found);
+    assert_eq!(allocations(), before);
+    assert_eq!(written, 2);
+}
+
+#[test]
+fn pitch_detect_into_truncates_to_a_short_buffer() {
+    let x: Vec<f32> = (0..1024).map(|i| (i as f32 * 0.07).sin()).collect();
+    let mut pitch = PitchDetector::new(16000.0, 1024);
+    let mut one = [0.0f32; 1];
+    assert_eq!(pitch.detect_into(&x, &mut one), 1);
+    assert_eq!(pitch.detect_into(&x, &mut []), 0);
+    assert!((one[0] - pitch.detect(&x)[0]).abs() < 1e-6);
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::*;
+use sanctuary_dsp::*;
+
+const SR: f32 = 16000.0;
+
+/// Two seconds of 4 Hz syllables: a 150 ms vowel then a 100 ms fricative
+fn syllables() -> Vec<f32> {
+    let mut x = Vec::new();
+    for k in 0..8 {
+        x.extend(vowel(SR, 120.0 + 10.0 * k as f32, &[(700.0, 80.0), (1200.0, 100.0)], 2400).iter().map(|v| v * 0.05));
+        x.extend(noise(1601, k).windows(2).map(|w| (w[1] - w[0]) * 0.05));
+    }
+    x
+}
+
+/// Two seconds of a sustained A major triad
+fn chord() -> Vec<f32> {
+    let tones = [440.0, 554.4, 659.3].map(|f| sine(SR, f, 0.2, 32000));
+    (0..32000).map(|i| tones.iter().map(|t| t[i]).sum()).collect()
+}
+
+#[test]
+fn speech_scores_high_and_music_low() {
+    let mut classifier = SpeechMusicClassifier::new(SR);
+    let (speech, music) = (classifier.speech_music_score(&syllables()), classifier.speech_music_score(&chord()));
+    assert!(speech > 0.8, "speech {}", speech);
+    assert!(music < 0.2, "music {}", music);
+    
+    // Half-second decision windows still separate them
+    classifier.set_window_ms(500.0);
+    assert!(classifier.speech_music_score(&syllables()) > 0.7);
+    assert!(classifier.speech_music_score(&chord()) < 0.3);
+    assert_eq!(classifier.speech_music_score(&[0.0; 100]), 0.5);
+}
@@ -0,0 +1,50 @@
+//! Synthetic test signals shared by the integration tests
+#![allow(dead_code)]
+
+use std::f32::consts::PI;
+
+/// Vowel-like signal: an impulse train at `f0` through a cascade of two-pole
+/// resonators, one per `(frequency, bandwidth)` formant
+pub fn vowel(sample_rate: f32, f0: f32, formants: &[(f32, f32)], len: usize) -> Vec<f32> {
+    let period = (sample_rate / f0) as usize;
+    let mut x: Vec<f32> = (0..len).map(|i| if i % period == 0 { 1.0 } else { 0.0 }).collect();
+    for &(freq, bandwidth) in formants {
+        let r = (-PI * bandwidth / sample_rate).exp();
+        let theta = 2.0 * PI * freq / sample_rate;
+        let (a1, a2) = (2.0 * r * theta.cos(), -r * r);
+        let (mut y1, mut y2) = (0.0f32, 0.0f32);
+        for v in x.iter_mut() {
+            let y = *v + a1 * y1 + a2 * y2;
+            y2 = y1;
+            y1 = y;
+            *v = y;
+        }
+    }
+    x
+}
+
+/// Uniform white noise in [-1, 1) from a 64-bit LCG, reproducible per `seed`
+pub fn noise(len: usize, seed: u64) -> Vec<f32> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 40) as f32 / (1u64 << 24) as f32) * 2.0 - 1.0
+        })
+        .collect()
+}
+
+/// Sine of `freq` Hz with peak `amplitude`
+pub fn sine(sample_rate: f32, freq: f32, amplitude: f32, len: usize) -> Vec<f32> {
+    (0..len).map(|i| amplitude * (2.0 * PI * freq * i as f32 / sample_rate).sin()).collect()
+}
+
+/// Sum of squares
+pub fn energy(x: &[f32]) -> f32 {
+    x.iter().map(|v| v * v).sum()
+}
+
+/// Root mean square level in dBFS
+pub fn rms_db(x: &[f32]) -> f32 {
+    10.0 * (energy(x) / x.len().max(1) as f32 + 1e-20).log10()
+}
@@ -0,0 +1,252 @@
+mod common;
+
+use common::*;
+use sanctuary_dsp::*;
+
+#[test]
+fn agc_converges_on_quiet_and_loud_input_and_holds_through_silence() {
+    let sr = 16000.0;
+    let mut agc = Agc::new(sr, -20.0);
+    agc.set_max_gain_db(30.0);
+
+    let quiet = agc.process(&sine(sr, 200.0, 0.01, 48000));
+    assert!((rms_db(&quiet[40000..]) + 20.0).abs() < 1.0, "quiet tail {} dB", rms_db(&quiet[40000..]));
+
+    // The gate freezes the gain over a pause instead of boosting silence
+    let gain_before = agc.gain_db();
+    let pause = agc.process(&vec![0.0; 16000]);
+    assert!(pause.iter().all(|&s| s == 0.0));
+    assert!((agc.gain_db() - gain_before).abs() < 0.1);
+
+    let loud = agc.process(&sine(sr, 200.0, 0.8, 48000));
+    assert!((rms_db(&loud[40000..]) + 20.0).abs() < 1.0, "loud tail {} dB", rms_db(&loud[40000..]));
+}
+
+fn unity_multiband(sr: f32) -> MultibandCompressor {
+    let mut compressor = MultibandCompressor::new(sr, &[500.0, 2000.0]);
+    for band in 0..3 {
+        compressor.set_band(band, -20.0, 1.0, 10.0, 100.0, 0.0);
+    }
+    compressor
+}
+
+/// Sum of `power` bins between `lo` and `hi` Hz
+fn band_power(power: &[f32], sr: f32, fft_size: usize, lo: f32, hi: f32) -> f32 {
+    let bin = |hz: f32| (hz / sr * fft_size as f32) as usize;
+    power[bin(lo)..bin(hi)].iter().sum()
+}
+
+#[test]
+fn multiband_crossover_sums_flat_at_unity_ratio() {
+    // Linkwitz-Riley bands sum to an allpass: flat magnitude impulse response
+    let mut impulse = vec![0.0f32; 4096];
+    impulse[0] = 1.0;
+    let response = unity_multiband(16000.0).process(&impulse);
+    let mut fft = FftProcessor::new(4096);
+    fft.set_window(WindowKind::Rectangular);
+    for (k, p) in fft.power_spectrum(&response).iter().enumerate().skip(1) {
+        assert!((p * 4096.0 - 1.0).abs() < 1e-3, "bin {}: {}", k, p * 4096.0);
+    }
+}
+
+#[test]
+fn compressing_one_band_leaves_the_others_alone() {
+    let sr = 16000.0;
+    let x: Vec<f32> = noise(16384, 11).iter().map(|v| v * 0.5).collect();
+    let reference = unity_multiband(sr).process(&x);
+    let mut squash_low = unity_multiband(sr);
+    squash_low.set_band(0, -60.0, 20.0, 1.0, 50.0, 0.0);
+    let squashed = squash_low.process(&x);
+
+    let mut fft = FftProcessor::new(16384);
+    let (reference, squashed) = (fft.power_spectrum(&reference), fft.power_spectrum(&squashed));
+    let ratio = |lo, hi| band_power(&squashed, sr, 16384, lo, hi) / band_power(&reference, sr, 16384, lo, hi);
+    assert!(ratio(20.0, 300.0) < 0.05, "low band {}", ratio(20.0, 300.0));
+    assert!(ratio(1000.0, 1600.0) > 0.9, "mid band {}", ratio(1000.0, 1600.0));
+    assert!((ratio(3000.0, 7000.0) - 1.0).abs() < 0.01, "high band {}", ratio(3000.0, 7000.0));
+}
+
+/// Peak of a limited 0.9 burst after 0.05 of quiet, aligned for the latency
+fn limited_burst_peak_db(lookahead_ms: f32, blocks: usize) -> f32 {
+    let sr = 48000.0;
+    let mut x = sine(sr, 200.0, 0.05, 4800);
+    x.extend(sine(sr, 200.0, 0.9, 4800));
+    let mut limiter = Compressor::new(sr);
+    limiter.set_threshold_db(-20.0);
+    limiter.set_ratio(100.0);
+    limiter.set_attack_release(0.5, 100.0);
+    limiter.set_lookahead_ms(lookahead_ms);
+    let y: Vec<f32> = x.chunks(x.len().div_ceil(blocks)).flat_map(|block| limiter.process(block)).collect();
+    let latency = limiter.latency_samples();
+    let burst = &y[4800 + latency..];
+    20.0 * burst.iter().fold(0.0f32, |acc, &s| acc.max(s.abs())).log10()
+}
+
+#[test]
+fn lookahead_catches_a_transient_the_causal_detector_lets_through() {
+    let causal = limited_burst_peak_db(0.0, 1);
+    assert!(causal > -14.0, "causal peak {} dB", causal);
+    let ahead = limited_burst_peak_db(5.0, 1);
+    assert!(ahead < -19.0, "look-ahead peak {} dB", ahead);
+
+    // The delay line carries across blocks: chunked processing matches one call
+    assert_eq!(limited_burst_peak_db(5.0, 7), ahead);
+    let mut compressor = Compressor::new(48000.0);
+    compressor.set_lookahead_ms(5.0);
+    assert_eq!(compressor.latency_samples(), 240);
+    assert_eq!(compressor.process(&[1.0; 240]), vec![0.0; 240]);
+}
+
+#[test]
+fn timestamp_gap_flags_a_discontinuity_and_resets_the_envelope() {
+    let sr = 16000.0;
+    let loud = sine(sr, 300.0, 0.9, 1600);
+    let quiet = sine(sr, 300.0, 0.05, 1600);
+    let mut fresh = Compressor::new(sr);
+    let from_reset = fresh.process(&quiet);
+    
+    let mut compressor = Compressor::new(sr);
+    compressor.process_at(&loud, 0.0);
+    assert!(!compressor.had_discontinuity());
+    let contiguous = compressor.process_at(&quiet, 1600.0);
+    assert!(!compressor.had_discontinuity());
+    assert_ne!(contiguous, from_reset);
+    
+    // Samples 4800..6400 were dropped: the flag is raised and the loud envelope forgotten
+    compressor.process_at(&loud, 3200.0);
+    assert!(!compressor.had_discontinuity());
+    let after_gap = compressor.process_at(&quiet, 6400.0);
+    assert!(compressor.had_discontinuity());
+    assert_eq!(after_gap, from_reset);
+    
+    // With resets off the gap is still reported but the state carries over
+    let mut carried = Compressor::new(sr);
+    carried.set_reset_on_discontinuity(false);
+    carried.process_at(&loud, 0.0);
+    let output = carried.process_at(&quiet, 8000.0);
+    assert!(carried.had_discontinuity());
+    assert_ne!(output, from_reset);
+}
+
+#[test]
+fn de_esser_cuts_a_sibilant_burst_and_passes_a_low_tone() {
+    let sr = 44100.0;
+    let tone = sine(sr, 200.0, 0.3, 44100);
+    let sibilant_band = |s: &[f32]| Biquad::lowpass(sr, 10000.0, 0.7).filtfilt(&Biquad::highpass(sr, 5000.0, 0.7).filtfilt(s));
+    let hiss = sibilant_band(&noise(44100, 17));
+    // Tone alone for the first half, tone plus an "s" burst in the second
+    let x: Vec<f32> = (0..44100).map(|i| tone[i] + if i >= 22050 { hiss[i] } else { 0.0 }).collect();
+    let mut de_esser = DeEsser::new(sr);
+    let y = de_esser.process(&x);
+    assert_eq!(y[..22050], x[..22050]);
+    
+    // The high band loses at least 4 dB, the 200 Hz tone under it survives within 1 dB
+    let burst = 24000..44100;
+    let high = |s: &[f32]| rms_db(&sibilant_band(s)[burst.clone()]);
+    let low = |s: &[f32]| rms_db(&Biquad::lowpass(sr, 1000.0, 0.7).filtfilt(s)[burst.clone()]);
+    assert!(high(&x) - high(&y) > 4.0, "high band {} → {} dB", high(&x), high(&y));
+    assert!((low(&x) - low(&y)).abs() < 1.0, "low band {} → {} dB", low(&x), low(&y));
+    
+    // Smooth gain: no sample jumps more than the input's own largest step plus a margin
+    let step = |s: &[f32]| s.windows(2).map(|w| (w[1] - w[0]).abs()).fold(0.0f32, f32::max);
+    assert!(step(&y) <= step(&x) * 1.1, "{} vs {}", step(&y), step(&x));
+}
+
+#[test]
+fn log_crossovers_are_geometrically_spaced() {
+    let crossovers = log_crossovers(4, 20.0, 20000.0);
+    assert_eq!(crossovers.len(), 3);
+    // Each step (band edges included) is the fourth root of 1000
+    let step = 1000f32.powf(0.25);
+    let edges = [&[20.0][..], &crossovers, &[20000.0]].concat();
+    for pair in edges.windows(2) {
+        assert!((pair[1] / pair[0] - step).abs() < 1e-3, "{:?}", edges);
+    }
+    assert!(log_crossovers(1, 20.0, 20000.0).is_empty());
+    assert!(log_crossovers(4, 2000.0, 200.0).is_empty());
+}
+
+#[test]
+fn hpss_puts_a_tone_in_the_harmonic_part_and_clicks_in_the_percussive() {
+    let n = 32768;
+    let tone = sine(16000.0, 440.0, 0.3, n);
+    let clicks: Vec<f32> = (0..n).map(|i| if i % 4000 == 2000 { 1.0 } else { 0.0 }).collect();
+    let x: Vec<f32> = tone.iter().zip(&clicks).map(|(t, c)| t + c).collect();
+    let mut hpss = Hpss::new(1024, 256);
+    let parts = hpss.separate(&x);
+    assert_eq!(parts.len(), 2 * n);
+    let (harmonic, percussive) = parts.split_at(n);
+    
+    // Project each part onto the two sources (edges skipped), as shares of each source's energy
+    let share = |part: &[f32], source: &[f32]| {
+        let inner = 2048..n - 2048;
+        let dot: f32 = part[inner.clone()].iter().zip(&source[inner.clone()]).map(|(p, s)| p * s).sum();
+        dot / energy(&source[inner])
+    };
+    assert!(share(harmonic, &tone) > 0.8, "tone in harmonic {}", share(harmonic, &tone));
+    assert!(share(percussive, &clicks) > 0.6, "clicks in percussive {}", share(percussive, &clicks));
+    assert!(share(harmonic, &clicks) < 0.4 && share(percussive, &tone) < 0.2);
+    
+    // The masks sum to one
+    let residual: Vec<f32> = (2048..n - 2048).map(|i| harmonic[i] + percussive[i] - x[i]).collect();
+    assert!(energy(&residual) < 1e-4 * energy(&x), "{}", energy(&residual));
+}
+
+#[test]
+fn primed_compressor_skips_the_attack_transient() {
+    let sr = 16000.0;
+    let mut compressor = Compressor::new(sr);
+    let causal = compressor.warmup_samples();
+    
+    // The look-ahead delay line is part of the state to warm up
+    compressor.set_lookahead_ms(5.0);
+    let warmup = compressor.warmup_samples();
+    assert_eq!(warmup, causal + compressor.latency_samples());
+    let x = sine(sr, 300.0, 0.9, warmup + 4000);
+    let (pre, body) = x.split_at(warmup);
+    let reference = compressor.process(&x)[warmup..].to_vec();
+    
+    compressor.reset();
+    let unprimed = compressor.process(body);
+    compressor.reset();
+    compressor.prime(pre);
+    let primed = compressor.process(body);
+    let max_error = |y: &[f32]| y.iter().zip(&reference).map(|(a, b)| (a - b).abs()).fold(0.0f32, f32::max);
+    assert!(max_error(&unprimed) > 0.1, "{}", max_error(&unprimed));
+    assert!(max_error(&primed) < 1e-3, "{}", max_error(&primed));
+}
+
+#[test]
+fn transient_steady_split_sums_back_and_follows_its_threshold() {
+    let n = 32768;
+    let tone = sine(16000.0, 440.0, 0.3, n);
+    let clicks: Vec<f32> = (0..n).map(|i| if i % 4000 == 2000 { 1.0 } else { 0.0 }).collect();
+    let x: Vec<f32> = tone.iter().zip(&clicks).map(|(t, c)| t + c).collect();
+    let mut hpss = Hpss::new(1024, 256);
+    let split = hpss.transient_steady_split(&x);
+    assert_eq!(split.len(), 1 + 2 * n);
+    assert_eq!(split[0], n as f32);
+    let (steady, transient) = split[1..].split_at(n);
+    
+    // Binary masks: the parts add back to the input away from the edges
+    let inner = 2048..n - 2048;
+    let residual: Vec<f32> = inner.clone().map(|i| steady[i] + transient[i] - x[i]).collect();
+    assert!(energy(&residual) < 1e-4 * energy(&x[inner.clone()]), "{}", energy(&residual));
+    
+    // The tone's energy stays steady, the clicks' goes transient
+    let share = |part: &[f32], source: &[f32]| {
+        let dot: f32 = part[inner.clone()].iter().zip(&source[inner.clone()]).map(|(p, s)| p * s).sum();
+        dot / energy(&source[inner.clone()])
+    };
+    assert!(share(steady, &tone) > 0.9, "{}", share(steady, &tone));
+    assert!(share(transient, &clicks) > 0.5, "{}", share(transient, &clicks));
+    
+    // A higher threshold keeps more of the signal steady
+    let transient_energy = |hpss: &mut Hpss| energy(&hpss.transient_steady_split(&x)[1 + n..]);
+    let default = transient_energy(&mut hpss);
+    hpss.set_transient_threshold(8.0);
+    let strict = transient_energy(&mut hpss);
+    hpss.set_transient_threshold(1.0);
+    let loose = transient_energy(&mut hpss);
+    assert!(strict < default && default < loose, "{} {} {}", strict, default, loose);
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::*;
+use sanctuary_dsp::*;
+
+fn peak_index(x: &[f32]) -> usize {
+    (0..x.len()).max_by(|&a, &b| x[a].total_cmp(&x[b])).unwrap()
+}
+
+#[test]
+fn filtfilt_does_not_shift_a_symmetric_pulse() {
+    // Gaussian pulse centred at 1000 plus a little noise, low-passed at 200 Hz
+    let x: Vec<f32> = noise(2001, 3).iter().enumerate()
+        .map(|(i, &n)| (-((i as f32 - 1000.0) / 40.0).powi(2)).exp() + 0.01 * n)
+        .collect();
+    let mut filter = Biquad::lowpass(16000.0, 200.0, std::f32::consts::FRAC_1_SQRT_2);
+    let zero_phase = filter.filtfilt(&x);
+    assert_eq!(zero_phase.len(), x.len());
+    assert_eq!(peak_index(&zero_phase), 1000);
+    
+    // A causal pass delays the peak; the zero-phase one stays symmetric about it
+    let causal = filter.process(&x);
+    assert!(peak_index(&causal) > 1005, "causal peak at {}", peak_index(&causal));
+    for k in 1..300 {
+        assert!((zero_phase[1000 - k] - zero_phase[1000 + k]).abs() < 0.01, "offset {}", k);
+    }
+    
+    // The edges start from steady state instead of ringing from zero
+    assert!(zero_phase[..50].iter().all(|v| v.abs() < 0.02));
+}
+
+/// Amplitude of the `freq` component of `x`, by correlation over whole seconds
+fn amplitude_at(x: &[f32], sample_rate: f32, freq: f32) -> f32 {
+    let w = std::f64::consts::TAU * freq as f64 / sample_rate as f64;
+    let (re, im) = x.iter().enumerate().fold((0.0f64, 0.0f64), |(re, im), (i, &v)| {
+        (re + v as f64 * (w * i as f64).cos(), im - v as f64 * (w * i as f64).sin())
+    });
+    (2.0 * (re * re + im * im).sqrt() / x.len() as f64) as f32
+}
+
+#[test]
+fn hum_remover_notches_mains_harmonics_and_keeps_nearby_tones() {
+    let sr = 16000.0;
+    let len = 3 * 16000;
+    let mix = |mains: f32| -> Vec<f32> {
+        let mut x = vec![0.0f32; len];
+        for (freq, amp) in [(mains, 0.2), (2.0 * mains, 0.1), (3.0 * mains, 0.05), (80.0, 0.2), (440.0, 0.3)] {
+            x.iter_mut().zip(sine(sr, freq, amp, len)).for_each(|(v, s)| *v += s);
+        }
+        x
+    };
+    let x = mix(60.0);
+    let mut remover = HumRemover::new(60.0, sr, 4);
+    let y = remover.process(&x);
+    // Skip the first second while the narrow notches settle
+    let tail = &y[16000..];
+    for (freq, amp) in [(60.0, 0.2), (120.0, 0.1), (180.0, 0.05)] {
+        let left = amplitude_at(tail, sr, freq);
+        assert!(left < 0.03 * amp, "{} Hz: {} of {}", freq, left, amp);
+    }
+    for (freq, amp) in [(80.0, 0.2), (440.0, 0.3)] {
+        let kept = amplitude_at(tail, sr, freq);
+        assert!((kept / amp - 1.0).abs() < 0.05, "{} Hz: {} of {}", freq, kept, amp);
+    }
+    
+    // Auto-detection tells the two mains standards apart
+    let mut auto = HumRemover::new(0.0, sr, 4);
+    assert_eq!(auto.mains_hz(), 0.0);
+    assert_eq!(auto.detect_mains(&mix(50.0)[..8000]), 50.0);
+    assert_eq!(auto.detect_mains(&x[..8000]), 60.0);
+    let mut auto = HumRemover::new(0.0, sr, 4);
+    let y = auto.process(&x);
+    assert_eq!(auto.mains_hz(), 60.0);
+    assert!(amplitude_at(&y[16000..], sr, 60.0) < 0.006);
+}
+
+#[test]
+fn primed_filters_start_at_steady_state() {
+    // Same signal split into pre-roll and body; the reference runs through both
+    let sr = 16000.0;
+    let mut filter = Biquad::lowpass(sr, 150.0, 4.0);
+    let warmup = filter.warmup_samples();
+    assert!(warmup > 100 && warmup < 16000, "{}", warmup);
+    let x = sine(sr, 150.0, 0.5, warmup + 2000);
+    let (pre, body) = x.split_at(warmup);
+    let reference = filter.process(&x)[warmup..].to_vec();
+    
+    filter.reset();
+    let unprimed = filter.process(body);
+    filter.reset();
+    filter.prime(pre);
+    let primed = filter.process(body);
+    let max_error = |y: &[f32]| y.iter().zip(&reference).map(|(a, b)| (a - b).abs()).fold(0.0f32, f32::max);
+    assert!(max_error(&unprimed) > 0.5, "{}", max_error(&unprimed));
+    assert!(max_error(&primed) < 2e-3, "{}", max_error(&primed));
+    
+    // A notch bank's warmup is the sum over its cascade
+    let hum = HumRemover::new(60.0, sr, 3);
+    let notches: usize = [60.0, 120.0, 180.0].iter().map(|&f| Biquad::notch(sr, f, 30.0).warmup_samples()).sum();
+    assert_eq!(hum.warmup_samples(), notches);
+}
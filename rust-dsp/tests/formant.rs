@@ -0,0 +1,264 @@
+mod common;
+
+use common::vowel;
+use sanctuary_dsp::*;
+
+const FOUR_FORMANTS: [(f32, f32); 4] = [(700.0, 80.0), (1200.0, 90.0), (2600.0, 150.0), (3500.0, 200.0)];
+
+fn finds(formants: &[f32], target: f32, tolerance: f32) -> bool {
+    formants.iter().any(|&f| (f - target).abs() < tolerance)
+}
+
+#[test]
+fn prominence_picking_finds_f4_the_average_heuristic_misses() {
+    let sr = 10000.0;
+    let x = vowel(sr, 120.0, &FOUR_FORMANTS, 1024);
+    let mut analyzer = FormantAnalyzer::new(sr, 12);
+    let legacy = analyzer.analyze(&x);
+    assert!(!finds(&legacy, 3500.0, 150.0), "legacy heuristic found F4: {:?}", legacy);
+    
+    analyzer.set_formant_prominence(0.002);
+    let formants = analyzer.analyze(&x);
+    assert_eq!(formants.len(), 4, "{:?}", formants);
+    for (&found, &(target, _)) in formants.iter().zip(&FOUR_FORMANTS) {
+        assert!((found - target).abs() < 100.0, "{} vs {}", found, target);
+    }
+}
+
+/// Impulse response of an AR(4) with two sharp resonances: noise-free, so the
+/// autocorrelation method recovers the coefficients up to rounding error
+fn sharp_ar4_impulse_response(len: usize) -> (Vec<f64>, Vec<f64>) {
+    let sections = [(0.98f64, 0.1f64), (0.97, 0.5)].map(|(r, theta)| [-2.0 * r * theta.cos(), r * r]);
+    let [s1, s2] = sections;
+    let coefficients = vec![
+        -(s1[0] + s2[0]),
+        -(s1[1] + s2[1] + s1[0] * s2[0]),
+        -(s1[0] * s2[1] + s1[1] * s2[0]),
+        -(s1[1] * s2[1]),
+    ];
+    let mut x = vec![0.0f64; len];
+    for n in 0..len {
+        let past: f64 = coefficients.iter().enumerate().filter(|(k, _)| n > *k).map(|(k, a)| a * x[n - k - 1]).sum();
+        x[n] = if n == 0 { 1.0 } else { 0.0 } + past;
+    }
+    (x, coefficients)
+}
+
+#[test]
+fn f64_path_recovers_ar_coefficients_more_accurately() {
+    let (x, truth) = sharp_ar4_impulse_response(2000);
+    let x32: Vec<f32> = x.iter().map(|&v| v as f32).collect();
+    let x64: Vec<f64> = x32.iter().map(|&v| v as f64).collect();
+    let c32 = FormantAnalyzer::new(16000.0, 4).lpc_coefficients(&x32);
+    let c64 = FormantAnalyzerF64::new(16000.0, 4).lpc_coefficients(&x64);
+    let error = |c: &[f64]| c.iter().zip(&truth).map(|(c, t)| (c - t).powi(2)).sum::<f64>().sqrt();
+    let e32 = error(&c32.iter().map(|&c| c as f64).collect::<Vec<_>>());
+    let e64 = error(&c64);
+    assert!(e64 < 1e-6, "f64 error {}", e64);
+    assert!(e32 > 100.0 * e64, "f32 error {} vs f64 {}", e32, e64);
+}
+
+#[test]
+fn f64_analyzer_handles_frames_no_longer_than_the_order() {
+    let mut analyzer = FormantAnalyzerF64::new(16000.0, 12);
+    for frame in [vec![], vec![0.3], vec![0.1; 10], vec![0.1; 12]] {
+        assert!(analyzer.analyze(&frame).is_empty());
+        assert!(analyzer.lpc_coefficients(&frame).iter().all(|&c| c == 0.0));
+    }
+}
+
+fn hamming(x: &[f32]) -> Vec<f32> {
+    let last = (x.len() - 1) as f32;
+    x.iter()
+        .enumerate()
+        .map(|(i, &v)| v * (0.54 - 0.46 * (2.0 * std::f32::consts::PI * i as f32 / last).cos()))
+        .collect()
+}
+
+#[test]
+fn decimating_to_10_khz_keeps_f1_f2_accurate() {
+    let sr = 48000.0;
+    let x = hamming(&vowel(sr, 120.0, &FOUR_FORMANTS, 2400));
+    let mut full = FormantAnalyzer::new(sr, 12);
+    full.set_formant_prominence(0.002);
+    let mut decimated = FormantAnalyzer::new(sr, 12);
+    decimated.set_formant_prominence(0.002);
+    decimated.set_analysis_sample_rate(10000.0);
+    
+    let error = |formants: &[f32], i: usize| (formants[i] - FOUR_FORMANTS[i].0).abs();
+    let (at_full, at_10k) = (full.analyze(&x), decimated.analyze(&x));
+    for i in 0..2 {
+        assert!(error(&at_10k, i) < 30.0, "decimated F{} = {}", i + 1, at_10k[i]);
+        assert!(error(&at_10k, i) <= error(&at_full, i), "F{}: {:?} vs {:?}", i + 1, at_10k, at_full);
+    }
+}
+
+#[test]
+fn decimation_filter_prevents_fold_back() {
+    // A strong 7 kHz resonance would alias to 3 kHz at a 10 kHz rate without the lowpass
+    let sr = 48000.0;
+    let x = hamming(&vowel(sr, 120.0, &[(700.0, 80.0), (1200.0, 90.0), (7000.0, 100.0)], 2400));
+    let mut analyzer = FormantAnalyzer::new(sr, 12);
+    analyzer.set_formant_prominence(0.002);
+    analyzer.set_analysis_sample_rate(10000.0);
+    let formants = analyzer.analyze(&x);
+    assert!(finds(&formants, 700.0, 50.0) && finds(&formants, 1200.0, 50.0), "{:?}", formants);
+    assert!(!finds(&formants, 3000.0, 300.0), "aliased peak in {:?}", formants);
+}
+
+#[test]
+fn batch_reports_the_unvoiced_sentinel_for_silent_frames() {
+    let sr = 16000.0;
+    let mut x = vowel(sr, 120.0, &FOUR_FORMANTS[..3], 4096);
+    x.extend(common::noise(4096, 9).iter().map(|v| v * 0.0001));
+    let mut analyzer = FormantAnalyzer::new(sr, 12);
+    analyzer.set_formant_prominence(0.002);
+    
+    analyzer.set_unvoiced_mode(UnvoicedMode::Nan);
+    let tracks = analyzer.analyze_batch(&x, 1024, 1024);
+    assert_eq!(tracks.len(), 8 * 4);
+    let (speech, silence) = tracks.split_at(16);
+    for frame in speech.chunks(4) {
+        assert!(finds(frame, 700.0, 100.0) && finds(frame, 1200.0, 100.0), "{:?}", frame);
+    }
+    assert!(silence.iter().all(|f| f.is_nan()), "{:?}", silence);
+    
+    analyzer.set_unvoiced_mode(UnvoicedMode::Zero);
+    assert!(analyzer.analyze_batch(&x, 1024, 1024)[16..].iter().all(|&f| f == 0.0));
+    analyzer.set_unvoiced_mode(UnvoicedMode::HoldLast);
+    let held = analyzer.analyze_batch(&x, 1024, 1024);
+    assert_eq!(held[28..32], held[12..16]);
+}
+
+#[test]
+fn frames_no_longer_than_the_order_report_no_formants() {
+    let mut analyzer = FormantAnalyzer::new(16000.0, 12);
+    analyzer.set_unvoiced_mode(UnvoicedMode::Nan);
+    for input in [vec![], vec![0.3], vec![0.1; 10], vec![0.1; 12]] {
+        assert!(analyzer.analyze(&input).is_empty());
+        let batch = analyzer.analyze_batch(&input, 1024, 512);
+        assert_eq!(batch.len(), 4);
+        assert!(batch.iter().all(|f| f.is_nan()));
+    }
+    analyzer.set_analysis_sample_rate(10000.0);
+    assert!(analyzer.analyze(&[0.3; 5]).is_empty());
+}
+
+/// Energy of the all-pole synthesis filter's impulse response in `range`
+fn synthesis_tail_energy(coeffs: &[f32], len: usize, range: std::ops::Range<usize>) -> f64 {
+    let mut y = vec![0.0f64; len];
+    for n in 0..len {
+        let excitation = if n == 0 { 1.0 } else { 0.0 };
+        y[n] = excitation + coeffs.iter().enumerate()
+            .filter(|&(k, _)| n > k)
+            .map(|(k, &a)| a as f64 * y[n - k - 1])
+            .sum::<f64>();
+    }
+    y[range].iter().map(|v| v * v).sum()
+}
+
+#[test]
+fn stabilize_pulls_poles_in_and_keeps_formant_frequencies() {
+    // 30-50 Hz bandwidths put the fitted poles within 2 % of the unit circle
+    let sr = 10000.0;
+    let x = vowel(sr, 100.0, &[(700.0, 30.0), (1800.0, 50.0)], 2048);
+    let mut analyzer = FormantAnalyzer::new(sr, 10);
+    analyzer.set_formant_prominence(0.01);
+    let ringing = analyzer.lpc_coefficients(&x);
+    let raw_formants = analyzer.analyze(&x);
+    
+    analyzer.stabilize(0.95);
+    let stable = analyzer.lpc_coefficients(&x);
+    let stable_formants = analyzer.analyze(&x);
+    
+    // Radius 0.95 decays as 0.95^n, so the second 1000 samples are silent; unclamped they still ring
+    let tail = |c: &[f32]| synthesis_tail_energy(c, 2000, 1000..2000) / synthesis_tail_energy(c, 2000, 0..1000);
+    assert!(tail(&ringing) > 1e-10, "unclamped tail {}", tail(&ringing));
+    assert!(tail(&stable) < 1e-30, "clamped tail {}", tail(&stable));
+    
+    for target in [700.0, 1800.0] {
+        assert!(finds(&raw_formants, target, 30.0), "{:?}", raw_formants);
+        assert!(finds(&stable_formants, target, 30.0), "{:?}", stable_formants);
+    }
+}
+
+#[test]
+fn response_smoothing_merges_a_split_f2() {
+    // F2 built from two resonances 150 Hz apart, which order-24 LPC resolves as two peaks
+    let sr = 16000.0;
+    let x = vowel(sr, 120.0, &[(700.0, 80.0), (1725.0, 60.0), (1875.0, 60.0), (2800.0, 150.0)], 2048);
+    let mut analyzer = FormantAnalyzer::new(sr, 24);
+    analyzer.set_formant_prominence(0.001);
+    let in_f2 = |formants: &[f32]| formants.iter().copied().filter(|f| (1650.0..1950.0).contains(f)).collect::<Vec<_>>();
+    let split = analyzer.analyze(&x);
+    assert_eq!(in_f2(&split).len(), 2, "{:?}", split);
+    
+    analyzer.set_response_smoothing(15);
+    let merged = analyzer.analyze(&x);
+    let f2 = in_f2(&merged);
+    assert_eq!(f2.len(), 1, "{:?}", merged);
+    assert!((f2[0] - 1800.0).abs() < 40.0, "{:?}", merged);
+    assert!(finds(&merged, 700.0, 40.0), "{:?}", merged);
+}
+
+/// AR(`coefficients.len()`) process driven by white noise
+fn ar_process(coefficients: &[f32], len: usize) -> Vec<f32> {
+    let excitation = common::noise(len, 101);
+    let mut x = vec![0.0f32; len];
+    for n in 0..len {
+        x[n] = 0.1 * excitation[n] + coefficients.iter().enumerate()
+            .filter(|&(k, _)| n > k)
+            .map(|(k, &a)| a * x[n - k - 1])
+            .sum::<f32>();
+    }
+    x
+}
+
+#[test]
+fn information_criteria_recover_a_known_ar_order() {
+    // Two resonances: poles 0.9·e^(±0.6i) and 0.8·e^(±1.8i), a true order of 4
+    let sections = [(0.9f32, 0.6f32), (0.8, 1.8)].map(|(r, theta)| [2.0 * r * theta.cos(), -r * r]);
+    let [s1, s2] = sections;
+    let coefficients = [
+        s1[0] + s2[0],
+        s1[1] + s2[1] - s1[0] * s2[0],
+        -(s1[0] * s2[1] + s1[1] * s2[0]),
+        -(s1[1] * s2[1]),
+    ];
+    let x = ar_process(&coefficients, 8000);
+    let mut analyzer = FormantAnalyzer::new(16000.0, 12);
+    let bic = analyzer.select_order(&x, 20, OrderCriterion::Bic);
+    let aic = analyzer.select_order(&x, 20, OrderCriterion::Aic);
+    assert_eq!(bic, 4);
+    assert!((4..=7).contains(&aic), "AIC picked {}", aic);
+    assert!(aic >= bic);
+}
+
+#[test]
+fn formant_transition_measures_the_voiced_f2_ramp() {
+    // 3 unvoiced frames, a 1200 → 1800 Hz ramp over 10 frames, a steady vowel, then a closure
+    let mut track = vec![0.0f32; 3];
+    track.extend((0..10).map(|i| 1200.0 + 60.0 * i as f32));
+    track.extend([1800.0; 12]);
+    track.extend([f32::NAN, 2500.0, 2500.0]);
+    let analyzer = FormantAnalyzer::new(16000.0, 12);
+    
+    let transition = analyzer.formant_transition(&track, 3);
+    assert_eq!(transition[0], 1200.0);
+    assert_eq!(transition[1], 1800.0);
+    assert!((transition[2] - 60.0).abs() < 1e-3, "slope {}", transition[2]);
+    assert_eq!(analyzer.formant_transition(&track, 0), vec![0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn gcis_fall_on_the_glottal_pulses() {
+    // Pulses every 128 samples (125 Hz); the first one precedes the LPC warm-up
+    let x = common::vowel(16000.0, 125.0, &[(700.0, 80.0), (1200.0, 90.0), (2600.0, 120.0)], 8000);
+    let mut analyzer = FormantAnalyzer::new(16000.0, 12);
+    let gcis = analyzer.detect_gci(&x);
+    let pulses: Vec<u32> = (128..8000).step_by(128).collect();
+    assert_eq!(gcis.len(), pulses.len(), "{:?}", gcis);
+    for (&gci, &pulse) in gcis.iter().zip(&pulses) {
+        assert!(gci.abs_diff(pulse) <= 3, "GCI {} for pulse {}", gci, pulse);
+    }
+}
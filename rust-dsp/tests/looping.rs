@@ -0,0 +1,45 @@
+mod common;
+
+use common::*;
+use sanctuary_dsp::*;
+
+#[test]
+fn loop_points_on_a_sine_span_whole_periods() {
+    for freq in [200.0, 220.0] {
+        let x = sine(16000.0, freq, 0.5, 8000);
+        let points = find_loop_points(&x, 4000, 16000.0);
+        let [start, end] = points[..] else { panic!("{:?}", points) };
+        let (start, end) = (start as usize, end as usize);
+        assert!(end - start >= 4000);
+        
+        // Both ends are upward zero crossings a whole number of periods apart
+        for i in [start, end] {
+            assert!(x[i - 1] < 0.0 && x[i] >= 0.0, "{} Hz: {} is not a rising crossing", freq, i);
+        }
+        let periods = (end - start) as f32 * freq / 16000.0;
+        assert!((periods - periods.round()).abs() * 16000.0 / freq < 1.0, "{} Hz: {} periods", freq, periods);
+    }
+    
+    assert!(find_loop_points(&sine(16000.0, 200.0, 0.5, 1000), 4000, 16000.0).is_empty());
+}
+
+#[test]
+fn snapped_index_lands_on_a_crossing_of_the_requested_slope() {
+    // 100 Hz at 16 kHz: one rising and one falling crossing every 160 samples
+    let x = sine(16000.0, 100.0, 0.5, 4000);
+    let rising = |i: usize| x[i - 1] < 0.0 && x[i] >= 0.0;
+    let falling = |i: usize| x[i - 1] >= 0.0 && x[i] < 0.0;
+    for index in [1000, 1037, 2222] {
+        let up = snap_to_zero_crossing(&x, index, 100, CrossingSlope::Rising);
+        let down = snap_to_zero_crossing(&x, index, 100, CrossingSlope::Falling);
+        let any = snap_to_zero_crossing(&x, index, 100, CrossingSlope::Any);
+        assert!(rising(up) && falling(down), "{}: {} {}", index, up, down);
+        // Nothing of the same slope is closer, and Any takes the nearer of the two
+        assert!((0..index.abs_diff(up)).all(|d| !rising(index + d) && !rising(index - d)));
+        assert_eq!(index.abs_diff(any), index.abs_diff(up).min(index.abs_diff(down)));
+    }
+    
+    // A radius under half a period can miss; then the index comes back unchanged
+    let unsnapped = (1000..1100).find(|&i| (i - 30..=i + 30).all(|j| !rising(j))).unwrap();
+    assert_eq!(snap_to_zero_crossing(&x, unsnapped, 30, CrossingSlope::Rising), unsnapped);
+}
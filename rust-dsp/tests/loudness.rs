@@ -0,0 +1,99 @@
+mod common;
+
+use common::*;
+use sanctuary_dsp::*;
+
+const SR: f32 = 48000.0;
+
+#[test]
+fn normalize_lufs_reaches_the_target_under_the_ceiling() {
+    let meter = LoudnessMeter::new(SR);
+    let quiet = sine(SR, 1000.0, 0.01, 3 * 48000);
+    assert!((meter.integrated_loudness(&quiet) + 43.0).abs() < 0.1);
+    let normalized = normalize_lufs(&quiet, SR, -16.0, -1.0);
+    assert!((meter.integrated_loudness(&normalized) + 16.0).abs() < 0.5);
+    assert!(meter.true_peak_db(&normalized) <= -1.0 + 1e-3);
+}
+
+#[test]
+fn normalize_lufs_limits_peaky_input_to_the_ceiling() {
+    let meter = LoudnessMeter::new(SR);
+    let mut peaky: Vec<f32> = noise(3 * 48000, 4).iter().map(|v| v * 0.05).collect();
+    for i in (0..peaky.len()).step_by(4800) {
+        peaky[i] = 0.9;
+    }
+    let normalized = normalize_lufs(&peaky, SR, -10.0, -1.0);
+    let loudness = meter.integrated_loudness(&normalized);
+    assert!(meter.true_peak_db(&normalized) <= -1.0 + 1e-3);
+    assert!(loudness > meter.integrated_loudness(&peaky) + 10.0 && loudness < -10.0 + 0.5, "{}", loudness);
+}
+
+#[test]
+fn relative_gate_never_readmits_blocks_below_the_absolute_gate() {
+    // 2 s at about -63 LUFS, then 18 s at about -71.5: only the loud part passes
+    // the absolute gate, and 10 LU under its mean (-73) is below -70
+    let mut x = sine(SR, 1000.0, db_amplitude(-60.0), 2 * 48000);
+    x.extend(sine(SR, 1000.0, db_amplitude(-68.5), 18 * 48000));
+    let meter = LoudnessMeter::new(SR);
+    let loud_only = meter.integrated_loudness(&x[..2 * 48000]);
+    let whole = meter.integrated_loudness(&x);
+    assert!((whole - loud_only).abs() < 0.5, "quiet blocks re-entered: {} vs {}", whole, loud_only);
+    assert_eq!(meter.integrated_loudness(&vec![0.0; 48000]), f32::NEG_INFINITY);
+}
+
+fn db_amplitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+#[test]
+fn loudness_range_spans_the_quiet_and_loud_sections() {
+    let meter = LoudnessMeter::new(SR);
+    let section = |db: f32| sine(SR, 1000.0, db_amplitude(db), 10 * 48000);
+    let steady = meter.loudness_range(&section(-20.0));
+    assert!(steady.abs() < 0.1, "{}", steady);
+    
+    // 12 dB quieter for half the programme: both sections pass the gates
+    let mut x = section(-20.0);
+    x.extend(section(-32.0));
+    let range = meter.loudness_range(&x);
+    assert!((range - 12.0).abs() < 0.5, "{} LU", range);
+    
+    // 25 dB down is under the relative gate (20 LU below the gated mean) and drops
+    // out; only windows straddling the change keep the range above 0
+    let mut gated = section(-20.0);
+    gated.extend(section(-45.0));
+    let range = meter.loudness_range(&gated);
+    assert!(range < 6.0, "{} LU", range);
+    assert!(meter.loudness_range(&vec![0.0; 4 * 48000]).is_nan());
+}
+
+#[test]
+fn batch_gain_targets_the_pooled_rms_under_the_loudest_peak() {
+    // One second each at 0.5 and 0.05 amplitude: pooled RMS sqrt((0.125 + 0.00125) / 2)
+    let loud = sine(SR, 1000.0, 0.5, 48000);
+    let quiet = sine(SR, 1000.0, 0.05, 48000);
+    let pooled_db = 10.0 * ((0.125f32 + 0.00125) / 2.0).log10();
+    let mut batch = BatchNormalizer::new(-20.0);
+    assert!(batch.finalize().iter().all(|v| v.is_nan()));
+    batch.observe(&loud);
+    batch.observe(&quiet);
+    let [peak, rms, gain] = batch.finalize()[..] else { panic!() };
+    assert!((peak + 6.02).abs() < 0.05 && (rms - pooled_db).abs() < 0.02, "{} {}", peak, rms);
+    assert!((gain - (-20.0 - pooled_db)).abs() < 0.02, "{}", gain);
+    
+    // Blocks of a file accumulate like the whole file
+    let mut blocks = BatchNormalizer::new(-20.0);
+    loud.chunks(1000).chain(quiet.chunks(777)).for_each(|c| blocks.observe(c));
+    assert_eq!(blocks.finalize().len(), 3);
+    assert!(blocks.finalize().iter().zip(batch.finalize()).all(|(a, b)| (a - b).abs() < 1e-3), "{:?} vs {:?}", blocks.finalize(), batch.finalize());
+    
+    // A loud target would clip the loud file, so the gain stops at the ceiling
+    let mut hot = BatchNormalizer::new(-3.0);
+    hot.observe(&loud);
+    hot.observe(&quiet);
+    let gain = hot.finalize()[2];
+    assert!((gain - (-1.0 - peak)).abs() < 1e-3, "{}", gain);
+    let mut silent = BatchNormalizer::new(-20.0);
+    silent.observe(&vec![0.0; 4800]);
+    assert!(silent.finalize()[2].is_nan());
+}
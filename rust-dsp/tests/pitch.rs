@@ -0,0 +1,681 @@
+mod common;
+
+use common::*;
+use sanctuary_dsp::*;
+
+fn detect_at(frame_size: usize, normalize: bool, threshold: f32, x: &[f32]) -> Vec<f32> {
+    let mut detector = PitchDetector::new(16000.0, frame_size);
+    detector.set_threshold(threshold);
+    detector.set_frame_normalization(normalize);
+    detector.detect(&x[..frame_size])
+}
+
+#[test]
+fn frame_normalization_makes_decisions_frame_size_independent() {
+    // 100 Hz: the plain difference function misses it at 1024 but not at 2048
+    let low = sine(16000.0, 100.0, 1.0, 2048);
+    assert_eq!(detect_at(1024, false, 0.1, &low)[0], 0.0);
+    assert!(detect_at(2048, false, 0.1, &low)[0] > 0.0);
+    let [short, long] = [1024, 2048].map(|size| detect_at(size, true, 0.1, &low));
+    for estimate in [&short, &long] {
+        assert!((estimate[0] - 100.0).abs() < 0.5, "{:?}", estimate);
+    }
+    assert!((short[1] - long[1]).abs() < 0.01);
+    
+    // Noisy 220 Hz at fixed SNR: normalized confidences agree across sizes
+    let noise = noise(2048, 3);
+    let noisy: Vec<f32> = sine(16000.0, 220.0, 1.0, 2048).iter().zip(&noise).map(|(s, n)| s + 0.5 * n).collect();
+    let spread = |normalize| (detect_at(1024, normalize, 0.3, &noisy)[1] - detect_at(2048, normalize, 0.3, &noisy)[1]).abs();
+    assert!(spread(true) < 0.01, "{}", spread(true));
+    assert!(spread(true) < spread(false));
+}
+
+fn sixty_hz_with_harmonic(noise_level: f32) -> Vec<f32> {
+    let sr = 16000.0;
+    let noise = noise(4096, 5);
+    sine(sr, 60.0, 1.0, 4096)
+        .iter()
+        .zip(&sine(sr, 120.0, 0.5, 4096))
+        .zip(&noise)
+        .map(|((a, b), n)| a + b + noise_level * n)
+        .collect()
+}
+
+fn detect_with_refinement(points: usize, x: &[f32]) -> f32 {
+    let mut detector = PitchDetector::new(16000.0, 4096);
+    detector.set_frame_normalization(true);
+    detector.set_threshold(0.3);
+    detector.set_lag_refinement(points);
+    detector.detect(x)[0]
+}
+
+#[test]
+fn five_point_refinement_is_more_accurate_at_60_hz() {
+    let x = sixty_hz_with_harmonic(0.05);
+    let [three, five] = [3, 5].map(|points| (detect_with_refinement(points, &x) - 60.0).abs());
+    assert!(five < three, "3-point error {} Hz, 5-point {} Hz", three, five);
+    assert!(five < 0.002);
+}
+
+#[test]
+fn wide_refinement_stays_stable_on_noisy_dips() {
+    let x = sixty_hz_with_harmonic(0.3);
+    for points in [3, 5, 9, 15] {
+        let f = detect_with_refinement(points, &x);
+        assert!((f - 60.0).abs() < 0.1, "{} points: {} Hz", points, f);
+    }
+}
+
+#[test]
+fn default_detect_refines_at_the_dip_minimum() {
+    // Refining at the first lag under the threshold read this tone as 230.2 Hz
+    let x = sine(16000.0, 220.0, 1.0, 2048);
+    let f = PitchDetector::new(16000.0, 2048).detect(&x)[0];
+    assert!((f - 220.0).abs() < 0.5, "{} Hz", f);
+}
+
+#[test]
+fn confidence_gate_removes_spurious_pitches_and_keeps_voiced_ones() {
+    // Clean 200 Hz, then the same tone buried in noise (confidence about 0.45-0.57)
+    let sr = 16000.0;
+    let mut x = sine(sr, 200.0, 0.8, 8192);
+    let buried = sine(sr, 200.0, 0.8, 8192);
+    x.extend(buried.iter().zip(noise(8192, 21)).map(|(s, n)| s + 0.8 * n));
+    let mut detector = PitchDetector::new(sr, 1024);
+    detector.set_threshold(0.7);
+    let ungated = detector.detect_batch(&x, 1024);
+    detector.set_min_confidence(0.6);
+    let gated = detector.detect_batch(&x, 1024);
+    
+    let frames: Vec<(&[f32], &[f32])> = ungated.chunks(2).zip(gated.chunks(2)).collect();
+    let (clean, noisy) = frames.split_at(8);
+    for (before, after) in clean {
+        assert_eq!(before, after, "voiced frame changed");
+        assert!((after[0] - 200.0).abs() < 1.0 && after[1] > 0.9, "{:?}", after);
+    }
+    assert!(noisy.iter().all(|(before, _)| before[0] > 0.0), "noisy frames should carry jittery pitches");
+    for (before, after) in noisy {
+        assert!(before[1] < 0.6);
+        assert_eq!(after[..], [0.0, before[1]]);
+    }
+    
+    // Single-frame detect is untouched by the gate
+    let mut reference = PitchDetector::new(sr, 1024);
+    reference.set_threshold(0.7);
+    for frame in x.chunks_exact(1024) {
+        assert_eq!(detector.detect(frame), reference.detect(frame));
+    }
+}
+
+#[test]
+fn pitch_units_convert_voiced_and_unvoiced_frames() {
+    let sr = 16000.0;
+    let tone = sine(sr, 440.0, 1.0, 2048);
+    let silence = vec![0.0f32; 2048];
+    let mut detector = PitchDetector::new(sr, 2048);
+    let hz = detector.detect(&tone)[0];
+    
+    detector.set_pitch_units(PitchUnits::Midi);
+    assert!((detector.detect(&tone)[0] - 69.0).abs() < 0.05);
+    assert_eq!(detector.detect(&silence)[0], -1.0);
+    let batch = detector.detect_batch(&[tone.clone(), silence.clone()].concat(), 2048);
+    assert!((batch[0] - 69.0).abs() < 0.05);
+    assert_eq!(batch[2], -1.0);
+    
+    // A4 is a semitone above the Ab4 reference
+    detector.set_pitch_units(PitchUnits::Cents);
+    detector.set_cents_reference(415.305);
+    assert!((detector.detect(&tone)[0] - 100.0).abs() < 5.0);
+    assert!(detector.detect(&silence)[0].is_nan());
+    
+    detector.set_pitch_units(PitchUnits::Log2Hz);
+    assert!((detector.detect(&tone)[0] - hz.log2()).abs() < 1e-5);
+    assert_eq!(detector.detect(&silence)[0], 0.0);
+    
+    detector.set_pitch_units(PitchUnits::Hz);
+    assert_eq!(detector.detect(&tone)[0], hz);
+    assert_eq!(detector.detect(&silence)[0], 0.0);
+}
+
+#[test]
+fn period_output_is_consistent_with_detect() {
+    let mut detector = PitchDetector::new(16000.0, 1024);
+    for freq in [160.0, 220.0, 333.3, 487.0] {
+        let x = sine(16000.0, freq, 0.8, 1024);
+        let [period, confidence] = detector.detect_period(&x)[..] else { panic!() };
+        let estimate = detector.detect(&x);
+        assert_eq!(16000.0 / period, estimate[0], "{} Hz", freq);
+        assert_eq!(confidence, estimate[1]);
+        assert!((period * freq / 16000.0 - 1.0).abs() < 0.006, "{} Hz: period {}", freq, period);
+    }
+    assert_eq!(detector.detect_period(&[0.0; 1024]), vec![0.0, 0.0]);
+}
+
+#[test]
+fn pyin_concentrates_on_clean_tones_and_survives_noise() {
+    let mut detector = PitchDetector::new(16000.0, 1024);
+    let clean = detector.detect_pyin(&sine(16000.0, 220.0, 0.8, 1024));
+    let total: f32 = clean.chunks(2).map(|c| c[1]).sum();
+    assert!(total <= 1.0 + 1e-5 && clean.chunks(2).all(|c| c[1] >= 0.0), "{:?}", clean);
+    // All the voiced mass on one candidate at the tone
+    assert_eq!(clean.len(), 2, "{:?}", clean);
+    assert!((clean[0] - 220.0).abs() < 1.0 && clean[1] > 0.5, "{:?}", clean);
+    
+    // Noise at -3 dB SNR: the fixed threshold rejects every frame, pYIN still ranks 220 Hz first
+    let (mut yin_hits, mut pyin_hits) = (0, 0);
+    for seed in 0..10 {
+        let x: Vec<f32> = sine(16000.0, 220.0, 1.0, 1024).iter().zip(noise(1024, seed))
+            .map(|(s, n)| s + 0.5 * n)
+            .collect();
+        yin_hits += ((detector.detect(&x)[0] - 220.0).abs() < 6.0) as usize;
+        let candidates = detector.detect_pyin(&x);
+        pyin_hits += candidates.first().is_some_and(|f| (f - 220.0).abs() < 6.0) as usize;
+    }
+    assert!(pyin_hits >= 9 && yin_hits < pyin_hits, "pYIN {} vs YIN {}", pyin_hits, yin_hits);
+    
+    // Candidates follow the pitch units like `detect`
+    detector.set_pitch_units(PitchUnits::Midi);
+    let midi = detector.detect_pyin(&sine(16000.0, 220.0, 0.8, 1024));
+    assert!((midi[0] - 57.0).abs() < 0.1 && midi[1] == clean[1], "{:?}", midi);
+}
+
+#[test]
+fn snap_to_scale_pulls_a_sharp_a_onto_440() {
+    // A minor: A B C D E F G
+    let a_minor = [1, 0, 1, 0, 1, 1, 0, 1, 0, 1, 0, 1];
+    let sharp_a = 440.0 * 2.0f32.powf(30.0 / 1200.0);
+    let hard = snap_to_scale(&[sharp_a, 0.0, f32::NAN], &a_minor, 440.0, 1.0);
+    assert!((hard[0] - 440.0).abs() < 1e-3, "{}", hard[0]);
+    assert_eq!(hard[1], 0.0);
+    assert!(hard[2].is_nan());
+    
+    // Half strength lands halfway in cents: 15 cents sharp
+    let half = snap_to_scale(&[sharp_a], &a_minor, 440.0, 0.5)[0];
+    assert!((1200.0 * (half / 440.0).log2() - 15.0).abs() < 0.01, "{}", half);
+    assert!((snap_to_scale(&[sharp_a], &a_minor, 440.0, 0.0)[0] - sharp_a).abs() < 1e-3);
+    
+    // 455 Hz rounds to A♯, which is outside the scale, so it goes to the nearer A
+    let a_sharp = snap_to_scale(&[455.0], &a_minor, 440.0, 1.0)[0];
+    assert!((a_sharp - 440.0).abs() < 1e-3, "{}", a_sharp);
+}
+
+#[test]
+fn streaming_closures_see_every_frame_once() {
+    let x: Vec<f32> = sine(16000.0, 220.0, 0.8, 8000).iter().zip(noise(8000, 2)).map(|(s, n)| s + 0.1 * n).collect();
+    let mut detector = PitchDetector::new(16000.0, 1024);
+    let batch = detector.detect_batch(&x, 256);
+    let mut streamed = Vec::new();
+    detector.detect_batch_with(&x, 256, |i, result| {
+        assert_eq!(i, streamed.len() / 2);
+        streamed.extend_from_slice(result);
+        Ok::<(), ()>(())
+    }).unwrap();
+    assert_eq!(streamed, batch);
+    
+    // The first error stops the scan
+    let mut calls = 0;
+    let stopped = detector.detect_batch_with(&x, 256, |i, _| {
+        calls += 1;
+        if i == 3 { Err(i) } else { Ok(()) }
+    });
+    assert_eq!((stopped, calls), (Err(3), 4));
+    
+    let mut fft = FftProcessor::new(512);
+    let rows = fft.spectrogram_ex(&x, 128, WindowKind::Hann, true);
+    let mut frames = 0;
+    fft.spectrogram_with(&x, 128, WindowKind::Hann, true, |t, row| {
+        assert_eq!(row, &rows[t * 257..(t + 1) * 257]);
+        frames += 1;
+        Ok::<(), ()>(())
+    }).unwrap();
+    assert_eq!(frames, rows.len() / 257);
+}
+
+/// Sum of harmonics `k·f0` with amplitude `amplitudes[k - 1]`
+fn harmonic_tone(f0: f32, amplitudes: &[f32], len: usize) -> Vec<f32> {
+    let mut x = vec![0.0f32; len];
+    for (k, &a) in amplitudes.iter().enumerate() {
+        for (v, h) in x.iter_mut().zip(sine(16000.0, f0 * (k + 1) as f32, a, len)) {
+            *v += h;
+        }
+    }
+    x
+}
+
+#[test]
+fn shs_recovers_a_weak_fundamental() {
+    // 180 Hz at 5 % of its harmonics' level
+    let x = harmonic_tone(180.0, &[0.05, 1.0, 0.8, 0.7, 0.6, 0.5], 2048);
+    let mut detector = PitchDetector::new(16000.0, 2048);
+    let shs = detector.detect_shs(&x, 6);
+    assert!((shs[0] - 180.0).abs() < 3.0 && shs[1] > 0.0, "{:?}", shs);
+    
+    // A coarser grid still lands within its own step
+    detector.set_shs_resolution(50.0);
+    let coarse = detector.detect_shs(&x, 6)[0];
+    assert!((1200.0 * (coarse / 180.0).log2()).abs() <= 25.0, "{}", coarse);
+    
+    // With no weight beyond the first harmonic it is a plain spectral peak pick
+    detector.set_shs_resolution(10.0);
+    detector.set_shs_decay(0.0);
+    let peak = detector.detect_shs(&x, 6)[0];
+    assert!((peak - 360.0).abs() < 5.0, "{}", peak);
+    
+    // In cents against the fundamental, within the grid step of 0
+    detector.set_shs_decay(0.84);
+    detector.set_cents_reference(180.0);
+    detector.set_pitch_units(PitchUnits::Cents);
+    let cents = detector.detect_shs(&x, 6)[0];
+    assert!(cents.abs() <= 10.0, "{}", cents);
+}
+
+#[test]
+fn loudness_weighting_hears_the_voice_over_a_loud_hum() {
+    // A 300 Hz tone with six harmonics under a 60 Hz hum twice as loud
+    let mut x = harmonic_tone(300.0, &[0.2; 6], 2048);
+    for (v, h) in x.iter_mut().zip(sine(16000.0, 60.0, 2.0, 2048)) {
+        *v += h;
+    }
+    let mut detector = PitchDetector::new(16000.0, 2048);
+    let plain = detector.detect_shs(&x, 6)[0];
+    assert!((plain - 60.0).abs() < 3.0, "{}", plain);
+    
+    detector.set_loudness_weighting(true);
+    let weighted = detector.detect_shs(&x, 6)[0];
+    assert!((weighted - 300.0).abs() < 5.0, "{}", weighted);
+    
+    // The weighting leaves a tone with no hum where it was
+    let clean = harmonic_tone(300.0, &[0.2; 6], 2048);
+    assert!((detector.detect_shs(&clean, 6)[0] - 300.0).abs() < 5.0);
+}
+
+#[test]
+fn multiresolution_detects_low_and_high_tones_one_frame_size_cannot() {
+    let (low, high) = (sine(16000.0, 60.0, 0.8, 2048), sine(16000.0, 2000.0, 0.8, 2048));
+    let mut detector = PitchDetector::new(16000.0, 2048);
+    detector.set_frame_normalization(true);
+    
+    // The long frame's configured range cannot reach 2 kHz
+    assert!((detector.detect(&low)[0] - 60.0).abs() < 1.0);
+    assert!((detector.detect(&high)[0] - 2000.0).abs() > 100.0, "{:?}", detector.detect(&high));
+    // and a frame short enough for 2 kHz resolution holds under one 60 Hz period
+    let mut short = PitchDetector::new(16000.0, 256);
+    assert!((short.detect(&low[..256])[0] - 60.0).abs() > 1.0);
+    
+    for (x, freq) in [(&low, 60.0), (&high, 2000.0)] {
+        let estimate = detector.detect_multiresolution(x);
+        assert!((estimate[0] / freq - 1.0).abs() < 0.01, "{} Hz: {:?}", freq, estimate);
+    }
+}
+
+#[test]
+fn detect_glides_recovers_a_ramp_and_ignores_vibrato() {
+    // 100 frames per second: 0.5 s at 220 Hz, a 1 s rise of 6 semitones/s, 0.5 s hold
+    let fps = 100.0;
+    let semitones = |i: usize| (i.clamp(50, 150) - 50) as f32 * 6.0 / fps;
+    let ramp: Vec<f32> = (0..200).map(|i| 220.0 * 2.0f32.powf(semitones(i) / 12.0)).collect();
+    let detector = PitchDetector::new(16000.0, 1024);
+    let glides = detector.detect_glides(&ramp, 160, 16000.0);
+    assert_eq!(glides.len(), 3, "{:?}", glides);
+    assert!((glides[0] - 50.0).abs() <= 10.0 && (glides[1] - 150.0).abs() <= 10.0, "{:?}", glides);
+    assert!((glides[2] - 6.0).abs() < 0.5, "{:?}", glides);
+    
+    // ±50-cent vibrato at 5.5 Hz is not a glide
+    let vibrato: Vec<f32> = (0..200)
+        .map(|i| 220.0 * 2.0f32.powf(0.5 * (2.0 * std::f32::consts::PI * 5.5 * i as f32 / fps).sin() / 12.0))
+        .collect();
+    assert!(detector.detect_glides(&vibrato, 160, 16000.0).is_empty());
+    
+    // Raising the minimum duration past the ramp's length drops it, as does the minimum rate
+    let mut detector = detector;
+    detector.set_glide_params(1500.0, 2.0);
+    assert!(detector.detect_glides(&ramp, 160, 16000.0).is_empty());
+    detector.set_glide_params(150.0, 8.0);
+    assert!(detector.detect_glides(&ramp, 160, 16000.0).is_empty());
+}
+
+#[test]
+fn period_histogram_of_a_steady_tone_is_one_sharp_peak() {
+    let mut detector = PitchDetector::new(16000.0, 1024);
+    detector.set_frequency_range(50.0, 800.0);
+    let x = [sine(16000.0, 220.0, 0.8, 16000), vec![0.0; 16000]].concat();
+    let histogram = detector.period_histogram(&x, 512, 48);
+    assert_eq!(histogram.len(), 48);
+    
+    // Silent frames are excluded, so the voiced bin alone carries all the mass
+    let peak = (0..48).max_by(|&a, &b| histogram[a].total_cmp(&histogram[b])).unwrap();
+    assert!(histogram[peak] > 0.95, "{:?}", histogram);
+    assert!((histogram.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+    
+    // Bin `peak` spans 50·16^(peak/48) Hz .. 50·16^((peak+1)/48) Hz
+    let edges = [peak, peak + 1].map(|b| 50.0 * 16f32.powf(b as f32 / 48.0));
+    assert!(edges[0] <= 221.0 && 219.0 <= edges[1], "{:?}", edges);
+    
+    assert!(detector.period_histogram(&vec![0.0; 8000], 512, 48).iter().all(|&h| h == 0.0));
+}
+
+#[test]
+fn skip_silence_only_changes_silent_frames() {
+    let x = [vec![0.0; 16000], sine(16000.0, 220.0, 0.5, 8000), vec![1e-5; 16000]].concat();
+    let mut detector = PitchDetector::new(16000.0, 1024);
+    let full = detector.detect_batch(&x, 512);
+    detector.set_skip_silence(-60.0);
+    let fast = detector.detect_batch(&x, 512);
+    assert_eq!(full.len(), fast.len());
+    
+    let mut skipped = 0;
+    for (i, (a, b)) in full.chunks(2).zip(fast.chunks(2)).enumerate() {
+        let frame = &x[i * 512..(i * 512 + 1024).min(x.len())];
+        if rms_db(frame) < -60.0 {
+            assert_eq!(b, [0.0, 0.0]);
+            skipped += 1;
+        } else {
+            assert_eq!(a, b, "frame {}", i);
+        }
+    }
+    assert!(skipped > 50);
+    
+    let mut fft = FftProcessor::new(1024);
+    let full = fft.spectrogram_ex(&x, 512, WindowKind::Hann, false);
+    fft.set_skip_silence(-60.0);
+    let fast = fft.spectrogram_ex(&x, 512, WindowKind::Hann, false);
+    for (i, (a, b)) in full.chunks(513).zip(fast.chunks(513)).enumerate() {
+        let frame = &x[i * 512..i * 512 + 1024];
+        if rms_db(frame) < -60.0 {
+            assert!(b.iter().all(|&p| p == 0.0));
+        } else {
+            assert_eq!(a, b, "frame {}", i);
+        }
+    }
+}
+
+#[test]
+fn nearest_reference_pitch_reports_the_cents_error() {
+    let references = [261.63, 293.66, 329.63, 440.0];
+    let sharp_a = 440.0 * 2.0f32.powf(12.0 / 1200.0);
+    let [reference, cents] = nearest_reference_pitch(sharp_a, &references)[..] else { panic!() };
+    assert_eq!(reference, 440.0);
+    assert!((cents - 12.0).abs() < 0.01, "{}", cents);
+    
+    // Flat of E: negative cents
+    let result = nearest_reference_pitch(320.0, &references);
+    assert_eq!(result[0], 329.63);
+    assert!((result[1] - 1200.0 * (320.0f32 / 329.63).log2()).abs() < 1e-3 && result[1] < 0.0);
+    
+    for empty in [nearest_reference_pitch(440.0, &[]), nearest_reference_pitch(0.0, &references)] {
+        assert!(empty[0] == 0.0 && empty[1].is_nan());
+    }
+}
+
+#[test]
+fn periodicity_orders_tone_mix_and_noise() {
+    let tone = sine(16000.0, 220.0, 0.5, 1024);
+    let hiss = noise(1024, 7);
+    let mix: Vec<f32> = tone.iter().zip(&hiss).map(|(t, n)| t + 0.3 * n).collect();
+    let mut detector = PitchDetector::new(16000.0, 1024);
+    let (clean, mixed, noisy) = (detector.periodicity(&tone), detector.periodicity(&mix), detector.periodicity(&hiss));
+    assert!(clean > 0.9, "tone {}", clean);
+    assert!(noisy < 0.5, "noise {}", noisy);
+    assert!(clean > mixed && mixed > noisy, "tone {}, mix {}, noise {}", clean, mixed, noisy);
+    assert_eq!(detector.periodicity(&[0.0; 1024]), 0.0);
+}
+
+#[test]
+fn reference_tracking_follows_a_slightly_sharp_a() {
+    let mut detector = PitchDetector::new(16000.0, 2048);
+    let tracked = detector.track_reference_pitch(&sine(16000.0, 443.0, 0.5, 2048), 440.0, 50.0);
+    assert!((tracked[0] - 443.0).abs() < 1.0, "{:?}", tracked);
+    assert!(tracked[1] > 0.9, "{:?}", tracked);
+    
+    // A tone a fifth away is outside ±50 cents and comes back empty or doubtful
+    let far = detector.track_reference_pitch(&sine(16000.0, 660.0, 0.5, 2048), 440.0, 50.0);
+    assert!(far[1] < 0.5, "{:?}", far);
+    
+    // The reference is in Hz, the result in the pitch units: 443 Hz is 11.8 cents sharp
+    detector.set_pitch_units(PitchUnits::Cents);
+    let cents = detector.track_reference_pitch(&sine(16000.0, 443.0, 0.5, 2048), 440.0, 50.0);
+    assert!((cents[0] - 11.8).abs() < 4.0 && cents[1] == tracked[1], "{:?}", cents);
+}
+
+#[test]
+fn detect_duet_separates_a_fifth_and_ignores_harmonics() {
+    let partials = [1.0, 0.6, 0.4, 0.3];
+    let low = harmonic_tone(220.0, &partials, 4096);
+    let high = harmonic_tone(330.0, &partials, 4096);
+    let dyad: Vec<f32> = low.iter().zip(&high).map(|(a, b)| a + 0.9 * b).collect();
+    let mut detector = PitchDetector::new(16000.0, 4096);
+    
+    let duet = detector.detect_duet(&dyad);
+    let mut pitches = [duet[0], duet[2]];
+    pitches.sort_by(f32::total_cmp);
+    assert!((pitches[0] - 220.0).abs() < 4.0 && (pitches[1] - 330.0).abs() < 5.0, "{:?}", duet);
+    assert!((duet[4] - 7.0).abs() < 0.3, "{:?}", duet);
+    
+    // One voice: its harmonics must not read as a second pitch
+    let solo = detector.detect_duet(&low);
+    assert!((solo[0] - 220.0).abs() < 4.0, "{:?}", solo);
+    assert_eq!((solo[2], solo[3]), (0.0, 0.0), "{:?}", solo);
+    assert!(solo[4].is_nan());
+    
+    // In MIDI the missing voice reads as its sentinel; the interval stays in semitones
+    detector.set_pitch_units(PitchUnits::Midi);
+    let midi = detector.detect_duet(&low);
+    assert!((midi[0] - 57.0).abs() < 0.3 && midi[2] == -1.0, "{:?}", midi);
+    let midi_dyad = detector.detect_duet(&dyad);
+    assert_eq!(midi_dyad[4], duet[4]);
+}
+
+#[test]
+fn note_names_use_scientific_octave_numbering() {
+    let mut detector = PitchDetector::new(16000.0, 1024);
+    assert_eq!(detector.note_name(440.0), "A4");
+    assert_eq!(detector.note_name(261.6), "C4");
+    assert_eq!(detector.note_name(246.9), "B3");
+    assert_eq!(detector.note_name(27.5), "A0");
+    assert_eq!(detector.note_name_batch(&[277.2, 0.0, f32::NAN]), vec!["C#4", "--", "--"]);
+    
+    // The names follow the reference pitch
+    detector.set_cents_reference(415.3);
+    assert_eq!(detector.note_name(415.3), "A4");
+}
+
+#[test]
+fn frame_size_for_fmin_resolves_that_lowest_note() {
+    let (sr, fmin) = (48000.0, 50.0);
+    let size = pitch_frame_size_for_fmin(sr, fmin);
+    assert_eq!(size, 2048);
+    assert!(size / 2 > (sr / fmin) as usize);
+    
+    let x = sine(sr, fmin, 0.5, size);
+    let mut detector = PitchDetector::new(sr, size);
+    detector.set_frame_normalization(true);
+    let pitch = detector.detect(&x)[0];
+    assert!((pitch - fmin).abs() < 0.5, "{}", pitch);
+    
+    // Half that frame cannot hold two periods
+    let mut small = PitchDetector::new(sr, size / 2);
+    small.set_frame_normalization(true);
+    assert!((small.detect(&x[..size / 2])[0] - fmin).abs() > 5.0);
+}
+
+#[test]
+fn two_unrelated_voices_score_as_overlap() {
+    let partials = [1.0, 0.7, 0.5, 0.4, 0.3];
+    let one = harmonic_tone(180.0, &partials, 16000);
+    let other = harmonic_tone(253.0, &partials, 16000);
+    let both: Vec<f32> = one.iter().zip(&other).map(|(a, b)| a + 0.8 * b).collect();
+    let mut detector = PitchDetector::new(16000.0, 2048);
+    let mean = |p: Vec<f32>| p.iter().sum::<f32>() / p.len() as f32;
+    
+    let (solo, duet) = (mean(detector.detect_overlap(&one, 1024)), mean(detector.detect_overlap(&both, 1024)));
+    let hiss = mean(detector.detect_overlap(&noise(16000, 61), 1024));
+    assert!(solo < 0.2, "single voice {}", solo);
+    assert!(hiss < 0.2, "noise {}", hiss);
+    assert!(duet > 0.5 && duet > solo + 0.3, "two voices {}", duet);
+}
+
+#[test]
+fn mpm_agrees_with_yin_and_its_clarity_threshold_resolves_a_weak_fundamental() {
+    let sr = 16000.0;
+    let mut detector = PitchDetector::new(sr, 2048);
+    let tone = sine(sr, 220.0, 0.5, 2048);
+    let (yin, mpm) = (detector.detect(&tone), detector.detect_mpm(&tone));
+    assert!((yin[0] - mpm[0]).abs() < 1.0 && (mpm[0] - 220.0).abs() < 0.5, "YIN {:?} MPM {:?}", yin, mpm);
+    assert!(mpm[1] > 0.99 && mpm[1] <= 1.0);
+    
+    // 200 Hz over a 100 Hz component at a tenth of its level: the true period is 10 ms,
+    // but the 5 ms lag nearly repeats. Default YIN reads the octave above, and no
+    // absolute threshold gets it to 100 Hz.
+    let x: Vec<f32> = sine(sr, 200.0, 0.5, 2048).iter().zip(sine(sr, 100.0, 0.05, 2048)).map(|(a, b)| a + b).collect();
+    assert!((detector.detect(&x)[0] - 200.0).abs() < 1.0);
+    for threshold in [0.05, 0.02, 0.01] {
+        detector.set_threshold(threshold);
+        assert!((detector.detect(&x)[0] - 100.0).abs() > 5.0, "threshold {}", threshold);
+    }
+    
+    // MPM picks relative to the clearest key maximum, so asking for 99 % of it skips the 5 ms one
+    detector.set_mpm_threshold(0.99);
+    let strict = detector.detect_mpm(&x);
+    assert!((strict[0] - 100.0).abs() < 0.5 && strict[1] > 0.99, "{:?}", strict);
+}
+
+#[test]
+fn cpp_drops_as_a_voice_gets_breathy() {
+    let sr = 16000.0;
+    let formants = [(700.0, 80.0), (1200.0, 100.0), (2600.0, 150.0)];
+    let voice = vowel(sr, 130.0, &formants, 2048);
+    let level = rms_db(&voice);
+    let hiss = noise(2048, 73);
+    // Aspiration noise at the voice's RMS level
+    let gain = 10f32.powf((level - rms_db(&hiss)) / 20.0);
+    let breathy: Vec<f32> = voice.iter().zip(&hiss).map(|(v, n)| v + gain * n).collect();
+    let mut detector = PitchDetector::new(sr, 2048);
+    
+    let (clear, rough, pure_noise) = (detector.cpp(&voice), detector.cpp(&breathy), detector.cpp(&hiss));
+    assert!(clear > 30.0, "clear voice {}", clear);
+    assert!(clear > rough + 10.0 && rough > pure_noise, "clear {} breathy {} noise {}", clear, rough, pure_noise);
+    assert!(detector.cpp(&[0.0; 2048]).is_nan());
+}
+
+#[test]
+fn pitch_statistics_of_a_rising_contour() {
+    // One octave up over 100 frames of 10 ms, with gaps and a low-confidence outlier
+    let frames = 101;
+    let mut track: Vec<f32> = (0..frames).map(|i| 150.0 * 2f32.powf(i as f32 / 100.0)).collect();
+    let mut confidences = vec![0.9f32; frames];
+    for i in [10, 11, 12, 50] {
+        track[i] = 0.0;
+    }
+    track[70] = 1000.0;
+    confidences[70] = 0.0;
+    let voiced: Vec<f32> = (0..frames).filter(|&i| confidences[i] > 0.0 && track[i] > 0.0).map(|i| track[i]).collect();
+    let mean = voiced.iter().sum::<f32>() / voiced.len() as f32;
+    // 96 voiced frames, so the median averages the middle two
+    let median = 0.5 * (voiced[47] + voiced[48]);
+    assert_eq!(voiced.len(), 96);
+    
+    let mut detector = PitchDetector::new(16000.0, 1024);
+    detector.set_min_confidence(0.5);
+    let stats = detector.pitch_statistics(&track, &confidences, 160);
+    assert!((stats[0] - mean).abs() < 0.01, "{:?}", stats);
+    assert!((stats[1] - median).abs() < 1e-3, "{:?}", stats);
+    assert!((stats[2] - 150.0).abs() < 1e-3 && (stats[3] - 300.0).abs() < 1e-3, "{:?}", stats);
+    assert!((stats[4] - 12.0).abs() < 1e-3, "{:?}", stats);
+    assert!((stats[5] - 12.0).abs() < 0.1, "{:?}", stats);
+    assert_eq!(stats[6], 1.0);
+    assert!(detector.pitch_statistics(&[0.0; 10], &[0.9; 10], 160).iter().all(|s| s.is_nan()));
+}
+
+#[test]
+fn shift_ratio_moves_a4_up_an_octave_and_leaves_unvoiced_alone() {
+    assert_eq!(shift_ratio_to_note(440.0, 81), 2.0);
+    assert_eq!(shift_semitones_to_note(440.0, 81), 12.0);
+    assert_eq!(shift_ratio_to_note(440.0, 69), 1.0);
+    // A flat 430 Hz onto A4, and 220 Hz (A3) down to A2
+    assert!((shift_ratio_to_note(430.0, 69) - 440.0 / 430.0).abs() < 1e-6);
+    assert!((shift_semitones_to_note(220.0, 45) + 12.0).abs() < 1e-4);
+    for unvoiced in [0.0, -1.0, f32::NAN] {
+        assert_eq!(shift_ratio_to_note(unvoiced, 81), 1.0);
+        assert_eq!(shift_semitones_to_note(unvoiced, 81), 0.0);
+    }
+}
+
+#[test]
+fn periodicity_curve_spans_the_lag_range_and_peaks_at_the_period() {
+    // 160 Hz at 16 kHz: a 100-sample period
+    let x = vowel(16000.0, 160.0, &[(700.0, 80.0), (1200.0, 90.0)], 2048);
+    let mut detector = PitchDetector::new(16000.0, 2048);
+    let curve = detector.periodicity_curve(&x);
+    // Default 50..500 Hz: lags 32 up to 320
+    assert_eq!(curve.len(), 320 - 32);
+    assert!(curve.iter().all(|v| (-1.0..=1.0).contains(v)));
+    // Multiples of the period repeat just as well; the first clear peak is the period
+    let peak = (1..curve.len() - 1).find(|&i| curve[i] > 0.9 && curve[i] >= curve[i - 1] && curve[i] >= curve[i + 1]);
+    assert_eq!(peak.map(|i| i + 32), Some(100));
+    
+    detector.set_frequency_range(80.0, 400.0);
+    assert_eq!(detector.periodicity_curve(&x).len(), 200 - 40);
+}
+
+#[test]
+fn smoothed_pitch_steadies_a_jittery_contour_and_holds_through_gaps() {
+    // Three harmonics around 200 Hz, f0 jumping ±4 % every 256 samples, with a
+    // silent gap in the middle
+    let sr = 16000.0;
+    let jitter = noise(64, 29);
+    let mut phase = 0.0f64;
+    let mut x: Vec<f32> = (0..16384)
+        .map(|i| {
+            phase += std::f64::consts::TAU * 200.0 * (1.0 + 0.04 * jitter[i / 256] as f64) / sr as f64;
+            (1..=3).map(|h| (0.3 / h as f64 * (h as f64 * phase).sin()) as f32).sum()
+        })
+        .collect();
+    x[7168..9216].fill(0.0);
+    let mut detector = PitchDetector::new(sr, 1024);
+    let raw: Vec<f32> = detector.detect_batch(&x, 256).chunks(2).map(|r| r[0]).collect();
+    let smooth = detector.smoothed_pitch(&x, 256, 8.0);
+    assert_eq!(smooth.len(), raw.len());
+    assert!(raw.contains(&0.0), "the gap is unvoiced");
+    // Frames before the first voiced one read unvoiced; after it the value holds
+    let first = raw.iter().position(|&f| f > 0.0).unwrap();
+    assert!(smooth[..first].iter().all(|&f| f == 0.0) && smooth[first..].iter().all(|&f| f > 0.0), "{:?}", smooth);
+    
+    let step_variance = |c: &[f32]| {
+        let voiced: Vec<f32> = c.iter().copied().filter(|&f| f > 0.0).collect();
+        let steps: Vec<f32> = voiced.windows(2).map(|w| w[1] - w[0]).collect();
+        steps.iter().map(|s| s * s).sum::<f32>() / steps.len() as f32
+    };
+    assert!(step_variance(&smooth) < 0.1 * step_variance(&raw), "{} vs {}", step_variance(&smooth), step_variance(&raw));
+    let mean = smooth[8..].iter().sum::<f32>() / (smooth.len() - 8) as f32;
+    assert!((mean / 200.0 - 1.0).abs() < 0.02, "{}", mean);
+    
+    // A shorter time constant follows the jitter more closely
+    let quick = detector.smoothed_pitch(&x, 256, 2.0);
+    assert!(step_variance(&quick) > 2.0 * step_variance(&smooth), "{} vs {}", step_variance(&quick), step_variance(&smooth));
+}
+
+#[test]
+fn virtual_pitch_hears_the_missing_fundamental() {
+    // Harmonics 3..8 of 200 Hz: nothing at 200 or 400 Hz
+    let sr = 16000.0;
+    let mut x = vec![0.0f32; 2048];
+    for h in 3..=8 {
+        x.iter_mut().zip(sine(sr, 200.0 * h as f32, 0.2, 2048)).for_each(|(v, s)| *v += s);
+    }
+    let mut detector = PitchDetector::new(sr, 2048);
+    let [perceived, strength] = detector.virtual_pitch(&x)[..] else { panic!() };
+    assert!((perceived - 200.0).abs() < 3.0, "{} Hz", perceived);
+    assert!(strength > 0.7, "{}", strength);
+    
+    // Noise has dense peaks that score every candidate alike
+    let hiss: Vec<f32> = noise(2048, 97).iter().map(|v| 0.3 * v).collect();
+    let noise_strength = detector.virtual_pitch(&hiss)[1];
+    assert!(noise_strength < strength - 0.3, "{} vs {}", noise_strength, strength);
+    assert_eq!(detector.virtual_pitch(&vec![0.0; 2048]), [0.0, 0.0]);
+    
+    // 200 Hz is MIDI 55.35; silence takes the MIDI sentinel
+    detector.set_pitch_units(PitchUnits::Midi);
+    let midi = detector.virtual_pitch(&x)[0];
+    assert!((midi - 55.35).abs() < 0.3, "{}", midi);
+    assert_eq!(detector.virtual_pitch(&vec![0.0; 2048]), [-1.0, 0.0]);
+}
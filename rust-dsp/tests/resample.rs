@@ -0,0 +1,146 @@
+mod common;
+
+use common::*;
+use sanctuary_dsp::*;
+
+#[test]
+fn resampled_output_reports_its_sample_rate() {
+    let x = sine(44100.0, 350.0, 1.0, 4410);
+    let resampler = Resampler::new(44100.0, 16000.0);
+    assert_eq!(resampler.output_sample_rate(), 16000.0);
+    let down = resampler.process(&x);
+    assert_eq!(down.len(), 1600);
+    // Downsampling lowers the kernel cutoff, which a 350 Hz tone passes untouched
+    let full_band = resample(&x, 44100.0, 16000.0);
+    let difference: Vec<f32> = down.iter().zip(&full_band).map(|(a, b)| a - b).collect();
+    assert!(energy(&difference[100..1500]) < 1e-4 * energy(&down[100..1500]));
+    // Upsampling keeps the full band: the same kernel as `resample`
+    let up = Resampler::new(16000.0, 44100.0);
+    assert_eq!(up.process(&down), resample(&down, 16000.0, 44100.0));
+    
+    let tagged = resample_with_rate(&x, 44100.0, 16000.0);
+    assert_eq!(tagged.sample_rate(), 16000.0);
+    assert_eq!(tagged.samples(), resample(&x, 44100.0, 16000.0));
+    
+    // A fixed output length implies the rate from the actual ratio
+    let fitted = resample_to_length(&x, 44100.0, 1000);
+    assert_eq!(fitted.samples().len(), 1000);
+    assert!((fitted.sample_rate() - 10000.0).abs() < 1e-3, "{}", fitted.sample_rate());
+}
+
+#[test]
+fn lower_cutoff_scale_reduces_aliasing() {
+    // 8.5 kHz is above the 8 kHz output Nyquist: everything that survives is alias
+    let x = sine(48000.0, 8500.0, 1.0, 48000);
+    let alias_energy = |scale: f32| {
+        let mut resampler = Resampler::new(48000.0, 16000.0);
+        resampler.set_cutoff_scale(scale);
+        let y = resampler.process(&x);
+        energy(&y[1000..y.len() - 1000])
+    };
+    let (nyquist, lowered) = (alias_energy(1.0), alias_energy(0.9));
+    assert!(lowered < 0.5 * nyquist, "scale 0.9: {}, scale 1.0: {}", lowered, nyquist);
+}
+
+#[test]
+fn cutoff_scale_is_clamped() {
+    let x = sine(48000.0, 3000.0, 1.0, 4800);
+    let output = |scale: f32| {
+        let mut resampler = Resampler::new(48000.0, 16000.0);
+        resampler.set_cutoff_scale(scale);
+        resampler.process(&x)
+    };
+    assert_eq!(output(10.0), output(1.2));
+    assert_ne!(output(1.2), output(1.0));
+    assert_eq!(output(0.0), output(0.5));
+    assert_eq!(output(-1.0), output(0.5));
+}
+
+#[test]
+fn taps_for_attenuation_grow_with_the_target_and_reach_it() {
+    let targets: Vec<usize> = (10..=90).step_by(5).map(|db| resampler_taps_for_attenuation(db as f32)).collect();
+    assert!(targets.windows(2).all(|w| w[0] <= w[1]), "{:?}", targets);
+    assert!(resampler_taps_for_attenuation(80.0) > resampler_taps_for_attenuation(40.0));
+    
+    // 12.5 kHz folds to 3.5 kHz at 16 kHz out; phase in f64 keeps the input's own noise floor low
+    let x: Vec<f32> = (0..48000)
+        .map(|i| (2.0 * std::f64::consts::PI * 12500.0 * i as f64 / 48000.0).sin() as f32)
+        .collect();
+    for db in [40.0, 60.0, 80.0] {
+        let taps = resampler_taps_for_attenuation(db);
+        let mut resampler = Resampler::new(48000.0, 16000.0);
+        resampler.set_taps(taps);
+        let y = resampler.process(&x);
+        let edge = 3 * taps + 10;
+        let achieved = rms_db(&x) - rms_db(&y[edge..y.len() - edge]);
+        assert!(achieved > db - 3.0, "{} taps: {} dB for a {} dB target", taps, achieved, db);
+    }
+}
+
+/// Sine with its phase computed in f64, so its own rounding stays far below the
+/// aliasing being measured
+fn precise_sine(sample_rate: f64, freq: f64, len: usize) -> Vec<f32> {
+    (0..len).map(|i| (std::f64::consts::TAU * freq * i as f64 / sample_rate).sin() as f32).collect()
+}
+
+/// Level in dB of a tone at `freq` over everything else in `y`: the least-squares
+/// sinusoid at `freq` is removed and the residual (images, aliases) compared
+fn tone_to_residual_db(y: &[f32], sample_rate: f64, freq: f64) -> f32 {
+    let w = std::f64::consts::TAU * freq / sample_rate;
+    let (c, s) = y.iter().enumerate().fold((0.0f64, 0.0f64), |(c, s), (i, &v)| {
+        (c + v as f64 * (w * i as f64).cos(), s + v as f64 * (w * i as f64).sin())
+    });
+    let (c, s) = (2.0 * c / y.len() as f64, 2.0 * s / y.len() as f64);
+    let residual: Vec<f32> = y.iter().enumerate()
+        .map(|(i, &v)| v - (c * (w * i as f64).cos() + s * (w * i as f64).sin()) as f32)
+        .collect();
+    rms_db(y) - rms_db(&residual)
+}
+
+#[test]
+fn make_resampler_takes_the_polyphase_path_for_44k1_to_48k() {
+    let up = make_resampler(44100.0, 48000.0, ResampleQuality::Balanced);
+    assert_eq!(up.rational_ratio(), Some((160, 147)));
+    assert_eq!(up.output_sample_rate(), 48000.0);
+    assert_eq!(make_resampler(48000.0, 44100.0, ResampleQuality::Fast).rational_ratio(), Some((147, 160)));
+    assert_eq!(make_resampler(16000.0, 48000.0, ResampleQuality::High).rational_ratio(), Some((3, 1)));
+    // No small ratio is close enough to 44.1 kHz · √2: the sinc path takes it
+    let sinc = make_resampler(44100.0, 62367.06, ResampleQuality::Balanced);
+    assert_eq!(sinc.rational_ratio(), None);
+    assert!((sinc.output_sample_rate() - 62367.06).abs() < 1e-2);
+    
+    // Images of tones across the band stay well down
+    for freq in [1000.0, 10000.0, 15000.0] {
+        let y = up.process(&precise_sine(44100.0, freq, 44100));
+        assert_eq!(y.len(), 48000);
+        let clean = tone_to_residual_db(&y[1000..47000], 48000.0, freq);
+        assert!(clean > 55.0, "{} Hz: {} dB", freq, clean);
+    }
+    
+    // Same output as the sinc resampler with the same kernel
+    let x = precise_sine(44100.0, 5000.0, 44100);
+    let mut reference = Resampler::new(44100.0, 48000.0);
+    reference.set_taps(32);
+    let (fast, slow) = (up.process(&x), reference.process(&x));
+    let difference: Vec<f32> = fast.iter().zip(&slow).map(|(a, b)| a - b).collect();
+    assert!(rms_db(&fast[1000..47000]) - rms_db(&difference[1000..47000]) > 55.0);
+    
+    // Near the band edge the wider kernel of a higher quality helps
+    let edge = precise_sine(44100.0, 18000.0, 44100);
+    let quality = |q| tone_to_residual_db(&make_resampler(44100.0, 48000.0, q).process(&edge)[1000..47000], 48000.0, 18000.0);
+    assert!(quality(ResampleQuality::High) > quality(ResampleQuality::Balanced) + 10.0);
+    assert!(quality(ResampleQuality::Balanced) > quality(ResampleQuality::Fast) + 10.0);
+}
+
+#[test]
+fn auto_resampler_wraps_the_make_resampler_choice() {
+    let auto = AutoResampler::new(44100.0, 48000.0, ResampleQuality::Balanced);
+    assert!(auto.is_polyphase());
+    assert_eq!(auto.output_sample_rate(), 48000.0);
+    let x = precise_sine(44100.0, 1000.0, 4410);
+    assert_eq!(auto.process(&x), make_resampler(44100.0, 48000.0, ResampleQuality::Balanced).process(&x));
+    
+    let sinc = AutoResampler::new(44100.0, 62367.06, ResampleQuality::Balanced);
+    assert!(!sinc.is_polyphase());
+    assert!((sinc.output_sample_rate() - 62367.06).abs() < 1e-2);
+}
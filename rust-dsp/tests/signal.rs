@@ -0,0 +1,311 @@
+mod common;
+
+use common::*;
+use sanctuary_dsp::*;
+
+#[test]
+fn dc_offset_is_detected_and_removed() {
+    let tone = sine(16000.0, 250.0, 0.5, 16000);
+    assert!(dc_offset(&tone).abs() < 1e-6);
+    
+    let mut biased: Vec<f32> = tone.iter().map(|s| s + 0.3).collect();
+    assert!((dc_offset(&biased) - 0.3).abs() < 1e-6);
+    remove_dc(&mut biased);
+    assert!(dc_offset(&biased).abs() < 1e-6);
+    for (a, b) in biased.iter().zip(&tone) {
+        assert!((a - b).abs() < 1e-6);
+    }
+    
+    assert_eq!(dc_offset(&[]), 0.0);
+}
+
+#[test]
+fn hop_and_frame_rate_round_trip() {
+    assert_eq!(hop_for_frame_rate(48000.0, 100.0), 480);
+    assert!((frame_rate(48000.0, 480) - 100.0).abs() < 1e-4);
+    assert_eq!(hop_for_frame_rate(44100.0, 86.0), 513);
+    assert!((frame_rate(44100.0, hop_for_frame_rate(44100.0, 86.0)) - 86.0).abs() < 0.2);
+    
+    // The hop never drops below one sample
+    assert_eq!(hop_for_frame_rate(16000.0, 1e6), 1);
+    assert_eq!(hop_for_frame_rate(16000.0, 0.0), 1);
+    assert_eq!(frame_rate(16000.0, 0), 16000.0);
+}
+
+#[test]
+fn even_onsets_give_constant_iois_and_quantize_to_the_grid() {
+    // Onsets every 0.5 s at 16 kHz: quarter notes at 120 bpm
+    let onsets: Vec<u32> = (0..6).map(|i| i * 8000).collect();
+    let iois = inter_onset_intervals(&onsets, 16000.0);
+    assert_eq!(iois, vec![0.5; 5]);
+    assert_eq!(quantize_iois(&iois, 120.0, 4), vec![1.0; 5]);
+    
+    // Slightly uneven gaps snap to sixteenths, or to eighths on a coarser grid
+    let swung = [0.26, 0.37, 0.13, 0.74];
+    assert_eq!(quantize_iois(&swung, 120.0, 4), vec![0.5, 0.75, 0.25, 1.5]);
+    assert_eq!(quantize_iois(&swung, 120.0, 2), vec![0.5, 0.5, 0.5, 1.5]);
+    
+    assert!(inter_onset_intervals(&[], 16000.0).is_empty());
+    assert!(inter_onset_intervals(&[1234], 16000.0).is_empty());
+}
+
+#[test]
+fn recommended_fft_size_is_a_bounded_power_of_two() {
+    assert_eq!(recommended_fft_size(1500, 64), 1024);
+    assert_eq!(recommended_fft_size(1024, 64), 1024);
+    assert_eq!(recommended_fft_size(10, 64), 64);
+    assert_eq!(recommended_fft_size(1500, 3000), 4096);
+    for len in [1, 7, 100, 513, 4095, 4096, 100_000] {
+        for min_size in [1, 32, 256] {
+            let size = recommended_fft_size(len, min_size);
+            assert!(size.is_power_of_two() && size >= min_size, "{} {} -> {}", len, min_size, size);
+            assert!(size <= len.max(min_size.next_power_of_two()), "{} {} -> {}", len, min_size, size);
+        }
+    }
+}
+
+#[test]
+fn headroom_of_a_minus_6_dbfs_peak_is_6_db() {
+    let x = sine(16000.0, 1000.0, 0.5, 1600);
+    assert!((headroom_db(&x) - 6.02).abs() < 0.01, "{}", headroom_db(&x));
+    assert_eq!(headroom_db(&[0.0; 64]), 120.0);
+    assert_eq!(headroom_db(&[]), 120.0);
+    assert!(headroom_db(&[1.0, -0.3]).abs() < 1e-6);
+    assert!(headroom_db(&[2.0]) < 0.0);
+}
+
+#[test]
+fn declicker_repairs_a_single_click_and_nothing_else() {
+    let clean: Vec<f32> = sine(16000.0, 440.0, 0.5, 8192).iter().zip(noise(8192, 41))
+        .map(|(s, n)| s + 1e-3 * n)
+        .collect();
+    let mut declicker = Declicker::new(16000.0);
+    assert_eq!(declicker.declick(&clean), clean);
+    
+    let mut clicked = clean.clone();
+    clicked[5000] += 0.8;
+    let repaired = declicker.declick(&clicked);
+    assert!((repaired[5000] - clean[5000]).abs() < 0.02, "{} vs {}", repaired[5000], clean[5000]);
+    let changed: Vec<usize> = (0..clicked.len()).filter(|&i| repaired[i] != clicked[i]).collect();
+    assert!(changed.iter().all(|&i| i.abs_diff(5000) <= 2), "{:?}", changed);
+}
+
+#[test]
+fn mono_compatibility_flags_anti_phase() {
+    let left = sine(16000.0, 300.0, 0.5, 16000);
+    let inverted: Vec<f32> = left.iter().map(|s| -s).collect();
+    let nearly_inverted: Vec<f32> = left.iter().zip(noise(16000, 51)).map(|(s, n)| -s + 0.01 * n).collect();
+    let unrelated = noise(16000, 52);
+    
+    let same = mono_compatibility(&left, &left);
+    assert!((same[0] - 1.0).abs() < 1e-5 && same[1].abs() < 1e-3, "{:?}", same);
+    
+    // Near-total cancellation in mono: correlation -1 and a big energy loss
+    let opposed = mono_compatibility(&left, &nearly_inverted);
+    assert!(opposed[0] < -0.99 && opposed[1] > 30.0, "{:?}", opposed);
+    assert_eq!(mono_compatibility(&left, &inverted)[1], 120.0);
+    
+    // Decorrelated channels lose 3 dB (powers add instead of amplitudes)
+    let decorrelated = mono_compatibility(&left, &unrelated);
+    assert!(decorrelated[0].abs() < 0.05 && (decorrelated[1] - 3.0).abs() < 0.5, "{:?}", decorrelated);
+}
+
+#[test]
+fn crest_factor_of_a_sine_and_a_click_train() {
+    let tone = crest_factor_contour(&sine(16000.0, 500.0, 0.5, 4096), 1024, 512);
+    assert!(tone.iter().all(|&c| (c - 3.01).abs() < 0.05), "{:?}", tone);
+    
+    // One click per 1024-sample frame: peak/RMS = sqrt(1024), about 30 dB
+    let clicks: Vec<f32> = (0..4096).map(|i| if i % 1024 == 100 { 1.0 } else { 0.0 }).collect();
+    let sparse = crest_factor_contour(&clicks, 1024, 1024);
+    assert!(sparse.iter().all(|&c| (c - 30.1).abs() < 0.1), "{:?}", sparse);
+    
+    assert_eq!(crest_factor_contour(&[0.0; 2048], 1024, 512), vec![0.0; 3]);
+}
+
+#[test]
+fn fft_size_for_resolution_round_trips() {
+    assert_eq!(fft_size_for_resolution(48000.0, 10.0), 8192);
+    assert!(frequency_resolution(48000.0, 8192) <= 10.0);
+    assert!(frequency_resolution(48000.0, 4096) > 10.0);
+    for (sr, target) in [(16000.0, 1.0), (44100.0, 5.0), (22050.0, 43.0), (8000.0, 8000.0)] {
+        let size = fft_size_for_resolution(sr, target);
+        assert!(size.is_power_of_two());
+        assert!(frequency_resolution(sr, size) <= target, "{} Hz at {}", target, sr);
+        assert!(size == 1 || frequency_resolution(sr, size / 2) > target);
+    }
+}
+
+#[test]
+fn beat_phase_recovers_the_offset_of_a_click_track() {
+    let (bpm, hop, sample_rate) = (120.0, 512, 44100.0);
+    let period = 60.0 * sample_rate / (bpm * hop as f32);
+    let mut onset_env = vec![0.0f32; 600];
+    for k in 0..13 {
+        onset_env[((0.3 + k as f32) * period).round() as usize] = 1.0;
+    }
+    let phase = beat_phase(&onset_env, bpm, hop, sample_rate);
+    assert!((phase - 0.3).abs() < 0.02, "phase {}", phase);
+    
+    // Predicted beats land on the clicks
+    for k in 0..13 {
+        let predicted = ((phase + k as f32) * period).round() as usize;
+        assert!(onset_env[predicted.saturating_sub(1)..=predicted + 1].contains(&1.0), "beat {}", k);
+    }
+}
+
+#[test]
+fn max_decimation_factor_keeps_the_useful_band_below_nyquist() {
+    assert_eq!(max_decimation_factor(48000.0, 4000.0), 5);
+    assert_eq!(max_decimation_factor(16000.0, 8000.0), 1);
+    assert_eq!(max_decimation_factor(16000.0, 0.0), 1);
+    for hz in [350.0f32, 1000.0, 2999.0, 3000.0, 5500.0] {
+        let factor = max_decimation_factor(48000.0, hz) as f32;
+        assert!(48000.0 / (2.0 * factor) > hz, "{} Hz: factor {}", hz, factor);
+        assert!(48000.0 / (2.0 * (factor + 1.0)) <= hz, "{} Hz: factor {} is not the largest", hz, factor);
+    }
+}
+
+/// Peak-to-peak ripple of the overlap-added window, relative to its mean
+fn ola_ripple(window: &[f32], hop: usize) -> f32 {
+    let sums: Vec<f32> = (0..hop).map(|n| window[n..].iter().step_by(hop).sum()).collect();
+    let (min, max) = sums.iter().fold((f32::INFINITY, 0.0f32), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+    (max - min) / (sums.iter().sum::<f32>() / hop as f32)
+}
+
+#[test]
+fn cola_hops_are_exactly_the_flat_overlaps() {
+    let size = 1024;
+    let hann: Vec<f32> = (0..size)
+        .map(|n| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * n as f32 / (size - 1) as f32).cos())
+        .collect();
+    let hops = cola_hops(WindowKind::Hann, size);
+    assert!(hops.contains(&512) && hops.contains(&256), "{:?}", hops);
+    assert!(!hops.contains(&1024));
+    for hop in (1..=size).filter(|h| size % h == 0) {
+        assert_eq!(hops.contains(&hop), ola_ripple(&hann, hop) < 0.01, "hop {}", hop);
+    }
+    assert!(!cola_hops(WindowKind::Blackman, size).contains(&512));
+    assert!(cola_hops(WindowKind::Rectangular, size).contains(&1024));
+}
+
+#[test]
+fn rt60_of_an_exponential_decay_picks_t30_or_t20_by_dynamic_range() {
+    // Noise decaying 60 dB in 0.5 s over a steady background at `floor`
+    let sr = 16000.0;
+    let impulse = |floor: f32| -> Vec<f32> {
+        let (tail, background) = (noise(16000, 41), noise(16000, 43));
+        (0..16000).map(|i| tail[i] * 10f32.powf(-6.0 * i as f32 / sr) + floor * background[i]).collect()
+    };
+    let clean = rt60_fit(&impulse(1e-5), sr);
+    assert!((clean[0] - 0.5).abs() < 0.025 && clean[1] == 30.0, "{:?}", clean);
+    assert_eq!(rt60(&impulse(1e-5), sr), clean[0]);
+    
+    let noisy = rt60_fit(&impulse(1e-2), sr);
+    assert!((noisy[0] - 0.5).abs() < 0.05 && noisy[1] == 20.0, "{:?}", noisy);
+    let buried = rt60_fit(&impulse(3e-2), sr);
+    assert!(buried[0].is_nan() && buried[1] == 0.0, "{:?}", buried);
+}
+
+#[test]
+fn config_and_input_hashes_key_a_cache() {
+    // Same configuration, same hash; any changed parameter moves it
+    let fft = FftProcessor::new(1024);
+    let mut windowed = FftProcessor::new(1024);
+    assert_eq!(fft.config_hash(), FftProcessor::new(1024).config_hash());
+    assert_ne!(fft.config_hash(), FftProcessor::new(2048).config_hash());
+    windowed.set_window(WindowKind::Blackman);
+    assert_ne!(fft.config_hash(), windowed.config_hash());
+    
+    let pitch = PitchDetector::new(16000.0, 1024);
+    let mut strict = PitchDetector::new(16000.0, 1024);
+    strict.set_threshold(0.05);
+    assert_ne!(pitch.config_hash(), strict.config_hash());
+    assert_ne!(pitch.config_hash(), PitchDetector::new(44100.0, 1024).config_hash());
+    
+    // The look-ahead changes a compressor's output, so it is part of the key
+    let mut lookahead = Compressor::new(48000.0);
+    lookahead.set_lookahead_ms(5.0);
+    assert_ne!(Compressor::new(48000.0).config_hash(), lookahead.config_hash());
+    
+    // Input hashes see order, length and the sign of zero
+    let x = noise(256, 59);
+    let mut swapped = x.clone();
+    swapped.swap(10, 11);
+    assert_eq!(input_hash(&x), input_hash(&x.clone()));
+    assert_ne!(input_hash(&x), input_hash(&swapped));
+    assert_ne!(input_hash(&x), input_hash(&x[..255]));
+    assert_ne!(input_hash(&[0.0]), input_hash(&[-0.0]));
+    assert_ne!(input_hash(&[]), input_hash(&[0.0]));
+}
+
+/// Two-feature trajectory `[sin, cos]` of a rising phase, sampled at `frames` points
+fn trajectory(frames: usize) -> Vec<f32> {
+    (0..frames)
+        .flat_map(|i| {
+            let t = 3.0 * (i as f32 / (frames - 1) as f32).powi(2);
+            [t.sin(), (2.0 * t).cos()]
+        })
+        .collect()
+}
+
+#[test]
+fn dtw_aligns_a_time_stretched_copy_along_the_diagonal() {
+    let (a, b) = (trajectory(100), trajectory(150));
+    let path = dtw(&a, &b, 2);
+    let pairs: Vec<(u32, u32)> = path.chunks(2).map(|p| (p[0], p[1])).collect();
+    assert_eq!(pairs.first(), Some(&(0, 0)));
+    assert_eq!(pairs.last(), Some(&(99, 149)));
+    for step in pairs.windows(2) {
+        let (di, dj) = (step[1].0 - step[0].0, step[1].1 - step[0].1);
+        assert!(di <= 1 && dj <= 1 && di + dj > 0, "{:?}", step);
+    }
+    assert!(pairs.iter().all(|&(i, j)| (j as f32 - 1.5 * i as f32).abs() <= 3.0), "{:?}", pairs);
+    
+    // A Sakoe-Chiba band around the diagonal finds the same alignment
+    let full = dtw_alignment(&a, &b, 2, DtwDistance::Euclidean, 0);
+    let banded = dtw_alignment(&a, &b, 2, DtwDistance::Euclidean, 10);
+    assert_eq!(banded.path(), path);
+    assert!((banded.cost() - full.cost()).abs() < 1e-4);
+    assert_eq!(dtw_alignment(&a, &a, 2, DtwDistance::Cosine, 5).cost(), 0.0);
+}
+
+#[test]
+fn recommended_framing_fits_a_20_ms_budget() {
+    let framing = recommend_framing(48000.0, 20.0, 200.0);
+    assert_eq!(framing, vec![512, 256]);
+    let [frame, hop] = [framing[0] as f32, framing[1] as f32];
+    assert!((frame + hop) / 48000.0 <= 0.020);
+    assert!(frame >= 2.0 * 48000.0 / 200.0);
+    
+    // A looser budget still stops at 50 % overlap; tighter ones shrink the hop
+    assert_eq!(recommend_framing(48000.0, 100.0, 200.0), vec![512, 256]);
+    assert_eq!(recommend_framing(48000.0, 12.0, 200.0), vec![512, 64]);
+    
+    // Two periods of 100 Hz alone take 20 ms: nothing fits
+    assert!(recommend_framing(48000.0, 20.0, 100.0).is_empty());
+    assert!(recommend_framing(48000.0, 20.0, 0.0).is_empty());
+}
+
+#[test]
+fn decay_rate_fits_the_tail_after_the_attack() {
+    // 440 Hz note: 50 ms linear attack, then -30 dB/s for 2 s over a faint floor
+    let sr = 16000.0;
+    let attack = 800;
+    let x: Vec<f32> = sine(sr, 440.0, 0.5, 32800).iter().zip(noise(32800, 71)).enumerate()
+        .map(|(i, (&s, n))| {
+            let gain = if i < attack { i as f32 / attack as f32 } else { 10f32.powf(-30.0 * (i - attack) as f32 / sr / 20.0) };
+            s * gain + 1e-4 * n
+        })
+        .collect();
+    let rate = decay_rate(&x, sr);
+    assert!((rate - 30.0).abs() < 1.0, "{} dB/s", rate);
+    
+    // Faster decays read faster; a steady tone has no decay to fit
+    let fast: Vec<f32> = x.iter().enumerate()
+        .map(|(i, &v)| v * 10f32.powf(-30.0 * i.saturating_sub(attack) as f32 / sr / 20.0))
+        .collect();
+    assert!((decay_rate(&fast, sr) - 60.0).abs() < 2.0, "{}", decay_rate(&fast, sr));
+    assert!(decay_rate(&sine(sr, 440.0, 0.5, 16000), sr).is_nan());
+    assert!(decay_rate(&x[..100], sr).is_nan());
+}
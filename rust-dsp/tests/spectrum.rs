@@ -0,0 +1,895 @@
+mod common;
+
+use sanctuary_dsp::*;
+
+fn loudest_bin(interleaved: &[f32]) -> usize {
+    let power: Vec<f32> = interleaved.chunks(2).map(|c| c[0] * c[0] + c[1] * c[1]).collect();
+    (0..power.len()).max_by(|&a, &b| power[a].total_cmp(&power[b])).unwrap()
+}
+
+#[test]
+fn fftshift_centres_dc_and_ifftshift_inverts_it() {
+    let even: Vec<f32> = (0..8).map(|i| i as f32).collect();
+    assert_eq!(fftshift(&even), vec![4.0, 5.0, 6.0, 7.0, 0.0, 1.0, 2.0, 3.0]);
+    assert_eq!(fftshift(&fftshift(&even)), even);
+    assert_eq!(ifftshift(&fftshift(&even)), even);
+    
+    // Odd lengths: DC lands at n / 2 and only ifftshift undoes the shift
+    let odd: Vec<f32> = (0..5).map(|i| i as f32).collect();
+    assert_eq!(fftshift(&odd), vec![3.0, 4.0, 0.0, 1.0, 2.0]);
+    assert_eq!(ifftshift(&fftshift(&odd)), odd);
+}
+
+#[test]
+fn shifted_complex_spectrum_puts_dc_in_the_centre() {
+    let constant = vec![1.0f32; 64];
+    let mut fft = FftProcessor::new(64);
+    assert_eq!(loudest_bin(&fft.complex_spectrum(&constant)), 0);
+    fft.set_fftshift(true);
+    assert_eq!(loudest_bin(&fft.complex_spectrum(&constant)), 32);
+}
+
+#[test]
+fn welch_psd_integrates_to_the_signal_variance() {
+    let x: Vec<f32> = common::noise(65536, 11).iter().map(|v| v * 0.5).collect();
+    let variance = common::energy(&x) / x.len() as f32;
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(16000.0);
+    let psd = fft.welch_psd(&x, 512);
+    assert_eq!(psd.len(), 513);
+    let integral = psd.iter().sum::<f32>() * 16000.0 / 1024.0;
+    assert!((integral / variance - 1.0).abs() < 0.02, "{} vs {}", integral, variance);
+}
+
+#[test]
+fn processors_sharing_a_planner_produce_independent_results() {
+    let planner = SharedPlanner::new();
+    let x = common::sine(16000.0, 440.0, 1.0, 1024);
+    let y = common::noise(1024, 4);
+    let mut a = FftProcessor::with_planner(1024, &planner);
+    let mut b = FftProcessor::with_planner(1024, &planner);
+    let mut small = FftProcessor::with_planner(256, &planner);
+    let mut pitch = PitchDetector::with_planner(16000.0, 1024, &planner);
+    
+    // Interleaved calls must match processors with private planners
+    for _ in 0..2 {
+        assert_eq!(a.power_spectrum(&x), FftProcessor::new(1024).power_spectrum(&x));
+        assert_eq!(b.power_spectrum(&y), FftProcessor::new(1024).power_spectrum(&y));
+        assert_eq!(small.power_spectrum(&y[..256]), FftProcessor::new(256).power_spectrum(&y[..256]));
+        assert_eq!(pitch.detect(&x), PitchDetector::new(16000.0, 1024).detect(&x));
+    }
+}
+
+#[test]
+fn tonality_mask_marks_the_tone_bin_and_not_the_noise() {
+    // Tone exactly on bin 100 of a 1024-point FFT, over white noise
+    let noise = common::noise(1024, 5);
+    let x: Vec<f32> = common::sine(1024.0, 100.0, 1.0, 1024).iter().zip(&noise).map(|(s, n)| s + 0.1 * n).collect();
+    for neighborhood in [4, 8, 16] {
+        let mut fft = FftProcessor::new(1024);
+        fft.set_tonality_neighborhood(neighborhood);
+        let mask = fft.tonality_mask(&x);
+        assert_eq!(mask.len(), 513);
+        assert!(mask.iter().all(|m| (0.0..=1.0).contains(m)));
+        assert!(mask[100] > 0.9, "tone bin {}", mask[100]);
+        let away: Vec<f32> = mask.iter().enumerate().filter(|(k, _)| k.abs_diff(100) > 3).map(|(_, &m)| m).collect();
+        let mean = away.iter().sum::<f32>() / away.len() as f32;
+        assert!(mean < 0.2, "neighborhood {}: mean elsewhere {}", neighborhood, mean);
+    }
+}
+
+#[test]
+fn auto_scaled_spectrum_matches_plain_up_to_the_reported_scale() {
+    let x: Vec<f32> = common::sine(16000.0, 440.0, 0.9, 1024).iter().zip(common::noise(1024, 5))
+        .map(|(s, n)| s + 0.09 * n)
+        .collect();
+    let mut plain = FftProcessor::new(1024);
+    let reference = plain.power_spectrum(&x);
+    
+    let mut scaled = FftProcessor::new(1024);
+    scaled.set_auto_scale(true);
+    scaled.set_report_scaled(true);
+    let normalized = scaled.power_spectrum(&x);
+    let scale = scaled.input_scale();
+    assert!((scale - 1.0 / x.iter().fold(0.0f32, |m, v| m.max(v.abs()))).abs() < 1e-6);
+    
+    // Same shape relative to the peak bin, and dividing by scale² recovers the level
+    let peak = |p: &[f32]| p.iter().fold(0.0f32, |m, &v| m.max(v));
+    let (peak_ref, peak_norm) = (peak(&reference), peak(&normalized));
+    for (k, (r, n)) in reference.iter().zip(&normalized).enumerate() {
+        assert!((r / peak_ref - n / peak_norm).abs() < 1e-5, "bin {}", k);
+        assert!((n / (scale * scale) - r).abs() <= 1e-4 * peak_ref, "bin {}", k);
+    }
+    
+    // Unscaled reporting (the default) maps back to the input's level itself
+    scaled.set_report_scaled(false);
+    for (r, u) in reference.iter().zip(scaled.power_spectrum(&x)) {
+        assert!((u - r).abs() <= 1e-4 * peak_ref);
+    }
+}
+
+#[test]
+fn sliding_dft_tracks_a_full_fft_of_the_window() {
+    let size = 256;
+    let x: Vec<f32> = common::sine(16000.0, 1000.0, 0.7, 40 * size).iter().zip(common::noise(40 * size, 8))
+        .map(|(s, n)| s + 0.2 * n)
+        .collect();
+    let mut sliding = SlidingDft::new(size);
+    let mut fft = FftProcessor::new(size);
+    fft.set_window(WindowKind::Rectangular);
+    
+    // Check part-way through a refresh interval and after several refreshes
+    let mut pushed = 0;
+    for target in [3 * size + 37, 40 * size - 5] {
+        x[pushed..target].iter().for_each(|&s| sliding.push(s));
+        pushed = target;
+        let exact = fft.complex_spectrum(&x[target - size..target]);
+        let incremental = sliding.spectrum();
+        let peak = exact.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+        for (k, (a, b)) in incremental.iter().zip(&exact).enumerate() {
+            assert!((a - b).abs() < 1e-4 * peak, "after {} pushes, value {}: {} vs {}", target, k, a, b);
+        }
+    }
+}
+
+#[test]
+fn centered_spectrogram_frames_follow_the_center_convention() {
+    let x = common::noise(4000, 12);
+    let (size, hop, n_bins) = (512, 128, 257);
+    let mut fft = FftProcessor::new(size);
+    let centered = fft.spectrogram_ex(&x, hop, WindowKind::Hann, true);
+    assert_eq!(centered.len(), (4000 / hop + 1) * n_bins);
+    assert_eq!(fft.spectrogram_ex(&x, hop, WindowKind::Hann, false).len(), ((4000 - size) / hop + 1) * n_bins);
+    
+    // Frame 10 is centred on sample 1280; frame 0 straddles the zero padding
+    let frame = |t: usize| &centered[t * n_bins..(t + 1) * n_bins];
+    assert_eq!(frame(10), fft.power_spectrum(&x[1280 - 256..1280 + 256]).as_slice());
+    let padded = [vec![0.0; 256], x[..256].to_vec()].concat();
+    assert_eq!(frame(0), fft.power_spectrum(&padded).as_slice());
+    
+    // The window applies to that call only: the processor's Hann is back afterwards
+    let blackman = fft.spectrogram_ex(&x, hop, WindowKind::Blackman, true);
+    assert_ne!(&blackman[10 * n_bins..11 * n_bins], frame(10));
+    assert_eq!(fft.power_spectrum(&x[1024..1536]).as_slice(), frame(10));
+}
+
+#[test]
+fn am_envelope_oscillates_at_the_modulation_rate() {
+    let sr = 16000.0;
+    let modulator = |i: usize| 1.0 + 0.5 * (2.0 * std::f32::consts::PI * 4.0 * i as f32 / sr).sin();
+    let x: Vec<f32> = common::sine(sr, 1000.0, 0.5, 32000).iter().enumerate().map(|(i, c)| c * modulator(i)).collect();
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(sr);
+    let envelope = fft.am_envelope(&x, 20.0);
+    assert_eq!(envelope.len(), x.len());
+    
+    // Away from the edges the envelope is the modulator itself
+    for i in (2000..30000).step_by(97) {
+        assert!((envelope[i] - 0.5 * modulator(i)).abs() < 0.01, "sample {}: {}", i, envelope[i]);
+    }
+    let modulation = dominant_modulation(&envelope, sr);
+    assert!((modulation[0] - 4.0).abs() < 0.05, "{:?}", modulation);
+}
+
+#[test]
+fn distortion_score_separates_clean_and_clipped_tones() {
+    let clean = common::sine(16000.0, 440.0, 0.9, 8192);
+    let clipped: Vec<f32> = common::sine(16000.0, 440.0, 2.0, 8192).iter().map(|s| s.clamp(-0.9, 0.9)).collect();
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(16000.0);
+    let (clean_score, clipped_score) = (fft.distortion_score(&clean), fft.distortion_score(&clipped));
+    assert!(clean_score < 0.05, "clean {}", clean_score);
+    assert!(clipped_score > 0.5, "clipped {}", clipped_score);
+    assert_eq!(fft.distortion_score(&[0.0; 1024]), 0.0);
+}
+
+/// Pink noise from white by Paul Kellet's economy filter (±0.5 dB above 40 Hz at 44.1 kHz)
+fn pink_noise(len: usize, seed: u64) -> Vec<f32> {
+    let (mut b0, mut b1, mut b2) = (0.0f32, 0.0f32, 0.0f32);
+    common::noise(len, seed).iter()
+        .map(|&w| {
+            b0 = 0.99765 * b0 + w * 0.0990460;
+            b1 = 0.96300 * b1 + w * 0.2965164;
+            b2 = 0.57000 * b2 + w * 1.0526913;
+            (b0 + b1 + b2 + w * 0.1848) * 0.1
+        })
+        .collect()
+}
+
+#[test]
+fn noise_color_reads_white_pink_and_brown_slopes() {
+    let sr = 44100.0;
+    let white: Vec<f32> = common::noise(1 << 17, 21).iter().map(|v| v * 0.3).collect();
+    let pink = pink_noise(1 << 17, 22);
+    let mut level = 0.0f32;
+    let brown: Vec<f32> = common::noise(1 << 17, 23).iter()
+        .map(|&w| {
+            level = 0.999 * level + 0.02 * w;
+            level
+        })
+        .collect();
+    
+    let mut fft = FftProcessor::new(2048);
+    fft.set_sample_rate(sr);
+    for (x, slope, index) in [(white, 0.0, 0.0), (pink, -3.0, 1.0), (brown, -6.0, 2.0)] {
+        let color = fft.noise_color(&x);
+        assert!((color[0] - slope).abs() < 0.5, "{:?} for {} dB/oct", color, slope);
+        assert_eq!(color[1], index);
+    }
+}
+
+#[test]
+fn top_peaks_recover_three_sines() {
+    let sr = 16000.0;
+    let partials = [(1234.5, 0.2), (440.0, 0.8), (3001.7, 0.4)];
+    let x: Vec<f32> = (0..2048)
+        .map(|i| partials.iter().map(|&(f, a)| a * (2.0 * std::f32::consts::PI * f * i as f32 / sr).sin()).sum())
+        .collect();
+    let mut fft = FftProcessor::new(2048);
+    fft.set_sample_rate(sr);
+    let peaks = fft.top_peaks(&x, 3);
+    assert_eq!(peaks.len(), 9);
+    
+    // Strongest first, frequencies within a tenth of a bin, amplitudes within 2 %
+    for (peak, &(freq, amplitude)) in peaks.chunks(3).zip(&[partials[1], partials[2], partials[0]]) {
+        assert!((peak[0] - freq).abs() < 0.8, "{:?} vs {} Hz", peak, freq);
+        assert!((peak[1] / amplitude - 1.0).abs() < 0.02, "{:?} vs {}", peak, amplitude);
+        assert!(peak[2].abs() <= std::f32::consts::PI);
+    }
+}
+
+#[test]
+fn log_spectral_distance_grows_with_the_filter_effect() {
+    let x: Vec<f32> = common::noise(8192, 31).iter().map(|v| v * 0.5).collect();
+    let mut fft = FftProcessor::new(512);
+    assert!(fft.log_spectral_distance(&x, &x).abs() < 1e-6);
+    
+    // A broadband gain shifts every bin equally: 6.02 dB per halving
+    let scaled = |g: f32| x.iter().map(|v| v * g).collect::<Vec<_>>();
+    assert!((fft.log_spectral_distance(&x, &scaled(0.5)) - 6.02).abs() < 0.01);
+    assert!((fft.log_spectral_distance(&x, &scaled(0.25)) - 12.04).abs() < 0.02);
+    
+    // Lower low-pass cutoffs remove more of the spectrum
+    let mut distance = |cutoff: f32| {
+        let filtered = Biquad::lowpass(16000.0, cutoff, 0.7).filtfilt(&x);
+        fft.log_spectral_distance(&x, &filtered)
+    };
+    let (mild, strong) = (distance(4000.0), distance(1000.0));
+    assert!(mild > 1.0 && strong > 1.5 * mild, "mild {} strong {}", mild, strong);
+}
+
+#[test]
+fn phase_refinement_beats_the_nearest_bin() {
+    let (sr, size, hop) = (16000.0, 1024, 256);
+    let mut fft = FftProcessor::new(size);
+    fft.set_sample_rate(sr);
+    let bin_hz = sr / size as f32;
+    for freq in [440.0, 1003.3, 2718.0] {
+        let x = common::sine(sr, freq, 0.7, size + hop);
+        let refined = fft.refine_pitch_phase(&x[..size], &x[hop..], freq + 5.0, hop);
+        let nearest_bin = (freq / bin_hz).round() * bin_hz;
+        assert!((refined - freq).abs() < 0.05, "{} Hz: {}", freq, refined);
+        assert!((refined - freq).abs() < (nearest_bin - freq).abs());
+    }
+}
+
+#[test]
+fn dominant_modulation_finds_a_6_hz_tremolo() {
+    // Envelope sampled at 100 Hz for 4 s
+    let rate = 100.0;
+    let tremolo: Vec<f32> = (0..400).map(|i| 1.0 + 0.3 * (2.0 * std::f32::consts::PI * 6.0 * i as f32 / rate).sin()).collect();
+    let [freq, depth, regularity] = dominant_modulation(&tremolo, rate)[..] else { panic!() };
+    assert!((freq - 6.0).abs() < 0.05, "{}", freq);
+    assert!((depth - 0.3).abs() < 0.03, "{}", depth);
+    assert!(regularity > 0.9, "{}", regularity);
+    
+    // Random fluctuation spreads over the band: low regularity
+    let random: Vec<f32> = common::noise(400, 61).iter().map(|n| 1.0 + 0.3 * n).collect();
+    assert!(dominant_modulation(&random, rate)[2] < 0.3, "{:?}", dominant_modulation(&random, rate));
+    assert_eq!(dominant_modulation(&[1.0; 3], rate), vec![0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn bright_sounds_are_sharper_than_dull_ones() {
+    let sr = 44100.0;
+    let x = common::noise(1 << 16, 81);
+    let filtered = |filter: Biquad| filter.filtfilt(&x);
+    let bright = filtered(Biquad::highpass(sr, 4000.0, 0.7));
+    let dull = filtered(Biquad::lowpass(sr, 500.0, 0.7));
+    // Resonant low- and high-pass at the same corner leave a narrow band around 1 kHz
+    let narrow_1k = Biquad::highpass(sr, 1000.0, 8.0).filtfilt(&filtered(Biquad::lowpass(sr, 1000.0, 8.0)));
+    
+    let mut fft = FftProcessor::new(2048);
+    fft.set_sample_rate(sr);
+    let [bright, dull, reference] = [&bright, &dull, &narrow_1k].map(|x| fft.sharpness(x));
+    assert!(bright > 2.0 * dull, "bright {} dull {}", bright, dull);
+    assert!((reference - 1.0).abs() < 0.25, "1 kHz narrowband {}", reference);
+}
+
+#[test]
+fn roughness_peaks_for_70_hz_modulation() {
+    let sr = 44100.0;
+    let modulated = |rate: f32| -> Vec<f32> {
+        common::sine(sr, 1000.0, 0.5, 1 << 15).iter().enumerate()
+            .map(|(i, c)| c * (1.0 + (2.0 * std::f32::consts::PI * rate * i as f32 / sr).sin()))
+            .collect()
+    };
+    let mut fft = FftProcessor::new(2048);
+    fft.set_sample_rate(sr);
+    let [slow, rough, fast] = [4.0, 70.0, 300.0].map(|rate| fft.roughness(&modulated(rate)));
+    assert!((rough - 0.25).abs() < 0.08, "70 Hz AM: {}", rough);
+    assert!(rough > 3.0 * slow && rough > 3.0 * fast, "4 Hz {} 70 Hz {} 300 Hz {}", slow, rough, fast);
+    assert!(fft.roughness(&common::sine(sr, 1000.0, 0.5, 1 << 15)) < 0.02);
+}
+
+#[test]
+fn rolloff_percentiles_rise_in_order() {
+    let sr = 16000.0;
+    let x = Biquad::lowpass(sr, 2000.0, 0.7).filtfilt(&common::noise(2048, 91));
+    let mut fft = FftProcessor::new(2048);
+    fft.set_sample_rate(sr);
+    
+    // Given out of order; invalid percentiles come back NaN in place
+    let rolloff = fft.spectral_rolloff_multi(&x, &[0.95, 0.5, 0.85, 0.0, 1.5]);
+    let [p95, p50, p85] = [rolloff[0], rolloff[1], rolloff[2]];
+    assert!(p50 < p85 && p85 < p95, "{:?}", rolloff);
+    assert!(p50 < 2000.0 && p95 > 2000.0 && p95 < 6000.0, "{:?}", rolloff);
+    assert!(rolloff[3].is_nan() && rolloff[4].is_nan());
+}
+
+#[test]
+fn intermittent_bins_have_higher_spectral_kurtosis() {
+    // 1 kHz throughout (bin 32 of 512 at 16 kHz), 3 kHz (bin 96) one block in four
+    let steady = common::sine(16000.0, 1000.0, 0.5, 32768);
+    let burst = common::sine(16000.0, 3000.0, 0.5, 32768);
+    let x: Vec<f32> = (0..32768).map(|i| steady[i] + if (i / 2048) % 4 == 0 { burst[i] } else { 0.0 }).collect();
+    let mut fft = FftProcessor::new(512);
+    let kurtosis = fft.spectral_kurtosis(&x, 256);
+    assert!((kurtosis[32] + 1.0).abs() < 0.05, "steady {}", kurtosis[32]);
+    assert!(kurtosis[96] > kurtosis[32] + 2.0, "intermittent {}", kurtosis[96]);
+    
+    // Gaussian-like noise averages to about 0; too few frames give nothing
+    let hiss = common::noise(65536, 3);
+    let kurtosis = fft.spectral_kurtosis(&hiss, 256);
+    let mean = kurtosis[10..240].iter().sum::<f32>() / 230.0;
+    assert!(mean.abs() < 0.1, "noise {}", mean);
+    assert!(fft.spectral_kurtosis(&hiss[..512 + 6 * 256], 256).is_empty());
+}
+
+#[test]
+fn spectral_tilt_reads_a_first_order_rolloff_as_6_db_per_octave() {
+    // A leaky integrator (corner near 2.5 Hz) makes white noise fall 6 dB/octave
+    let white = common::noise(16384, 23);
+    let mut state = 0.0f32;
+    let integrated: Vec<f32> = white.iter().map(|&w| { state = 0.999 * state + w; state }).collect();
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(16000.0);
+    fft.set_tilt_range(200.0, 2000.0);
+    let mean = |tilt: Vec<f32>| tilt.iter().sum::<f32>() / tilt.len() as f32;
+    
+    let flat = mean(fft.spectral_tilt(&white, 512));
+    let falling = mean(fft.spectral_tilt(&integrated, 512));
+    assert!(flat.abs() < 0.5, "white {}", flat);
+    assert!((falling + 6.0).abs() < 0.5, "integrated {}", falling);
+    assert!(fft.spectral_tilt(&[0.0; 2048], 512).iter().all(|t| t.is_nan()));
+}
+
+#[test]
+fn occupied_bandwidth_finds_a_band_away_from_dc() {
+    // Equal tones every 50 Hz across 2-3 kHz: an ideal band-pass signal
+    let sr = 16000.0;
+    let tone = |i: usize, k: usize| {
+        let freq = 2000.0 + 50.0 * k as f32;
+        0.1 * (2.0 * std::f32::consts::PI * freq * i as f32 / sr + k as f32).sin()
+    };
+    let x: Vec<f32> = (0..32768).map(|i| (0..=20).map(|k| tone(i, k)).sum()).collect();
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(sr);
+    let bin_hz = sr / 1024.0;
+    let band = fft.occupied_bandwidth(&x, 0.99);
+    assert!((band[0] - 2000.0).abs() <= bin_hz && (band[1] - 3000.0).abs() <= bin_hz, "{:?}", band);
+    
+    // A smaller fraction gives a narrower band inside it
+    let core = fft.occupied_bandwidth(&x, 0.5);
+    assert!(core[0] >= band[0] && core[1] <= band[1] && core[1] - core[0] < 700.0, "{:?}", core);
+    for fraction in [0.0, 1.5, f32::NAN] {
+        assert!(fft.occupied_bandwidth(&x, fraction).iter().all(|f| f.is_nan()), "{}", fraction);
+    }
+}
+
+/// Ideal graphic EQ: scales every FFT bin of the whole signal by the `match_eq`
+/// gain of the log-spaced band it falls in, with the same band edges
+fn apply_band_gains(x: &[f32], gains_db: &[f32], sr: f32) -> Vec<f32> {
+    use rustfft::{FftPlanner, num_complex::Complex};
+    let n = x.len();
+    let mut spectrum: Vec<Complex<f32>> = x.iter().map(|&v| Complex::new(v, 0.0)).collect();
+    let mut planner = FftPlanner::new();
+    planner.plan_fft_forward(n).process(&mut spectrum);
+    let bands = gains_db.len() as f32;
+    for (k, c) in spectrum.iter_mut().enumerate() {
+        let hz = k.min(n - k) as f32 * sr / n as f32;
+        let band = ((hz / 20.0).max(1.0).ln() / (sr / 40.0).ln() * bands) as usize;
+        *c *= 10f32.powf(gains_db[band.min(gains_db.len() - 1)] / 20.0) / n as f32;
+    }
+    planner.plan_fft_inverse(n).process(&mut spectrum);
+    spectrum.iter().map(|c| c.re).collect()
+}
+
+#[test]
+fn match_eq_gains_bring_the_source_towards_the_reference() {
+    let sr = 16000.0;
+    let reference = common::noise(16384, 37);
+    let source = Biquad::lowpass(sr, 1500.0, 0.7).filtfilt(&reference);
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(sr);
+    
+    let gains = fft.match_eq(&source, &reference, 10);
+    assert_eq!(gains.len(), 10);
+    assert!(gains[0].abs() < 1.0 && gains[9] > 20.0, "{:?}", gains);
+    assert!(gains.windows(2).all(|w| w[1] > w[0] - 1.0), "{:?}", gains);
+    
+    let matched = apply_band_gains(&source, &gains, sr);
+    let (before, after) = (fft.log_spectral_distance(&source, &reference), fft.log_spectral_distance(&matched, &reference));
+    assert!(after < 0.5 * before, "before {} after {}", before, after);
+}
+
+#[test]
+fn thd_plus_n_reads_the_injected_harmonics() {
+    let sr = 48000.0;
+    let fundamental = common::sine(sr, 997.0, 0.5, 48000);
+    let distorted: Vec<f32> = fundamental.iter()
+        .zip(common::sine(sr, 1994.0, 0.005, 48000))
+        .zip(common::sine(sr, 2991.0, 0.0025, 48000))
+        .map(|((f, h2), h3)| f + h2 + h3)
+        .collect();
+    // sqrt(1 % ² + 0.5 % ²) relative to the fundamental
+    let expected = 100.0 * (0.01f32.powi(2) + 0.005f32.powi(2)).sqrt() / (1.0 + 0.01f32.powi(2) + 0.005f32.powi(2)).sqrt();
+    
+    let planner = SharedPlanner::new();
+    let mut shared = FftProcessor::with_planner(1024, &planner);
+    let mut own = FftProcessor::new(1024);
+    let clean = shared.thd_plus_n(&fundamental, 997.0, sr);
+    let measured = shared.thd_plus_n(&distorted, 997.0, sr);
+    assert!(clean < 0.05, "clean {} %", clean);
+    assert!((measured - expected).abs() < 0.05 * expected, "{} % for {} %", measured, expected);
+    assert_eq!(measured, own.thd_plus_n(&distorted, 997.0, sr));
+    
+    // A spur 100 Hz from the fundamental counts until the notch is widened over it
+    let spurred: Vec<f32> = fundamental.iter().zip(common::sine(sr, 1097.0, 0.01, 48000)).map(|(f, s)| f + s).collect();
+    assert!(shared.thd_plus_n(&spurred, 997.0, sr) > 1.5);
+    shared.set_thd_notch_width(300.0);
+    assert!(shared.thd_plus_n(&spurred, 997.0, sr) < 0.05);
+}
+
+#[test]
+fn deconvolving_a_sweep_recovers_the_impulse_response() {
+    // 1 s exponential sweep from 20 Hz to 7 kHz at 16 kHz, phase in f64
+    let (sr, f1, f2, len) = (16000.0f64, 20.0f64, 7000.0f64, 16000);
+    let (duration, rate) = (len as f64 / sr, (f2 / f1).ln());
+    let sweep: Vec<f32> = (0..len)
+        .map(|i| {
+            let t = i as f64 / sr;
+            (2.0 * std::f64::consts::PI * f1 * duration / rate * ((t / duration * rate).exp() - 1.0)).sin() as f32
+        })
+        .collect();
+    let taps = [(0usize, 1.0f32), (40, 0.5), (100, -0.25)];
+    let mut recorded = vec![0.0f32; len + 200];
+    for &(delay, gain) in &taps {
+        for (i, &s) in sweep.iter().enumerate() {
+            recorded[i + delay] += gain * s;
+        }
+    }
+    
+    let planner = SharedPlanner::new();
+    let mut fft = FftProcessor::with_planner(1024, &planner);
+    let ir = fft.deconvolve_sweep(&recorded, &sweep);
+    assert_eq!(ir.len(), recorded.len());
+    assert_eq!(ir, FftProcessor::new(1024).deconvolve_sweep(&recorded, &sweep));
+    
+    // Taps come back in proportion (the sweep stops short of Nyquist, so each is
+    // a slightly smeared band-limited impulse) with little energy in between
+    let main = ir[0];
+    assert!(main > 0.8, "direct tap {}", main);
+    for &(delay, gain) in &taps {
+        assert!((ir[delay] / main - gain).abs() < 0.03, "tap {}: {}", delay, ir[delay] / main);
+    }
+    let between = ir.iter()
+        .enumerate()
+        .filter(|&(i, _)| taps.iter().all(|&(d, _)| i.abs_diff(d) > 3))
+        .fold(0.0f32, |acc, (_, v)| acc.max(v.abs()));
+    assert!(between < 0.15 * main, "{}", between);
+}
+
+#[test]
+fn a_delayed_copy_shows_up_as_comb_notches() {
+    let sr = 16000.0;
+    let x = common::noise(32768, 47);
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(sr);
+    for delay in [16usize, 40] {
+        let combed: Vec<f32> = (0..x.len()).map(|i| x[i] + if i >= delay { x[i - delay] } else { 0.0 }).collect();
+        let comb = fft.detect_comb_filtering(&combed);
+        assert!((sr / comb[0] - delay as f32).abs() < 0.2, "{} samples: {:?}", delay, comb);
+        assert!(comb[1] > 0.8, "{} samples: {:?}", delay, comb);
+    }
+    assert!(fft.detect_comb_filtering(&x)[1] < 0.2);
+    assert_eq!(fft.detect_comb_filtering(&[0.0; 4096]), vec![0.0, 0.0]);
+}
+
+#[test]
+fn a_steady_tone_is_phase_coherent_and_clicks_are_not() {
+    // 1 kHz (bin 32 of 512 at 16 kHz) plus a click every 4000 samples
+    let tone = common::sine(16000.0, 1000.0, 0.3, 32000);
+    let x: Vec<f32> = tone.iter().enumerate().map(|(i, &t)| t + if i % 4000 == 2000 { 1.0 } else { 0.0 }).collect();
+    let mut fft = FftProcessor::new(512);
+    let coherence = fft.phase_coherence(&x, 256);
+    assert_eq!(coherence.len(), 257);
+    assert!(coherence[32] > 0.95, "tone bin {}", coherence[32]);
+    
+    // Bins far above the tone see only the clicks
+    let clicks = coherence[150..250].iter().sum::<f32>() / 100.0;
+    assert!(clicks < 0.3, "click bins {}", clicks);
+}
+
+#[test]
+fn partial_count_follows_the_number_of_sines() {
+    let sr = 16000.0;
+    let sines = |freqs: &[f32]| -> Vec<f32> {
+        let mut x = vec![0.0f32; 2048];
+        for &f in freqs {
+            for (v, s) in x.iter_mut().zip(common::sine(sr, f, 0.2, 2048)) {
+                *v += s;
+            }
+        }
+        x
+    };
+    let mut fft = FftProcessor::new(2048);
+    fft.set_sample_rate(sr);
+    let freqs = [440.0, 1230.0, 2050.0, 3610.0, 5200.0];
+    for count in [1, 3, 5] {
+        assert_eq!(fft.estimate_num_partials(&sines(&freqs[..count]), 20.0), count);
+    }
+    assert_eq!(fft.estimate_num_partials(&common::noise(2048, 53), 20.0), 0);
+}
+
+#[test]
+fn coherence_is_high_for_a_filtered_copy_and_low_for_unrelated_noise() {
+    let a = common::noise(32768, 67);
+    let filtered = Biquad::lowpass(16000.0, 3000.0, 0.7).process(&a);
+    let unrelated = common::noise(32768, 71);
+    let mut fft = FftProcessor::new(512);
+    let mean = |c: &[f32]| c.iter().sum::<f32>() / c.len() as f32;
+    
+    // Passband bins (up to ~2 kHz) of a linear filter stay coherent
+    let linear = fft.coherence(&a, &filtered, 256);
+    assert_eq!(linear.len(), 257);
+    assert!(mean(&linear[2..64]) > 0.95, "{}", mean(&linear[2..64]));
+    let independent = fft.coherence(&a, &unrelated, 256);
+    assert!(mean(&independent[2..250]) < 0.05, "{}", mean(&independent[2..250]));
+    assert!(independent.iter().all(|c| (0.0..=1.0).contains(c)));
+    
+    // Too few segments for a meaningful estimate
+    assert!(fft.coherence(&a[..512 + 6 * 256], &unrelated, 256).is_empty());
+}
+
+#[test]
+fn band_energy_ratio_matches_the_injected_balance() {
+    // 500 Hz at 0.5 and 4 kHz at 0.05: the high band sits 20 dB under the low one
+    let sr = 16000.0;
+    let x: Vec<f32> = common::sine(sr, 500.0, 0.5, 16384).iter()
+        .zip(common::sine(sr, 4000.0, 0.05, 16384))
+        .map(|(a, b)| a + b)
+        .collect();
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(sr);
+    let ratio = fft.band_energy_ratio(&x, 100.0, 1000.0, 2000.0, 6000.0);
+    assert!((ratio + 20.0).abs() < 0.1, "{} dB", ratio);
+    assert!((fft.band_energy_ratio(&x, 2000.0, 6000.0, 100.0, 1000.0) - 20.0).abs() < 0.1);
+    
+    // Narrower than a bin (15.6 Hz) between centres: no bin, no ratio
+    assert!(fft.band_energy_ratio(&x, 100.0, 1000.0, 2001.0, 2010.0).is_nan());
+    assert!(fft.band_energy_ratio(&x, 100.0, 1000.0, 9000.0, 12000.0).is_nan());
+}
+
+#[test]
+fn binaural_cues_recover_a_delayed_attenuated_right_channel() {
+    // Right = left delayed 8 samples (0.5 ms) at half amplitude (-6.02 dB)
+    let left = common::noise(32768, 79);
+    let right: Vec<f32> = (0..32768).map(|i| if i >= 8 { 0.5 * left[i - 8] } else { 0.0 }).collect();
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(16000.0);
+    
+    // Several cycles of phase at the top bands: the delay only comes out right if unwrapped
+    for bands in [8, 4] {
+        fft.set_cue_bands(bands);
+        let cues = fft.binaural_cues(&left, &right, 512);
+        assert_eq!(cues.len(), 3 * bands);
+        for band in cues.chunks(3) {
+            assert!((band[1] - 0.0005).abs() < 3e-5, "{} Hz: ITD {}", band[0], band[1]);
+            assert!((band[2] - 6.02).abs() < 0.1, "{} Hz: ILD {}", band[0], band[2]);
+        }
+    }
+    
+    // Swapping the channels flips both signs
+    let swapped = fft.binaural_cues(&right, &left, 512);
+    assert!(swapped.chunks(3).all(|band| band[1] < 0.0 && band[2] < 0.0), "{:?}", swapped);
+}
+
+/// 3150 Hz test tone frequency-modulated by `(rate_hz, depth)` pairs, depth as a fraction
+fn modulated_test_tone(sr: f32, seconds: f32, modulation: &[(f32, f32)]) -> Vec<f32> {
+    let mut phase = 0.0f64;
+    (0..(seconds * sr) as usize)
+        .map(|i| {
+            let t = i as f64 / sr as f64;
+            let deviation: f64 = modulation.iter()
+                .map(|&(rate, depth)| depth as f64 * (2.0 * std::f64::consts::PI * rate as f64 * t).sin())
+                .sum();
+            phase += 2.0 * std::f64::consts::PI * 3150.0 * (1.0 + deviation) / sr as f64;
+            (0.5 * phase.sin()) as f32
+        })
+        .collect()
+}
+
+#[test]
+fn wow_flutter_recovers_injected_modulation() {
+    let sr = 48000.0;
+    let mut fft = FftProcessor::new(256);
+    fft.set_sample_rate(sr);
+    
+    // 1 Hz at ±0.5 % is 0.354 % RMS of wow and no flutter
+    let wow = fft.wow_flutter(&modulated_test_tone(sr, 4.0, &[(1.0, 0.005)]), 3150.0, sr);
+    assert!((wow[0] - 0.354).abs() < 0.02, "{:?}", wow);
+    assert!(wow[1] < 0.01, "{:?}", wow);
+    
+    // 20 Hz at ±0.1 % lands in the flutter band
+    let flutter = fft.wow_flutter(&modulated_test_tone(sr, 4.0, &[(20.0, 0.001)]), 3150.0, sr);
+    assert!((flutter[1] - 0.0707).abs() < 0.007, "{:?}", flutter);
+    assert!(flutter[0] < 0.01, "{:?}", flutter);
+    
+    // Moving the split well above 20 Hz makes the same modulation count as wow
+    fft.set_wow_flutter_bands(80.0, 200.0);
+    let moved = fft.wow_flutter(&modulated_test_tone(sr, 4.0, &[(20.0, 0.001)]), 3150.0, sr);
+    assert!((moved[0] - 0.0707).abs() < 0.007 && moved[1] < 0.01, "{:?}", moved);
+}
+
+#[test]
+fn timbre_descriptor_tells_bright_from_warm() {
+    let sr = 16000.0;
+    let high = Biquad::highpass(sr, 3000.0, 0.7).filtfilt(&common::noise(8192, 83));
+    let low = Biquad::lowpass(sr, 300.0, 0.7).filtfilt(&common::noise(8192, 89));
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(sr);
+    let averages = |x: &[f32], fft: &mut FftProcessor| {
+        let frames = fft.timbre_descriptor(x, 512);
+        assert!(frames.iter().all(|d| (0.0..=1.0).contains(d)), "{:?}", frames);
+        let count = (frames.len() / 3) as f32;
+        [0, 1].map(|k| frames.iter().skip(k).step_by(3).sum::<f32>() / count)
+    };
+    let ([bright, cold], [dull, warm]) = (averages(&high, &mut fft), averages(&low, &mut fft));
+    assert!(bright > 0.7 && dull < 0.4, "brightness {} vs {}", bright, dull);
+    assert!(warm > 0.8 && cold < 0.05, "warmth {} vs {}", warm, cold);
+    assert_eq!(fft.timbre_descriptor(&[0.0; 2048], 512), vec![0.0; 9]);
+}
+
+#[test]
+fn beat_spectrum_peaks_at_the_loop_period() {
+    // A 512 ms loop (32 hops of 256 at 16 kHz): a noise burst, then two tones
+    let tile: Vec<f32> = common::noise(2048, 97).iter()
+        .map(|v| 0.5 * v)
+        .chain(common::sine(16000.0, 330.0, 0.3, 3072))
+        .chain(common::sine(16000.0, 523.0, 0.3, 3072))
+        .collect();
+    let looped: Vec<f32> = tile.iter().cycle().take(8 * tile.len()).copied().collect();
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(16000.0);
+    for feature in [SimilarityFeature::LogSpectrum, SimilarityFeature::Chroma] {
+        fft.set_similarity_feature(feature);
+        let beat = fft.beat_spectrum(&looped, 256);
+        assert!((beat[0] - 1.0).abs() < 1e-4, "{:?}", feature);
+        let peak = (8..48).max_by(|&a, &b| beat[a].total_cmp(&beat[b])).unwrap();
+        assert_eq!(peak, 32, "{:?}: {:?}", feature, &beat[..48]);
+        assert!(beat[32] > 0.9 && beat[16] < 0.5, "{:?}: {} / {}", feature, beat[32], beat[16]);
+    }
+}
+
+#[test]
+fn music_resolves_two_tones_closer_than_a_bin() {
+    // 256 samples at 8 kHz: bins are 31.25 Hz apart, the tones 15 Hz
+    let sr = 8000.0;
+    let x: Vec<f32> = common::sine(sr, 1000.0, 0.5, 256).iter()
+        .zip(common::sine(sr, 1015.0, 0.5, 256))
+        .zip(common::noise(256, 101))
+        .map(|((a, b), n)| a + b + 0.001 * n)
+        .collect();
+    let planner = SharedPlanner::new();
+    let mut fft = FftProcessor::with_planner(256, &planner);
+    fft.set_sample_rate(sr);
+    let tones = fft.estimate_frequencies(&x, 2);
+    assert_eq!(tones.len(), 2, "{:?}", tones);
+    assert!((tones[0] - 1000.0).abs() < 1.5 && (tones[1] - 1015.0).abs() < 1.5, "{:?}", tones);
+    
+    // The plain spectrum shows a single peak there
+    let power = fft.power_spectrum(&x);
+    let is_peak = |k: usize| power[k] > power[k - 1] && power[k] > power[k + 1] && power[k] > 0.01 * power[32];
+    let peaks = (1..power.len() - 1).filter(|&k| is_peak(k)).count();
+    assert_eq!(peaks, 1);
+    assert!(fft.estimate_frequencies(&x, 0).is_empty());
+}
+
+#[test]
+fn modulation_spectrum_peaks_at_the_am_rate() {
+    // 1 kHz carrier, 80 % amplitude modulation at 8 Hz, 2 s at 16 kHz
+    let sr = 16000.0;
+    let x: Vec<f32> = common::sine(sr, 1000.0, 0.5, 32000).iter().enumerate()
+        .map(|(i, c)| c * (1.0 + 0.8 * (std::f32::consts::TAU * 8.0 * i as f32 / sr).sin()))
+        .collect();
+    let planner = SharedPlanner::new();
+    let mut fft = FftProcessor::with_planner(512, &planner);
+    fft.set_sample_rate(sr);
+    let map = fft.modulation_spectrum(&x, 128);
+    let (bands, bins, mod_hz) = (map[0] as usize, map[1] as usize, map[2]);
+    assert_eq!(bands, 16);
+    assert_eq!(map.len(), 3 + bands * bins);
+    
+    // The band holding the carrier has the most energy; its envelope peaks at 8 Hz
+    let rows: Vec<&[f32]> = map[3..].chunks(bins).collect();
+    let energy = |r: &[f32]| r.iter().map(|v| v * v).sum::<f32>();
+    let carrier = (0..bands).max_by(|&a, &b| energy(rows[a]).total_cmp(&energy(rows[b]))).unwrap();
+    let peak = (1..bins).max_by(|&a, &b| rows[carrier][a].total_cmp(&rows[carrier][b])).unwrap();
+    assert!((peak as f32 * mod_hz - 8.0).abs() < mod_hz, "peak at {} Hz", peak as f32 * mod_hz);
+    
+    // Same map as a processor planning on its own
+    let mut own = FftProcessor::new(512);
+    own.set_sample_rate(sr);
+    assert_eq!(own.modulation_spectrum(&x, 128), map);
+}
+
+#[test]
+fn octave_flatness_separates_a_tonal_octave_from_noisy_ones() {
+    // A strong 1 kHz tone over faint white noise, 4 s at 16 kHz
+    let sr = 16000.0;
+    let x: Vec<f32> = common::sine(sr, 1000.0, 0.5, 64000).iter()
+        .zip(common::noise(64000, 57))
+        .map(|(t, n)| t + 0.01 * n)
+        .collect();
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(sr);
+    let bands = fft.octave_flatness(&x);
+    // Bands up to 8 kHz start below Nyquist; 16 kHz does not
+    let nominal: Vec<f32> = bands.iter().step_by(2).copied().collect();
+    assert_eq!(nominal, [31.5, 63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0]);
+    let flatness = |hz: f32| bands[2 * nominal.iter().position(|&n| n == hz).unwrap() + 1];
+    
+    assert!(flatness(1000.0) < 0.05, "{:?}", bands);
+    for hz in [250.0, 500.0, 2000.0, 4000.0, 8000.0] {
+        assert!(flatness(hz) > 0.8, "{} Hz: {:?}", hz, bands);
+    }
+    
+    // Silence has no flatness to report
+    assert!(fft.octave_flatness(&vec![0.0; 16000]).iter().skip(1).step_by(2).all(|f| f.is_nan()));
+}
+
+#[test]
+fn recommended_mel_bins_grow_with_nyquist_and_stay_under_the_bin_count() {
+    let counts: Vec<usize> = [8000.0, 16000.0, 22050.0, 44100.0, 48000.0, 96000.0].iter()
+        .map(|&sr| recommended_mel_bins(sr, 2048))
+        .collect();
+    assert!(counts.windows(2).all(|w| w[0] < w[1]), "{:?}", counts);
+    assert_eq!((counts[1], counts[4]), (28, 40));
+    
+    // Small FFTs cap the count below their fft_size / 2 + 1 bins
+    for fft_size in [16, 32, 64, 128] {
+        let bins = recommended_mel_bins(48000.0, fft_size);
+        assert!(bins < fft_size / 2 + 1, "{} for {}", bins, fft_size);
+    }
+    assert_eq!(recommended_mel_bins(48000.0, 32), 16);
+    assert_eq!(recommended_mel_bins(0.0, 1024), 0);
+    assert_eq!(recommended_mel_bins(f32::NAN, 1024), 0);
+}
+
+#[test]
+fn spectral_moments_skew_toward_the_weaker_tail() {
+    let sr = 16000.0;
+    let mut fft = FftProcessor::new(1024);
+    fft.set_sample_rate(sr);
+    let tones = |parts: &[(f32, f32)]| -> Vec<f32> {
+        let mut x = vec![0.0f32; 1024];
+        for &(freq, amp) in parts {
+            x.iter_mut().zip(common::sine(sr, freq, amp, 1024)).for_each(|(v, s)| *v += s);
+        }
+        x
+    };
+    
+    // Equal tones at 2 and 6 kHz: centred at 4 kHz, spread 2 kHz, no skew, kurtosis 1
+    let m = fft.spectral_moments(&tones(&[(2000.0, 0.4), (6000.0, 0.4)]));
+    assert!((m[0] - 4000.0).abs() < 20.0 && (m[1] - 2000.0).abs() < 40.0, "{:?}", m);
+    assert!(m[2].abs() < 0.05 && (m[3] - 1.0).abs() < 0.1, "{:?}", m);
+    
+    // Mostly high energy with a weak low tone: skewed toward low frequencies
+    let low_tail = fft.spectral_moments(&tones(&[(1000.0, 0.1), (6000.0, 0.5)]));
+    assert!(low_tail[2] < -0.5, "{:?}", low_tail);
+    let high_tail = fft.spectral_moments(&tones(&[(1000.0, 0.5), (6000.0, 0.1)]));
+    assert!(high_tail[2] > 0.5, "{:?}", high_tail);
+    
+    assert_eq!(fft.spectral_moments(&vec![0.0; 1024]), [0.0; 4]);
+}
+
+#[test]
+fn inharmonicity_recovers_b_of_a_stiff_string_series() {
+    let sr = 16000.0;
+    let series = |f0: f32, b: f32| -> Vec<f32> {
+        let mut x = vec![0.0f32; 16384];
+        for n in 1..=12 {
+            let freq = n as f32 * f0 * (1.0 + b * (n * n) as f32).sqrt();
+            x.iter_mut().zip(common::sine(sr, freq, 0.3 / n as f32, 16384)).for_each(|(v, s)| *v += s);
+        }
+        x
+    };
+    let mut fft = FftProcessor::new(16384);
+    fft.set_sample_rate(sr);
+    
+    let b = fft.inharmonicity(&series(110.0, 4e-4), 110.0);
+    assert!((b / 4e-4 - 1.0).abs() < 0.1, "B = {}", b);
+    // A slightly wrong fundamental is absorbed by the fit's intercept
+    let b = fft.inharmonicity(&series(110.0, 4e-4), 112.0);
+    assert!((b / 4e-4 - 1.0).abs() < 0.1, "B = {}", b);
+    
+    let harmonic = fft.inharmonicity(&series(110.0, 0.0), 110.0);
+    assert!(harmonic.abs() < 2e-6, "B = {}", harmonic);
+    assert!(fft.inharmonicity(&vec![0.0; 16384], 110.0).is_nan());
+}
+
+#[test]
+fn harmonic_profile_recovers_levels_and_reports_a_missing_harmonic() {
+    let sr = 16000.0;
+    let partials = |f0: f32, amps: &[f32]| -> Vec<f32> {
+        let mut x = vec![0.0f32; 4096];
+        for (h, &amp) in amps.iter().enumerate() {
+            x.iter_mut().zip(common::sine(sr, f0 * (h + 1) as f32, amp, 4096)).for_each(|(v, s)| *v += s);
+        }
+        x
+    };
+    let mut fft = FftProcessor::new(4096);
+    fft.set_sample_rate(sr);
+    // Harmonic 3 is absent
+    let profile = fft.harmonic_profile(&partials(210.0, &[0.4, 0.2, 0.0, 0.1, 0.04]), 210.0, 5);
+    assert_eq!(profile[0], 0.0);
+    for (h, expected) in [(1, -6.02), (3, -12.04), (4, -20.0)] {
+        assert!((profile[h] - expected).abs() < 0.3, "{:?}", profile);
+    }
+    assert!(profile[2] < -60.0, "{:?}", profile);
+    
+    // Past Nyquist, and without the fundamental, there is nothing to report
+    let high = fft.harmonic_profile(&partials(3000.0, &[0.4, 0.2]), 3000.0, 3);
+    assert!(high[1].is_finite() && high[2].is_nan(), "{:?}", high);
+    let no_fundamental = fft.harmonic_profile(&partials(210.0, &[0.0, 0.2, 0.1]), 210.0, 3);
+    assert!(no_fundamental.iter().all(|v| v.is_nan()), "{:?}", no_fundamental);
+    assert!(fft.harmonic_profile(&vec![0.0; 4096], 210.0, 3).iter().all(|v| v.is_nan()));
+}
+
+#[test]
+fn tonal_noise_split_recovers_a_known_tone_to_noise_mix() {
+    let sr = 16000.0;
+    let mut fft = FftProcessor::new(2048);
+    fft.set_sample_rate(sr);
+    let noise: Vec<f32> = common::noise(2048, 83).iter().map(|v| 0.3 * v).collect();
+    for amplitude in [0.2, 0.4] {
+        let tone = common::sine(sr, 1000.0, amplitude, 2048);
+        let x: Vec<f32> = tone.iter().zip(&noise).map(|(t, n)| t + n).collect();
+        let expected = common::energy(&tone) / (common::energy(&tone) + common::energy(&noise));
+        let split = fft.tonal_noise_split(&x);
+        assert!((split[0] + split[1] - 1.0).abs() < 1e-5, "{:?}", split);
+        assert!((split[0] - expected).abs() < 0.05, "{:?} vs {}", split, expected);
+    }
+    
+    // Noise alone is mostly residual: its rare 10 dB peaks go at a higher prominence
+    let x: Vec<f32> = common::sine(sr, 1000.0, 0.4, 2048).iter().zip(&noise).map(|(t, n)| t + n).collect();
+    let default = fft.tonal_noise_split(&noise)[0];
+    fft.set_peak_prominence_db(20.0);
+    let strict = fft.tonal_noise_split(&noise)[0];
+    assert!(default < 0.06 && strict < 0.005, "{} / {}", default, strict);
+    assert!(fft.tonal_noise_split(&x)[0] > 0.5);
+    fft.set_peak_prominence_db(200.0);
+    assert_eq!(fft.tonal_noise_split(&x), [0.0, 1.0]);
+    assert!(fft.tonal_noise_split(&vec![0.0; 2048]).iter().all(|v| v.is_nan()));
+}
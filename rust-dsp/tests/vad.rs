@@ -0,0 +1,445 @@
+mod common;
+
+use common::*;
+use sanctuary_dsp::*;
+
+/// Low-frequency sine whose level rises 60 dB over `len` samples, so frame
+/// energies increase strictly and no earlier frame's hangover reaches a later one
+fn rising_tone(len: usize) -> Vec<f32> {
+    sine(16000.0, 100.0, 1.0, len)
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| s * 10f32.powf(-3.0 + 3.0 * i as f32 / len as f32))
+        .collect()
+}
+
+#[test]
+fn energy_contour_matches_the_detection_energy_frame_by_frame() {
+    let x = rising_tone(8192);
+    let mut vad = VoiceActivityDetector::new(512, 256);
+    let contour = vad.energy_contour(&x, true);
+    assert_eq!(contour.len(), vad.detect(&x).len());
+    
+    // A threshold just under a frame's contour value makes it speech, just over does not
+    for (i, &e) in contour.iter().enumerate() {
+        vad.set_thresholds(e - 1e-3, 1.0);
+        assert_eq!(vad.detect(&x)[i], 1, "frame {} at {} dB", i, e);
+        vad.set_thresholds(e + 1e-3, 1.0);
+        assert_eq!(vad.detect(&x)[i], 0, "frame {} at {} dB", i, e);
+    }
+}
+
+const SR: f32 = 16000.0;
+
+/// `[(is_speech, seconds), ...]` as vowel bursts separated by digital silence
+fn speech_and_pauses(parts: &[(bool, f32)]) -> Vec<f32> {
+    parts.iter()
+        .flat_map(|&(speech, seconds)| {
+            let len = (seconds * SR) as usize;
+            if speech {
+                vowel(SR, 140.0, &[(700.0, 80.0), (1200.0, 100.0)], len).iter().map(|v| v * 0.05).collect()
+            } else {
+                vec![0.0; len]
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn find_silences_returns_the_one_long_pause() {
+    let x = speech_and_pauses(&[(false, 0.2), (true, 0.5), (false, 1.0), (true, 0.5), (false, 0.2)]);
+    let vad = VoiceActivityDetector::new(512, 256);
+    let speech = vad.get_segments(&x);
+    assert_eq!(speech.len(), 4, "{:?}", speech);
+    
+    // Only the middle pause is long enough, and it spans exactly the gap between segments
+    assert_eq!(vad.find_silences(&x, 500.0, SR), vec![speech[1], speech[2]]);
+    
+    // With no minimum the edge silences come back too
+    let all = vad.find_silences(&x, 0.0, SR);
+    assert_eq!(all, vec![0, speech[0], speech[1], speech[2], speech[3], x.len() as u32]);
+}
+
+#[test]
+fn rle_decodes_back_to_detect() {
+    let vad = VoiceActivityDetector::new(512, 256);
+    let mixed = speech_and_pauses(&[(false, 0.3), (true, 0.4), (false, 0.6), (true, 0.3)]);
+    for x in [mixed, speech_and_pauses(&[(true, 1.0)]), vec![0.0; 16000]] {
+        let rle = vad.detect_rle(&x);
+        let decoded: Vec<u8> = rle.chunks_exact(2)
+            .flat_map(|run| std::iter::repeat_n(run[0] as u8, run[1] as usize))
+            .collect();
+        assert_eq!(decoded, vad.detect(&x));
+    }
+    
+    // Uniform input is a single run
+    assert_eq!(vad.detect_rle(&vec![0.0; 16000]).len(), 2);
+    assert_eq!(vad.detect_rle(&speech_and_pauses(&[(true, 1.0)]))[0], 1);
+    assert_eq!(vad.detect_rle(&speech_and_pauses(&[(true, 1.0)])).len(), 2);
+}
+
+#[test]
+fn sample_mask_runs_match_get_segments() {
+    let x = speech_and_pauses(&[(false, 0.3), (true, 0.4), (false, 0.6), (true, 0.3), (false, 0.1)]);
+    let vad = VoiceActivityDetector::new(512, 256);
+    let mask = vad.detect_sample_mask(&x);
+    assert_eq!(mask.len(), x.len());
+    
+    let mut runs = Vec::new();
+    for i in 0..mask.len() {
+        let previous = if i == 0 { 0 } else { mask[i - 1] };
+        if mask[i] != previous {
+            runs.push(i as u32);
+        }
+    }
+    if mask.last() == Some(&1) {
+        runs.push(mask.len() as u32);
+    }
+    assert_eq!(runs, vad.get_segments(&x));
+}
+
+#[test]
+fn crest_criterion_rejects_a_sustained_tone_but_keeps_speech() {
+    let speech = speech_and_pauses(&[(true, 0.5)]);
+    let tone = sine(SR, 440.0, 0.3, 8000);
+    let mut vad = VoiceActivityDetector::new(512, 256);
+    assert!(vad.detect(&tone).iter().all(|&v| v == 1));
+    
+    vad.set_spectral_features(true, false);
+    assert!(vad.detect(&speech).iter().all(|&v| v == 1));
+    assert!(vad.detect(&tone).iter().all(|&v| v == 0));
+    
+    // The tone's crest sits above the 35 dB default, the vowel's below it
+    let crest = |x: &[f32]| vad.spectral_contour(x).chunks(2).map(|c| c[0]).fold(f32::NAN, f32::max);
+    assert!(crest(&speech) < 35.0 && crest(&tone) > 35.0);
+}
+
+#[test]
+fn segment_into_snaps_boundaries_to_pauses() {
+    // Four 0.7 s words separated by 0.3 s pauses (boundaries ideally at 1 s steps)
+    let x = speech_and_pauses(&[
+        (true, 0.7), (false, 0.3), (true, 0.7), (false, 0.3),
+        (true, 0.7), (false, 0.3), (true, 0.7),
+    ]);
+    let vad = VoiceActivityDetector::new(512, 256);
+    let bounds = vad.segment_into(&x, 4, SR);
+    assert_eq!(bounds.len(), 5, "{:?}", bounds);
+    assert_eq!((bounds[0], bounds[4]), (0, x.len() as u32));
+    
+    // Every interior boundary is inside a pause, never mid-word
+    let mask = vad.detect_sample_mask(&x);
+    for &b in &bounds[1..4] {
+        assert_eq!(mask[b as usize], 0, "boundary {} cuts speech", b);
+    }
+    
+    // Asking for more chunks than there are pauses gives fewer chunks
+    assert!(vad.segment_into(&x, 12, SR).len() <= 5);
+}
+
+#[test]
+fn auto_threshold_splits_speech_from_background() {
+    // Speech over a -60 dB noise floor
+    let floor: Vec<f32> = noise(32000, 71).iter().map(|n| 1e-3 * n).collect();
+    let x: Vec<f32> = speech_and_pauses(&[(false, 0.5), (true, 0.5), (false, 0.5), (true, 0.5)])
+        .iter().zip(&floor).map(|(s, n)| s + n).collect();
+    let mut vad = VoiceActivityDetector::new(512, 256);
+    let energies = vad.energy_contour(&x, true);
+    let (quiet, loud) = energies.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &e| (lo.min(e), hi.max(e)));
+    let threshold = vad.auto_threshold(&x);
+    assert!(threshold > quiet + 10.0 && threshold < loud - 10.0, "{} in {}..{}", threshold, quiet, loud);
+    
+    // Unimodal input keeps the configured threshold
+    vad.set_thresholds(-37.0, 0.3);
+    assert_eq!(vad.auto_threshold(&floor), -37.0);
+    assert_eq!(vad.auto_threshold(&speech_and_pauses(&[(true, 1.0)])), -37.0);
+}
+
+#[test]
+fn reverb_lowers_the_signal_to_reverberation_ratio() {
+    let dry = speech_and_pauses(&[(true, 0.3), (false, 0.4), (true, 0.3), (false, 0.4)]);
+    
+    // Direct path plus a noise tail decaying 60 dB over 300 ms
+    let len = (0.3 * SR) as usize;
+    let tail = noise(len, 5);
+    let ir: Vec<f32> = (0..len)
+        .map(|i| if i == 0 { 1.0 } else { 0.1 * tail[i] * 10f32.powf(-3.0 * i as f32 / len as f32) })
+        .collect();
+    let wet: Vec<f32> = (0..dry.len()).map(|n| (0..len.min(n + 1)).map(|k| ir[k] * dry[n - k]).sum()).collect();
+    
+    let vad = VoiceActivityDetector::new(512, 256);
+    let (dry_ratio, wet_ratio) = (vad.reverberation_ratio(&dry, SR), vad.reverberation_ratio(&wet, SR));
+    assert_eq!(dry_ratio, 60.0);
+    assert!(wet_ratio < 10.0, "wet {} dB", wet_ratio);
+    
+    // A signal that never stops has no decay to measure
+    assert!(vad.reverberation_ratio(&sine(SR, 200.0, 0.3, 16000), SR).is_nan());
+}
+
+/// 1 kHz tone under an amplitude envelope given as a function of time in seconds
+fn enveloped(envelope: impl Fn(f32) -> f32) -> Vec<f32> {
+    sine(SR, 1000.0, 1.0, 16000).iter().enumerate().map(|(i, c)| c * envelope(i as f32 / SR)).collect()
+}
+
+#[test]
+fn adsr_of_a_hit_and_of_a_sustained_note() {
+    let vad = VoiceActivityDetector::new(512, 256);
+    
+    // 10 ms linear attack (8 ms from 10 % to 90 %), then a 100 ms exponential fall:
+    // no sustain, release to 10 % after 100·ln(10) ≈ 230 ms
+    let hit = enveloped(|t| if t < 0.01 { t / 0.01 } else { (-(t - 0.01) / 0.1).exp() });
+    let adsr = vad.estimate_adsr(&hit, SR);
+    assert!((adsr[0] - 8.0).abs() <= 2.0, "{:?}", adsr);
+    assert_eq!((adsr[1], adsr[2]), (0.0, 1.0));
+    assert!((adsr[3] - 230.0).abs() <= 10.0, "{:?}", adsr);
+    
+    // 20 ms attack, 50 ms decay to half level, sustain to 400 ms, 100 ms linear release
+    let note = enveloped(|t| match t {
+        t if t < 0.02 => t / 0.02,
+        t if t < 0.07 => 1.0 - 0.5 * (t - 0.02) / 0.05,
+        t if t < 0.4 => 0.5,
+        t => (0.5 - 5.0 * (t - 0.4)).max(0.0),
+    });
+    let adsr = vad.estimate_adsr(&note, SR);
+    assert!((adsr[0] - 16.0).abs() <= 2.0, "{:?}", adsr);
+    assert!((adsr[1] - 50.0).abs() <= 8.0, "{:?}", adsr);
+    assert!((adsr[2] - 0.5).abs() <= 0.05, "{:?}", adsr);
+    assert!((adsr[3] - 90.0).abs() <= 10.0, "{:?}", adsr);
+    assert_eq!(vad.estimate_adsr(&[0.0; 4000], SR), vec![0.0; 4]);
+}
+
+#[test]
+fn sliding_calibration_follows_a_slow_gain_ramp() {
+    // Alternating 300 ms of background and vowel under a 50 dB ramp, as from an upstream AGC
+    let part = (0.3 * SR) as usize;
+    let len = 20 * part;
+    let speech = vowel(SR, 140.0, &[(700.0, 80.0), (1200.0, 100.0)], part);
+    let background = noise(len, 3);
+    let is_speech = |i: usize| (i / part) % 2 == 1;
+    let x: Vec<f32> = (0..len)
+        .map(|i| {
+            let gain = 10f32.powf((-50.0 + 50.0 * i as f32 / len as f32) / 20.0);
+            gain * (if is_speech(i) { 0.05 * speech[i % part] } else { 0.0 } + 0.001 * background[i])
+        })
+        .collect();
+    
+    // Misses and false alarms against the frame centres
+    let mut vad = VoiceActivityDetector::new(512, 256);
+    let errors = |vad: &VoiceActivityDetector| {
+        let decisions = vad.detect(&x);
+        let truth: Vec<bool> = (0..decisions.len()).map(|f| is_speech(f * 256 + 256)).collect();
+        let missed = decisions.iter().zip(&truth).filter(|&(&d, &t)| t && d == 0).count();
+        let false_alarms = decisions.iter().zip(&truth).filter(|&(&d, &t)| !t && d == 1).count();
+        let first_speech = (0..decisions.len()).filter(|&f| truth[f] && f * 256 < 2 * part).collect::<Vec<_>>();
+        let first_found = first_speech.iter().filter(|&&f| decisions[f] == 1).count();
+        (missed, false_alarms, first_found as f32 / first_speech.len() as f32, truth.len())
+    };
+    let (fixed_missed, ..) = errors(&vad);
+    vad.set_sliding_calibration(60);
+    let (missed, false_alarms, first, frames) = errors(&vad);
+    assert!(missed * 20 < frames && missed * 2 < fixed_missed, "missed {} vs {} fixed", missed, fixed_missed);
+    assert!(false_alarms * 10 < frames, "{} false alarms", false_alarms);
+    assert!(first > 0.8, "first segment {}", first);
+}
+
+#[test]
+fn syllable_rate_of_4_hz_amplitude_modulation() {
+    let carrier = vowel(SR, 140.0, &[(700.0, 80.0), (1200.0, 100.0)], 3 * SR as usize);
+    let modulated: Vec<f32> = carrier.iter()
+        .enumerate()
+        .map(|(i, &c)| 0.05 * c * (0.5 - 0.5 * (2.0 * std::f32::consts::PI * 4.0 * i as f32 / SR).cos()))
+        .collect();
+    let vad = VoiceActivityDetector::new(512, 256);
+    let rate = vad.syllable_rate(&modulated, SR);
+    assert!((rate - 4.0).abs() <= 0.4, "{} syllables/s", rate);
+    
+    // A steady vowel has no nuclei to count
+    let steady: Vec<f32> = carrier.iter().map(|&c| 0.05 * c).collect();
+    assert!(vad.syllable_rate(&steady, SR) < 0.5);
+}
+
+#[test]
+fn energy_entropy_drops_at_an_onset_and_stays_finite_in_silence() {
+    // 512-sample frames every 512 samples: silence, a frame where the tone starts
+    // three quarters of the way in, then steady tone
+    let mut x = vec![0.0f32; 512 + 384];
+    x.extend(sine(16000.0, 440.0, 0.5, 128 + 1024));
+    let vad = VoiceActivityDetector::new(512, 512);
+    let entropy = vad.energy_entropy(&x, 8);
+    assert_eq!(entropy.len(), 4);
+    assert!(entropy.iter().all(|e| e.is_finite()), "{:?}", entropy);
+    
+    // Silence counts as uniform; the steady tone is close to the 3-bit maximum
+    assert_eq!(entropy[0], 3.0);
+    assert!(entropy[2] > 2.95 && entropy[3] > 2.95, "{:?}", entropy);
+    // Energy in two of eight sub-blocks: about 1 bit
+    assert!((entropy[1] - 1.0).abs() < 0.05, "{:?}", entropy);
+}
+
+#[test]
+fn transient_density_counts_clicks_per_second() {
+    let sr = 16000.0;
+    let steady = sine(sr, 220.0, 0.5, 32000);
+    let vad = VoiceActivityDetector::new(512, 256);
+    assert_eq!(vad.transient_density(&steady, sr), 0.0);
+    
+    // 20 decaying noise bursts in 2 s
+    let burst = noise(320, 61);
+    let mut clicks = vec![0.0f32; 32000];
+    for start in (0..32000).step_by(1600) {
+        for (i, &n) in burst.iter().enumerate() {
+            clicks[start + i] = 0.5 * n * (-(i as f32) / 80.0).exp();
+        }
+    }
+    let density = vad.transient_density(&clicks, sr);
+    assert!((density - 10.0).abs() < 0.6, "{} onsets/s", density);
+    
+    // The threshold is the sensitivity setting; short buffers have no rate
+    let mut deaf = VoiceActivityDetector::new(512, 256);
+    deaf.set_onset_sensitivity(120.0);
+    assert_eq!(deaf.transient_density(&clicks, sr), 0.0);
+    assert!(vad.transient_density(&clicks[..7000], sr).is_nan());
+}
+
+#[test]
+fn complex_domain_novelty_catches_legato_note_changes() {
+    // Eight notes a whole tone apart, 0.25 s each at one level with continuous phase
+    let sr = 16000.0;
+    let mut phase = 0.0f32;
+    let mut legato = Vec::new();
+    for f in [392.0, 440.0, 494.0, 554.0, 622.0, 554.0, 494.0, 440.0] {
+        for _ in 0..4000 {
+            legato.push(0.5 * phase.sin());
+            phase += 2.0 * std::f32::consts::PI * f / sr;
+        }
+    }
+    let density = |mode| {
+        let mut vad = VoiceActivityDetector::new(512, 256);
+        vad.set_onset_sensitivity(3.3);
+        vad.set_novelty_mode(mode);
+        vad.transient_density(&legato, sr)
+    };
+    
+    // The level never rises and the magnitude spectrum only drifts between notes,
+    // but the phase stops following its prediction at each of the 7 changes
+    assert_eq!(density(NoveltyMode::EnergyRise), 0.0);
+    assert_eq!(density(NoveltyMode::SpectralFlux), 0.0);
+    assert_eq!(density(NoveltyMode::ComplexDomain), 3.5);
+    
+    // A steady tone is predicted exactly, so its novelty stays near 0 dB
+    let mut vad = VoiceActivityDetector::new(512, 256);
+    vad.set_novelty_mode(NoveltyMode::ComplexDomain);
+    let steady = vad.onset_novelty(&sine(sr, 440.0, 0.5, 16000), sr);
+    assert!(steady.iter().all(|&v| v < 0.1), "{:?}", steady);
+}
+
+#[test]
+fn only_full_flux_rectification_registers_note_offs() {
+    // Four 0.25 s notes with 20 ms releases, 0.25 s apart
+    let sr = 16000.0;
+    let notes: Vec<f32> = sine(sr, 440.0, 0.5, 32000)
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| if (i / 4000) % 2 == 0 { v * ((4000 - i % 4000) as f32 / 320.0).min(1.0) } else { 0.0 })
+        .collect();
+    
+    // Peak novelty over the 5 ms hops whose frames see sample `t` change
+    let peaks = |rectify| {
+        let mut vad = VoiceActivityDetector::new(512, 256);
+        vad.set_novelty_mode(NoveltyMode::SpectralFlux);
+        vad.set_flux_rectify(rectify);
+        let novelty = vad.onset_novelty(&notes, sr);
+        let peak = |t: usize| novelty[t / 80 - 2..t / 80 + 8].iter().fold(0.0f32, |acc, &v| acc.max(v));
+        let ons: Vec<f32> = (1..4).map(|k| peak(8000 * k)).collect();
+        let offs: Vec<f32> = (0..4).map(|k| peak(8000 * k + 4000)).collect();
+        (ons, offs)
+    };
+    for rectify in [FluxRectify::PositiveOnly, FluxRectify::Full, FluxRectify::L2] {
+        let (ons, offs) = peaks(rectify);
+        assert!(ons.iter().all(|&v| v > 20.0), "{:?} {:?}", rectify, ons);
+        if rectify == FluxRectify::Full {
+            assert!(offs.iter().all(|&v| v > 5.0), "{:?}", offs);
+        } else {
+            assert!(offs.iter().all(|&v| v < 0.5), "{:?} {:?}", rectify, offs);
+        }
+    }
+}
+
+#[test]
+fn energy_gmm_recovers_both_components_and_a_separating_threshold() {
+    // 512-sample frames holding whole 500 Hz periods, each at a level drawn from
+    // -50 ± 3 dB (noise) or -15 ± 6 dB (speech), uniformly; 80 noise, 60 speech
+    let spread = noise(140, 5);
+    let is_speech = |i: usize| (40..70).contains(&i) || i >= 110;
+    let levels: Vec<f32> = (0..140)
+        .map(|i| if is_speech(i) { -15.0 + 6.0 * spread[i] } else { -50.0 + 3.0 * spread[i] })
+        .collect();
+    let x: Vec<f32> = levels.iter()
+        .flat_map(|&db| sine(16000.0, 500.0, (2.0 * 10f32.powf(db / 10.0)).sqrt(), 512))
+        .collect();
+    let vad = VoiceActivityDetector::new(512, 512);
+    let [noise_mean, noise_std, speech_mean, speech_std, threshold] = vad.fit_energy_gmm(&x)[..] else { panic!() };
+    
+    let moments = |speech: bool| {
+        let picked: Vec<f32> = (0..140).filter(|&i| is_speech(i) == speech).map(|i| levels[i]).collect();
+        let mean = picked.iter().sum::<f32>() / picked.len() as f32;
+        (mean, (picked.iter().map(|l| (l - mean).powi(2)).sum::<f32>() / picked.len() as f32).sqrt())
+    };
+    let ((nm, ns), (sm, ss)) = (moments(false), moments(true));
+    assert!((noise_mean - nm).abs() < 0.1 && (noise_std - ns).abs() < 0.1, "{} {} vs {} {}", noise_mean, noise_std, nm, ns);
+    assert!((speech_mean - sm).abs() < 0.1 && (speech_std - ss).abs() < 0.1, "{} {} vs {} {}", speech_mean, speech_std, sm, ss);
+    assert!(threshold > -47.0 && threshold < -21.0, "{}", threshold);
+    
+    // A unimodal contour or too few frames cannot be split
+    assert!(vad.fit_energy_gmm(&sine(16000.0, 500.0, 0.1, 512 * 40)).iter().all(|v| v.is_nan()));
+    assert!(vad.fit_energy_gmm(&x[..3 * 512]).iter().all(|v| v.is_nan()));
+}
+
+/// Flat JSON object (string, number and boolean values only) as key/value pairs,
+/// strings unquoted
+fn parse_flat_json(json: &str) -> std::collections::HashMap<String, String> {
+    let body = json.trim().strip_prefix('{').and_then(|b| b.strip_suffix('}')).expect("an object");
+    body.split(',')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once(':').expect("key:value");
+            (key.trim_matches('"').to_string(), value.trim_matches('"').to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn speech_vad_recommendation_parses_into_a_working_detector() {
+    let config = parse_flat_json(&recommend_config(AnalysisTask::SpeechVad, SR));
+    let get = |key: &str| config.get(key).unwrap_or_else(|| panic!("{} missing: {:?}", key, config));
+    assert_eq!(get("task"), "SpeechVad");
+    assert_eq!(get("window"), "Hann");
+    let frame: usize = get("frame_size").parse().unwrap();
+    let hop: usize = get("hop_size").parse().unwrap();
+    // 25 ms is 400 samples; the nearest power of two, and a 10 ms hop
+    assert_eq!((frame, hop), (512, 160));
+    assert_eq!(get("fft_size").parse::<usize>().unwrap(), frame);
+    
+    let mut vad = VoiceActivityDetector::new(frame, hop);
+    vad.set_thresholds(get("energy_threshold_db").parse().unwrap(), get("zcr_threshold").parse().unwrap());
+    let x = speech_and_pauses(&[(false, 0.5), (true, 1.0), (false, 0.5)]);
+    let segments = vad.get_segments(&x);
+    assert_eq!(segments.len(), 2, "{:?}", segments);
+    assert!(segments[0].abs_diff(8000) < 2 * frame as u32 && segments[1].abs_diff(24000) < 2 * frame as u32, "{:?}", segments);
+}
+
+#[test]
+fn every_recommendation_is_consistent() {
+    let tasks = [AnalysisTask::SpeechVad, AnalysisTask::MusicPitch, AnalysisTask::FormantAnalysis, AnalysisTask::Spectrogram];
+    for task in tasks {
+        for sample_rate in [8000.0, 16000.0, 44100.0, 48000.0] {
+            let config = parse_flat_json(&recommend_config(task, sample_rate));
+            let size = |key: &str| config[key].parse::<usize>().unwrap();
+            let (frame, hop, fft) = (size("frame_size"), size("hop_size"), size("fft_size"));
+            assert!(frame.is_power_of_two() && frame == fft && (1..=frame).contains(&hop), "{:?}", config);
+            assert_eq!(config["sample_rate"].parse::<f32>().unwrap(), sample_rate);
+            assert!(["Hann", "Hamming", "Blackman", "Rectangular"].contains(&config["window"].as_str()));
+        }
+    }
+    assert_eq!(recommend_config(AnalysisTask::SpeechVad, 0.0), "{}");
+    assert_eq!(recommend_config(AnalysisTask::Spectrogram, f32::NAN), "{}");
+}
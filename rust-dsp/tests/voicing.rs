@@ -0,0 +1,70 @@
+mod common;
+
+use common::*;
+use sanctuary_dsp::*;
+
+const SR: f32 = 16000.0;
+
+/// Vowel normalized to a 0.3 peak
+fn vowel_of_len(len: usize) -> Vec<f32> {
+    let v = vowel(SR, 150.0, &[(700.0, 80.0), (1200.0, 90.0), (2600.0, 120.0)], len);
+    let peak = v.iter().fold(0.0f32, |acc, &s| acc.max(s.abs()));
+    v.iter().map(|s| s * 0.3 / peak).collect()
+}
+
+fn vowel_frame() -> Vec<f32> {
+    vowel_of_len(1024)
+}
+
+/// Fricative-like noise: first-differenced (high-passed) white noise
+fn fricative(len: usize, gain: f32) -> Vec<f32> {
+    noise(len + 1, 3).windows(2).map(|w| (w[1] - w[0]) * gain).collect()
+}
+
+fn fricative_frame(gain: f32) -> Vec<f32> {
+    fricative(1024, gain)
+}
+
+#[test]
+fn vowels_score_high_fricatives_medium_silence_near_zero() {
+    let mut estimator = VoicingEstimator::new(SR, 1024, 512);
+    assert!(estimator.probability(&vowel_frame()) > 0.95);
+    for gain in [0.05, 0.2, 0.5] {
+        let p = estimator.probability(&fricative_frame(gain));
+        assert!(p > 0.1 && p < 0.5, "fricative at gain {}: {}", gain, p);
+    }
+    assert!(estimator.probability(&vec![0.0; 1024]) < 0.05);
+    let hiss: Vec<f32> = noise(1024, 9).iter().map(|v| v * 1e-4).collect();
+    assert!(estimator.probability(&hiss) < 0.05);
+}
+
+#[test]
+fn classify_frames_labels_silence_noise_and_voice() {
+    let mut estimator = VoicingEstimator::new(SR, 1024, 512);
+    let x: Vec<f32> = [vec![0.0; 4096], fricative(4096, 0.2), vowel_of_len(4096)].concat();
+    let labels = estimator.classify_frames(&x);
+    assert_eq!(labels.len(), estimator.probabilities(&x).len());
+    
+    // Frames wholly inside each part: frames 0-6, 8-14 and 16-22
+    assert!(labels[..7].iter().all(|&l| l == 0), "{:?}", labels);
+    assert!(labels[8..15].iter().all(|&l| l == 1), "{:?}", labels);
+    assert!(labels[16..].iter().all(|&l| l == 2), "{:?}", labels);
+}
+
+#[test]
+fn voicing_onset_finds_the_tone_after_silence_and_noise() {
+    let mut estimator = VoicingEstimator::new(SR, 1024, 512);
+    let x: Vec<f32> = [vec![0.0; 4000], fricative(4000, 0.2), vowel_of_len(8000)].concat();
+    let onset = estimator.voicing_onset(&x);
+    assert!((onset - 8000).abs() <= 1024, "onset {}", onset);
+    
+    // A stray voiced blip inside the noise burst (under 3 frames) does not count
+    let mut stray = fricative(16000, 0.2);
+    stray[8192..8704].copy_from_slice(&vowel_of_len(512));
+    let voiced = estimator.classify_frames(&stray).iter().filter(|&&l| l == 2).count();
+    assert!(voiced >= 1);
+    assert_eq!(estimator.voicing_onset(&stray), -1);
+    
+    assert_eq!(estimator.voicing_onset(&fricative(8000, 0.2)), -1);
+    assert_eq!(estimator.voicing_onset(&vec![0.0; 8000]), -1);
+}